@@ -31,14 +31,34 @@ External Path Example:
 from __future__ import annotations
 
 import os
+import re
+from collections.abc import AsyncIterator, Callable
 from pathlib import Path
 from typing import TYPE_CHECKING
 
 from agent_gear._rust_core import (
+    DEFAULT_IGNORED_DIRS,
+    BinaryMode,
+    DiffHunk,
     FileMetadata,
+    FollowHandle,
+    LineEnding,
+    Location,
     SearchOptions,
+    SearchOrder,
+    SearchIter,
     SearchResult,
+    SearchSummary,
+    WatchEvent,
+    WatchHandle,
+    WatchMode,
+    WriteVerificationError,
     __version__,
+    grep_text_content,
+    max_open_files,
+    set_max_open_files,
+    validate_glob,
+    validate_regex,
 )
 from agent_gear._rust_core import FileSystem as _RustFileSystem
 
@@ -59,12 +79,76 @@ class _PythonSearchResult:
         content: str,
         context_before: list[str] | None = None,
         context_after: list[str] | None = None,
+        file_match_count: int | None = None,
+        pattern_index: int | None = None,
+        symbol: str | None = None,
+        metadata: FileMetadata | None = None,
+        matched_glob: str | None = None,
+        capture_span: tuple[int, int] | None = None,
     ) -> None:
         self.file = file
         self.line_number = line_number
         self.content = content
         self.context_before = context_before or []
         self.context_after = context_after or []
+        self.file_match_count = file_match_count
+        self.pattern_index = pattern_index
+        self.symbol = symbol
+        self.metadata = metadata
+        self.matched_glob = matched_glob
+        self.capture_span = capture_span
+
+    @property
+    def numbered_context_before(self) -> list[tuple[int, str]]:
+        """Context lines before the match as (line_number, text) pairs."""
+        start = self.line_number - len(self.context_before)
+        return list(enumerate(self.context_before, start))
+
+    @property
+    def numbered_context_after(self) -> list[tuple[int, str]]:
+        """Context lines after the match as (line_number, text) pairs."""
+        return list(enumerate(self.context_after, self.line_number + 1))
+
+
+def _paginate(content: str, page_chars: int) -> list[tuple[int, str]]:
+    """Split text into (start_line, text) pages, as FileSystem.read_paged does."""
+    pages: list[tuple[int, str]] = []
+    page: list[str] = []
+    page_len = 0
+    page_line = 1
+    lines = [line + "\n" for line in content.split("\n")]
+    lines[-1] = lines[-1][:-1]
+    for line_number, line in enumerate(lines, 1):
+        if not line:
+            break
+        if page_len and page_len + len(line) > page_chars:
+            pages.append((page_line, "".join(page)))
+            page, page_len = [], 0
+        if not page_len:
+            page_line = line_number
+        while len(line) > page_chars:
+            pages.append((line_number, line[:page_chars]))
+            line = line[page_chars:]
+        page.append(line)
+        page_len += len(line)
+    if page_len:
+        pages.append((page_line, "".join(page)))
+    return pages
+
+
+def _line_starts(data: str | bytes) -> list[int]:
+    """Offset of each line start, as line_offsets() and read_indexed() return.
+
+    Offsets count bytes for bytes input and characters for str input; a
+    trailing newline does not start another line.
+    """
+    newline = b"\n" if isinstance(data, bytes) else "\n"
+    starts = [0] if data else []
+    end = data.find(newline)
+    while 0 <= end < len(data) - 1:
+        starts.append(end + 1)
+        end = data.find(newline, end + 1)
+    return starts
 
 
 def _create_search_result(
@@ -73,17 +157,65 @@ def _create_search_result(
     content: str,
     context_before: list[str] | None = None,
     context_after: list[str] | None = None,
+    matched_glob: str | None = None,
+    capture_span: tuple[int, int] | None = None,
 ) -> _PythonSearchResult:
     """Create a Python SearchResult object."""
-    return _PythonSearchResult(file, line_number, content, context_before, context_after)
+    return _PythonSearchResult(
+        file,
+        line_number,
+        content,
+        context_before,
+        context_after,
+        matched_glob=matched_glob,
+        capture_span=capture_span,
+    )
+
+
+def _capture_results(
+    query: str, case_sensitive: bool, group: int, results: list[dict]
+) -> list[dict]:
+    """Replace each grep result's content with a capture group, as grep does."""
+    pattern = re.compile(query, 0 if case_sensitive else re.IGNORECASE)
+    if group > pattern.groups:
+        raise ValueError(
+            f"Regex error: capture group {group} out of range; "
+            f"the pattern has {pattern.groups} groups"
+        )
+    captured = []
+    for r in results:
+        match = pattern.search(r["content"])
+        if match is None or match.group(group) is None:
+            continue
+        start, end = match.span(group)
+        captured.append({**r, "content": match.group(group), "capture_span": (start + 1, end + 1)})
+    return captured
 
 __all__ = [
     "FileSystem",
     "AsyncFileSystem",
+    "BinaryMode",
+    "DEFAULT_IGNORED_DIRS",
+    "DiffHunk",
     "FileMetadata",
+    "FollowHandle",
+    "LineEnding",
+    "Location",
     "SearchOptions",
+    "SearchIter",
+    "SearchOrder",
     "SearchResult",
+    "SearchSummary",
+    "WatchEvent",
+    "WatchHandle",
+    "WatchMode",
+    "WriteVerificationError",
     "__version__",
+    "grep_text_content",
+    "max_open_files",
+    "set_max_open_files",
+    "validate_glob",
+    "validate_regex",
 ]
 
 
@@ -97,6 +229,10 @@ class FileSystem:
         auto_watch: Whether to automatically watch for file changes (default: True).
         allow_external: Whether to allow operations on paths outside root (default: False).
             When True, external paths use a Python fallback implementation.
+        index_snapshot: Optional snapshot file (see save_index) to load instead of
+            walking the tree. Falls back to a full build if it cannot be read.
+        case_insensitive: Treat paths differing only in case as the same file, as on
+            default macOS and Windows filesystems (default: None = detect).
 
     Example:
         >>> with FileSystem("/path/to/project") as fs:
@@ -122,6 +258,24 @@ class FileSystem:
         root: str,
         auto_watch: bool = True,
         allow_external: bool = False,
+        index_snapshot: str | None = None,
+        case_insensitive: bool | None = None,
+        auto_refresh_secs: int | None = None,
+        include_extensions: list[str] | None = None,
+        watch_recursive: bool = True,
+        cache_reads: bool = False,
+        skip_binary_detection_above: int | None = None,
+        watch_debounce_ms: int = 100,
+        writable_extensions: list[str] | None = None,
+        allow_extensionless_writes: bool = False,
+        max_read_bytes: int | None = 1024 * 1024 * 1024,
+        watch_mode: WatchMode = WatchMode.AUTO,
+        watch_poll_interval_ms: int = 1000,
+        max_index_entries: int | None = None,
+        use_default_ignores: bool = False,
+        ignored_dirs: list[str] | None = None,
+        cache_line_offsets: bool = False,
+        custom_ignore_files: list[str] | None = None,
     ) -> None:
         """Initialize the FileSystem.
 
@@ -129,10 +283,104 @@ class FileSystem:
             root: Root directory path.
             auto_watch: Whether to automatically watch for file changes.
             allow_external: Whether to allow operations on paths outside root.
+            index_snapshot: Optional snapshot file to load instead of walking the tree.
+            case_insensitive: Treat paths differing only in case as the same file
+                (None = detect from the filesystem).
+            auto_refresh_secs: Reconcile the index with the disk every N seconds
+                to catch missed watcher events (None = never). Each pass walks
+                the whole tree, so keep the interval well above the walk time.
+            include_extensions: Only index and watch files with these
+                extensions, e.g. ["py", "rs"] (None = all files). Directories
+                are always traversed.
+            watch_recursive: Watch the whole tree (default). When False only root
+                itself is watched; changes inside subdirectories are not detected
+                and the index will not track them until refresh().
+            cache_reads: Keep recently read files in memory (up to 256 files and
+                32 MiB) and serve read_file() from there while a file's mtime and
+                size are unchanged. Helps agents that re-read the same files.
+                Only UTF-8 reads are cached; other encodings read from disk.
+            skip_binary_detection_above: Assume files larger than this many bytes
+                are binary instead of reading their first bytes while indexing
+                (None = always read). Cuts indexing I/O on repos full of large
+                media files; such files are then skipped by default searches.
+            watch_debounce_ms: How long a path must be quiet before its change
+                is applied to the index (default 100). 0 selects raw mode, where
+                every event is applied immediately and unmerged for the lowest
+                latency (e.g. live-reload loops). Raw mode is noisy: one save can
+                arrive as several events, and a file created and deleted in quick
+                succession is added and removed rather than skipped.
+            writable_extensions: Only allow writes to files with these
+                extensions, e.g. ["py", "md"] (None = no restriction). Write
+                methods raise PermissionError for any other target before
+                touching the disk, so a semi-trusted agent cannot create
+                scripts or dotfiles. A guardrail on top of the root check, not
+                a sandbox.
+            allow_extensionless_writes: With writable_extensions set, also allow
+                targets without an extension (Makefile, dotfiles such as
+                .bashrc, directory names). Refused by default.
+            max_read_bytes: Refuse to load files larger than this many bytes in
+                read_file(), read_batch() and open(), raising IOError instead
+                (default 1 GiB, None = no limit). The size is checked before reading, so
+                an accidentally opened huge artifact cannot exhaust memory.
+            watch_mode: Watcher backend. AUTO (default) uses native notifications
+                and polls only if they fail to start; NATIVE never polls; POLL
+                rescans the tree every watch_poll_interval_ms, keeping the index
+                correct at the cost of CPU. Use POLL on network shares, FUSE and
+                Docker bind mounts, where native watching can start yet deliver
+                nothing. watcher_healthy() cannot detect that; instead write a
+                probe file and check that get_metadata() sees it within a second.
+            watch_poll_interval_ms: Interval between scans when polling (default
+                1000). Each scan stats every file in the watched tree.
+            max_index_entries: Stop indexing once this many files and directories
+                are held (None = no limit), so a gigantic tree yields a partial
+                index instead of exhausting memory. The index still becomes
+                ready; check is_truncated() and fall back to direct reads for
+                paths it lacks.
+            use_default_ignores: Also skip the vendor and build directories in
+                DEFAULT_IGNORED_DIRS (node_modules, target, .git, __pycache__,
+                dist, build, ...) wherever they appear, on top of .gitignore.
+                Off by default.
+            ignored_dirs: Further directory names to skip the same way (None =
+                none). To replace the defaults rather than extend them, leave
+                use_default_ignores off and pass an edited copy of
+                DEFAULT_IGNORED_DIRS. Searches with include_ignored=True see
+                these directories again.
+            cache_line_offsets: Remember where each line starts in files read
+                with read_lines(), read_numbered() or read_around() (up to 64
+                files), so repeated range reads of an unchanged file seek to the
+                first requested line instead of scanning from the top.
+            custom_ignore_files: Further ignore file names to honour in every
+                directory, e.g. [".agentignore"], to manage what agents see
+                apart from .gitignore (None = none). They use gitignore syntax
+                and take precedence over .gitignore and .ignore, so a "!"
+                pattern re-includes a gitignored path; later names beat earlier
+                ones. Searches with include_ignored=True skip them.
         """
-        self._inner = _RustFileSystem(root, auto_watch)
+        self._inner = _RustFileSystem(
+            root,
+            auto_watch,
+            index_snapshot,
+            case_insensitive,
+            auto_refresh_secs,
+            include_extensions,
+            watch_recursive,
+            cache_reads,
+            skip_binary_detection_above,
+            watch_debounce_ms,
+            writable_extensions,
+            allow_extensionless_writes,
+            max_read_bytes,
+            watch_mode,
+            watch_poll_interval_ms,
+            max_index_entries,
+            use_default_ignores,
+            ignored_dirs,
+            cache_line_offsets,
+            custom_ignore_files,
+        )
         self._root = os.path.abspath(root)
         self._allow_external = allow_external
+        self._max_read_bytes = max_read_bytes
         self._python_backend = None
         if allow_external:
             from agent_gear.python_backend import PythonFileBackend
@@ -165,6 +413,22 @@ class FileSystem:
                 "and allow_external=False"
             )
 
+    def _check_read_size(self, path: str, max_read_bytes: int | None) -> None:
+        """Apply the read limit to an external path read by the Python backend.
+
+        Raises:
+            IOError: If the file is larger than the read limit.
+        """
+        limit = max_read_bytes if max_read_bytes is not None else self._max_read_bytes
+        if not limit or not os.path.isfile(path):
+            return
+        size = os.path.getsize(path)
+        if size > limit:
+            raise IOError(
+                f"File too large: {path} is {size} bytes, over the read limit of "
+                f"{limit} bytes; use read_lines() or read_file_range() to read part of it"
+            )
+
     def wait_ready(self, timeout: float = 30.0) -> bool:
         """Wait for the index to be ready.
 
@@ -183,12 +447,16 @@ class FileSystem:
             time.sleep(0.1)
         return True
 
-    def list(self, pattern: str = "**/*", only_files: bool = True) -> list[str]:
+    def list(
+        self, pattern: str = "**/*", only_files: bool = True, canonicalize: bool = False
+    ) -> list[str]:
         """List files matching the given pattern from memory index.
 
         Args:
             pattern: Glob pattern (default: "**/*"). Can be absolute path for external dirs.
+                Brace alternation works as in the shell, e.g. "**/*.{rs,toml}".
             only_files: If true, only return files (not directories).
+            canonicalize: Resolve symlinks in each returned path (see glob()).
 
         Returns:
             List of file paths relative to root (or absolute for external).
@@ -207,14 +475,21 @@ class FileSystem:
                 assert self._python_backend is not None
                 # Extract pattern after base path
                 remaining_pattern = "/".join(parts[2:]) if len(parts) > 2 else "**/*"
-                return self._python_backend.list_files(base_path, remaining_pattern, only_files)
-        return self._inner.list(pattern, only_files)
+                paths = self._python_backend.list_files(base_path, remaining_pattern, only_files)
+                return [os.path.realpath(p) for p in paths] if canonicalize else paths
+        return self._inner.list(pattern, only_files, canonicalize)
 
-    def glob(self, pattern: str) -> list[str]:
+    def glob(self, pattern: str, canonicalize: bool = False) -> list[str]:
         """Match files using glob pattern.
 
         Args:
             pattern: Glob pattern. Can be absolute path for external dirs.
+            canonicalize: Resolve symlinks in each returned path, for tools
+                that expect canonical paths. Costs a syscall per path, run in
+                parallel. A path that stays inside root is returned relative to
+                it; one whose symlink points elsewhere becomes absolute and may
+                lie outside root, so passing it back in is subject to
+                allow_external. Unresolvable paths are returned unchanged.
 
         Returns:
             List of matching file paths.
@@ -231,36 +506,454 @@ class FileSystem:
                 self._check_external_allowed(base_path)
                 assert self._python_backend is not None
                 remaining_pattern = "/".join(parts[2:]) if len(parts) > 2 else "*"
-                return self._python_backend.glob(base_path, remaining_pattern)
-        return self._inner.glob(pattern)
+                paths = self._python_backend.glob(base_path, remaining_pattern)
+                return [os.path.realpath(p) for p in paths] if canonicalize else paths
+        return self._inner.glob(pattern, canonicalize)
+
+    def glob_grouped(self, patterns: list[str]) -> dict[str, list[str]]:
+        """Match files against several globs, keeping the results per pattern.
+
+        Unlike globbing each pattern and merging the results, this shows which
+        pattern matched which files, so patterns that matched nothing (e.g. a
+        mistyped test glob) are easy to spot.
+
+        Args:
+            patterns: Glob patterns to match.
+
+        Returns:
+            Dict mapping each pattern to its sorted matching file paths
+            (an empty list if the pattern matched nothing).
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.glob_grouped(patterns)
+
+    def expand_specs(self, specs: list[str]) -> list[str]:
+        """Resolve file specs mixing literal paths and globs to a file list.
+
+        Turns what a user or model hands over as "the files", such as
+        ["src/main.rs", "tests/**/*.rs"], into concrete paths. A spec
+        containing *, ?, [ or { is a glob expanded against the index; any
+        other spec is a literal path, kept if it names an existing file, even
+        one the ignore rules leave out of the index.
+
+        Args:
+            specs: Literal paths (relative to root or absolute) and globs
+                (relative to root).
+
+        Returns:
+            Paths relative to root in spec order, each glob's matches sorted,
+            without duplicates. Specs that match nothing contribute nothing;
+            use glob_grouped() to find which globs came up empty.
+
+        Raises:
+            ValueError: If a glob spec is invalid.
+            RuntimeError: If the index is not ready yet.
+
+        Example:
+            >>> fs.expand_specs(["README.md", "src/*.rs", "src/main.rs"])
+            ['README.md', 'src/lib.rs', 'src/main.rs']
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.expand_specs(specs)
+
+    def list_regex(self, path_regex: str, only_files: bool = True) -> list[str]:
+        """List files whose relative path matches a regex.
+
+        For matches globs cannot express, such as paths containing a 4-digit
+        number (r"\\d{4}"). The regex is unanchored; use ^ and $ to anchor it
+        to the "/"-separated path relative to root.
+
+        Args:
+            path_regex: Regular expression matched against relative paths.
+            only_files: If true, only return files (not directories).
+
+        Returns:
+            List of matching paths relative to root.
+        """
+        return self._inner.list_regex(path_regex, only_files)
+
+    def common_ancestor(self, paths: list[str]) -> str:
+        """Find the deepest directory containing every given path.
+
+        Paths are compared component-wise, so "src/foo" and "src/foobar" share
+        "src" (a plain string prefix would wrongly give "src/foo"). A single
+        path yields its parent, and an empty list yields the root.
+
+        Args:
+            paths: File or directory paths (relative to root or absolute).
+
+        Returns:
+            The common directory relative to root ("." for root itself), or an
+            absolute path if it lies outside root.
+
+        Example:
+            >>> fs.common_ancestor(["src/fs/mod.rs", "src/utils/path.rs"])
+            'src'
+        """
+        return self._inner.common_ancestor(paths)
+
+    def diff_paths(self, paths: list[str]) -> tuple[list[str], list[str]]:
+        """Compare a list of file paths against the indexed files.
+
+        For validating a build manifest or lockfile against the tree; answered
+        from the in-memory index without touching the disk.
+
+        Args:
+            paths: File paths (relative to root or absolute).
+
+        Returns:
+            Tuple of (missing_on_disk, untracked_in_list): listed paths the index
+            has no file for, and indexed files the list leaves out. Both are
+            sorted and relative to root. A listed directory counts as missing.
+
+        Example:
+            >>> fs.diff_paths(["src/main.rs", "src/removed.rs"])
+            (['src/removed.rs'], ['README.md', 'src/lib.rs'])
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.diff_paths(paths)
+
+    def find_by_name(self, name: str, case_insensitive: bool = False) -> list[str]:
+        """Find paths whose filename exactly equals name.
+
+        Only the final path component is compared, so unlike a glob there is
+        no risk of matching substrings or misparsing special characters.
+
+        Args:
+            name: Basename to look for (e.g. "Cargo.toml").
+            case_insensitive: Compare names ignoring case.
+
+        Returns:
+            Sorted list of matching paths relative to root.
+        """
+        return self._inner.find_by_name(name, case_insensitive)
+
+    def recent_files(self, n: int = 20, pattern: str | None = None) -> list[tuple[str, float]]:
+        """List the most recently modified files.
+
+        Answers "what changed lately?" from the index's own metadata, without
+        listing the whole tree and calling get_metadata() on every file.
+
+        Args:
+            n: Maximum number of files to return.
+            pattern: Only consider files matching this glob (None = all files).
+
+        Returns:
+            List of (path, mtime) tuples, newest first. mtime is a Unix timestamp.
+        """
+        return self._inner.recent_files(n, pattern)
+
+    def changed_under(self, subpath: str, timestamp: float) -> bool:
+        """Check whether anything under a subtree changed since a timestamp.
+
+        For scoped cache invalidation: walks only the indexed entries below
+        subpath and stops at the first one with mtime >= timestamp, so it is
+        cheaper than recent_files() when only one directory and a yes/no
+        answer matter. Directory mtimes count, which is how removals show up.
+
+        Args:
+            subpath: Directory (or file) to check, relative to root or absolute.
+            timestamp: Unix timestamp, e.g. time.time() saved on the last run.
+
+        Returns:
+            True if an entry has mtime >= timestamp. False otherwise, including
+            for paths the index does not hold.
+
+        Raises:
+            RuntimeError: If the index is not ready yet.
+
+        Example:
+            >>> if fs.changed_under("src/api", last_run):
+            ...     regenerate_client()
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.changed_under(subpath, timestamp)
+
+    def empty_files(self) -> list[str]:
+        """List zero-byte files, for cleanup passes.
+
+        Answered from the in-memory index without touching the disk.
+
+        Returns:
+            Sorted list of empty file paths relative to root.
+        """
+        return self._inner.empty_files()
+
+    def empty_dirs(self) -> list[str]:
+        """List directories with no entries, for cleanup passes.
+
+        Answered from the in-memory index, so a directory containing only
+        ignored or filtered-out files is listed too. A directory whose only
+        child is an empty directory is not.
+
+        Returns:
+            Sorted list of empty directory paths relative to root.
+        """
+        return self._inner.empty_dirs()
+
+    def list_dirs_with_matches(self, pattern: str) -> list[str]:
+        """List the directories that directly contain matching files.
+
+        Answers "which folders have Python files" for directory pickers
+        without pulling every file path into Python to dedupe parents.
+
+        Args:
+            pattern: Glob pattern for files, e.g. "**/*.py".
+
+        Returns:
+            Sorted, deduplicated directory paths relative to root ("." for
+            root itself). Ancestors of those directories are not added.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.list_dirs_with_matches(pattern)
+
+    def glob_matches(self, pattern: str, path: str) -> bool:
+        """Check whether a glob pattern matches a path.
+
+        Applies the pattern exactly as list() and glob() would, without
+        listing the tree, so an agent can check "will this rule catch
+        src/foo.rs?" directly. The path does not have to exist.
+
+        Args:
+            pattern: Glob pattern, e.g. "src/**/*.rs".
+            path: Path relative to root, or absolute. Absolute paths outside
+                root never match.
+
+        Returns:
+            True if the path matches the pattern.
+
+        Raises:
+            ValueError: If the pattern is invalid.
+        """
+        return self._inner.glob_matches(pattern, path)
+
+    def code_stats(self) -> dict[str, tuple[int, int, int]]:
+        """Summarise the indexed code by language, a lightweight cloc.
+
+        Languages are detected from file names; binary files and files of
+        unknown kind are not counted. Only total lines are reported, with no
+        blank or comment distinction. Line counts are cached per file until
+        it changes, so repeated calls for a project overview are cheap.
+
+        Returns:
+            Dict mapping language name to (files, lines, bytes).
+        """
+        return self._inner.code_stats()
+
+    def count_files(self, pattern: str = "**/*") -> int:
+        """Count the indexed files matching a glob pattern.
+
+        Args:
+            pattern: Glob pattern (default: "**/*").
+
+        Returns:
+            Number of matching files.
+        """
+        return self._inner.count_files(pattern)
+
+    def count_lines(self, pattern: str = "**/*") -> int:
+        """Total lines across the text files matching a glob pattern.
+
+        Shares the cached per-file line counts with code_stats(); binary
+        files are skipped.
+
+        Args:
+            pattern: Glob pattern (default: "**/*").
+
+        Returns:
+            Sum of line counts.
+        """
+        return self._inner.count_lines(pattern)
+
+    def count_lines_exact(self, path: str, convention: LineEnding = LineEnding.LF) -> int:
+        """Count a file's lines under an explicit line ending convention.
+
+        For matching the count another tool reports, such as a Windows tool
+        that only breaks at CRLF or an editor that honours classic Mac line
+        endings (ANY). count_lines() and code_stats() split on "\\n" only, the
+        same as LF, and read_lines() splits on "\\n" and "\\r\\n"; neither
+        breaks at a lone "\\r". This always reads the file with a byte scan,
+        bypassing the cache.
+
+        Args:
+            path: File path (relative to root or absolute).
+            convention: LineEnding deciding what ends a line. CRLF counts only
+                "\\r\\n"; ANY counts "\\r\\n", "\\n" and a lone "\\r" each as
+                one break.
+
+        Returns:
+            Number of lines, counting a final line without a terminator; 0 for
+            an empty file.
 
-    def read_file(self, path: str, encoding: str = "utf-8") -> str:
+        Example:
+            >>> fs.count_lines_exact("legacy.txt", LineEnding.ANY)
+            120
+        """
+        if self._is_within_root(path):
+            return self._inner.count_lines_exact(path, convention)
+        self._check_external_allowed(path)
+        with open(path, "rb") as f:
+            data = f.read()
+        if convention == LineEnding.CRLF:
+            breaks = list(re.finditer(rb"\r\n", data))
+        elif convention == LineEnding.ANY:
+            breaks = list(re.finditer(rb"\r\n|\n|\r", data))
+        else:
+            breaks = list(re.finditer(rb"\n", data))
+        end = breaks[-1].end() if breaks else 0
+        return len(breaks) + (end < len(data))
+
+    def read_file(
+        self, path: str, encoding: str = "utf-8", max_read_bytes: int | None = None
+    ) -> str:
         """Read a single file.
 
         Args:
             path: File path (relative to root or absolute).
             encoding: Text encoding (default: utf-8).
+            max_read_bytes: Override the instance's max_read_bytes for this call
+                (None = use the instance limit, 0 = no limit).
 
         Returns:
             File content as string.
 
         Raises:
             ValueError: If path is external and allow_external=False.
+            IOError: If the file is larger than the read limit.
         """
         if self._is_within_root(path):
-            return self._inner.read_file(path, encoding)
+            return self._inner.read_file(path, encoding, max_read_bytes)
         self._check_external_allowed(path)
+        self._check_read_size(path, max_read_bytes)
         assert self._python_backend is not None
         return self._python_backend.read_file(path, encoding)
 
-    def read_batch(self, paths: list[str]) -> dict[str, str]:
+    def set_binary_extensions(self, always_binary: list[str], always_text: list[str]) -> int:
+        """Override binary detection by file extension.
+
+        Files with an always_binary extension are marked binary without being
+        read; files with an always_text extension are treated as text, and so
+        searched, even if they contain null bytes. Indexing, classify() and
+        searches consult the overrides before sniffing bytes. Each call
+        replaces the previous overrides; pass two empty lists to clear them.
+
+        Args:
+            always_binary: Extensions to treat as binary, e.g. ["dat", "bin"].
+            always_text: Extensions to treat as text, e.g. ["fixture"]. Wins
+                over always_binary for an extension listed in both.
+
+        Returns:
+            Number of indexed files whose binary flag changed.
+
+        Note:
+            Only paths within root are affected; the Python fallback for
+            external paths keeps its plain null-byte check.
+        """
+        return self._inner.set_binary_extensions(always_binary, always_text)
+
+    def file_id(self, path: str) -> str:
+        """Get a stable, content-based id for a file.
+
+        The id is the BLAKE3 hash of the file's bytes, so it survives renames
+        and moves: an agent can track "the file I was editing" across sessions
+        by id. Files with identical content share an id, and any edit changes
+        it.
+
+        Args:
+            path: File path (relative to root or absolute).
+
+        Returns:
+            64-character lowercase hex string.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.file_id(path)
+
+    def classify(self, path: str) -> str:
+        """Classify a file as "text", "binary", "empty" or "missing".
+
+        One call covering the existence check, the empty-file case and the
+        null-byte binary heuristic used by the index. The indexed binary flag
+        is reused while it is current; other files are sniffed directly.
+
+        Args:
+            path: File path (relative to root or absolute).
+
+        Returns:
+            "text", "binary", "empty" or "missing".
+
+        Raises:
+            IOError: If the path is a directory.
+        """
+        self._check_external_allowed(path)
+        return self._inner.classify(path)
+
+    def open(
+        self, path: str, max_read_bytes: int | None = None
+    ) -> tuple[str, FileMetadata | dict]:
+        """Read a file and its metadata in one call.
+
+        Saves a separate get_metadata() round trip. The metadata is taken from
+        the opened file, so size and mtime match the returned content.
+
+        Args:
+            path: File path (relative to root or absolute).
+            max_read_bytes: Override the instance's max_read_bytes for this call
+                (None = use the instance limit, 0 = no limit).
+
+        Returns:
+            Tuple of (content, metadata); metadata is a FileMetadata object
+            (Rust) or dict (Python fallback).
+
+        Raises:
+            ValueError: If path is external and allow_external=False.
+            IOError: If the file is larger than the read limit.
+
+        Example:
+            >>> content, meta = fs.open("src/main.py")
+            >>> print(f"{meta.size} bytes")
+        """
+        if self._is_within_root(path):
+            return self._inner.open(path, max_read_bytes)
+        self._check_external_allowed(path)
+        self._check_read_size(path, max_read_bytes)
+        assert self._python_backend is not None
+        return (
+            self._python_backend.read_file(path),
+            self._python_backend.get_metadata(path),
+        )
+
+    def read_batch(
+        self,
+        paths: list[str],
+        max_file_size: int | None = None,
+        max_read_bytes: int | None = None,
+    ) -> dict[str, str]:
         """Read multiple files in parallel.
 
         Args:
             paths: List of file paths.
+            max_file_size: Skip files larger than this many bytes (None = no limit).
+                The size is checked before reading, so oversized files are never loaded.
+            max_read_bytes: Override the instance's max_read_bytes for this call
+                (None = use the instance limit, 0 = no limit). Unlike
+                max_file_size, a file over this limit fails the whole batch.
 
         Returns:
-            Dict mapping path to content.
+            Dict keyed by each path exactly as given (so read_batch(["a.txt"])
+            returns {"a.txt": ...}), mapping to its content. Skipped or
+            unreadable files are omitted.
 
         Note:
             For mixed internal/external paths, internal paths use Rust,
@@ -277,11 +970,22 @@ class FileSystem:
                 external_paths.append(p)
 
         # Read internal paths with Rust
-        result = self._inner.read_batch(internal_paths) if internal_paths else {}
+        result = (
+            self._inner.read_batch(internal_paths, max_file_size, max_read_bytes)
+            if internal_paths
+            else {}
+        )
 
         # Read external paths with Python
         if external_paths and self._python_backend:
             for p in external_paths:
+                try:
+                    if max_file_size is not None and os.path.getsize(p) > max_file_size:
+                        continue
+                except OSError:
+                    continue
+                # Like the Rust side, only the read limit fails the batch
+                self._check_read_size(p, max_read_bytes)
                 try:
                     result[p] = self._python_backend.read_file(p)
                 except Exception as e:
@@ -290,7 +994,13 @@ class FileSystem:
 
         return result
 
-    def read_lines(self, path: str, start_line: int = 0, count: int | None = None) -> list[str]:
+    def read_lines(
+        self,
+        path: str,
+        start_line: int = 0,
+        count: int | None = None,
+        max_bytes: int | None = None,
+    ) -> list[str]:
         """Read specific lines from a file (for large files).
 
         Efficiently reads a range of lines without loading the entire file.
@@ -300,115 +1010,681 @@ class FileSystem:
             path: File path.
             start_line: Starting line number (0-indexed).
             count: Number of lines to read (None = read to end).
+            max_bytes: Byte budget for the returned lines. Reading stops
+                before the first line that would exceed it, so only whole
+                lines are returned (None = no budget).
 
         Returns:
-            List of line strings (without trailing newlines).
+            List of line strings (without trailing newlines). Shorter than
+            count when the budget or the end of file is reached.
 
         Example:
             >>> # Read first 100 lines
             >>> lines = fs.read_lines("large_log.txt", 0, 100)
             >>> # Read lines 1000-1100
             >>> lines = fs.read_lines("large_log.txt", 1000, 100)
+            >>> # Preview: up to 50 lines or 4KB, whichever comes first
+            >>> lines = fs.read_lines("main.py", 0, 50, max_bytes=4096)
         """
         if self._is_within_root(path):
-            return self._inner.read_lines(path, start_line, count)
+            return self._inner.read_lines(path, start_line, count, max_bytes)
         self._check_external_allowed(path)
         assert self._python_backend is not None
-        return self._python_backend.read_lines(path, start_line, count)
+        return self._python_backend.read_lines(path, start_line, count, max_bytes)
 
-    def read_file_range(self, path: str, offset: int, limit: int) -> str:
-        """Read a byte range from a file.
+    def read_first_line(self, path: str) -> str | None:
+        """Read the first line of a file.
+
+        Only reads up to the first newline, so it is cheap even for huge files.
 
         Args:
             path: File path.
-            offset: Byte offset to start reading from.
-            limit: Maximum bytes to read.
 
         Returns:
-            Content as string.
+            The first line without its terminator, or None for an empty file.
+
+        Example:
+            >>> if (fs.read_first_line("build.sh") or "").startswith("#!"):
+            ...     print("script")
         """
         if self._is_within_root(path):
-            return self._inner.read_file_range(path, offset, limit)
+            return self._inner.read_first_line(path)
         self._check_external_allowed(path)
         assert self._python_backend is not None
-        return self._python_backend.read_file_range(path, offset, limit)
+        lines = self._python_backend.read_lines(path, 0, 1)
+        return lines[0] if lines else None
 
-    def write_file(self, path: str, content: str) -> bool:
-        """Write content to file atomically.
+    def read_numbered(
+        self, path: str, start_line: int = 0, count: int | None = None
+    ) -> list[tuple[int, str]]:
+        """Read specific lines paired with their line numbers.
 
         Args:
             path: File path.
-            content: Content to write.
+            start_line: Starting line number (0-indexed).
+            count: Number of lines to read (None = read to end).
 
         Returns:
-            True if successful.
+            List of (line_number, text) tuples, line_number is 1-indexed.
+
+        Example:
+            >>> for number, text in fs.read_numbered("main.py", 40, 5):
+            ...     print(f"{number:>4} | {text}")
         """
         if self._is_within_root(path):
-            return self._inner.write_file(path, content)
+            return self._inner.read_numbered(path, start_line, count)
         self._check_external_allowed(path)
         assert self._python_backend is not None
-        return self._python_backend.write_file(path, content)
-
-    def write_file_fast(self, path: str, content: str) -> bool:
-        """Write content to file without atomicity guarantee (fast mode).
+        lines = self._python_backend.read_lines(path, start_line, count)
+        return [(start_line + i + 1, line) for i, line in enumerate(lines)]
 
-        Much faster than write_file() but does not guarantee data integrity
-        on crash. Use for temporary files or when speed is critical.
+    def read_around(
+        self, path: str, center_line: int, radius: int = 10
+    ) -> list[tuple[int, str]]:
+        """Read the numbered lines around a given line.
 
         Args:
             path: File path.
-            content: Content to write.
+            center_line: Line to center on (1-indexed, as in grep results).
+            radius: Number of lines to include before and after center_line.
 
         Returns:
-            True if successful.
+            List of (line_number, text) tuples for lines
+            [center_line - radius, center_line + radius], clamped to the file.
+
+        Raises:
+            ValueError: If center_line is less than 1.
+
+        Example:
+            >>> for r in fs.grep("def main"):
+            ...     context = fs.read_around(r.file, r.line, 5)
         """
         if self._is_within_root(path):
-            return self._inner.write_file_fast(path, content)
-        self._check_external_allowed(path)
-        assert self._python_backend is not None
-        return self._python_backend.write_file_fast(path, content)
+            return self._inner.read_around(path, center_line, radius)
+        if center_line < 1:
+            raise ValueError("center_line is 1-indexed")
+        first = max(center_line - radius, 1)
+        return self.read_numbered(path, first - 1, center_line + radius - first + 1)
 
-    def edit_replace(
-        self,
-        path: str,
-        old_text: str,
-        new_text: str,
-        strict: bool = True,
-    ) -> bool:
-        """Replace text in file.
+    def line_offsets(self, path: str) -> list[int]:
+        """Get the byte offset where each line of a file starts.
+
+        Entry i is where line i (0-indexed, as in read_lines) starts; a trailing
+        newline does not start another line. With cache_line_offsets the result
+        is cached and later range reads of the file seek with it.
 
         Args:
             path: File path.
-            old_text: Text to find.
-            new_text: Replacement text.
-            strict: If true, error if old_text is not unique or not found.
 
         Returns:
-            True if replacement was made.
+            List of byte offsets, empty for an empty file.
         """
         if self._is_within_root(path):
-            return self._inner.edit_replace(path, old_text, new_text, strict)
+            return self._inner.line_offsets(path)
         self._check_external_allowed(path)
-        assert self._python_backend is not None
-        return self._python_backend.edit_replace(path, old_text, new_text, strict)
+        with open(path, "rb") as f:
+            return _line_starts(f.read())
 
-    def grep(
-        self,
-        query: str,
-        glob_pattern: str = "**/*",
-        case_sensitive: bool = False,
-        max_results: int = 1000,
-    ) -> list[SearchResult]:
-        """Search files for content matching query.
+    def read_indexed(
+        self, path: str, max_read_bytes: int | None = None
+    ) -> tuple[str, list[int]]:
+        """Read a file together with the character offset of each line start.
 
-        Args:
-            query: Search pattern (regex).
-            glob_pattern: File pattern to search in. Can be absolute path for external dirs.
-            case_sensitive: Case sensitive search.
-            max_results: Maximum number of results.
+        For editors that look lines up repeatedly: one read yields both the
+        content and its line offsets, computed from that same content, so
+        they cannot disagree the way read_file() followed by line_offsets()
+        can when the file changes in between.
+
+        Args:
+            path: File path (relative to root or absolute).
+            max_read_bytes: Override the instance's max_read_bytes for this call
+                (None = use the instance limit, 0 = no limit).
 
         Returns:
-            List of SearchResult objects.
+            Tuple of (content, offsets). Unlike line_offsets() the offsets count
+            characters, so content[offsets[i]:offsets[i + 1]] is line i.
+
+        Raises:
+            ValueError: If path is external and allow_external=False.
+            IOError: If the file is larger than the read limit.
+
+        Example:
+            >>> content, offsets = fs.read_indexed("src/lib.rs")
+            >>> line_3 = content[offsets[2]:offsets[3]]
+        """
+        if self._is_within_root(path):
+            return self._inner.read_indexed(path, max_read_bytes)
+        content = self.read_file(path, max_read_bytes=max_read_bytes)
+        return content, _line_starts(content)
+
+    def read_paged(self, path: str, page_chars: int) -> list[tuple[int, str]]:
+        """Read a file split into pages of bounded size.
+
+        Pages hold at most page_chars characters and break after a newline
+        whenever a whole line fits; only lines longer than a page are cut, and
+        never inside a character. Joining the page texts gives back the file.
+
+        Args:
+            path: File path.
+            page_chars: Maximum characters per page.
+
+        Returns:
+            List of (start_line, text) tuples, where start_line is the 1-indexed
+            line each page begins on, for citing locations.
+
+        Raises:
+            ValueError: If page_chars is less than 1.
+            IOError: If the file is larger than max_read_bytes.
+
+        Example:
+            >>> for start_line, page in fs.read_paged("big.py", 8000):
+            ...     send(f"big.py from line {start_line}:\n{page}")
+        """
+        if self._is_within_root(path):
+            return self._inner.read_paged(path, page_chars)
+        if page_chars < 1:
+            raise ValueError("page_chars must be positive")
+        return _paginate(self.read_file(path), page_chars)
+
+    def read_file_range(self, path: str, offset: int, limit: int) -> str:
+        """Read a byte range from a file.
+
+        Args:
+            path: File path.
+            offset: Byte offset to start reading from.
+            limit: Maximum bytes to read.
+
+        Returns:
+            Content as string.
+        """
+        if self._is_within_root(path):
+            return self._inner.read_file_range(path, offset, limit)
+        self._check_external_allowed(path)
+        assert self._python_backend is not None
+        return self._python_backend.read_file_range(path, offset, limit)
+
+    def read_page(
+        self, path: str, cursor: int | None = None, max_lines: int = 1000
+    ) -> tuple[list[str], int | None]:
+        """Read a page of lines starting at a cursor.
+
+        Pages through a file too large to load with constant memory: each call
+        seeks to the cursor and reads forward, so nothing before it is
+        rescanned (unlike read_lines with a growing start_line). Treat the
+        cursor as opaque and only pass back values returned by an earlier call.
+
+        Args:
+            path: File path.
+            cursor: Where to resume (None = start of file).
+            max_lines: Maximum number of lines to return (default 1000).
+
+        Returns:
+            Tuple of (lines, next_cursor). Lines have no terminators;
+            next_cursor is None once the end of the file is reached.
+
+        Raises:
+            ValueError: If max_lines is 0.
+
+        Example:
+            >>> cursor = None
+            >>> while True:
+            ...     lines, cursor = fs.read_page("huge.log", cursor, 500)
+            ...     process(lines)
+            ...     if cursor is None:
+            ...         break
+        """
+        if self._is_within_root(path):
+            return self._inner.read_page(path, cursor, max_lines)
+        if max_lines <= 0:
+            raise ValueError("max_lines must be positive")
+        self._check_external_allowed(path)
+        assert self._python_backend is not None
+        return self._python_backend.read_page(path, cursor, max_lines)
+
+    def write_file(
+        self,
+        path: str,
+        content: str,
+        encoding: str = "utf-8",
+        replace_unencodable: bool = False,
+        verify: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+    ) -> bool:
+        """Write content to file atomically.
+
+        Args:
+            path: File path.
+            content: Content to write.
+            encoding: Output encoding (e.g. "utf-8", "shift_jis", "gbk").
+            replace_unencodable: Write "?" for characters the encoding cannot
+                represent instead of raising.
+            verify: Read the file back after the rename and compare it with
+                what was written, catching silent corruption on unreliable
+                storage. Doubles the I/O, so it is off by default.
+            ensure_trailing_newline: Append a line ending if the content does not
+                end with one (CRLF if the content already uses CRLF), as many
+                linters require.
+            strip_trailing_whitespace: Trim spaces and tabs from the end of every
+                line.
+
+        Returns:
+            True if successful.
+
+        Raises:
+            ValueError: If the encoding is unknown, or content is unencodable
+                and replace_unencodable is false.
+            WriteVerificationError: If verify is set and the file on disk
+                differs from what was written.
+            PermissionError: If writable_extensions does not allow the path.
+        """
+        if self._is_within_root(path):
+            return self._inner.write_file(
+                path,
+                content,
+                encoding,
+                replace_unencodable,
+                verify,
+                ensure_trailing_newline,
+                strip_trailing_whitespace,
+            )
+        self._check_external_allowed(path)
+        self._inner.check_writable(path)
+        assert self._python_backend is not None
+        return self._python_backend.write_file(
+            path,
+            content,
+            encoding,
+            "replace" if replace_unencodable else "strict",
+            verify,
+            ensure_trailing_newline,
+            strip_trailing_whitespace,
+        )
+
+    def write_new(self, path: str, content: str) -> bool:
+        """Create a file only if it does not already exist.
+
+        The safe way to scaffold a file: an existing file is never
+        overwritten, even if another process creates it concurrently.
+
+        Args:
+            path: File path.
+            content: Content to write.
+
+        Returns:
+            True if the file was created, False if it already existed.
+        """
+        if self._is_within_root(path):
+            return self._inner.write_new(path, content)
+        self._check_external_allowed(path)
+        self._inner.check_writable(path)
+        assert self._python_backend is not None
+        return self._python_backend.write_new(path, content)
+
+    def write_transaction(self, files: dict[str, str]) -> bool:
+        """Write several files with all-or-nothing semantics.
+
+        Every file is staged in a temp file beside its target and fsynced
+        before any target is touched; if staging fails the temps are removed
+        and nothing changes. The temps are then renamed into place one by one.
+
+        Note:
+            The rename phase is not atomic across files: a crash or a failing
+            rename partway through leaves the earlier files replaced and the
+            rest unchanged. The window is short, since only renames remain,
+            but it is not zero. Missing parent directories created while
+            staging are kept on failure.
+
+        Args:
+            files: Dict mapping each file path to its new content.
+
+        Returns:
+            True if every file was written.
+
+        Raises:
+            IOError: If staging fails (nothing changed) or a rename fails (the
+                message says how many files were already replaced).
+        """
+        for path in files:
+            self._check_external_allowed(path)
+        return self._inner.write_transaction(files)
+
+    def write_file_fast(self, path: str, content: str) -> bool:
+        """Write content to file without atomicity guarantee (fast mode).
+
+        Much faster than write_file() but does not guarantee data integrity
+        on crash. Use for temporary files or when speed is critical.
+
+        Args:
+            path: File path.
+            content: Content to write.
+
+        Returns:
+            True if successful.
+        """
+        if self._is_within_root(path):
+            return self._inner.write_file_fast(path, content)
+        self._check_external_allowed(path)
+        self._inner.check_writable(path)
+        assert self._python_backend is not None
+        return self._python_backend.write_file_fast(path, content)
+
+    def apply_patch(
+        self, path: str, unified_diff: str, strict: bool = True, fuzzy: bool = False
+    ) -> bool:
+        """Apply a unified diff to a file, without shelling out to patch.
+
+        Each hunk's context and removed lines are checked against the file at
+        the line its @@ header names, the changes are applied in memory and
+        the result is written atomically. File headers (---/+++) are optional.
+
+        Args:
+            path: File path.
+            unified_diff: Single-file patch text, e.g. from diff -u or git diff.
+            strict: If true, raise if any hunk does not match and write nothing.
+                If false, skip hunks that do not match and apply the rest.
+            fuzzy: Also accept a hunk whose context moved to a nearby line or
+                differs in trailing whitespace, for slightly drifted patches.
+
+        Returns:
+            True if every hunk applied, False if some were skipped.
+
+        Raises:
+            ValueError: If the diff is malformed, touches several files, or
+                (when strict) a hunk does not match.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.apply_patch(path, unified_diff, strict, fuzzy)
+
+    def follow(
+        self, path: str, callback: Callable[[str], object], from_end: bool = True
+    ) -> FollowHandle:
+        """Stream lines appended to a file, like tail -f.
+
+        The file is polled every 100ms and each new complete line is passed to
+        callback. Truncation and log rotation restart following from the start
+        of the file. Useful for watching a log of a process the agent started.
+
+        Args:
+            path: File path.
+            callback: Called with each new line (without its line ending). It
+                runs on a background thread; if it raises, following stops.
+            from_end: Only report lines appended from now on. When False, the
+                existing content is reported first.
+
+        Returns:
+            FollowHandle; call stop() on it (or use it in a with block) to stop
+            following. Closing the FileSystem stops it too.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.follow(path, callback, from_end)
+
+    def diff_content(self, path: str, new_content: str) -> list[DiffHunk]:
+        """Compare a file with proposed content, line by line.
+
+        Nothing is written; use this to review a whole-file rewrite hunk by
+        hunk before calling write_file().
+
+        Args:
+            path: File path.
+            new_content: Proposed full content of the file.
+
+        Returns:
+            List of DiffHunk objects in file order (empty if unchanged).
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.diff_content(path, new_content)
+
+    def write_staged(self, target_path: str, content: str) -> str:
+        """Write content to a staged file beside target_path, without touching it.
+
+        Together with promote() this is a two-phase commit for one file:
+        stage the content, then promote(staged, target_path) to swap it in
+        atomically, or delete the staged file to abandon the edit.
+
+        Staged files are named ".<target name>.<random>.agstaged" in the
+        target's directory. They are not cleaned up automatically; orphans
+        left by a crash can be found with glob("**/*.agstaged").
+
+        Args:
+            target_path: Path the content is meant to replace.
+            content: Content to write.
+
+        Returns:
+            Path of the staged file, relative to root when inside it.
+        """
+        self._check_external_allowed(target_path)
+        return self._inner.write_staged(target_path, content)
+
+    def promote(self, temp_path: str, final_path: str) -> bool:
+        """Atomically move a staged file into its final location.
+
+        Performs a single rename, replacing any existing file at final_path,
+        and updates the index (temp removed, final added or refreshed).
+
+        Args:
+            temp_path: Staged file to promote.
+            final_path: Destination path.
+
+        Returns:
+            True if successful.
+
+        Raises:
+            IOError: If the paths are on different filesystems, where a rename
+                is not atomic. Copy with read_file() + write_file() instead.
+        """
+        self._check_external_allowed(temp_path)
+        self._check_external_allowed(final_path)
+        return self._inner.promote(temp_path, final_path)
+
+    def snapshot_file(self, path: str) -> int:
+        """Save a file's current content in memory for a later restore_file().
+
+        A single-level undo for speculative edits, with no backup file on disk.
+        The 32 most recent snapshots are kept, up to 64MB in total; older ones
+        are evicted and their tokens stop working.
+
+        Args:
+            path: File path.
+
+        Returns:
+            Token to pass to restore_file().
+
+        Raises:
+            IOError: If the file is larger than the 64MB budget.
+
+        Example:
+            >>> token = fs.snapshot_file("src/app.py")
+            >>> fs.edit_replace("src/app.py", "retries = 3", "retries = 5")
+            >>> if not tests_pass():
+            ...     fs.restore_file(token)
+        """
+        self._check_external_allowed(path)
+        return self._inner.snapshot_file(path)
+
+    def restore_file(self, token: int) -> str:
+        """Atomically rewrite a file to the content saved by snapshot_file().
+
+        Each snapshot restores once; if the write fails it is kept for a retry.
+
+        Args:
+            token: Token returned by snapshot_file().
+
+        Returns:
+            The restored path, relative to root.
+
+        Raises:
+            ValueError: If the token was already restored or has been evicted.
+            PermissionError: If writable_extensions does not allow the path.
+        """
+        return self._inner.restore_file(token)
+
+    def edit_replace(
+        self,
+        path: str,
+        old_text: str,
+        new_text: str,
+        strict: bool = True,
+        encoding: str = "utf-8",
+        replace_unencodable: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+        max_replacements: int | None = None,
+        line_start: int | None = None,
+        line_end: int | None = None,
+    ) -> int:
+        """Replace text in file.
+
+        Args:
+            path: File path.
+            old_text: Text to find.
+            new_text: Replacement text.
+            strict: If true, error if old_text is not unique or not found.
+            encoding: Encoding the file is read in and written back in, so
+                non-UTF8 files round-trip unchanged.
+            replace_unencodable: Write "?" for characters the encoding cannot
+                represent instead of raising.
+            ensure_trailing_newline: Make the edited file end with a line ending.
+            strip_trailing_whitespace: Trim trailing spaces and tabs from every
+                line of the edited file, not just the replaced text.
+            max_replacements: In non-strict mode, replace only the first N
+                occurrences and leave the rest (None = all).
+            line_start: First 1-indexed line of the region to edit (None = 1).
+            line_end: Last line of the region, inclusive (None = end of file).
+                Both counting and replacing only look inside the region, so in
+                strict mode old_text must be unique there and must appear there.
+
+        Returns:
+            Number of occurrences replaced (0 if old_text was not found and
+            strict is false). Still truthy exactly when something changed.
+        """
+        if self._is_within_root(path):
+            return self._inner.edit_replace(
+                path,
+                old_text,
+                new_text,
+                strict,
+                encoding,
+                replace_unencodable,
+                ensure_trailing_newline,
+                strip_trailing_whitespace,
+                max_replacements,
+                line_start,
+                line_end,
+            )
+        self._check_external_allowed(path)
+        self._inner.check_writable(path)
+        assert self._python_backend is not None
+        return self._python_backend.edit_replace(
+            path,
+            old_text,
+            new_text,
+            strict,
+            encoding,
+            "replace" if replace_unencodable else "strict",
+            ensure_trailing_newline,
+            strip_trailing_whitespace,
+            max_replacements,
+            line_start,
+            line_end,
+        )
+
+    def grep(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        include_metadata: bool = False,
+        options: SearchOptions | None = None,
+        canonicalize: bool = False,
+        capture_group: int | None = None,
+        languages: list[str] | None = None,
+    ) -> list[SearchResult]:
+        """Search files for content matching query.
+
+        Args:
+            query: Search pattern (regex).
+            glob_pattern: File pattern to search in. Can be absolute path for external dirs.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            first_per_file_with_count: Return only the first match per file,
+                with file_match_count set to the file's total matches.
+            include_hidden: Search hidden files (dotfiles).
+            include_ignored: Search files excluded by .gitignore/.ignore rules.
+                This exposes secrets files, build output and .git internals,
+                so only enable it when that scope is intended.
+            max_files_scanned: Stop after searching this many files, bounding latency
+                on huge trees independently of max_results (None = no limit).
+            order_by: SearchOrder for the results, e.g. SearchOrder.RELEVANCE to put
+                matches in small files whose path matches the query first. Every
+                match is ranked before truncating to max_results, so an ordered
+                search reads the whole tree (bounded by max_files_scanned) rather
+                than stopping at the first max_results, holding only the best
+                max_results in memory (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files. SKIP (default) ignores
+                them, TEXT decodes them lossily, STRINGS searches printable runs
+                like the `strings` tool. Line numbers in binary content are
+                approximate (STRINGS numbers the extracted runs).
+            normalize_whitespace: Collapse runs of whitespace in each line to a
+                single space before matching, so code formatted inconsistently
+                still matches (e.g. r"foo\\( bar \\)" finds "foo(  bar )").
+                Only the file content is normalized; the regex is used as
+                given. Returned lines keep their original spacing.
+            head_bytes: Search only the first N bytes of each file, e.g. to scan
+                for license headers, shebangs or front matter. A line cut by the
+                region edge is skipped.
+            tail_bytes: Search only the last N bytes of each file. Line numbers
+                stay relative to the whole file. Mutually exclusive with
+                head_bytes.
+            symbol_context: Set each result's symbol to the enclosing function,
+                class or method name, e.g. "handle_request". Found by scanning
+                upward for a declaration with per-language regexes, so it is
+                best-effort; files in unrecognised languages get None.
+            include_metadata: Set each result's metadata to the file's indexed
+                FileMetadata (size, mtime, is_binary), saving a get_metadata()
+                call per file when rendering results. Looked up once per file;
+                None for files the index has not picked up yet.
+            options: A fully configured SearchOptions, for settings with no
+                argument here such as context_lines, max_file_size,
+                per_file_timeout_ms or encoding. When given it takes precedence:
+                every other argument except query, glob_pattern and canonicalize
+                is ignored.
+            canonicalize: Resolve symlinks in each result's file path, with the
+                same rules and cost as glob(canonicalize=True).
+            capture_group: Report this capture group of the query as each
+                result's content instead of the whole line, with its columns in
+                capture_span, so r'version = "(.*)"' with capture_group=1
+                extracts the version string. Lines where the group did not
+                participate are skipped.
+            languages: Search only files whose detected language is one of these
+                names, compared case-insensitively, e.g. ["python", "rust"]
+                instead of a "*.{py,pyi}" glob. Detection is by file name, the
+                same as code_stats(), and needs nothing enabled at construction;
+                files of an unrecognised kind, such as extensionless shebang
+                scripts, never match. Applied on top of glob_pattern and the
+                ignore rules. Not supported for paths outside root.
+
+        Returns:
+            List of SearchResult objects. Each result's matched_glob is the
+            glob_pattern its file was selected by, for routing results by
+            the kind of file they came from.
 
         Note:
             If glob_pattern starts with "/" and points outside root,
@@ -423,16 +1699,464 @@ class FileSystem:
             if not self._is_within_root(base_path):
                 self._check_external_allowed(base_path)
                 assert self._python_backend is not None
+                if options is not None:
+                    case_sensitive = options.case_sensitive
+                    max_results = options.max_results
+                    capture_group = options.capture_group
+                    languages = options.languages
+                if languages is not None:
+                    raise ValueError("languages is not supported for paths outside root")
                 results = self._python_backend.grep(
                     query, base_path, glob_pattern[len(base_path):].lstrip("/"),
                     case_sensitive, max_results
                 )
+                if capture_group is not None:
+                    results = _capture_results(query, case_sensitive, capture_group, results)
                 # Convert dict results to SearchResult-like objects
                 return [
-                    _create_search_result(r["file"], r["line_number"], r["content"])
+                    _create_search_result(
+                        os.path.realpath(r["file"]) if canonicalize else r["file"],
+                        r["line_number"],
+                        r["content"],
+                        matched_glob=glob_pattern,
+                        capture_span=r.get("capture_span"),
+                    )
                     for r in results
                 ]
-        return self._inner.grep(query, glob_pattern, case_sensitive, max_results)
+        return self._inner.grep(
+            query,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            first_per_file_with_count=first_per_file_with_count,
+            include_hidden=include_hidden,
+            include_ignored=include_ignored,
+            max_files_scanned=max_files_scanned,
+            order_by=order_by,
+            binary=binary,
+            normalize_whitespace=normalize_whitespace,
+            head_bytes=head_bytes,
+            tail_bytes=tail_bytes,
+            symbol_context=symbol_context,
+            include_metadata=include_metadata,
+            options=options,
+            canonicalize=canonicalize,
+            capture_group=capture_group,
+            languages=languages,
+        )
+
+    def grep_iter(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        options: SearchOptions | None = None,
+    ) -> SearchIter:
+        """Search files for content, yielding results as they are found.
+
+        Returns immediately while the search runs in the background, so results
+        can be processed before it completes. Leaving the loop early and
+        dropping the iterator (or calling its cancel()) stops the search.
+
+        Args:
+            query: Search pattern (regex).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            options: A fully configured SearchOptions; when given, every other
+                argument except query and glob_pattern is ignored.
+
+        Returns:
+            SearchIter yielding SearchResult objects in the order they are
+            found (order_by does not apply).
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_iter(
+            query, glob_pattern, case_sensitive, max_results, options=options
+        )
+
+    def grep_summary(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        include_metadata: bool = False,
+        options: SearchOptions | None = None,
+        capture_group: int | None = None,
+        languages: list[str] | None = None,
+    ) -> SearchSummary:
+        """Search files for content, returning results with search statistics.
+
+        Takes the same arguments as grep(), but also reports how many files were
+        searched, whether the search stopped early (truncated), and which files
+        were skipped because reading them failed or timed out (skipped). Set
+        SearchOptions.per_file_timeout_ms so a file on a stalled network mount
+        is skipped instead of hanging the search.
+
+        Returns:
+            SearchSummary object.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_summary(
+            query,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            first_per_file_with_count=first_per_file_with_count,
+            include_hidden=include_hidden,
+            include_ignored=include_ignored,
+            max_files_scanned=max_files_scanned,
+            order_by=order_by,
+            binary=binary,
+            normalize_whitespace=normalize_whitespace,
+            head_bytes=head_bytes,
+            tail_bytes=tail_bytes,
+            symbol_context=symbol_context,
+            include_metadata=include_metadata,
+            options=options,
+            capture_group=capture_group,
+            languages=languages,
+        )
+
+    def grep_text(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        context_lines: int = 0,
+    ) -> str:
+        """Search files and return ripgrep-style text.
+
+        Matches are formatted as "path:line:content". With context_lines > 0,
+        context lines are formatted as "path-line-content" and "--" separates
+        non-adjacent groups. Ready to print or paste into a prompt without
+        building a Python object per result.
+
+        Args:
+            query: Search pattern (regex supported).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of matches.
+            context_lines: Lines of context around each match.
+
+        Returns:
+            Formatted results, sorted by path.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_text(
+            query, glob_pattern, case_sensitive, max_results, context_lines
+        )
+
+    def grep_columnar(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        options: SearchOptions | None = None,
+    ) -> tuple[list[str], list[int], list[str]]:
+        """Search files and return the results as parallel lists.
+
+        For large result sets, three lists cross from Rust far more cheaply
+        than one SearchResult object per match. Entry i of each list is the
+        same match, so use zip(files, line_numbers, contents) to iterate.
+        Context lines, symbols and metadata are not included.
+
+        Args:
+            query: Search pattern (regex).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            options: A fully configured SearchOptions; when given, every other
+                argument except query and glob_pattern is ignored.
+
+        Returns:
+            Tuple of (files, line_numbers, contents).
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_columnar(
+            query, glob_pattern, case_sensitive, max_results, options
+        )
+
+    def grep_locations(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+    ) -> list[Location]:
+        """Search files and return each match as a source range.
+
+        Maps directly onto editor location lists (quickfix, diagnostics): a
+        line matching twice yields two locations. Lines and columns are
+        1-indexed, columns count characters and end_col is exclusive.
+
+        Args:
+            query: Search pattern (regex).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of matching lines; the number of
+                locations can be higher.
+
+        Returns:
+            List of Location objects, sorted by path.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_locations(query, glob_pattern, case_sensitive, max_results)
+
+    def grep_count_by_dir(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+    ) -> dict[str, int]:
+        """Count matching lines per directory.
+
+        Gives a heatmap of where matches concentrate, e.g. to find which module
+        a concept lives in, without going through every hit. All candidate
+        files are searched; there is no result limit.
+
+        Args:
+            query: Search pattern (regex supported).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+
+        Returns:
+            Dict mapping directories relative to root ("." for root itself) to
+            their number of matching lines; directories without matches are
+            omitted.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_count_by_dir(
+            query, glob_pattern, case_sensitive, include_hidden, include_ignored
+        )
+
+    def any_match(
+        self, query: str, glob_pattern: str = "**/*", case_sensitive: bool = False
+    ) -> bool:
+        """Check whether any file contains a match.
+
+        Cheaper than grep(max_results=1): no results are built, and the first
+        match cancels the files still being searched in parallel.
+
+        Args:
+            query: Search pattern (regex supported).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+
+        Returns:
+            True as soon as one match is found, False if no file matches.
+        """
+        if glob_pattern.startswith("/"):
+            parts = glob_pattern.split("/")
+            base_path = "/" + parts[1] if len(parts) > 1 else "/"
+            if not self._is_within_root(base_path):
+                self._check_external_allowed(base_path)
+                assert self._python_backend is not None
+                results = self._python_backend.grep(
+                    query, base_path, glob_pattern[len(base_path):].lstrip("/"),
+                    case_sensitive, 1
+                )
+                return bool(results)
+        return self._inner.any_match(query, glob_pattern, case_sensitive)
+
+    def find(
+        self, path_glob: str, content_query: str, options: SearchOptions | None = None
+    ) -> list[str]:
+        """Find files matching both a path glob and a content query.
+
+        Replaces a glob() followed by a grep() and an intersection: candidates
+        come from the index and each is searched only until its first match.
+
+        Args:
+            path_glob: Glob pattern for candidate files, e.g. "src/**/*.py".
+            content_query: Search pattern (regex) a file must contain.
+            options: SearchOptions for the content search; max_results caps the
+                number of files returned (default: 1000).
+
+        Returns:
+            Sorted, deduplicated file paths relative to root.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.find(path_glob, content_query, options)
+
+    def grep_multi(
+        self,
+        patterns: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search files for several independent patterns in one pass.
+
+        Much cheaper than one grep() per pattern: every file is read once and
+        files matching none of the patterns are rejected with a single check.
+
+        Args:
+            patterns: Regex patterns to search for.
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum total number of results.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+
+        Returns:
+            List of SearchResult objects. Each result's pattern_index is the
+            position in patterns of the pattern that matched; a line matching
+            several patterns yields one result per pattern.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_multi(
+            patterns,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            include_hidden=include_hidden,
+            include_ignored=include_ignored,
+            max_files_scanned=max_files_scanned,
+            order_by=order_by,
+            binary=binary,
+        )
+
+    def grep_all_keywords(
+        self,
+        keywords: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search for lines containing every one of several keywords.
+
+        Keywords are plain substrings, not regexes, which is easier to get right
+        than a hand-built regex: grep_all_keywords(["error", "timeout"]) finds
+        lines mentioning both, in either order.
+
+        Args:
+            keywords: Substrings that must all appear on a line.
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+
+        Returns:
+            List of SearchResult objects.
+
+        Raises:
+            ValueError: If keywords is empty.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_all_keywords(
+            keywords,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            include_hidden=include_hidden,
+            include_ignored=include_ignored,
+            max_files_scanned=max_files_scanned,
+            order_by=order_by,
+            binary=binary,
+        )
+
+    def grep_any_keyword(
+        self,
+        keywords: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search for lines containing at least one of several keywords.
+
+        The OR counterpart of grep_all_keywords(). Keywords are plain substrings,
+        and a line containing several of them is reported once.
+
+        Args:
+            keywords: Substrings of which at least one must appear on a line.
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+
+        Returns:
+            List of SearchResult objects.
+
+        Raises:
+            ValueError: If keywords is empty.
+
+        Note:
+            Only paths within root are supported; there is no Python fallback.
+        """
+        return self._inner.grep_any_keyword(
+            keywords,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            include_hidden=include_hidden,
+            include_ignored=include_ignored,
+            max_files_scanned=max_files_scanned,
+            order_by=order_by,
+            binary=binary,
+        )
 
     def get_metadata(self, path: str) -> FileMetadata | dict:
         """Get file metadata.
@@ -449,17 +2173,241 @@ class FileSystem:
         assert self._python_backend is not None
         return self._python_backend.get_metadata(path)
 
-    def refresh(self) -> None:
-        """Force refresh the file index."""
-        self._inner.refresh()
+    def refresh(self) -> bool:
+        """Force refresh the file index.
+
+        The tree is re-walked into a separate index that replaces the current
+        one when complete, so queries keep answering from the previous entries
+        meanwhile.
+
+        Returns:
+            True if the new index was swapped in, False if the refresh was
+            cancelled or another build was already running.
+        """
+        return self._inner.refresh()
+
+    def cancel_refresh(self) -> bool:
+        """Cancel a refresh in progress, keeping the current entries.
+
+        The initial build is not cancelled, since the index would be left not
+        ready.
+
+        Returns:
+            True if a refresh was running, False if none was or the initial
+            build is still in progress.
+        """
+        return self._inner.cancel_refresh()
+
+    def refresh_paths(self, paths: list[str]) -> int:
+        """Refresh specific paths in the index without a full rebuild.
+
+        Each path is added, updated or removed depending on whether it exists,
+        so changes made by an external tool show up immediately.
+
+        Args:
+            paths: File or directory paths, relative to root or absolute.
+
+        Returns:
+            Number of the given paths present in the index afterwards.
+        """
+        return self._inner.refresh_paths(paths)
 
     def is_ready(self) -> bool:
         """Check if the index is ready."""
         return self._inner.is_ready()
 
-    def is_watching(self) -> bool:
-        """Check if file watching is active."""
-        return self._inner.is_watching()
+    def ping(self) -> str:
+        """Confirm the extension is loaded and this object is alive.
+
+        For health checks and warmup timing: does no I/O and takes no locks, so
+        polling it never waits on an index build. Use is_ready() for index state.
+
+        Returns:
+            "agent-gear <version> <root>", e.g. "agent-gear 0.1.0 /work/repo".
+        """
+        return self._inner.ping()
+
+    def is_truncated(self) -> bool:
+        """Check whether max_index_entries left paths out of the index.
+
+        When True, list(), glob() and grep() only see part of the tree, so
+        read or stat paths directly rather than relying on the index.
+        """
+        return self._inner.is_truncated()
+
+    def index_fingerprint(self) -> str:
+        """Fingerprint the indexed tree, for keying cached derived data.
+
+        A BLAKE3 hash over every indexed file's path, size and mtime in path
+        order: two processes indexing the same unchanged tree get the same
+        value, and adding, removing or modifying a file changes it. Contents
+        are not read, so an edit that keeps both size and mtime goes unnoticed.
+
+        Returns:
+            64 hex digits.
+
+        Raises:
+            RuntimeError: If the index is not ready yet.
+
+        Example:
+            >>> key = fs.index_fingerprint()
+            >>> if cache.get("symbols_key") != key:
+            ...     cache["symbols"] = rebuild_symbols()
+            ...     cache["symbols_key"] = key
+        """
+        return self._inner.index_fingerprint()
+
+    def memory_usage(self) -> int:
+        """Estimate the memory used by the file index, in bytes.
+
+        Counts path lengths, per-entry struct sizes and a fixed per-slot map
+        overhead for the index entries, file list, directory children and
+        pattern caches. Allocator padding and compiled matcher internals are
+        not included, so the real footprint is somewhat higher. Use it to
+        notice when the index is getting large, not for exact accounting.
+
+        Returns:
+            Estimated size in bytes.
+        """
+        return self._inner.memory_usage()
+
+    def is_case_insensitive(self) -> bool:
+        """Check whether paths are matched case-insensitively."""
+        return self._inner.is_case_insensitive()
+
+    def check_writable(self, path: str) -> None:
+        """Check a path against the writable_extensions policy, without I/O.
+
+        Every write method runs this check itself; call it to vet a path up
+        front. Always passes when writable_extensions is not set.
+
+        Raises:
+            PermissionError: If the policy does not allow writing to the path.
+        """
+        self._inner.check_writable(path)
+
+    def cached_globs(self) -> list[str]:
+        """List the glob patterns currently held in the compiled-pattern cache.
+
+        Useful for diagnosing cache thrashing: a hot pattern that keeps
+        disappearing means the cache is too small for the workload.
+
+        Returns:
+            Sorted list of cached pattern strings.
+        """
+        return self._inner.cached_globs()
+
+    def clear_glob_cache(self) -> None:
+        """Empty the compiled glob cache to reclaim memory."""
+        self._inner.clear_glob_cache()
+
+    def save_index(self, path: str, compression_level: int | None = None) -> None:
+        """Save the file index to a snapshot file.
+
+        Args:
+            path: Snapshot file path.
+            compression_level: zstd compression level (1-22), or None to store
+                the snapshot uncompressed. Level 3 is a good default for large trees.
+        """
+        self._inner.save_index(path, compression_level)
+
+    def load_index(self, path: str) -> None:
+        """Replace the file index with a saved snapshot.
+
+        Compressed and uncompressed snapshots are detected automatically.
+
+        Args:
+            path: Snapshot file path.
+        """
+        self._inner.load_index(path)
+
+    def is_watching(self) -> bool:
+        """Check if file watching is active."""
+        return self._inner.is_watching()
+
+    def watcher_healthy(self) -> bool:
+        """Check whether the watcher is still delivering events.
+
+        Returns False if there is no watcher, or if its backend died (for
+        example because the root directory was deleted or unmounted). The
+        index is then frozen; recreate the FileSystem to resume watching.
+
+        A native backend that starts but never receives events, as on some
+        network and bind mounts, still reports healthy; see watch_mode in the
+        constructor for how to detect that and switch to polling.
+        """
+        return self._inner.watcher_healthy()
+
+    def watch_mode(self) -> WatchMode | None:
+        """Get the watcher backend in use.
+
+        NATIVE or POLL, so a FileSystem created with WatchMode.AUTO shows
+        whether it fell back to polling. None if nothing is being watched.
+        """
+        return self._inner.watch_mode()
+
+    def watch_subpath(self, subpath: str, debounce_ms: int = 100) -> WatchHandle:
+        """Watch a subtree separately, with its own debounce and event queue.
+
+        Gives fast, focused notifications on one hot directory without
+        lowering the debounce for the whole tree. The extra watcher uses the
+        same backend as the main one; its events only go to the handle, and
+        the index is still kept current by the main watcher.
+
+        Args:
+            subpath: Directory to watch (relative to root or absolute).
+            debounce_ms: How long a path must be quiet before its change is
+                reported (default 100, 0 = raw mode).
+
+        Returns:
+            WatchHandle; call events() on it to take ready WatchEvent objects.
+            The watch stops on handle.stop(), when the handle is garbage
+            collected or a with block exits, or when the FileSystem is closed.
+
+        Raises:
+            ValueError: If subpath is not a directory or the FileSystem is closed.
+        """
+        return self._inner.watch_subpath(subpath, debounce_ms)
+
+    def pending_paths(self) -> list[str]:
+        """List paths with changes the index has not applied yet.
+
+        Events wait out a short debounce window before reaching the index, so
+        metadata for these paths may be stale. Use it to decide whether to wait
+        for the index to settle before trusting get_metadata() or list() for a
+        specific file. Peeking does not flush the events.
+
+        Returns:
+            Sorted list of paths relative to root; empty without a watcher. A
+            rename lists both the old and the new path.
+        """
+        return self._inner.pending_paths()
+
+    def pending_changes(self) -> int:
+        """Count applied changes that poll_changes() has not taken yet.
+
+        Every counted change is already in the index, and counting does not
+        consume them. Changes still in the debounce window are not included;
+        see pending_paths() for those.
+
+        Returns:
+            Number of waiting changes; 0 without a watcher.
+        """
+        return self._inner.pending_changes()
+
+    def poll_changes(self) -> list[WatchEvent]:
+        """Take the changes the watcher has applied to the index.
+
+        The background watcher is the only consumer of file events: it updates
+        the index first and then logs the change, so after poll_changes()
+        returns an event the index already reflects it. The log keeps the most
+        recent 10000 changes.
+
+        Returns:
+            List of WatchEvent objects, oldest first, with paths relative to
+            root; empty without a watcher.
+        """
+        return self._inner.poll_changes()
 
     def close(self) -> None:
         """Close the filesystem and release resources."""
@@ -482,6 +2430,9 @@ class AsyncFileSystem:
         root: Root directory path to operate on.
         auto_watch: Whether to automatically watch for file changes (default: True).
         allow_external: Whether to allow operations on paths outside root (default: False).
+        index_snapshot: Optional snapshot file to load instead of walking the tree.
+        case_insensitive: Treat paths differing only in case as the same file
+            (default: None = detect).
 
     Example:
         >>> async with AsyncFileSystem("/path/to/project") as fs:
@@ -500,6 +2451,24 @@ class AsyncFileSystem:
         root: str,
         auto_watch: bool = True,
         allow_external: bool = False,
+        index_snapshot: str | None = None,
+        case_insensitive: bool | None = None,
+        auto_refresh_secs: int | None = None,
+        include_extensions: list[str] | None = None,
+        watch_recursive: bool = True,
+        cache_reads: bool = False,
+        skip_binary_detection_above: int | None = None,
+        watch_debounce_ms: int = 100,
+        writable_extensions: list[str] | None = None,
+        allow_extensionless_writes: bool = False,
+        max_read_bytes: int | None = 1024 * 1024 * 1024,
+        watch_mode: WatchMode = WatchMode.AUTO,
+        watch_poll_interval_ms: int = 1000,
+        max_index_entries: int | None = None,
+        use_default_ignores: bool = False,
+        ignored_dirs: list[str] | None = None,
+        cache_line_offsets: bool = False,
+        custom_ignore_files: list[str] | None = None,
     ) -> None:
         """Initialize the AsyncFileSystem.
 
@@ -507,8 +2476,102 @@ class AsyncFileSystem:
             root: Root directory path.
             auto_watch: Whether to automatically watch for file changes.
             allow_external: Whether to allow operations on paths outside root.
+            index_snapshot: Optional snapshot file to load instead of walking the tree.
+            case_insensitive: Treat paths differing only in case as the same file
+                (None = detect from the filesystem).
+            auto_refresh_secs: Reconcile the index with the disk every N seconds
+                to catch missed watcher events (None = never). Each pass walks
+                the whole tree, so keep the interval well above the walk time.
+            include_extensions: Only index and watch files with these
+                extensions, e.g. ["py", "rs"] (None = all files). Directories
+                are always traversed.
+            watch_recursive: Watch the whole tree (default). When False only root
+                itself is watched; changes inside subdirectories are not detected
+                and the index will not track them until refresh().
+            cache_reads: Keep recently read files in memory (up to 256 files and
+                32 MiB) and serve read_file() from there while a file's mtime and
+                size are unchanged. Helps agents that re-read the same files.
+                Only UTF-8 reads are cached; other encodings read from disk.
+            skip_binary_detection_above: Assume files larger than this many bytes
+                are binary instead of reading their first bytes while indexing
+                (None = always read). Cuts indexing I/O on repos full of large
+                media files; such files are then skipped by default searches.
+            watch_debounce_ms: How long a path must be quiet before its change
+                is applied to the index (default 100). 0 selects raw mode, where
+                every event is applied immediately and unmerged for the lowest
+                latency (e.g. live-reload loops). Raw mode is noisy: one save can
+                arrive as several events, and a file created and deleted in quick
+                succession is added and removed rather than skipped.
+            writable_extensions: Only allow writes to files with these
+                extensions, e.g. ["py", "md"] (None = no restriction). Write
+                methods raise PermissionError for any other target before
+                touching the disk, so a semi-trusted agent cannot create
+                scripts or dotfiles. A guardrail on top of the root check, not
+                a sandbox.
+            allow_extensionless_writes: With writable_extensions set, also allow
+                targets without an extension (Makefile, dotfiles such as
+                .bashrc, directory names). Refused by default.
+            max_read_bytes: Refuse to load files larger than this many bytes in
+                read_file(), read_batch() and open(), raising IOError instead
+                (default 1 GiB, None = no limit). The size is checked before reading, so
+                an accidentally opened huge artifact cannot exhaust memory.
+            watch_mode: Watcher backend. AUTO (default) uses native notifications
+                and polls only if they fail to start; NATIVE never polls; POLL
+                rescans the tree every watch_poll_interval_ms, keeping the index
+                correct at the cost of CPU. Use POLL on network shares, FUSE and
+                Docker bind mounts, where native watching can start yet deliver
+                nothing. watcher_healthy() cannot detect that; instead write a
+                probe file and check that get_metadata() sees it within a second.
+            watch_poll_interval_ms: Interval between scans when polling (default
+                1000). Each scan stats every file in the watched tree.
+            max_index_entries: Stop indexing once this many files and directories
+                are held (None = no limit), so a gigantic tree yields a partial
+                index instead of exhausting memory. The index still becomes
+                ready; check is_truncated() and fall back to direct reads for
+                paths it lacks.
+            use_default_ignores: Also skip the vendor and build directories in
+                DEFAULT_IGNORED_DIRS (node_modules, target, .git, __pycache__,
+                dist, build, ...) wherever they appear, on top of .gitignore.
+                Off by default.
+            ignored_dirs: Further directory names to skip the same way (None =
+                none). To replace the defaults rather than extend them, leave
+                use_default_ignores off and pass an edited copy of
+                DEFAULT_IGNORED_DIRS. Searches with include_ignored=True see
+                these directories again.
+            cache_line_offsets: Remember where each line starts in files read
+                with read_lines(), read_numbered() or read_around() (up to 64
+                files), so repeated range reads of an unchanged file seek to the
+                first requested line instead of scanning from the top.
+            custom_ignore_files: Further ignore file names to honour in every
+                directory, e.g. [".agentignore"], to manage what agents see
+                apart from .gitignore (None = none). They use gitignore syntax
+                and take precedence over .gitignore and .ignore, so a "!"
+                pattern re-includes a gitignored path; later names beat earlier
+                ones. Searches with include_ignored=True skip them.
         """
-        self._sync = FileSystem(root, auto_watch, allow_external)
+        self._sync = FileSystem(
+            root,
+            auto_watch,
+            allow_external,
+            index_snapshot,
+            case_insensitive,
+            auto_refresh_secs,
+            include_extensions,
+            watch_recursive,
+            cache_reads,
+            skip_binary_detection_above,
+            watch_debounce_ms,
+            writable_extensions,
+            allow_extensionless_writes,
+            max_read_bytes,
+            watch_mode,
+            watch_poll_interval_ms,
+            max_index_entries,
+            use_default_ignores,
+            ignored_dirs,
+            cache_line_offsets,
+            custom_ignore_files,
+        )
 
     async def wait_ready(self, timeout: float = 30.0) -> bool:
         """Wait for the index to be ready (async).
@@ -523,32 +2586,167 @@ class AsyncFileSystem:
 
         return await asyncio.to_thread(self._sync.wait_ready, timeout)
 
-    async def list(self, pattern: str = "**/*", only_files: bool = True) -> list[str]:
+    async def list(
+        self, pattern: str = "**/*", only_files: bool = True, canonicalize: bool = False
+    ) -> list[str]:
         """List files matching the given pattern from memory index (async)."""
         import asyncio
 
-        return await asyncio.to_thread(self._sync.list, pattern, only_files)
+        return await asyncio.to_thread(self._sync.list, pattern, only_files, canonicalize)
 
-    async def glob(self, pattern: str) -> list[str]:
+    async def glob(self, pattern: str, canonicalize: bool = False) -> list[str]:
         """Match files using glob pattern (async)."""
         import asyncio
 
-        return await asyncio.to_thread(self._sync.glob, pattern)
+        return await asyncio.to_thread(self._sync.glob, pattern, canonicalize)
+
+    async def glob_grouped(self, patterns: list[str]) -> dict[str, list[str]]:
+        """Match files against several globs, grouped by pattern (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.glob_grouped, patterns)
+
+    async def expand_specs(self, specs: list[str]) -> list[str]:
+        """Resolve file specs mixing literal paths and globs (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.expand_specs, specs)
+
+    async def list_regex(self, path_regex: str, only_files: bool = True) -> list[str]:
+        """List files whose relative path matches a regex (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.list_regex, path_regex, only_files)
+
+    async def diff_paths(self, paths: list[str]) -> tuple[list[str], list[str]]:
+        """Compare a list of file paths against the indexed files (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.diff_paths, paths)
+
+    async def index_fingerprint(self) -> str:
+        """Fingerprint the indexed tree (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.index_fingerprint)
+
+    async def find_by_name(self, name: str, case_insensitive: bool = False) -> list[str]:
+        """Find paths whose filename exactly equals name (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.find_by_name, name, case_insensitive)
+
+    async def recent_files(
+        self, n: int = 20, pattern: str | None = None
+    ) -> list[tuple[str, float]]:
+        """List the most recently modified files (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.recent_files, n, pattern)
+
+    async def changed_under(self, subpath: str, timestamp: float) -> bool:
+        """Check whether anything under a subtree changed since a timestamp (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.changed_under, subpath, timestamp)
+
+    async def empty_files(self) -> list[str]:
+        """List zero-byte files (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.empty_files)
+
+    async def empty_dirs(self) -> list[str]:
+        """List directories with no entries (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.empty_dirs)
+
+    async def list_dirs_with_matches(self, pattern: str) -> list[str]:
+        """List the directories that directly contain matching files (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.list_dirs_with_matches, pattern)
+
+    async def code_stats(self) -> dict[str, tuple[int, int, int]]:
+        """Summarise the indexed code by language (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.code_stats)
+
+    async def count_files(self, pattern: str = "**/*") -> int:
+        """Count the indexed files matching a glob pattern (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.count_files, pattern)
 
-    async def read_file(self, path: str, encoding: str = "utf-8") -> str:
+    async def count_lines(self, pattern: str = "**/*") -> int:
+        """Total lines across the text files matching a glob pattern (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.count_lines, pattern)
+
+    async def count_lines_exact(
+        self, path: str, convention: LineEnding = LineEnding.LF
+    ) -> int:
+        """Count a file's lines under an explicit line ending convention (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.count_lines_exact, path, convention)
+
+    async def read_file(
+        self, path: str, encoding: str = "utf-8", max_read_bytes: int | None = None
+    ) -> str:
         """Read a single file (async)."""
         import asyncio
 
-        return await asyncio.to_thread(self._sync.read_file, path, encoding)
+        return await asyncio.to_thread(self._sync.read_file, path, encoding, max_read_bytes)
+
+    async def set_binary_extensions(self, always_binary: list[str], always_text: list[str]) -> int:
+        """Override binary detection by file extension (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.set_binary_extensions, always_binary, always_text)
+
+    async def file_id(self, path: str) -> str:
+        """Get a stable, content-based id for a file (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.file_id, path)
+
+    async def classify(self, path: str) -> str:
+        """Classify a file as text, binary, empty or missing (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.classify, path)
+
+    async def open(
+        self, path: str, max_read_bytes: int | None = None
+    ) -> tuple[str, FileMetadata | dict]:
+        """Read a file and its metadata in one call (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.open, path, max_read_bytes)
 
-    async def read_batch(self, paths: list[str]) -> dict[str, str]:
+    async def read_batch(
+        self,
+        paths: list[str],
+        max_file_size: int | None = None,
+        max_read_bytes: int | None = None,
+    ) -> dict[str, str]:
         """Read multiple files in parallel (async)."""
         import asyncio
 
-        return await asyncio.to_thread(self._sync.read_batch, paths)
+        return await asyncio.to_thread(
+            self._sync.read_batch, paths, max_file_size, max_read_bytes
+        )
 
     async def read_lines(
-        self, path: str, start_line: int = 0, count: int | None = None
+        self,
+        path: str,
+        start_line: int = 0,
+        count: int | None = None,
+        max_bytes: int | None = None,
     ) -> list[str]:
         """Read specific lines from a file (async).
 
@@ -556,13 +2754,58 @@ class AsyncFileSystem:
             path: File path.
             start_line: Starting line number (0-indexed).
             count: Number of lines to read (None = read to end).
+            max_bytes: Byte budget for the returned lines (None = no budget).
 
         Returns:
             List of line strings (without trailing newlines).
         """
         import asyncio
 
-        return await asyncio.to_thread(self._sync.read_lines, path, start_line, count)
+        return await asyncio.to_thread(
+            self._sync.read_lines, path, start_line, count, max_bytes
+        )
+
+    async def read_first_line(self, path: str) -> str | None:
+        """Read the first line of a file (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.read_first_line, path)
+
+    async def read_numbered(
+        self, path: str, start_line: int = 0, count: int | None = None
+    ) -> list[tuple[int, str]]:
+        """Read specific lines paired with their line numbers (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.read_numbered, path, start_line, count)
+
+    async def read_around(
+        self, path: str, center_line: int, radius: int = 10
+    ) -> list[tuple[int, str]]:
+        """Read the numbered lines around a given line (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.read_around, path, center_line, radius)
+
+    async def line_offsets(self, path: str) -> list[int]:
+        """Get the byte offset where each line of a file starts (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.line_offsets, path)
+
+    async def read_indexed(
+        self, path: str, max_read_bytes: int | None = None
+    ) -> tuple[str, list[int]]:
+        """Read a file together with the character offset of each line start (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.read_indexed, path, max_read_bytes)
+
+    async def read_paged(self, path: str, page_chars: int) -> list[tuple[int, str]]:
+        """Read a file split into pages of bounded size (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.read_paged, path, page_chars)
 
     async def read_file_range(self, path: str, offset: int, limit: int) -> str:
         """Read a byte range from a file (async)."""
@@ -570,11 +2813,49 @@ class AsyncFileSystem:
 
         return await asyncio.to_thread(self._sync.read_file_range, path, offset, limit)
 
-    async def write_file(self, path: str, content: str) -> bool:
+    async def read_page(
+        self, path: str, cursor: int | None = None, max_lines: int = 1000
+    ) -> tuple[list[str], int | None]:
+        """Read a page of lines starting at a cursor (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.read_page, path, cursor, max_lines)
+
+    async def write_file(
+        self,
+        path: str,
+        content: str,
+        encoding: str = "utf-8",
+        replace_unencodable: bool = False,
+        verify: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+    ) -> bool:
         """Write content to file atomically (async)."""
         import asyncio
 
-        return await asyncio.to_thread(self._sync.write_file, path, content)
+        return await asyncio.to_thread(
+            self._sync.write_file,
+            path,
+            content,
+            encoding,
+            replace_unencodable,
+            verify,
+            ensure_trailing_newline,
+            strip_trailing_whitespace,
+        )
+
+    async def write_new(self, path: str, content: str) -> bool:
+        """Create a file only if it does not already exist (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.write_new, path, content)
+
+    async def write_transaction(self, files: dict[str, str]) -> bool:
+        """Write several files with all-or-nothing semantics (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.write_transaction, files)
 
     async def write_file_fast(self, path: str, content: str) -> bool:
         """Write content to file without atomicity guarantee (async)."""
@@ -582,17 +2863,75 @@ class AsyncFileSystem:
 
         return await asyncio.to_thread(self._sync.write_file_fast, path, content)
 
+    async def diff_content(self, path: str, new_content: str) -> list[DiffHunk]:
+        """Compare a file with proposed content, line by line (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.diff_content, path, new_content)
+
+    async def write_staged(self, target_path: str, content: str) -> str:
+        """Write content to a staged file beside target_path (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.write_staged, target_path, content)
+
+    async def promote(self, temp_path: str, final_path: str) -> bool:
+        """Atomically move a staged file into its final location (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.promote, temp_path, final_path)
+
+    async def snapshot_file(self, path: str) -> int:
+        """Save a file's current content in memory for restore_file (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.snapshot_file, path)
+
+    async def restore_file(self, token: int) -> str:
+        """Rewrite a file to the content saved by snapshot_file (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.restore_file, token)
+
     async def edit_replace(
         self,
         path: str,
         old_text: str,
         new_text: str,
         strict: bool = True,
-    ) -> bool:
+        encoding: str = "utf-8",
+        replace_unencodable: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+        max_replacements: int | None = None,
+        line_start: int | None = None,
+        line_end: int | None = None,
+    ) -> int:
         """Replace text in file (async)."""
         import asyncio
 
-        return await asyncio.to_thread(self._sync.edit_replace, path, old_text, new_text, strict)
+        return await asyncio.to_thread(
+            self._sync.edit_replace,
+            path,
+            old_text,
+            new_text,
+            strict,
+            encoding,
+            replace_unencodable,
+            ensure_trailing_newline,
+            strip_trailing_whitespace,
+            max_replacements,
+            line_start,
+            line_end,
+        )
+
+    async def apply_patch(
+        self, path: str, unified_diff: str, strict: bool = True, fuzzy: bool = False
+    ) -> bool:
+        """Apply a unified diff to a file (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.apply_patch, path, unified_diff, strict, fuzzy)
 
     async def grep(
         self,
@@ -600,12 +2939,287 @@ class AsyncFileSystem:
         glob_pattern: str = "**/*",
         case_sensitive: bool = False,
         max_results: int = 1000,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        include_metadata: bool = False,
+        options: SearchOptions | None = None,
+        canonicalize: bool = False,
+        capture_group: int | None = None,
+        languages: list[str] | None = None,
     ) -> list[SearchResult]:
         """Search files for content matching query (async)."""
         import asyncio
 
         return await asyncio.to_thread(
-            self._sync.grep, query, glob_pattern, case_sensitive, max_results
+            self._sync.grep,
+            query,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            first_per_file_with_count,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            normalize_whitespace,
+            head_bytes,
+            tail_bytes,
+            symbol_context,
+            include_metadata,
+            options,
+            canonicalize,
+            capture_group,
+            languages,
+        )
+
+    async def grep_iter(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        options: SearchOptions | None = None,
+    ) -> AsyncIterator[SearchResult]:
+        """Search files, yielding results as they are found (async).
+
+        Each result is awaited off the event loop. Closing the generator
+        early cancels the remaining search.
+        """
+        import asyncio
+
+        results = self._sync.grep_iter(
+            query, glob_pattern, case_sensitive, max_results, options
+        )
+        try:
+            while (result := await asyncio.to_thread(next, results, None)) is not None:
+                yield result
+        finally:
+            results.cancel()
+
+    async def grep_summary(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        include_metadata: bool = False,
+        options: SearchOptions | None = None,
+        capture_group: int | None = None,
+        languages: list[str] | None = None,
+    ) -> SearchSummary:
+        """Search files, returning results with search statistics (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_summary,
+            query,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            first_per_file_with_count,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            normalize_whitespace,
+            head_bytes,
+            tail_bytes,
+            symbol_context,
+            include_metadata,
+            options,
+            capture_group,
+            languages,
+        )
+
+    async def grep_text(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        context_lines: int = 0,
+    ) -> str:
+        """Search files and return ripgrep-style text (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_text,
+            query,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            context_lines,
+        )
+
+    async def grep_columnar(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        options: SearchOptions | None = None,
+    ) -> tuple[list[str], list[int], list[str]]:
+        """Search files and return the results as parallel lists (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_columnar, query, glob_pattern, case_sensitive, max_results, options
+        )
+
+    async def grep_locations(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+    ) -> list[Location]:
+        """Search files and return each match as a source range (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_locations, query, glob_pattern, case_sensitive, max_results
+        )
+
+    async def grep_count_by_dir(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+    ) -> dict[str, int]:
+        """Count matching lines per directory (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_count_by_dir,
+            query,
+            glob_pattern,
+            case_sensitive,
+            include_hidden,
+            include_ignored,
+        )
+
+    async def any_match(
+        self, query: str, glob_pattern: str = "**/*", case_sensitive: bool = False
+    ) -> bool:
+        """Check whether any file contains a match (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.any_match, query, glob_pattern, case_sensitive
+        )
+
+    async def find(
+        self, path_glob: str, content_query: str, options: SearchOptions | None = None
+    ) -> list[str]:
+        """Find files matching both a path glob and a content query (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.find, path_glob, content_query, options)
+
+    async def grep_multi(
+        self,
+        patterns: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search files for several independent patterns in one pass (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_multi,
+            patterns,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+        )
+
+    async def grep_all_keywords(
+        self,
+        keywords: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search for lines containing every one of several keywords (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_all_keywords,
+            keywords,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+        )
+
+    async def grep_any_keyword(
+        self,
+        keywords: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search for lines containing at least one of several keywords (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(
+            self._sync.grep_any_keyword,
+            keywords,
+            glob_pattern,
+            case_sensitive,
+            max_results,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
         )
 
     async def get_metadata(self, path: str) -> FileMetadata:
@@ -614,20 +3228,108 @@ class AsyncFileSystem:
 
         return await asyncio.to_thread(self._sync.get_metadata, path)
 
-    async def refresh(self) -> None:
+    async def refresh(self) -> bool:
         """Force refresh the file index (async)."""
         import asyncio
 
         return await asyncio.to_thread(self._sync.refresh)
 
+    def cancel_refresh(self) -> bool:
+        """Cancel a refresh in progress (sync - non-blocking)."""
+        return self._sync.cancel_refresh()
+
+    async def refresh_paths(self, paths: list[str]) -> int:
+        """Refresh specific paths in the index (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.refresh_paths, paths)
+
+    async def save_index(self, path: str, compression_level: int | None = None) -> None:
+        """Save the file index to a snapshot file (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.save_index, path, compression_level)
+
+    async def load_index(self, path: str) -> None:
+        """Replace the file index with a saved snapshot (async)."""
+        import asyncio
+
+        return await asyncio.to_thread(self._sync.load_index, path)
+
     def is_ready(self) -> bool:
         """Check if the index is ready (sync - non-blocking)."""
         return self._sync.is_ready()
 
+    def ping(self) -> str:
+        """Confirm the extension is loaded and this object is alive (sync - non-blocking)."""
+        return self._sync.ping()
+
+    def is_truncated(self) -> bool:
+        """Check whether max_index_entries left paths out of the index (sync - non-blocking)."""
+        return self._sync.is_truncated()
+
+    def follow(
+        self, path: str, callback: Callable[[str], object], from_end: bool = True
+    ) -> FollowHandle:
+        """Stream lines appended to a file, like tail -f (sync - non-blocking)."""
+        return self._sync.follow(path, callback, from_end)
+
     def is_watching(self) -> bool:
         """Check if file watching is active (sync - non-blocking)."""
         return self._sync.is_watching()
 
+    def watcher_healthy(self) -> bool:
+        """Check whether the watcher is still delivering events (sync - non-blocking)."""
+        return self._sync.watcher_healthy()
+
+    def watch_mode(self) -> WatchMode | None:
+        """Get the watcher backend in use (sync - non-blocking)."""
+        return self._sync.watch_mode()
+
+    def watch_subpath(self, subpath: str, debounce_ms: int = 100) -> WatchHandle:
+        """Watch a subtree separately with its own debounce (sync - non-blocking)."""
+        return self._sync.watch_subpath(subpath, debounce_ms)
+
+    def pending_paths(self) -> list[str]:
+        """List paths with changes the index has not applied yet (sync - non-blocking)."""
+        return self._sync.pending_paths()
+
+    def pending_changes(self) -> int:
+        """Count applied changes not yet taken by poll_changes (sync - non-blocking)."""
+        return self._sync.pending_changes()
+
+    def poll_changes(self) -> list[WatchEvent]:
+        """Take the changes the watcher has applied to the index (sync - non-blocking)."""
+        return self._sync.poll_changes()
+
+    def common_ancestor(self, paths: list[str]) -> str:
+        """Find the deepest directory containing every given path (sync - non-blocking)."""
+        return self._sync.common_ancestor(paths)
+
+    def memory_usage(self) -> int:
+        """Estimate the memory used by the file index (sync - non-blocking)."""
+        return self._sync.memory_usage()
+
+    def is_case_insensitive(self) -> bool:
+        """Check whether paths are matched case-insensitively (sync - non-blocking)."""
+        return self._sync.is_case_insensitive()
+
+    def check_writable(self, path: str) -> None:
+        """Check a path against the write policy (sync - non-blocking)."""
+        self._sync.check_writable(path)
+
+    def cached_globs(self) -> list[str]:
+        """List cached glob patterns (sync - non-blocking)."""
+        return self._sync.cached_globs()
+
+    def glob_matches(self, pattern: str, path: str) -> bool:
+        """Check whether a glob pattern matches a path (sync - non-blocking)."""
+        return self._sync.glob_matches(pattern, path)
+
+    def clear_glob_cache(self) -> None:
+        """Empty the compiled glob cache (sync - non-blocking)."""
+        self._sync.clear_glob_cache()
+
     def close(self) -> None:
         """Close the filesystem and release resources."""
         self._sync.close()
@@ -2,8 +2,13 @@
 
 from __future__ import annotations
 
+from collections.abc import Callable
+
 __version__: str
 
+DEFAULT_IGNORED_DIRS: list[str]
+"""Directory names skipped by FileSystem(use_default_ignores=True)."""
+
 class FileMetadata:
     """File metadata stored in the index."""
 
@@ -19,6 +24,54 @@ class FileMetadata:
     is_binary: bool
     """Whether this appears to be a binary file."""
 
+class SearchOrder:
+    """Ordering applied to search results after collection."""
+
+    FILE_PATH: SearchOrder
+    """Lexicographic by file path, then line number."""
+
+    LINE_NUMBER: SearchOrder
+    """By line number, then file path."""
+
+    RELEVANCE: SearchOrder
+    """Files whose path matches the query first, then smaller files."""
+
+class BinaryMode:
+    """How binary and non-UTF8 files are handled by search."""
+
+    SKIP: BinaryMode
+    """Skip binary and non-UTF8 files."""
+
+    TEXT: BinaryMode
+    """Decode lossily and search as text; line numbers are approximate."""
+
+    STRINGS: BinaryMode
+    """Search printable ASCII runs; each run is reported as its own line."""
+
+class WatchMode:
+    """How a FileSystem receives changes from the operating system."""
+
+    AUTO: WatchMode
+    """Native notifications, falling back to polling if they cannot start."""
+
+    NATIVE: WatchMode
+    """Native notifications only (inotify, FSEvents, ReadDirectoryChangesW)."""
+
+    POLL: WatchMode
+    """Periodically rescan the tree; works on network and bind mounts."""
+
+class LineEnding:
+    """Which byte sequences end a line for FileSystem.count_lines_exact."""
+
+    LF: LineEnding
+    """Only "\\n" ends a line; "\\r\\n" ends in one too."""
+
+    CRLF: LineEnding
+    """Only "\\r\\n" ends a line; a bare "\\n" or "\\r" is part of it."""
+
+    ANY: LineEnding
+    """Any of "\\r\\n", "\\n" or a lone "\\r" ends a line."""
+
 class SearchOptions:
     """Search options for grep operations."""
 
@@ -34,12 +87,85 @@ class SearchOptions:
     context_lines: int
     """Number of context lines before/after match."""
 
+    first_per_file_with_count: bool
+    """Report only the first match per file, annotated with its match count."""
+
+    include_hidden: bool
+    """Include hidden files (dotfiles) in the search."""
+
+    include_ignored: bool
+    """Include files excluded by .gitignore/.ignore rules."""
+
+    max_files_scanned: int | None
+    """Stop after searching this many files (None = no limit)."""
+
+    order_by: SearchOrder | None
+    """Result ordering (None = unspecified, fastest)."""
+
+    binary: BinaryMode
+    """Handling of binary and non-UTF8 files."""
+
+    normalize_whitespace: bool
+    """Collapse runs of whitespace in each line to one space before matching."""
+
+    head_bytes: int | None
+    """Search only the first N bytes of each file (None = whole file)."""
+
+    tail_bytes: int | None
+    """Search only the last N bytes of each file (None = whole file)."""
+
+    symbol_context: bool
+    """Attach the enclosing function/class name to each result."""
+
+    per_file_timeout_ms: int | None
+    """Give up reading a file after this many milliseconds and record it as
+    skipped (None = wait indefinitely). Timed reads share a small pool of helper
+    threads, so while several files are stalled other files may time out too."""
+
+    include_metadata: bool
+    """Attach the file's indexed metadata to each result."""
+
+    encoding: str | None
+    """Decode files from this encoding label before matching, e.g. "shift_jis"
+    (None = UTF-8). UTF-8 is searched in place; other encodings cost an extra
+    decode and copy per file. A byte order mark overrides the label."""
+
+    capture_group: int | None
+    """Report this capture group as each result's content instead of the line,
+    skipping lines where it did not participate (None = whole line)."""
+
+    max_matches_per_dir: int | None
+    """Stop reporting matches from a directory once it has this many, counting
+    only files directly inside it, so one cluster cannot crowd out the rest of
+    the tree (None = no limit)."""
+
+    languages: list[str] | None
+    """Search only files whose detected language is one of these names, compared
+    case-insensitively (None = every language). Detection is by file name, as
+    in code_stats(); unrecognised files never match."""
+
     def __init__(
         self,
         case_sensitive: bool = False,
         max_results: int = 1000,
         max_file_size: int = 10485760,
         context_lines: int = 0,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        per_file_timeout_ms: int | None = None,
+        include_metadata: bool = False,
+        encoding: str | None = None,
+        capture_group: int | None = None,
+        max_matches_per_dir: int | None = None,
+        languages: list[str] | None = None,
     ) -> None: ...
 
 class SearchResult:
@@ -52,7 +178,7 @@ class SearchResult:
     """Line number (1-indexed)."""
 
     content: str
-    """The matching line content."""
+    """The matching line content, or the captured text with capture_group."""
 
     context_before: list[str]
     """Context lines before the match."""
@@ -60,60 +186,560 @@ class SearchResult:
     context_after: list[str]
     """Context lines after the match."""
 
+    numbered_context_before: list[tuple[int, str]]
+    """Context lines before the match as (line_number, text) pairs."""
+
+    numbered_context_after: list[tuple[int, str]]
+    """Context lines after the match as (line_number, text) pairs."""
+
+    file_match_count: int | None
+    """Total matches in this file (only set in first-per-file mode)."""
+
+    pattern_index: int | None
+    """Index of the pattern that matched (only set by grep_multi)."""
+
+    symbol: str | None
+    """Enclosing function/class name (only set with symbol_context)."""
+
+    metadata: FileMetadata | None
+    """Indexed metadata of the file (only set with include_metadata)."""
+
+    matched_glob: str | None
+    """Include glob the file was selected by (None for in-memory search)."""
+
+    capture_span: tuple[int, int] | None
+    """1-indexed, end-exclusive columns of the captured text in the line (only
+    set with capture_group)."""
+
+class SearchSummary:
+    """Search results together with statistics about the search."""
+
+    results: list[SearchResult]
+    """Matches found."""
+
+    files_scanned: int
+    """Number of files that were actually searched."""
+
+    truncated: bool
+    """Whether the search stopped early (result or file limit reached)."""
+
+    skipped: list[tuple[str, str]]
+    """Files that could not be searched, as (path, reason) pairs."""
+
+class SearchIter:
+    """Iterator over the results of a search still running in the background.
+
+    Dropping the iterator or calling cancel() stops the search.
+    """
+
+    def __iter__(self) -> SearchIter: ...
+    def __next__(self) -> SearchResult: ...
+    def cancel(self) -> None:
+        """Stop the search; results already buffered are still yielded."""
+
+class Location:
+    """A match as a source range (1-indexed lines and character columns)."""
+
+    file: str
+    """File path relative to root."""
+
+    start_line: int
+    """Line where the match starts."""
+
+    start_col: int
+    """Column where the match starts."""
+
+    end_line: int
+    """Line where the match ends."""
+
+    end_col: int
+    """Column just past the end of the match (exclusive)."""
+
+    text: str
+    """The full line containing the match."""
+
+class DiffHunk:
+    """A contiguous run of changed lines (1-indexed starts)."""
+
+    old_start: int
+    """First line of the hunk in the current file."""
+
+    old_lines: int
+    """Number of lines replaced in the current file (0 for a pure insertion)."""
+
+    new_start: int
+    """First line of the hunk in the new content."""
+
+    new_lines: int
+    """Number of lines in the new content (0 for a pure deletion)."""
+
+    removed: list[str]
+    """Lines removed from the current file."""
+
+    added: list[str]
+    """Lines added by the new content."""
+
+class FollowHandle:
+    """Handle to a running follow(); stop it with stop() or a with block."""
+
+    def stop(self) -> None:
+        """Stop following; no callback runs after this returns."""
+
+    def is_active(self) -> bool:
+        """Check whether lines are still being delivered."""
+
+    def __enter__(self) -> FollowHandle: ...
+    def __exit__(self, *args) -> bool: ...
+
+class WatchEvent:
+    """A change reported by a WatchHandle."""
+
+    @property
+    def kind(self) -> str:
+        """"created", "modified", "attr_changed", "deleted" or "renamed"."""
+
+    @property
+    def path(self) -> str:
+        """Path that changed, relative to root; the old path for renames."""
+
+    @property
+    def dest_path(self) -> str | None:
+        """New path of a rename, None otherwise."""
+
+    @property
+    def merge_count(self) -> int:
+        """Number of raw events merged into this one."""
+
+class WatchHandle:
+    """Handle to an extra watch from watch_subpath(); stop it with stop() or a with block."""
+
+    def events(self) -> list[WatchEvent]:
+        """Take the changes whose debounce window has passed (non-blocking)."""
+
+    def stop(self) -> None:
+        """Stop watching and release the backend."""
+
+    def is_active(self) -> bool:
+        """Check whether the watch is running and healthy."""
+
+    def __enter__(self) -> WatchHandle: ...
+    def __exit__(self, *args) -> bool: ...
+
+class WriteVerificationError(OSError):
+    """Raised when a verified write reads back different bytes than were written."""
+
+def validate_glob(pattern: str) -> bool:
+    """Check that a glob pattern compiles, without running it.
+
+    Raises:
+        ValueError: If the pattern is invalid.
+    """
+
+def validate_regex(pattern: str, case_sensitive: bool = False) -> bool:
+    """Check that a regex pattern compiles, without running it.
+
+    Raises:
+        ValueError: If the pattern is invalid.
+    """
+
+def grep_text_content(
+    content: str,
+    query: str,
+    label: str = "",
+    case_sensitive: bool = False,
+    max_results: int = 1000,
+    options: SearchOptions | None = None,
+) -> list[SearchResult]:
+    """Search an in-memory string with the same line matching as grep().
+
+    Nothing is read from disk, so an agent can search a model's output or
+    an unsaved buffer exactly as it searches files. File-reading options
+    (size limits, binary mode, head/tail bytes) have no effect.
+
+    Args:
+        content: Text to search.
+        query: Search pattern (regex).
+        label: Reported as each result's file; with symbol_context its
+            extension picks the language (e.g. "reply.py").
+        case_sensitive: Case sensitive search.
+        max_results: Maximum number of results.
+        options: A fully configured SearchOptions; when given,
+            case_sensitive and max_results are ignored.
+
+    Returns:
+        List of SearchResult objects in line order.
+
+    Raises:
+        ValueError: If the query is invalid.
+    """
+
+def set_max_open_files(limit: int) -> None:
+    """Set how many files batch reads and searches may hold open at once.
+
+    The cap is process-wide (default: 256) and keeps parallel operations
+    from failing with "too many open files" on hosts with a low ulimit.
+
+    Raises:
+        ValueError: If limit is 0.
+    """
+
+def max_open_files() -> int:
+    """Get the current cap on concurrently open files."""
+
 class FileSystem:
     """High-performance file system interface.
 
     Provides stateful, concurrent file operations with in-memory indexing.
     """
 
-    def __init__(self, root: str, auto_watch: bool = True) -> None:
+    def __init__(
+        self,
+        root: str,
+        auto_watch: bool = True,
+        index_snapshot: str | None = None,
+        case_insensitive: bool | None = None,
+        auto_refresh_secs: int | None = None,
+        include_extensions: list[str] | None = None,
+        watch_recursive: bool = True,
+        cache_reads: bool = False,
+        skip_binary_detection_above: int | None = None,
+        watch_debounce_ms: int = 100,
+        writable_extensions: list[str] | None = None,
+        allow_extensionless_writes: bool = False,
+        max_read_bytes: int | None = 1073741824,
+        watch_mode: WatchMode = WatchMode.AUTO,
+        watch_poll_interval_ms: int = 1000,
+        max_index_entries: int | None = None,
+        use_default_ignores: bool = False,
+        ignored_dirs: list[str] | None = None,
+        cache_line_offsets: bool = False,
+        custom_ignore_files: list[str] | None = None,
+    ) -> None:
         """Create a new FileSystem instance.
 
         Args:
             root: Root directory path.
             auto_watch: Whether to automatically watch for file changes.
+            index_snapshot: Optional snapshot file to load instead of walking the tree.
+            case_insensitive: Treat paths differing only in case as the same file
+                (None = detect from the filesystem).
+            auto_refresh_secs: Reconcile the index with the disk every N seconds
+                to catch missed watcher events (None = never). Each pass walks
+                the whole tree, so keep the interval well above the walk time.
+            include_extensions: Only index and watch files with these
+                extensions, e.g. ["py", "rs"] (None = all files). Directories
+                are always traversed.
+            watch_recursive: Watch the whole tree (default). When False only root
+                itself is watched; changes inside subdirectories are not detected
+                and the index will not track them until refresh().
+            cache_reads: Keep recently read files in memory (up to 256 files and
+                32 MiB) and serve read_file() from there while a file's mtime and
+                size are unchanged. Helps agents that re-read the same files.
+                Only UTF-8 reads are cached; other encodings read from disk.
+            skip_binary_detection_above: Assume files larger than this many bytes
+                are binary instead of reading their first bytes while indexing
+                (None = always read). Cuts indexing I/O on repos full of large
+                media files; such files are then skipped by default searches.
+            watch_debounce_ms: How long a path must be quiet before its change
+                is applied to the index (default 100). 0 selects raw mode, where
+                every event is applied immediately and unmerged for the lowest
+                latency (e.g. live-reload loops). Raw mode is noisy: one save can
+                arrive as several events, and a file created and deleted in quick
+                succession is added and removed rather than skipped.
+            writable_extensions: Only allow writes to files with these
+                extensions (None = no restriction). Write methods raise
+                PermissionError for any other target before touching the disk.
+            allow_extensionless_writes: With writable_extensions set, also allow
+                targets without an extension (Makefile, .bashrc, directories).
+            max_read_bytes: Refuse to load files larger than this many bytes in
+                read_file(), read_batch() and open(), raising IOError (default
+                1 GiB, None = no limit).
+            watch_mode: Watcher backend: AUTO (native, polling only if native
+                fails to start), NATIVE or POLL.
+            watch_poll_interval_ms: Interval between scans when polling.
+            max_index_entries: Stop indexing once this many entries are held,
+                leaving a partial index (None = no limit).
+            use_default_ignores: Also skip the directories in
+                DEFAULT_IGNORED_DIRS, on top of .gitignore.
+            ignored_dirs: Further directory names to skip (None = none).
+            cache_line_offsets: Cache line start offsets of files read by line
+                range, so repeated range reads seek directly (up to 64 files).
+            custom_ignore_files: Further gitignore-syntax file names to honour,
+                e.g. [".agentignore"], with precedence over .gitignore.
         """
 
-    def list(self, pattern: str = "**/*", only_files: bool = True) -> list[str]:
+    def list(
+        self, pattern: str = "**/*", only_files: bool = True, canonicalize: bool = False
+    ) -> list[str]:
         """List files matching the given pattern from memory index.
 
         Args:
             pattern: Glob pattern (default: "**/*").
             only_files: If true, only return files (not directories).
+            canonicalize: Resolve symlinks in each returned path (see glob()).
 
         Returns:
             List of file paths relative to root.
         """
 
-    def glob(self, pattern: str) -> list[str]:
+    def glob(self, pattern: str, canonicalize: bool = False) -> list[str]:
         """Match files using glob pattern.
 
         Args:
             pattern: Glob pattern.
+            canonicalize: Resolve symlinks in each returned path, one syscall
+                per path run in parallel. Paths a symlink leads outside root
+                become absolute; unresolvable paths are returned unchanged.
 
         Returns:
             List of matching file paths.
         """
 
-    def read_file(self, path: str, encoding: str = "utf-8") -> str:
+    def glob_grouped(self, patterns: list[str]) -> dict[str, list[str]]:
+        """Match files against several globs, keeping the results per pattern.
+
+        Args:
+            patterns: Glob patterns to match.
+
+        Returns:
+            Dict mapping each pattern to its sorted matching file paths
+            (an empty list if the pattern matched nothing).
+        """
+
+    def expand_specs(self, specs: list[str]) -> list[str]:
+        """Resolve file specs mixing literal paths and globs to a file list.
+
+        Specs containing *, ?, [ or { are globs expanded via the index; others
+        are literal paths, kept if they name an existing file.
+
+        Args:
+            specs: Literal paths and globs, e.g. ["src/main.rs", "tests/**/*.rs"].
+
+        Returns:
+            Deduplicated paths relative to root, in spec order.
+
+        Raises:
+            ValueError: If a glob spec is invalid.
+            RuntimeError: If the index is not ready yet.
+        """
+
+    def list_regex(self, path_regex: str, only_files: bool = True) -> list[str]:
+        """List files whose relative path matches a regex.
+
+        Args:
+            path_regex: Regular expression matched against relative paths.
+            only_files: If true, only return files (not directories).
+
+        Returns:
+            List of matching paths relative to root.
+        """
+
+    def common_ancestor(self, paths: list[str]) -> str:
+        """Find the deepest directory containing every given path.
+
+        Args:
+            paths: File or directory paths (relative to root or absolute).
+
+        Returns:
+            The common directory relative to root ("." for root itself), or an
+            absolute path if it lies outside root.
+        """
+
+    def diff_paths(self, paths: list[str]) -> tuple[list[str], list[str]]:
+        """Compare a list of file paths against the indexed files.
+
+        Args:
+            paths: File paths (relative to root or absolute).
+
+        Returns:
+            Tuple of (missing_on_disk, untracked_in_list), both sorted and
+            relative to root. A listed directory counts as missing.
+        """
+
+    def find_by_name(self, name: str, case_insensitive: bool = False) -> list[str]:
+        """Find paths whose filename exactly equals name.
+
+        Args:
+            name: Basename to look for (e.g. "Cargo.toml").
+            case_insensitive: Compare names ignoring case.
+
+        Returns:
+            Sorted list of matching paths relative to root.
+        """
+
+    def recent_files(self, n: int = 20, pattern: str | None = None) -> list[tuple[str, float]]:
+        """List the most recently modified files.
+
+        Args:
+            n: Maximum number of files to return.
+            pattern: Only consider files matching this glob (None = all files).
+
+        Returns:
+            List of (path, mtime) tuples, newest first.
+        """
+
+    def changed_under(self, subpath: str, timestamp: float) -> bool:
+        """Check whether any indexed entry under subpath has mtime >= timestamp.
+
+        Args:
+            subpath: Directory (or file), relative to root or absolute.
+            timestamp: Unix timestamp.
+
+        Returns:
+            True on the first entry modified at or after timestamp, else False.
+
+        Raises:
+            RuntimeError: If the index is not ready yet.
+        """
+
+    def empty_files(self) -> list[str]:
+        """List zero-byte files, from the index.
+
+        Returns:
+            Sorted list of empty file paths relative to root.
+        """
+
+    def empty_dirs(self) -> list[str]:
+        """List directories with no indexed children, from the index.
+
+        Returns:
+            Sorted list of empty directory paths relative to root.
+        """
+
+    def list_dirs_with_matches(self, pattern: str) -> list[str]:
+        """List the directories that directly contain files matching pattern.
+
+        Returns:
+            Sorted, deduplicated directory paths relative to root ("." for root).
+        """
+
+    def glob_matches(self, pattern: str, path: str) -> bool:
+        """Check whether a glob pattern matches a path, as list() would.
+
+        Args:
+            pattern: Glob pattern.
+            path: Path relative to root, or absolute.
+
+        Returns:
+            True if the path matches; absolute paths outside root never do.
+
+        Raises:
+            ValueError: If the pattern is invalid.
+        """
+
+    def code_stats(self) -> dict[str, tuple[int, int, int]]:
+        """Summarise the indexed code by language, with cached line counts.
+
+        Returns:
+            Dict mapping language name to (files, lines, bytes).
+        """
+
+    def count_files(self, pattern: str = "**/*") -> int:
+        """Count the indexed files matching a glob pattern."""
+
+    def count_lines(self, pattern: str = "**/*") -> int:
+        """Total lines across the text files matching a glob pattern."""
+
+    def count_lines_exact(self, path: str, convention: LineEnding = LineEnding.LF) -> int:
+        """Count a file's lines under an explicit line ending convention.
+
+        Unlike the cached count_lines(), which splits on "\\n" only, this can
+        count "\\r\\n" only (CRLF) or any of "\\r\\n", "\\n" and "\\r" (ANY).
+
+        Args:
+            path: File path (relative to root or absolute).
+            convention: LineEnding deciding what ends a line (default: LF).
+
+        Returns:
+            Number of lines, counting a final unterminated line.
+        """
+
+    def read_file(
+        self, path: str, encoding: str = "utf-8", max_read_bytes: int | None = None
+    ) -> str:
         """Read a single file.
 
         Args:
             path: File path (relative to root or absolute).
             encoding: Text encoding (default: "utf-8").
+            max_read_bytes: Override the instance's read limit for this call
+                (0 = no limit).
 
         Returns:
             File content as string.
+
+        Raises:
+            IOError: If the file is larger than the read limit.
+        """
+
+    def set_binary_extensions(self, always_binary: list[str], always_text: list[str]) -> int:
+        """Override binary detection by file extension.
+
+        Args:
+            always_binary: Extensions to treat as binary without reading.
+            always_text: Extensions to treat as text even with null bytes;
+                wins over always_binary.
+
+        Returns:
+            Number of indexed files whose binary flag changed.
+        """
+
+    def file_id(self, path: str) -> str:
+        """Get a stable, content-based id for a file (BLAKE3 hex digest).
+
+        Args:
+            path: File path (relative to root or absolute).
+
+        Returns:
+            64-character lowercase hex string; identical files share an id.
+        """
+
+    def classify(self, path: str) -> str:
+        """Classify a file as "text", "binary", "empty" or "missing".
+
+        Args:
+            path: File path (relative to root or absolute).
+
+        Returns:
+            "text", "binary", "empty" or "missing".
+        """
+
+    def open(
+        self, path: str, max_read_bytes: int | None = None
+    ) -> tuple[str, FileMetadata]:
+        """Read a file and its metadata in one call.
+
+        Args:
+            path: File path (relative to root or absolute).
+            max_read_bytes: Override the instance's read limit for this call
+                (0 = no limit).
+
+        Returns:
+            Tuple of (content, FileMetadata).
+
+        Raises:
+            IOError: If the file is larger than the read limit.
         """
 
-    def read_batch(self, paths: list[str]) -> dict[str, str]:
+    def read_batch(
+        self,
+        paths: list[str],
+        max_file_size: int | None = None,
+        max_read_bytes: int | None = None,
+    ) -> dict[str, str]:
         """Read multiple files in parallel.
 
         Args:
             paths: List of file paths.
+            max_file_size: Skip files larger than this many bytes (None = no limit).
+            max_read_bytes: Override the instance's read limit for this call
+                (0 = no limit).
 
         Returns:
-            Dict mapping path to content.
+            Dict mapping each path, exactly as given, to its content.
+
+        Raises:
+            IOError: If a file not skipped by max_file_size exceeds the read limit.
         """
 
     def read_lines(
@@ -121,6 +747,7 @@ class FileSystem:
         path: str,
         start_line: int = 0,
         count: int | None = None,
+        max_bytes: int | None = None,
     ) -> list[str]:
         """Read specific lines from a file (for large files).
 
@@ -128,67 +755,337 @@ class FileSystem:
             path: File path.
             start_line: Starting line number (0-indexed).
             count: Number of lines to read (None = read to end).
+            max_bytes: Byte budget for the returned lines; stops before the
+                first line that would exceed it (None = no budget).
 
         Returns:
             List of line strings (without trailing newlines).
         """
 
-    def read_file_range(
-        self,
-        path: str,
-        offset: int,
-        limit: int,
-    ) -> str:
-        """Read a byte range from a file.
+    def read_first_line(self, path: str) -> str | None:
+        """Read the first line of a file.
 
         Args:
             path: File path.
-            offset: Byte offset to start reading from.
-            limit: Maximum bytes to read.
 
         Returns:
-            Content as string.
+            The first line without its terminator, or None for an empty file.
         """
 
-    def write_file(self, path: str, content: str) -> bool:
-        """Write content to file atomically.
+    def read_numbered(
+        self,
+        path: str,
+        start_line: int = 0,
+        count: int | None = None,
+    ) -> list[tuple[int, str]]:
+        """Read specific lines paired with their line numbers.
 
         Args:
             path: File path.
-            content: Content to write.
+            start_line: Starting line number (0-indexed).
+            count: Number of lines to read (None = read to end).
 
         Returns:
-            True if successful.
+            List of (line_number, text) tuples, line_number is 1-indexed.
         """
 
-    def write_file_fast(self, path: str, content: str) -> bool:
-        """Write content to file without atomicity guarantee (fast mode).
+    def read_around(
+        self,
+        path: str,
+        center_line: int,
+        radius: int = 10,
+    ) -> list[tuple[int, str]]:
+        """Read the numbered lines around a given line.
 
         Args:
             path: File path.
-            content: Content to write.
+            center_line: Line to center on (1-indexed).
+            radius: Number of lines to include on each side.
 
         Returns:
-            True if successful.
+            List of (line_number, text) tuples, clamped to the file.
+
+        Raises:
+            ValueError: If center_line is 0.
         """
 
-    def edit_replace(
-        self,
-        path: str,
-        old_text: str,
-        new_text: str,
-        strict: bool = True,
-    ) -> bool:
-        """Replace text in file.
+    def line_offsets(self, path: str) -> list[int]:
+        """Get the byte offset where each line of a file starts.
 
         Args:
             path: File path.
-            old_text: Text to find.
-            new_text: Replacement text.
-            strict: If true, error if old_text is not unique or not found.
 
         Returns:
-            True if replacement was made.
+            List of byte offsets (entry i is where 0-indexed line i starts),
+            empty for an empty file.
+        """
+
+    def read_indexed(
+        self, path: str, max_read_bytes: int | None = None
+    ) -> tuple[str, list[int]]:
+        """Read a file together with the character offset of each line start.
+
+        Args:
+            path: File path (relative to root or absolute).
+            max_read_bytes: Override the instance's read limit for this call
+                (0 = no limit).
+
+        Returns:
+            Tuple of (content, offsets), offsets computed from content and
+            counted in characters, so they slice content directly.
+
+        Raises:
+            IOError: If the file is larger than the read limit.
+        """
+
+    def read_paged(self, path: str, page_chars: int) -> list[tuple[int, str]]:
+        """Read a file split into pages of at most page_chars characters.
+
+        Args:
+            path: File path.
+            page_chars: Maximum characters per page.
+
+        Returns:
+            List of (start_line, text) tuples, start_line is 1-indexed.
+
+        Raises:
+            ValueError: If page_chars is 0.
+        """
+
+    def read_page(
+        self, path: str, cursor: int | None = None, max_lines: int = 1000
+    ) -> tuple[list[str], int | None]:
+        """Read a page of lines starting at a cursor.
+
+        Args:
+            path: File path.
+            cursor: Byte offset returned by an earlier call (None = start of file).
+            max_lines: Maximum number of lines to return.
+
+        Returns:
+            Tuple of (lines, next_cursor); next_cursor is None at end of file.
+
+        Raises:
+            ValueError: If max_lines is 0.
+        """
+
+    def read_file_range(
+        self,
+        path: str,
+        offset: int,
+        limit: int,
+    ) -> str:
+        """Read a byte range from a file.
+
+        Args:
+            path: File path.
+            offset: Byte offset to start reading from.
+            limit: Maximum bytes to read.
+
+        Returns:
+            Content as string.
+        """
+
+    def write_file(
+        self,
+        path: str,
+        content: str,
+        encoding: str = "utf-8",
+        replace_unencodable: bool = False,
+        verify: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+    ) -> bool:
+        """Write content to file atomically.
+
+        Args:
+            path: File path.
+            content: Content to write.
+            encoding: Output encoding (e.g. "utf-8", "shift_jis").
+            replace_unencodable: Write "?" for unencodable characters
+                instead of raising.
+            verify: Read the file back and compare it with what was written.
+            ensure_trailing_newline: Append a line ending if missing.
+            strip_trailing_whitespace: Trim trailing spaces and tabs from each line.
+
+        Returns:
+            True if successful.
+
+        Raises:
+            ValueError: If the encoding is unknown or content is unencodable.
+            WriteVerificationError: If verify is set and the content differs.
+            PermissionError: If writable_extensions does not allow the path.
+        """
+
+    def write_new(self, path: str, content: str) -> bool:
+        """Create a file only if it does not already exist.
+
+        Args:
+            path: File path.
+            content: Content to write.
+
+        Returns:
+            True if the file was created, False if it already existed.
+        """
+
+    def write_transaction(self, files: dict[str, str]) -> bool:
+        """Write several files with all-or-nothing semantics.
+
+        All files are staged and fsynced before any is renamed into place; a
+        staging failure changes nothing. The rename phase itself is not
+        atomic across files.
+
+        Args:
+            files: Dict mapping each file path to its new content.
+
+        Returns:
+            True if every file was written.
+        """
+
+    def write_file_fast(self, path: str, content: str) -> bool:
+        """Write content to file without atomicity guarantee (fast mode).
+
+        Args:
+            path: File path.
+            content: Content to write.
+
+        Returns:
+            True if successful.
+        """
+
+    def apply_patch(
+        self, path: str, unified_diff: str, strict: bool = True, fuzzy: bool = False
+    ) -> bool:
+        """Apply a unified diff to a file, writing the result atomically.
+
+        Args:
+            path: File path.
+            unified_diff: Single-file patch text.
+            strict: Raise if any hunk does not match; otherwise skip it.
+            fuzzy: Accept hunks at a nearby line or with trailing whitespace
+                differences.
+
+        Returns:
+            True if every hunk applied.
+        """
+
+    def follow(
+        self, path: str, callback: Callable[[str], object], from_end: bool = True
+    ) -> FollowHandle:
+        """Stream lines appended to a file, like tail -f.
+
+        Args:
+            path: File path.
+            callback: Called with each new line from a background thread.
+            from_end: Only report lines appended from now on.
+
+        Returns:
+            FollowHandle used to stop following.
+        """
+
+    def diff_content(self, path: str, new_content: str) -> list[DiffHunk]:
+        """Compare a file with proposed content, line by line.
+
+        Args:
+            path: File path.
+            new_content: Proposed full content of the file.
+
+        Returns:
+            List of DiffHunk objects in file order (empty if unchanged).
+        """
+
+    def write_staged(self, target_path: str, content: str) -> str:
+        """Write content to a staged file beside target_path, without touching it.
+
+        Staged files are named ".<target name>.<random>.agstaged"; pass the
+        returned path to promote() to swap it in.
+
+        Args:
+            target_path: Path the content is meant to replace.
+            content: Content to write.
+
+        Returns:
+            Path of the staged file, relative to root when inside it.
+        """
+
+    def promote(self, temp_path: str, final_path: str) -> bool:
+        """Atomically move a staged file into its final location.
+
+        Args:
+            temp_path: Staged file to promote.
+            final_path: Destination path.
+
+        Returns:
+            True if successful.
+
+        Raises:
+            IOError: If the paths are on different filesystems.
+        """
+
+    def snapshot_file(self, path: str) -> int:
+        """Save a file's current content in memory for a later restore_file().
+
+        Keeps the 32 most recent snapshots, up to 64MB in total.
+
+        Args:
+            path: File path.
+
+        Returns:
+            Token to pass to restore_file().
+
+        Raises:
+            IOError: If the file is larger than the 64MB budget.
+        """
+
+    def restore_file(self, token: int) -> str:
+        """Atomically rewrite a file to the content saved by snapshot_file().
+
+        Args:
+            token: Token returned by snapshot_file().
+
+        Returns:
+            The restored path, relative to root.
+
+        Raises:
+            ValueError: If the token was already restored or has been evicted.
+        """
+
+    def edit_replace(
+        self,
+        path: str,
+        old_text: str,
+        new_text: str,
+        strict: bool = True,
+        encoding: str = "utf-8",
+        replace_unencodable: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+        max_replacements: int | None = None,
+        line_start: int | None = None,
+        line_end: int | None = None,
+    ) -> int:
+        """Replace text in file.
+
+        Args:
+            path: File path.
+            old_text: Text to find.
+            new_text: Replacement text.
+            strict: If true, error if old_text is not unique or not found.
+            encoding: Encoding the file is read in and written back in.
+            replace_unencodable: Write "?" for unencodable characters
+                instead of raising.
+            ensure_trailing_newline: Append a line ending if missing.
+            strip_trailing_whitespace: Trim trailing spaces and tabs from each line.
+            max_replacements: In non-strict mode, replace at most this many
+                occurrences, first ones first (None = all).
+            line_start: First 1-indexed line of the region to edit (None = 1).
+            line_end: Last line of the region, inclusive (None = end of file).
+
+        Returns:
+            Number of occurrences replaced.
+
+        Raises:
+            ValueError: If line_start is 0 or line_end is before line_start.
         """
 
     def grep(
@@ -197,6 +1094,21 @@ class FileSystem:
         glob_pattern: str = "**/*",
         case_sensitive: bool = False,
         max_results: int = 1000,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        include_metadata: bool = False,
+        options: SearchOptions | None = None,
+        canonicalize: bool = False,
+        capture_group: int | None = None,
+        languages: list[str] | None = None,
     ) -> list[SearchResult]:
         """Search files for content matching query.
 
@@ -205,9 +1117,291 @@ class FileSystem:
             glob_pattern: File pattern to search in.
             case_sensitive: Case sensitive search.
             max_results: Maximum number of results.
+            first_per_file_with_count: Return only the first match per file,
+                with file_match_count set to the file's total matches.
+            include_hidden: Search hidden files (dotfiles).
+            include_ignored: Search files excluded by .gitignore/.ignore rules.
+                This exposes secrets files, build output and .git internals.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+            normalize_whitespace: Collapse runs of whitespace in each line to a
+                single space before matching (the regex itself is unchanged).
+            head_bytes: Search only the first N bytes of each file.
+            tail_bytes: Search only the last N bytes of each file; line numbers
+                stay file-relative. Mutually exclusive with head_bytes.
+            symbol_context: Set each result's symbol to the enclosing function,
+                class or method name (best-effort, recognised languages only).
+            include_metadata: Set each result's metadata to the file's indexed
+                FileMetadata, looked up once per file (None if not indexed yet).
+            options: A fully configured SearchOptions. When given it takes
+                precedence and every argument except query, glob_pattern and
+                canonicalize is ignored.
+            canonicalize: Resolve symlinks in each result's file path, as in
+                glob(canonicalize=True).
+            capture_group: Report this capture group as each result's content,
+                with its columns in capture_span, skipping lines where the
+                group did not participate.
+            languages: Search only files whose detected language is one of
+                these names, compared case-insensitively, e.g. ["python"].
+                Detection is by file name; unrecognised files never match.
+
+        Returns:
+            List of SearchResult objects.
+        """
+
+    def grep_iter(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        options: SearchOptions | None = None,
+    ) -> SearchIter:
+        """Search files for content, yielding results as they are found.
+
+        Returns immediately; dropping the iterator cancels the search.
+
+        Returns:
+            SearchIter yielding SearchResult objects in completion order.
+        """
+
+    def grep_summary(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        first_per_file_with_count: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+        normalize_whitespace: bool = False,
+        head_bytes: int | None = None,
+        tail_bytes: int | None = None,
+        symbol_context: bool = False,
+        include_metadata: bool = False,
+        options: SearchOptions | None = None,
+        capture_group: int | None = None,
+        languages: list[str] | None = None,
+    ) -> SearchSummary:
+        """Search files for content, returning results with search statistics.
+
+        Takes the same arguments as grep().
+
+        Returns:
+            SearchSummary object.
+        """
+
+    def grep_text(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        context_lines: int = 0,
+    ) -> str:
+        """Search files and return ripgrep-style "path:line:content" text.
+
+        Args:
+            query: Search pattern (regex supported).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of matches.
+            context_lines: Lines of context around each match.
+
+        Returns:
+            Formatted results, sorted by path.
+        """
+
+    def grep_columnar(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        options: SearchOptions | None = None,
+    ) -> tuple[list[str], list[int], list[str]]:
+        """Search files and return the results as parallel lists.
+
+        Args:
+            query: Search pattern (regex).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            options: A fully configured SearchOptions; when given, every other
+                argument except query and glob_pattern is ignored.
+
+        Returns:
+            Tuple of (files, line_numbers, contents), one entry per match.
+        """
+
+    def grep_locations(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+    ) -> list[Location]:
+        """Search files and return each match as a source range.
+
+        Args:
+            query: Search pattern (regex).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of matching lines.
+
+        Returns:
+            List of Location objects, one per regex match.
+        """
+
+    def grep_count_by_dir(
+        self,
+        query: str,
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+    ) -> dict[str, int]:
+        """Count matching lines per directory.
+
+        Args:
+            query: Search pattern (regex supported).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+
+        Returns:
+            Dict mapping directories relative to root ("." for root itself)
+            to their number of matching lines.
+        """
+
+    def any_match(
+        self, query: str, glob_pattern: str = "**/*", case_sensitive: bool = False
+    ) -> bool:
+        """Check whether any file contains a match.
+
+        Args:
+            query: Search pattern (regex supported).
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+
+        Returns:
+            True as soon as one match is found, False if no file matches.
+        """
+
+    def find(
+        self, path_glob: str, content_query: str, options: SearchOptions | None = None
+    ) -> list[str]:
+        """Find files matching both a path glob and a content query.
+
+        Args:
+            path_glob: Glob pattern for candidate files, e.g. "src/**/*.py".
+            content_query: Search pattern (regex) a file must contain.
+            options: SearchOptions for the content search; max_results caps the
+                number of files returned.
+
+        Returns:
+            Sorted, deduplicated file paths relative to root.
+
+        Raises:
+            ValueError: If the query, glob pattern or options are invalid.
+        """
+
+    def grep_multi(
+        self,
+        patterns: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search files for several independent patterns in one pass.
+
+        Args:
+            patterns: Regex patterns to search for.
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum total number of results.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+
+        Returns:
+            List of SearchResult objects, each with pattern_index set.
+        """
+
+    def grep_all_keywords(
+        self,
+        keywords: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search for lines containing every one of several keywords.
+
+        Args:
+            keywords: Substrings that must all appear on a line.
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
 
         Returns:
             List of SearchResult objects.
+
+        Raises:
+            ValueError: If keywords is empty.
+        """
+
+    def grep_any_keyword(
+        self,
+        keywords: list[str],
+        glob_pattern: str = "**/*",
+        case_sensitive: bool = False,
+        max_results: int = 1000,
+        include_hidden: bool = True,
+        include_ignored: bool = False,
+        max_files_scanned: int | None = None,
+        order_by: SearchOrder | None = None,
+        binary: BinaryMode = BinaryMode.SKIP,
+    ) -> list[SearchResult]:
+        """Search for lines containing at least one of several keywords.
+
+        Args:
+            keywords: Substrings of which at least one must appear on a line.
+            glob_pattern: File pattern to search in.
+            case_sensitive: Case sensitive search.
+            max_results: Maximum number of results.
+            include_hidden: Search dotfiles and dot-directories.
+            include_ignored: Search files excluded by ignore rules.
+            max_files_scanned: Stop after searching this many files (None = no limit).
+            order_by: SearchOrder applied to the results (None = unspecified order).
+            binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+
+        Returns:
+            List of SearchResult objects.
+
+        Raises:
+            ValueError: If keywords is empty.
         """
 
     def get_metadata(self, path: str) -> FileMetadata:
@@ -220,17 +1414,133 @@ class FileSystem:
             FileMetadata object.
         """
 
-    def refresh(self) -> None:
-        """Force refresh the file index."""
+    def refresh(self) -> bool:
+        """Force refresh the file index.
+
+        The tree is re-walked into a separate index that replaces the current
+        one when complete, so queries keep answering from the previous entries.
+
+        Returns:
+            True if the new index was swapped in, False if cancelled or another
+            build was already running.
+        """
+
+    def cancel_refresh(self) -> bool:
+        """Cancel a refresh in progress, keeping the current entries.
+
+        The initial build is not cancelled, since the index would be left not
+        ready.
+
+        Returns:
+            True if a refresh was running, False if none was or the initial
+            build is still in progress.
+        """
+
+    def refresh_paths(self, paths: list[str]) -> int:
+        """Refresh specific paths in the index without a full rebuild."""
 
     def is_ready(self) -> bool:
         """Check if the index is ready."""
 
+    def ping(self) -> str:
+        """Return "agent-gear <version> <root>" without any I/O or locking."""
+
+    def is_truncated(self) -> bool:
+        """Check whether max_index_entries left paths out of the index."""
+
+    def index_fingerprint(self) -> str:
+        """Fingerprint the indexed tree from each file's path, size and mtime.
+
+        Returns:
+            64 hex digits, equal for indexes of the same unchanged tree.
+
+        Raises:
+            RuntimeError: If the index is not ready yet.
+        """
+
+    def memory_usage(self) -> int:
+        """Estimate the memory used by the file index, in bytes."""
+
+    def is_case_insensitive(self) -> bool:
+        """Check whether paths are matched case-insensitively."""
+
+    def check_writable(self, path: str) -> None:
+        """Check a path against the writable_extensions policy, without I/O.
+
+        Raises:
+            PermissionError: If the policy does not allow writing to the path.
+        """
+
+    def cached_globs(self) -> list[str]:
+        """List the glob patterns currently held in the compiled-pattern cache.
+
+        Returns:
+            Sorted list of cached pattern strings.
+        """
+
+    def clear_glob_cache(self) -> None:
+        """Empty the compiled glob cache to reclaim memory."""
+
+    def save_index(self, path: str, compression_level: int | None = None) -> None:
+        """Save the file index to a snapshot file.
+
+        Args:
+            path: Snapshot file path.
+            compression_level: zstd compression level (1-22), or None for uncompressed.
+        """
+
+    def load_index(self, path: str) -> None:
+        """Replace the file index with a saved snapshot (compression auto-detected).
+
+        Args:
+            path: Snapshot file path.
+        """
+
     def is_watching(self) -> bool:
         """Check if file watching is active."""
 
+    def watcher_healthy(self) -> bool:
+        """Check whether the watcher is still delivering events.
+
+        Returns False if there is no watcher or its backend died (e.g. the
+        root directory was deleted); recreate the FileSystem to recover.
+        """
+
+    def watch_mode(self) -> WatchMode | None:
+        """Get the watcher backend in use (NATIVE or POLL), or None."""
+
+    def watch_subpath(self, subpath: str, debounce_ms: int = 100) -> WatchHandle:
+        """Watch a subtree separately, with its own debounce and event queue.
+
+        Args:
+            subpath: Directory to watch (relative to root or absolute).
+            debounce_ms: Debounce for this watch (0 = raw mode).
+
+        Returns:
+            WatchHandle; stopped on stop(), when dropped, or on close().
+        """
+
+    def pending_paths(self) -> list[str]:
+        """List paths with changes the index has not applied yet.
+
+        Returns:
+            Sorted list of paths relative to root; empty without a watcher.
+        """
+
     def pending_changes(self) -> int:
-        """Get the number of pending file change events."""
+        """Count applied changes that poll_changes() has not taken yet.
+
+        Counting does not consume them; changes still in the debounce window
+        are not included.
+        """
+
+    def poll_changes(self) -> list[WatchEvent]:
+        """Take the changes the watcher has applied to the index.
+
+        Returns:
+            List of WatchEvent objects, oldest first, already reflected in the
+            index; empty without a watcher.
+        """
 
     def close(self) -> None:
         """Close the filesystem and release resources."""
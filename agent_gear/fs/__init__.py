@@ -5,15 +5,29 @@ for convenience.
 """
 
 from agent_gear import (
+    BinaryMode,
+    DiffHunk,
     FileMetadata,
     FileSystem,
+    FollowHandle,
+    Location,
     SearchOptions,
+    SearchOrder,
     SearchResult,
+    SearchSummary,
+    WriteVerificationError,
 )
 
 __all__ = [
+    "BinaryMode",
+    "DiffHunk",
     "FileSystem",
     "FileMetadata",
+    "FollowHandle",
+    "Location",
     "SearchOptions",
+    "SearchOrder",
     "SearchResult",
+    "SearchSummary",
+    "WriteVerificationError",
 ]
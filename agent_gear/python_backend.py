@@ -13,6 +13,17 @@ import re
 from pathlib import Path
 
 
+def _line_offset(content: str, line: int) -> int:
+    """Return the offset where a 1-indexed line starts, clamped to the end."""
+    offset = 0
+    for _ in range(line - 1):
+        offset = content.find("\n", offset)
+        if offset < 0:
+            return len(content)
+        offset += 1
+    return offset
+
+
 class PythonFileBackend:
     """Pure Python file system backend for external path operations.
 
@@ -56,6 +67,7 @@ class PythonFileBackend:
         path: str,
         start_line: int = 0,
         count: int | None = None,
+        max_bytes: int | None = None,
         encoding: str = "utf-8",
     ) -> list[str]:
         """Read specific lines from a file.
@@ -64,6 +76,7 @@ class PythonFileBackend:
             path: Absolute file path.
             start_line: Starting line number (0-indexed).
             count: Number of lines to read (None = read to end).
+            max_bytes: Byte budget for the returned lines (None = no budget).
             encoding: Text encoding.
 
         Returns:
@@ -74,13 +87,20 @@ class PythonFileBackend:
             raise FileNotFoundError(f"File not found: {path}")
 
         lines: list[str] = []
+        budget = max_bytes
         with open(file_path, encoding=encoding) as f:
             for i, line in enumerate(f):
                 if i < start_line:
                     continue
                 if count is not None and len(lines) >= count:
                     break
-                lines.append(line.rstrip("\n\r"))
+                line = line.rstrip("\n\r")
+                if budget is not None:
+                    size = len(line.encode(encoding))
+                    if size > budget:
+                        break
+                    budget -= size
+                lines.append(line)
         return lines
 
     def read_file_range(
@@ -110,18 +130,65 @@ class PythonFileBackend:
             data = f.read(limit)
         return data.decode(encoding)
 
-    def write_file(self, path: str, content: str, encoding: str = "utf-8") -> bool:
+    def read_page(
+        self, path: str, cursor: int | None = None, max_lines: int = 1000
+    ) -> tuple[list[str], int | None]:
+        """Read a page of lines starting at a byte offset.
+
+        Args:
+            path: Absolute file path.
+            cursor: Byte offset to resume at (None = start of file).
+            max_lines: Maximum number of lines to return.
+
+        Returns:
+            Tuple of (lines, next_cursor); next_cursor is None at end of file.
+        """
+        file_path = Path(path)
+        if not file_path.exists():
+            raise FileNotFoundError(f"File not found: {path}")
+
+        lines: list[str] = []
+        with open(file_path, "rb") as f:
+            f.seek(cursor or 0)
+            while len(lines) < max_lines:
+                raw = f.readline()
+                if not raw:
+                    return lines, None
+                lines.append(raw.removesuffix(b"\n").removesuffix(b"\r").decode("utf-8"))
+            return lines, (f.tell() if f.peek(1) else None)
+
+    def write_file(
+        self,
+        path: str,
+        content: str,
+        encoding: str = "utf-8",
+        errors: str = "strict",
+        verify: bool = False,
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+    ) -> bool:
         """Write content to file atomically (using temp file + rename).
 
         Args:
             path: Absolute file path.
             content: Content to write.
             encoding: Text encoding.
+            errors: Codec error handling ("strict" or "replace").
+            verify: Read the file back and compare it with what was written.
+            ensure_trailing_newline: Append a line ending if missing.
+            strip_trailing_whitespace: Trim trailing spaces and tabs from each line.
 
         Returns:
             True if successful.
+
+        Raises:
+            WriteVerificationError: If verify is set and the content differs.
         """
         file_path = Path(path)
+        content = self._normalize_text(
+            content, ensure_trailing_newline, strip_trailing_whitespace
+        )
+        data = content.encode(encoding, errors)
 
         # Create parent directories if needed
         file_path.parent.mkdir(parents=True, exist_ok=True)
@@ -129,7 +196,7 @@ class PythonFileBackend:
         # Write to temp file first, then rename for atomicity
         temp_path = file_path.with_suffix(file_path.suffix + ".tmp")
         try:
-            temp_path.write_text(content, encoding=encoding)
+            temp_path.write_bytes(data)
             # fsync to ensure data is on disk
             fd = os.open(str(temp_path), os.O_RDONLY)
             try:
@@ -138,12 +205,83 @@ class PythonFileBackend:
                 os.close(fd)
             # Atomic rename
             temp_path.rename(file_path)
-            return True
         except Exception:
             if temp_path.exists():
                 temp_path.unlink()
             raise
 
+        if verify:
+            self._verify_content(file_path, data)
+        return True
+
+    @staticmethod
+    def _normalize_text(
+        content: str, ensure_trailing_newline: bool, strip_trailing_whitespace: bool
+    ) -> str:
+        """Apply the same end-of-line fixups as the Rust writer."""
+        if strip_trailing_whitespace:
+            lines = content.split("\n")
+            for i, line in enumerate(lines):
+                if line.endswith("\r"):
+                    lines[i] = line[:-1].rstrip(" \t") + "\r"
+                else:
+                    lines[i] = line.rstrip(" \t")
+            content = "\n".join(lines)
+        if ensure_trailing_newline and content and not content.endswith("\n"):
+            content += "\r\n" if "\r\n" in content else "\n"
+        return content
+
+    @staticmethod
+    def _verify_content(file_path: Path, expected: bytes) -> None:
+        """Stream a file through a hash and compare it with the expected bytes."""
+        import hashlib
+
+        from agent_gear._rust_core import WriteVerificationError
+
+        actual = hashlib.sha256()
+        with open(file_path, "rb") as f:
+            for chunk in iter(lambda: f.read(64 * 1024), b""):
+                actual.update(chunk)
+        if actual.digest() != hashlib.sha256(expected).digest():
+            raise WriteVerificationError(
+                f"Verification failed for {file_path}: "
+                "content on disk differs from what was written"
+            )
+
+    def write_new(self, path: str, content: str, encoding: str = "utf-8") -> bool:
+        """Create a file only if it does not already exist.
+
+        Writes a temp file, then hard-links it into place, which fails
+        atomically if the target exists.
+
+        Args:
+            path: Absolute file path.
+            content: Content to write.
+            encoding: Text encoding.
+
+        Returns:
+            True if the file was created, False if it already existed.
+        """
+        file_path = Path(path)
+        file_path.parent.mkdir(parents=True, exist_ok=True)
+
+        temp_path = file_path.with_suffix(file_path.suffix + ".tmp")
+        try:
+            temp_path.write_text(content, encoding=encoding)
+            fd = os.open(str(temp_path), os.O_RDONLY)
+            try:
+                os.fsync(fd)
+            finally:
+                os.close(fd)
+            try:
+                os.link(temp_path, file_path)
+            except FileExistsError:
+                return False
+            return True
+        finally:
+            if temp_path.exists():
+                temp_path.unlink()
+
     def write_file_fast(self, path: str, content: str, encoding: str = "utf-8") -> bool:
         """Write content to file without atomicity guarantee (fast mode).
 
@@ -167,7 +305,13 @@ class PythonFileBackend:
         new_text: str,
         strict: bool = True,
         encoding: str = "utf-8",
-    ) -> bool:
+        errors: str = "strict",
+        ensure_trailing_newline: bool = False,
+        strip_trailing_whitespace: bool = False,
+        max_replacements: int | None = None,
+        line_start: int | None = None,
+        line_end: int | None = None,
+    ) -> int:
         """Replace text in file.
 
         Args:
@@ -176,30 +320,57 @@ class PythonFileBackend:
             new_text: Replacement text.
             strict: If true, error if old_text is not unique or not found.
             encoding: Text encoding.
+            errors: Codec error handling when writing ("strict" or "replace").
+            ensure_trailing_newline: Make the edited file end with a line ending.
+            strip_trailing_whitespace: Trim trailing spaces and tabs from each line.
+            max_replacements: Replace at most this many occurrences (None = all).
+            line_start: First 1-indexed line of the region to edit in (None = 1).
+            line_end: Last line of the region, inclusive (None = end of file).
+                Occurrences not entirely inside the region are ignored.
 
         Returns:
-            True if replacement was made.
+            Number of occurrences replaced.
 
         Raises:
-            ValueError: If strict mode and text not found or not unique.
+            ValueError: If strict mode and text not found or not unique, or the
+                line range is invalid.
         """
+        if line_start is not None and line_start < 1:
+            raise ValueError("line_start is 1-indexed")
+        if line_end is not None and line_end < (line_start or 1):
+            raise ValueError("line_end is before line_start")
+
         file_path = Path(path)
         if not file_path.exists():
             raise FileNotFoundError(f"File not found: {path}")
 
         content = file_path.read_text(encoding=encoding)
-        count = content.count(old_text)
+        start = _line_offset(content, line_start or 1)
+        end = len(content) if line_end is None else _line_offset(content, line_end + 1)
+        region = content[start:end]
+        count = region.count(old_text)
 
         if count == 0:
             if strict:
                 raise ValueError(f"Text not found in file: {path}")
-            return False
+            return 0
 
         if count > 1 and strict:
             raise ValueError(f"Text found {count} times in file (must be unique): {path}")
 
-        new_content = content.replace(old_text, new_text, 1)
-        return self.write_file(path, new_content, encoding)
+        replaced = count if max_replacements is None else min(count, max_replacements)
+        if replaced == 0:
+            return 0
+        new_content = content[:start] + region.replace(old_text, new_text, replaced) + content[end:]
+        self.write_file(
+            path,
+            new_content,
+            encoding,
+            errors,
+            ensure_trailing_newline=ensure_trailing_newline,
+            strip_trailing_whitespace=strip_trailing_whitespace,
+        )
+        return replaced
 
     def list_files(
         self,
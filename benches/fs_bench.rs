@@ -55,5 +55,47 @@ fn bench_glob(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, bench_index_build, bench_list, bench_glob);
+fn bench_read_cache(c: &mut Criterion) {
+    let dir = tempdir().unwrap();
+    create_test_files(dir.path(), 1000);
+
+    // An agent re-reading a small working set out of a larger tree
+    let paths: Vec<_> = (0..1000)
+        .map(|i| dir.path().join(format!("dir_{}/file_{}.txt", i / 100, i)))
+        .collect();
+    let working_set: Vec<_> = (0..1000).map(|i| &paths[(i * 7) % 20]).collect();
+
+    let cache = agent_gear::fs::io::ReadCache::new(256, 32 * 1024 * 1024);
+    c.bench_function("read_cache_working_set", |b| {
+        b.iter(|| {
+            for path in &working_set {
+                black_box(cache.read(path, None).unwrap());
+            }
+        })
+    });
+
+    let (hits, misses) = cache.stats();
+    println!(
+        "read_cache hit rate: {:.1}% ({} hits, {} misses)",
+        100.0 * hits as f64 / (hits + misses).max(1) as f64,
+        hits,
+        misses
+    );
+
+    c.bench_function("read_uncached_working_set", |b| {
+        b.iter(|| {
+            for path in &working_set {
+                black_box(std::fs::read_to_string(path).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_index_build,
+    bench_list,
+    bench_glob,
+    bench_read_cache
+);
 criterion_main!(benches);
@@ -3,8 +3,11 @@
 //! Implements the "write to temp -> fsync -> rename" pattern for atomic file updates.
 //! This ensures that file writes are atomic even if the process crashes during the write.
 
+use parking_lot::Mutex;
+use std::collections::VecDeque;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::utils::error::{AgentGearError, Result};
 
@@ -53,11 +56,210 @@ pub fn atomic_write(path: &Path, content: &[u8]) -> Result<()> {
     Ok(())
 }
 
+/// Write several files so that either all of them change or none do
+///
+/// Runs in two phases:
+/// 1. Prepare: write and fsync a temp file next to every target. Any
+///    failure here drops the temps already written, which deletes them, so
+///    no target is touched.
+/// 2. Commit: rename each temp over its target, in the given order.
+///
+/// Without a journal the commit phase is not atomic as a whole: a crash or
+/// a failing rename partway through leaves the earlier targets replaced and
+/// the later ones unchanged. The window is only a series of renames, with
+/// all data already on disk. Missing parent directories are created during
+/// the prepare phase and are not removed on failure.
+///
+/// # Errors
+/// Returns an error from the first failing step; a commit-phase error says
+/// how many files were already replaced.
+pub fn atomic_write_all(files: &[(PathBuf, Vec<u8>)]) -> Result<()> {
+    let mut staged = Vec::with_capacity(files.len());
+    for (path, content) in files {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        if !dir.exists() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut temp_file = tempfile::NamedTempFile::new_in(dir)?;
+        temp_file.write_all(content)?;
+        temp_file.as_file().sync_all()?;
+        staged.push((temp_file, path));
+    }
+
+    let total = staged.len();
+    for (committed, (temp_file, path)) in staged.into_iter().enumerate() {
+        temp_file.persist(path).map_err(|e| {
+            AgentGearError::Io(std::io::Error::other(format!(
+                "Failed to persist {} after replacing {} of {} files: {}",
+                path.display(),
+                committed,
+                total,
+                e
+            )))
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Write content to a new file atomically, never replacing an existing one
+///
+/// Same temp-file pattern as `atomic_write`, but the final step is an
+/// exclusive rename (`link`/`RENAME_NOREPLACE`), so there is no window where
+/// another writer's file could be clobbered and readers never see a partial
+/// file.
+///
+/// # Returns
+/// `true` if the file was created, `false` if it already existed
+pub fn atomic_write_new(path: &Path, content: &[u8]) -> Result<bool> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let mut temp_file = tempfile::NamedTempFile::new_in(dir)?;
+    temp_file.write_all(content)?;
+    temp_file.as_file().sync_all()?;
+
+    match temp_file.persist_noclobber(path) {
+        Ok(_) => Ok(true),
+        Err(e) if e.error.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(AgentGearError::Io(std::io::Error::other(format!(
+            "Failed to persist file: {}",
+            e
+        )))),
+    }
+}
+
+/// Read a file back and check it holds exactly `expected`
+///
+/// The file is streamed through a hasher in fixed-size chunks, so only the
+/// caller's copy of the content is held in memory, even for large files.
+///
+/// # Errors
+/// Returns `VerifyFailed` if the length or hash of the file differs.
+pub fn verify_content(path: &Path, expected: &[u8]) -> Result<()> {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+    use std::io::Read;
+
+    let mismatch = || AgentGearError::VerifyFailed(path.display().to_string());
+
+    let mut file = std::fs::File::open(path)?;
+    if file.metadata()?.len() != expected.len() as u64 {
+        return Err(mismatch());
+    }
+
+    let mut expected_hash = DefaultHasher::new();
+    expected_hash.write(expected);
+
+    let mut actual_hash = DefaultHasher::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        actual_hash.write(&buffer[..n]);
+    }
+
+    if actual_hash.finish() != expected_hash.finish() {
+        return Err(mismatch());
+    }
+    Ok(())
+}
+
+/// Suffix of every staged file written by `write_staged`
+pub const STAGED_SUFFIX: &str = ".agstaged";
+
+/// Write content to a new staged file beside `target`, leaving it in place
+///
+/// The staged file is named `.<target name>.<random>.agstaged` in the
+/// target's directory, so a later `promote` onto the target is a rename
+/// within one filesystem. Nothing removes it automatically: a staged file
+/// that is never promoted has to be deleted by the caller, and orphans
+/// from a crash can be found by the `STAGED_SUFFIX` suffix.
+///
+/// # Arguments
+/// * `target` - Path the content is meant to end up at
+/// * `content` - Bytes to write
+///
+/// # Errors
+/// Returns an error if the directory cannot be created or the write fails;
+/// no staged file is left behind in that case.
+pub fn write_staged(target: &Path, content: &[u8]) -> Result<PathBuf> {
+    let dir = target.parent().unwrap_or(Path::new("."));
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let name = target
+        .file_name()
+        .ok_or_else(|| AgentGearError::PathNotFound(target.display().to_string()))?
+        .to_string_lossy();
+    let mut temp_file = tempfile::Builder::new()
+        .prefix(&format!(".{}.", name))
+        .suffix(STAGED_SUFFIX)
+        .tempfile_in(dir)?;
+
+    temp_file.write_all(content)?;
+    temp_file.as_file().sync_all()?;
+
+    temp_file
+        .into_temp_path()
+        .keep()
+        .map_err(|e| AgentGearError::Io(e.error))
+}
+
+/// Atomically move a file into place, replacing any existing target
+///
+/// Uses a single `rename`, which is only atomic when both paths live on the
+/// same filesystem. Cross-filesystem moves are rejected instead of silently
+/// degrading to a copy.
+///
+/// # Arguments
+/// * `from` - Source file (typically a staged temp file)
+/// * `to` - Final destination path
+///
+/// # Errors
+/// Returns `CrossDevice` if the paths are on different filesystems.
+pub fn promote(from: &Path, to: &Path) -> Result<()> {
+    if !from.exists() {
+        return Err(AgentGearError::PathNotFound(from.display().to_string()));
+    }
+
+    let dir = to.parent().unwrap_or(Path::new("."));
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let cross_device =
+        || AgentGearError::CrossDevice(from.display().to_string(), to.display().to_string());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if std::fs::metadata(from)?.dev() != std::fs::metadata(dir)?.dev() {
+            return Err(cross_device());
+        }
+    }
+
+    std::fs::rename(from, to).map_err(|e| {
+        // EXDEV on Unix, ERROR_NOT_SAME_DEVICE on Windows
+        let exdev = if cfg!(windows) { 17 } else { 18 };
+        if e.raw_os_error() == Some(exdev) {
+            cross_device()
+        } else {
+            AgentGearError::Io(e)
+        }
+    })
+}
+
 /// Write content to a file atomically, preserving permissions
 ///
 /// Similar to `atomic_write`, but preserves the original file's permissions
 /// if the file already exists.
-#[allow(dead_code)]
 pub fn atomic_write_preserve_perms(path: &Path, content: &[u8]) -> Result<()> {
     // Get original permissions if file exists
     let original_perms = path.metadata().ok().map(|m| m.permissions());
@@ -102,11 +304,172 @@ pub fn create_backup(path: &Path) -> Result<std::path::PathBuf> {
     Ok(backup_path)
 }
 
+/// Default number of snapshots a `FileStash` keeps
+pub const DEFAULT_STASH_ENTRIES: usize = 32;
+
+/// Default total content size a `FileStash` keeps (64MB)
+pub const DEFAULT_STASH_BYTES: usize = 64 * 1024 * 1024;
+
+/// A file's content as it was when stashed
+struct StashedFile {
+    token: u64,
+    path: PathBuf,
+    content: Vec<u8>,
+}
+
+/// In-memory copies of files, restorable by token (single-level undo)
+///
+/// Nothing is written to disk until a restore. Once either budget is
+/// exceeded the oldest snapshots are evicted, so a token can expire.
+pub struct FileStash {
+    entries: Mutex<VecDeque<StashedFile>>,
+    next_token: AtomicU64,
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl FileStash {
+    /// Create a stash holding at most `max_entries` snapshots and
+    /// `max_bytes` of content
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            next_token: AtomicU64::new(1),
+            max_entries,
+            max_bytes,
+        }
+    }
+
+    /// Copy a file's current content into the stash
+    ///
+    /// Fails with `FileTooLarge` for a file over the byte budget, since it
+    /// could never be kept.
+    pub fn stash(&self, path: &Path) -> Result<u64> {
+        use std::io::Read;
+
+        let file = std::fs::File::open(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        })?;
+        let too_large = |size: u64| AgentGearError::FileTooLarge {
+            path: path.display().to_string(),
+            size,
+            limit: self.max_bytes as u64,
+        };
+
+        // Refuse by size before reading; the capped read also catches a
+        // file that grew after the stat
+        let limit = self.max_bytes as u64;
+        let size = file.metadata()?.len();
+        if size > limit {
+            return Err(too_large(size));
+        }
+        let mut content = Vec::with_capacity(size as usize);
+        file.take(limit + 1).read_to_end(&mut content)?;
+        if content.len() as u64 > limit {
+            return Err(too_large(content.len() as u64));
+        }
+
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        let mut entries = self.entries.lock();
+        entries.push_back(StashedFile {
+            token,
+            path: path.to_path_buf(),
+            content,
+        });
+
+        // Evict the oldest snapshots until both budgets are met
+        let mut bytes: usize = entries.iter().map(|entry| entry.content.len()).sum();
+        while entries.len() > self.max_entries || bytes > self.max_bytes {
+            match entries.pop_front() {
+                Some(evicted) => bytes -= evicted.content.len(),
+                None => break,
+            }
+        }
+        Ok(token)
+    }
+
+    /// Path a token was stashed from, if it is still held
+    pub fn path(&self, token: u64) -> Option<PathBuf> {
+        self.entries
+            .lock()
+            .iter()
+            .find(|entry| entry.token == token)
+            .map(|entry| entry.path.clone())
+    }
+
+    /// Atomically rewrite a stashed file to its stashed content
+    ///
+    /// The snapshot is consumed on success and kept if the write fails, so
+    /// the restore can be retried. Returns the restored path, or None for a
+    /// token that was already restored or has been evicted.
+    pub fn restore(&self, token: u64) -> Result<Option<PathBuf>> {
+        let mut entries = self.entries.lock();
+        let Some(position) = entries.iter().position(|entry| entry.token == token) else {
+            return Ok(None);
+        };
+        let entry = &entries[position];
+        atomic_write_preserve_perms(&entry.path, &entry.content)?;
+        Ok(entries.remove(position).map(|entry| entry.path))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tempfile::tempdir;
 
+    #[test]
+    fn test_file_stash() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("main.rs");
+        std::fs::write(&path, "fn main() {}").unwrap();
+
+        let stash = FileStash::new(2, 1024);
+        let token = stash.stash(&path).unwrap();
+        std::fs::write(&path, "fn main() { broken").unwrap();
+        assert_eq!(stash.restore(token).unwrap(), Some(path.clone()));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "fn main() {}");
+
+        // Each snapshot restores once
+        assert_eq!(stash.restore(token).unwrap(), None);
+
+        // The oldest snapshot is evicted past the entry budget
+        let first = stash.stash(&path).unwrap();
+        let second = stash.stash(&path).unwrap();
+        stash.stash(&path).unwrap();
+        assert_eq!(stash.path(first), None);
+        assert_eq!(stash.path(second), Some(path.clone()));
+
+        std::fs::write(&path, vec![b'x'; 2048]).unwrap();
+        assert!(matches!(
+            stash.stash(&path),
+            Err(AgentGearError::FileTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_write_staged() {
+        let dir = tempdir().unwrap();
+        let target = dir.path().join("config.toml");
+        std::fs::write(&target, "old").unwrap();
+
+        let staged = write_staged(&target, b"new").unwrap();
+        assert_eq!(staged.parent(), target.parent());
+        let name = staged.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with(".config.toml."));
+        assert!(name.ends_with(STAGED_SUFFIX));
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "old");
+        assert_eq!(std::fs::read_to_string(&staged).unwrap(), "new");
+
+        promote(&staged, &target).unwrap();
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "new");
+        assert!(!staged.exists());
+    }
+
     #[test]
     fn test_atomic_write() {
         let dir = tempdir().unwrap();
@@ -156,4 +519,101 @@ mod tests {
         let content = std::fs::read_to_string(&file_path).unwrap();
         assert_eq!(content, "Hello, World!");
     }
+
+    #[test]
+    fn test_atomic_write_all() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("nested").join("b.txt");
+        atomic_write(&a, b"old a").unwrap();
+
+        atomic_write_all(&[
+            (a.clone(), b"new a".to_vec()),
+            (b.clone(), b"new b".to_vec()),
+        ])
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "new a");
+        assert_eq!(std::fs::read_to_string(&b).unwrap(), "new b");
+    }
+
+    #[test]
+    fn test_atomic_write_all_prepare_failure() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        atomic_write(&a, b"old a").unwrap();
+
+        // A regular file cannot be a parent directory, so staging fails
+        let blocked = a.join("b.txt");
+        let result = atomic_write_all(&[(a.clone(), b"new a".to_vec()), (blocked, b"b".to_vec())]);
+        assert!(result.is_err());
+
+        // Nothing changed and the staged temp was removed
+        assert_eq!(std::fs::read_to_string(&a).unwrap(), "old a");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_promote() {
+        let dir = tempdir().unwrap();
+        let temp_path = dir.path().join("foo.tmp");
+        let final_path = dir.path().join("foo");
+
+        atomic_write(&final_path, b"Old").unwrap();
+        atomic_write(&temp_path, b"New").unwrap();
+
+        promote(&temp_path, &final_path).unwrap();
+
+        assert!(!temp_path.exists());
+        let content = std::fs::read_to_string(&final_path).unwrap();
+        assert_eq!(content, "New");
+    }
+
+    #[test]
+    fn test_promote_missing_source() {
+        let dir = tempdir().unwrap();
+        let result = promote(&dir.path().join("missing"), &dir.path().join("foo"));
+        assert!(matches!(result, Err(AgentGearError::PathNotFound(_))));
+    }
+
+    #[test]
+    fn test_atomic_write_new() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("new").join("foo.txt");
+
+        assert!(atomic_write_new(&file_path, b"First").unwrap());
+        assert!(!atomic_write_new(&file_path, b"Second").unwrap());
+
+        let content = std::fs::read_to_string(&file_path).unwrap();
+        assert_eq!(content, "First");
+
+        // The losing write leaves no temp file behind
+        assert_eq!(
+            std::fs::read_dir(file_path.parent().unwrap())
+                .unwrap()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_verify_content() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("test.txt");
+        let content = "x".repeat(200 * 1024);
+
+        atomic_write(&file_path, content.as_bytes()).unwrap();
+        verify_content(&file_path, content.as_bytes()).unwrap();
+
+        // Same length, different bytes
+        let mut corrupted = content.into_bytes();
+        corrupted[100 * 1024] = b'y';
+        assert!(matches!(
+            verify_content(&file_path, &corrupted),
+            Err(AgentGearError::VerifyFailed(_))
+        ));
+        assert!(matches!(
+            verify_content(&file_path, b"short"),
+            Err(AgentGearError::VerifyFailed(_))
+        ));
+    }
 }
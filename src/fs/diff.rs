@@ -0,0 +1,407 @@
+//! Line-level diffs
+//!
+//! Compares a file against proposed content and reports the changed regions
+//! as hunks, so an agent can review a whole-file rewrite piece by piece
+//! before writing it. Also applies unified diffs, the format agents tend to
+//! produce edits in.
+
+use std::ops::Range;
+
+use pyo3::prelude::*;
+use similar::{DiffOp, TextDiff};
+
+use crate::utils::error::{AgentGearError, Result};
+
+/// A contiguous run of changed lines
+///
+/// Starts are 1-indexed. A pure insertion has `old_lines == 0` and
+/// `old_start` is the old line the new lines are inserted before; a pure
+/// deletion likewise has `new_lines == 0`.
+#[pyclass]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffHunk {
+    /// First line of the hunk in the current file
+    #[pyo3(get)]
+    pub old_start: usize,
+
+    /// Number of lines replaced in the current file
+    #[pyo3(get)]
+    pub old_lines: usize,
+
+    /// First line of the hunk in the new content
+    #[pyo3(get)]
+    pub new_start: usize,
+
+    /// Number of lines in the new content
+    #[pyo3(get)]
+    pub new_lines: usize,
+
+    /// Lines removed from the current file, without terminators
+    #[pyo3(get)]
+    pub removed: Vec<String>,
+
+    /// Lines added by the new content, without terminators
+    #[pyo3(get)]
+    pub added: Vec<String>,
+}
+
+#[pymethods]
+impl DiffHunk {
+    fn __repr__(&self) -> String {
+        format!(
+            "DiffHunk(-{},{} +{},{})",
+            self.old_start, self.old_lines, self.new_start, self.new_lines
+        )
+    }
+}
+
+/// Diff two texts line by line, returning the changed regions
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let old_lines: Vec<&str> = diff.old_slices().to_vec();
+    let new_lines: Vec<&str> = diff.new_slices().to_vec();
+    let strip = |line: &&str| line.trim_end_matches(['\n', '\r']).to_string();
+
+    // Adjacent insert/delete/replace ops form a single changed region
+    let mut regions: Vec<(Range<usize>, Range<usize>)> = Vec::new();
+    let mut pending: Option<(Range<usize>, Range<usize>)> = None;
+    for op in diff.ops() {
+        if matches!(op, DiffOp::Equal { .. }) {
+            regions.extend(pending.take());
+            continue;
+        }
+        let (old_range, new_range) = (op.old_range(), op.new_range());
+        pending = Some(match pending.take() {
+            Some((old, new)) => (old.start..old_range.end, new.start..new_range.end),
+            None => (old_range, new_range),
+        });
+    }
+    regions.extend(pending);
+
+    regions
+        .into_iter()
+        .map(|(old_range, new_range)| DiffHunk {
+            old_start: old_range.start + 1,
+            old_lines: old_range.len(),
+            new_start: new_range.start + 1,
+            new_lines: new_range.len(),
+            removed: old_lines[old_range].iter().map(strip).collect(),
+            added: new_lines[new_range].iter().map(strip).collect(),
+        })
+        .collect()
+}
+
+/// One line of a unified diff hunk
+#[derive(Clone, Debug, PartialEq)]
+enum PatchLine {
+    Context(String),
+    Remove(String),
+    /// Added text, and whether it is left without a line ending
+    Add(String, bool),
+}
+
+/// A hunk parsed from a unified diff
+#[derive(Debug)]
+struct PatchHunk {
+    /// Header as written, for error messages
+    header: String,
+    /// `old_start` from the header (1-indexed, or 0 for an empty file)
+    old_start: usize,
+    lines: Vec<PatchLine>,
+}
+
+impl PatchHunk {
+    /// Lines the hunk expects in the current file
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| match line {
+                PatchLine::Context(text) | PatchLine::Remove(text) => Some(text.as_str()),
+                PatchLine::Add(..) => None,
+            })
+            .collect()
+    }
+}
+
+/// Parse the `-a,b +c,d` counts of a hunk header
+fn parse_header(header: &str) -> Option<(usize, usize, usize)> {
+    let ranges = header.strip_prefix("@@ ")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(' ')?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_count) = range(old.strip_prefix('-')?)?;
+    let (_, new_count) = range(new.strip_prefix('+')?)?;
+    Some((old_start, old_count, new_count))
+}
+
+/// Parse the hunks of a single-file unified diff
+///
+/// File headers (`---`, `+++`, `diff`, `index`) are skipped. A blank line
+/// inside a hunk is read as an empty context line, since some tools strip
+/// the space in front of it.
+fn parse_patch(patch: &str) -> Result<Vec<PatchHunk>> {
+    let malformed = |msg: String| AgentGearError::Patch(msg);
+    let mut hunks = Vec::new();
+    let mut files = 0;
+    let mut lines = patch.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.starts_with("+++ ") {
+            files += 1;
+            if files > 1 {
+                return Err(malformed("the diff touches more than one file".into()));
+            }
+            continue;
+        }
+        if !line.starts_with("@@ ") {
+            continue;
+        }
+        let (old_start, old_count, new_count) =
+            parse_header(line).ok_or_else(|| malformed(format!("bad hunk header: {}", line)))?;
+
+        let mut hunk = PatchHunk {
+            header: line.to_string(),
+            old_start,
+            lines: Vec::new(),
+        };
+        let (mut old_seen, mut new_seen) = (0, 0);
+        while old_seen < old_count || new_seen < new_count {
+            let Some(body) = lines.next() else {
+                return Err(malformed(format!("hunk {} ends early", line)));
+            };
+            let mut chars = body.chars();
+            let tag = chars.next().unwrap_or(' ');
+            let text = chars.as_str().to_string();
+            match tag {
+                ' ' => {
+                    old_seen += 1;
+                    new_seen += 1;
+                    hunk.lines.push(PatchLine::Context(text));
+                }
+                '-' => {
+                    old_seen += 1;
+                    hunk.lines.push(PatchLine::Remove(text));
+                }
+                '+' => {
+                    new_seen += 1;
+                    hunk.lines.push(PatchLine::Add(text, false));
+                }
+                '\\' => {}
+                _ => return Err(malformed(format!("unexpected line in hunk: {}", body))),
+            }
+            // "\ No newline at end of file" marks the line before it
+            if lines.peek().is_some_and(|next| next.starts_with('\\')) {
+                lines.next();
+                if let Some(PatchLine::Add(_, no_newline)) = hunk.lines.last_mut() {
+                    *no_newline = true;
+                }
+            }
+        }
+        if old_seen != old_count || new_seen != new_count {
+            return Err(malformed(format!("line counts do not match {}", line)));
+        }
+        hunks.push(hunk);
+    }
+
+    if hunks.is_empty() {
+        return Err(malformed("no hunks found".into()));
+    }
+    Ok(hunks)
+}
+
+/// Find where a hunk's old lines sit in the file
+///
+/// Exact matching only accepts the position the header names. Fuzzy
+/// matching also compares lines ignoring trailing whitespace and accepts
+/// any position at or after `from`, preferring the one nearest the header.
+fn locate(
+    lines: &[&str],
+    old: &[&str],
+    expected: usize,
+    from: usize,
+    fuzzy: bool,
+) -> Option<usize> {
+    let strip = |line: &str| line.trim_end_matches(['\n', '\r']).to_string();
+    let matches_at = |start: usize| {
+        start + old.len() <= lines.len()
+            && lines[start..start + old.len()]
+                .iter()
+                .zip(old)
+                .all(|(have, want)| {
+                    if fuzzy {
+                        have.trim_end() == want.trim_end()
+                    } else {
+                        strip(have) == *want
+                    }
+                })
+    };
+
+    if !fuzzy {
+        return (expected >= from && matches_at(expected)).then_some(expected);
+    }
+    let last = lines.len().checked_sub(old.len())?;
+    (from..=last)
+        .filter(|&start| matches_at(start))
+        .min_by_key(|&start| start.abs_diff(expected))
+}
+
+/// Apply a unified diff to text
+///
+/// Context and removed lines must match the text at the line the hunk
+/// header names; with `fuzzy`, a hunk may also match at another position
+/// and with different trailing whitespace. Added lines take the text's
+/// line ending (`\r\n` if its first line ends so). A hunk that does not
+/// match is an error when `strict`, and is otherwise skipped.
+///
+/// # Returns
+/// The patched text and the number of hunks skipped
+pub fn apply_patch(
+    content: &str,
+    patch: &str,
+    strict: bool,
+    fuzzy: bool,
+) -> Result<(String, usize)> {
+    let hunks = parse_patch(patch)?;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let eol = match content.find('\n') {
+        Some(i) if content[..i].ends_with('\r') => "\r\n",
+        _ => "\n",
+    };
+
+    let mut output = String::with_capacity(content.len() + patch.len());
+    let mut cursor = 0;
+    let mut skipped = 0;
+    for hunk in &hunks {
+        let old = hunk.old_lines();
+        // A hunk without old lines inserts after line old_start
+        let expected = match old.len() {
+            0 => hunk.old_start,
+            _ => hunk.old_start.saturating_sub(1),
+        };
+        let position = match old.len() {
+            0 if expected >= cursor && expected <= lines.len() => Some(expected),
+            0 => None,
+            _ => locate(&lines, &old, expected, cursor, fuzzy),
+        };
+        let Some(start) = position else {
+            if strict {
+                return Err(AgentGearError::Patch(format!(
+                    "hunk {} does not match the file",
+                    hunk.header
+                )));
+            }
+            skipped += 1;
+            continue;
+        };
+
+        output.extend(lines[cursor..start].iter().copied());
+        let mut at = start;
+        for line in &hunk.lines {
+            match line {
+                PatchLine::Context(_) => {
+                    output.push_str(lines[at]);
+                    at += 1;
+                }
+                PatchLine::Remove(_) => at += 1,
+                PatchLine::Add(text, no_newline) => {
+                    output.push_str(text);
+                    if !no_newline {
+                        output.push_str(eol);
+                    }
+                }
+            }
+        }
+        cursor = at;
+    }
+    output.extend(lines[cursor..].iter().copied());
+    Ok((output, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "a\nB\nc\nd\ne\n";
+
+        let hunks = diff_lines(old, new);
+        assert_eq!(hunks.len(), 2);
+
+        assert_eq!(
+            (
+                hunks[0].old_start,
+                hunks[0].old_lines,
+                hunks[0].new_start,
+                hunks[0].new_lines
+            ),
+            (2, 1, 2, 1)
+        );
+        assert_eq!(hunks[0].removed, vec!["b"]);
+        assert_eq!(hunks[0].added, vec!["B"]);
+
+        // Pure insertion at the end
+        assert_eq!(
+            (
+                hunks[1].old_start,
+                hunks[1].old_lines,
+                hunks[1].new_start,
+                hunks[1].new_lines
+            ),
+            (5, 0, 5, 1)
+        );
+        assert!(hunks[1].removed.is_empty());
+        assert_eq!(hunks[1].added, vec!["e"]);
+
+        assert!(diff_lines(old, old).is_empty());
+    }
+
+    const PATCH: &str =
+        "--- a/f.txt\n+++ b/f.txt\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -5,0 +6,1 @@\n+f\n";
+
+    #[test]
+    fn test_apply_patch() {
+        let (patched, skipped) = apply_patch("a\nb\nc\nd\ne\n", PATCH, true, false).unwrap();
+        assert_eq!(patched, "a\nB\nc\nd\ne\nf\n");
+        assert_eq!(skipped, 0);
+
+        // Added lines follow the file's line endings
+        let (patched, _) = apply_patch("a\r\nb\r\nc\r\nd\r\ne\r\n", PATCH, true, false).unwrap();
+        assert_eq!(patched, "a\r\nB\r\nc\r\nd\r\ne\r\nf\r\n");
+
+        let patch = "@@ -1 +1,2 @@\n-x\n\\ No newline at end of file\n+x\n+y\n\\ No newline at end of file\n";
+        assert_eq!(apply_patch("x", patch, true, false).unwrap().0, "x\ny");
+
+        let patch = "@@ -0,0 +1 @@\n+new\n";
+        assert_eq!(apply_patch("", patch, true, false).unwrap().0, "new\n");
+    }
+
+    #[test]
+    fn test_apply_patch_mismatch() {
+        // The first hunk's context drifted down by one line
+        let drifted = "header\na\nb\nc\nd\ne\n";
+        assert!(matches!(
+            apply_patch(drifted, PATCH, true, false),
+            Err(AgentGearError::Patch(_))
+        ));
+
+        let (patched, skipped) = apply_patch(drifted, PATCH, false, false).unwrap();
+        assert_eq!(patched, "header\na\nb\nc\nd\nf\ne\n");
+        assert_eq!(skipped, 1);
+
+        // Fuzzy matching finds the nearby context, ignoring trailing spaces
+        let drifted = "header\na  \nb\nc\nd\ne\n";
+        let patch = "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n";
+        let (patched, skipped) = apply_patch(drifted, patch, true, true).unwrap();
+        assert_eq!(patched, "header\na  \nB\nc\nd\ne\n");
+        assert_eq!(skipped, 0);
+
+        assert!(apply_patch("a\n", "not a diff", true, false).is_err());
+        assert!(apply_patch("a\n", "@@ -1,2 +1,2 @@\n a\n", true, false).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+//! Text encoding conversion
+//!
+//! Wraps `encoding_rs` so files in legacy encodings (Shift-JIS, GBK,
+//! Windows-1252, ...) can be written back in the encoding they were read in.
+//! UTF-8 stays a zero-copy fast path.
+
+use encoding_rs::{EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use std::borrow::Cow;
+
+use crate::utils::error::{AgentGearError, Result};
+
+/// Character written in place of unencodable input when replacing
+const REPLACEMENT: &str = "?";
+
+/// Look up an encoding by its WHATWG label (e.g. "utf-8", "shift_jis")
+///
+/// Python codec spellings such as "latin-1" or "latin_1" are accepted too,
+/// by retrying the label with its separators removed.
+pub fn lookup(label: &str) -> Result<&'static Encoding> {
+    let label = label.trim();
+    Encoding::for_label(label.as_bytes())
+        .or_else(|| {
+            let compact: String = label.chars().filter(|c| !matches!(c, '-' | '_')).collect();
+            Encoding::for_label(compact.as_bytes())
+        })
+        .ok_or_else(|| AgentGearError::Encoding(format!("Unknown encoding: {}", label)))
+}
+
+/// Whether a label names UTF-8 (unknown labels are not UTF-8)
+pub fn is_utf8(label: &str) -> bool {
+    lookup(label).is_ok_and(|encoding| encoding == UTF_8)
+}
+
+/// Encode text into the given encoding
+///
+/// # Arguments
+/// * `content` - Text to encode
+/// * `label` - Encoding label
+/// * `replace` - Write `?` for characters the encoding cannot represent
+///   instead of failing
+///
+/// # Errors
+/// Returns `Encoding` for unknown labels, or for unencodable characters
+/// when `replace` is false.
+pub fn encode<'a>(content: &'a str, label: &str, replace: bool) -> Result<Cow<'a, [u8]>> {
+    let encoding = lookup(label)?;
+
+    if encoding == UTF_8 {
+        return Ok(Cow::Borrowed(content.as_bytes()));
+    }
+    // encoding_rs only decodes UTF-16; its encoders emit UTF-8 instead
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let units = content.encode_utf16();
+        let bytes = if encoding == UTF_16LE {
+            units.flat_map(u16::to_le_bytes).collect()
+        } else {
+            units.flat_map(u16::to_be_bytes).collect()
+        };
+        return Ok(Cow::Owned(bytes));
+    }
+
+    let mut encoder = encoding.new_encoder();
+    let mut out = Vec::with_capacity(
+        encoder
+            .max_buffer_length_from_utf8_without_replacement(content.len())
+            .unwrap_or(content.len()),
+    );
+    let mut input = content;
+
+    loop {
+        let (result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(input, &mut out, true);
+        input = &input[read..];
+
+        match result {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => out.reserve(input.len().max(16) * 2),
+            EncoderResult::Unmappable(_) if replace => {
+                // Route the replacement through the encoder so stateful
+                // encodings (ISO-2022-JP) switch back to ASCII first
+                out.reserve(8);
+                let _ = encoder.encode_from_utf8_to_vec_without_replacement(
+                    REPLACEMENT,
+                    &mut out,
+                    false,
+                );
+            }
+            EncoderResult::Unmappable(c) => {
+                return Err(AgentGearError::Encoding(format!(
+                    "Cannot encode {:?} (U+{:04X}) as {}",
+                    c,
+                    c as u32,
+                    encoding.name()
+                )));
+            }
+        }
+    }
+
+    Ok(Cow::Owned(out))
+}
+
+/// Decode bytes from the given encoding, failing on malformed input
+pub fn decode(bytes: &[u8], label: &str) -> Result<String> {
+    let encoding = lookup(label)?;
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .map(Cow::into_owned)
+        .ok_or_else(|| AgentGearError::Encoding(format!("File is not valid {}", encoding.name())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let text = "こんにちは, world";
+
+        let bytes = encode(text, "shift_jis", false).unwrap();
+        assert_ne!(bytes.as_ref(), text.as_bytes());
+        assert_eq!(decode(&bytes, "shift_jis").unwrap(), text);
+
+        assert!(matches!(
+            encode(text, "utf-8", false).unwrap(),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(encode("hi", "utf-16le", false).unwrap().as_ref(), b"h\0i\0");
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        assert!(matches!(
+            encode("abc", "no-such-encoding", false),
+            Err(AgentGearError::Encoding(_))
+        ));
+        assert!(matches!(
+            encode("snow ☃", "windows-1252", false),
+            Err(AgentGearError::Encoding(_))
+        ));
+        assert_eq!(
+            encode("snow ☃!", "windows-1252", true).unwrap().as_ref(),
+            b"snow ?!"
+        );
+        assert!(decode(&[0x82], "shift_jis").is_err());
+        assert_eq!(decode(&[0x63, 0xE9], "latin-1").unwrap(), "cé");
+        assert!(is_utf8("UTF_8") && !is_utf8("latin_1"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Following appended lines (`tail -f`)
+//!
+//! Polls a single file and hands each newly appended line to a callback.
+//! Polling is used instead of the directory watcher so that following works
+//! for files outside a watched tree and survives rotation, where the watched
+//! inode is replaced.
+
+use pyo3::prelude::*;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::utils::error::{AgentGearError, Result};
+
+/// How often a followed file is checked for new content
+pub const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Incremental reader of the complete lines appended to a file
+pub struct LineTail {
+    path: PathBuf,
+    /// Byte offset up to which the file has been read
+    offset: u64,
+    /// Bytes of the last line read, held back until its newline arrives
+    pending: Vec<u8>,
+    /// Identity of the file being read, to detect rotation
+    file_id: Option<u64>,
+}
+
+impl LineTail {
+    /// Start following `path`, either from its current end or from the start
+    pub fn new(path: PathBuf, from_end: bool) -> Result<Self> {
+        let metadata = std::fs::metadata(&path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        })?;
+
+        Ok(Self {
+            offset: if from_end { metadata.len() } else { 0 },
+            file_id: file_id(&metadata),
+            path,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Read whatever was appended since the last poll
+    ///
+    /// Returns the newly completed lines without their terminators. A file
+    /// that shrank (truncation) or was replaced (rotation) is read again
+    /// from the start; a file that is briefly missing mid-rotation yields
+    /// no lines.
+    pub fn poll(&mut self) -> Result<Vec<String>> {
+        let mut file = match std::fs::File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let metadata = file.metadata()?;
+
+        let id = file_id(&metadata);
+        if metadata.len() < self.offset || id != self.file_id {
+            self.offset = 0;
+            self.pending.clear();
+            self.file_id = id;
+        }
+        if metadata.len() == self.offset {
+            return Ok(Vec::new());
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let read = file.read_to_end(&mut self.pending)?;
+        self.offset += read as u64;
+
+        let Some(end) = self.pending.iter().rposition(|&b| b == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let complete: Vec<u8> = self.pending.drain(..=end).collect();
+
+        Ok(complete[..end]
+            .split(|&b| b == b'\n')
+            .map(|line| {
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                String::from_utf8_lossy(line).into_owned()
+            })
+            .collect())
+    }
+}
+
+/// Inode number on Unix; rotation is only detected by truncation elsewhere
+#[inline]
+fn file_id(metadata: &std::fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Handle to a running `follow`; call `stop()` to end it
+#[pyclass]
+pub struct FollowHandle {
+    stop_flag: Arc<AtomicBool>,
+    active: Arc<AtomicBool>,
+    thread: Mutex<Option<std::thread::JoinHandle<()>>>,
+}
+
+impl FollowHandle {
+    /// Follow `tail` on a background thread, calling `callback` per line
+    ///
+    /// Following ends when the handle is stopped, when `owner_stop` is set
+    /// (the FileSystem was closed), or when the callback raises.
+    pub fn spawn(mut tail: LineTail, callback: Py<PyAny>, owner_stop: Arc<AtomicBool>) -> Self {
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        let active = Arc::new(AtomicBool::new(true));
+
+        let stop = Arc::clone(&stop_flag);
+        let running = Arc::clone(&active);
+        let thread = std::thread::spawn(move || {
+            while !stop.load(Ordering::SeqCst) && !owner_stop.load(Ordering::SeqCst) {
+                match tail.poll() {
+                    Ok(lines) if !lines.is_empty() => {
+                        let delivered = Python::with_gil(|py| {
+                            lines.into_iter().try_for_each(|line| {
+                                callback.call1(py, (line,)).map(|_| ()).map_err(|e| {
+                                    tracing::warn!("follow callback raised, stopping: {}", e);
+                                })
+                            })
+                        });
+                        if delivered.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to read followed file: {}", e),
+                }
+                std::thread::sleep(FOLLOW_POLL_INTERVAL);
+            }
+            running.store(false, Ordering::SeqCst);
+        });
+
+        Self {
+            stop_flag,
+            active,
+            thread: Mutex::new(Some(thread)),
+        }
+    }
+}
+
+#[pymethods]
+impl FollowHandle {
+    /// Stop following and wait for the background thread to exit
+    ///
+    /// No callback runs after this returns. Calling it again is a no-op.
+    pub fn stop(&self, py: Python<'_>) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        let thread = self.thread.lock().ok().and_then(|mut t| t.take());
+        if let Some(thread) = thread {
+            // The thread may be waiting for the GIL to run a callback
+            py.allow_threads(|| {
+                let _ = thread.join();
+            });
+        }
+    }
+
+    /// Check whether lines are still being delivered
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        self.stop(py);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    fn append(path: &std::path::Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new().append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_line_tail() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "old\n").unwrap();
+
+        let mut tail = LineTail::new(path.clone(), true).unwrap();
+        assert!(tail.poll().unwrap().is_empty());
+
+        // A partial line is held back until its newline arrives
+        append(&path, "one\r\ntw");
+        assert_eq!(tail.poll().unwrap(), vec!["one"]);
+        append(&path, "o\nthree\n");
+        assert_eq!(tail.poll().unwrap(), vec!["two", "three"]);
+
+        // Truncation restarts from the beginning
+        std::fs::write(&path, "new\n").unwrap();
+        assert_eq!(tail.poll().unwrap(), vec!["new"]);
+
+        let mut from_start = LineTail::new(path.clone(), false).unwrap();
+        assert_eq!(from_start.poll().unwrap(), vec!["new"]);
+
+        assert!(matches!(
+            LineTail::new(dir.path().join("missing.log"), true),
+            Err(AgentGearError::PathNotFound(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_line_tail_rotation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "a long line before rotation\n").unwrap();
+
+        let mut tail = LineTail::new(path.clone(), true).unwrap();
+
+        // Rotated to a new file that is already longer than the old offset
+        std::fs::rename(&path, dir.path().join("app.log.1")).unwrap();
+        std::fs::write(&path, "first line after rotation\nsecond\n").unwrap();
+        assert_eq!(
+            tail.poll().unwrap(),
+            vec!["first line after rotation", "second"]
+        );
+    }
+}
@@ -6,19 +6,36 @@
 use dashmap::DashMap;
 use globset::{Glob, GlobMatcher};
 use pyo3::prelude::*;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::RwLock;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::SystemTime;
 
+use super::language;
+use super::snapshot::{Snapshot, SnapshotEntry};
 use crate::utils::error::{AgentGearError, Result};
+use crate::utils::limit;
+use crate::utils::path::{
+    fold_case, is_case_insensitive_fs, to_posix_string, BinaryOverrides, DirIgnore, ExtensionFilter,
+};
 
 /// Maximum number of cached glob patterns
 const GLOB_CACHE_SIZE: usize = 128;
 
+/// Maximum number of cached path regexes
+const REGEX_CACHE_SIZE: usize = 64;
+
 /// Threshold for using parallel iteration (below this, serial is faster)
 const PARALLEL_ITER_THRESHOLD: usize = 500;
 
+/// Estimated per-entry overhead of a hash map slot (hash, control byte and
+/// spare capacity at the typical load factor), used by `memory_usage`
+const MAP_ENTRY_OVERHEAD: usize = 16;
+
+/// Read buffer size used when counting a file's lines
+const LINE_COUNT_CHUNK: usize = 64 * 1024;
+
 /// File metadata stored in the index
 #[pyclass]
 #[derive(Clone, Debug)]
@@ -50,13 +67,30 @@ impl FileMetadata {
     }
 }
 
-/// Lock-free glob cache using DashMap
-struct GlobCache {
-    cache: DashMap<String, GlobMatcher>,
+/// Cached line count of a file, valid while its mtime and size are unchanged
+#[derive(Clone, Copy, Debug)]
+struct LineCount {
+    mtime: f64,
+    size: u64,
+    lines: u64,
+}
+
+/// Per-language totals from `code_stats`: (files, lines, bytes)
+pub type LanguageStats = (usize, u64, u64);
+
+/// Lock-free cache of compiled patterns using DashMap
+struct PatternCache<M> {
+    cache: DashMap<String, M>,
     capacity: usize,
 }
 
-impl GlobCache {
+/// Cache of compiled glob patterns
+type GlobCache = PatternCache<GlobMatcher>;
+
+/// Cache of compiled path regexes
+type RegexCache = PatternCache<regex::Regex>;
+
+impl<M: Clone> PatternCache<M> {
     fn new(capacity: usize) -> Self {
         Self {
             cache: DashMap::with_capacity(capacity),
@@ -66,12 +100,12 @@ impl GlobCache {
 
     /// Get a cached matcher (lock-free read)
     #[inline]
-    fn get(&self, pattern: &str) -> Option<GlobMatcher> {
+    fn get(&self, pattern: &str) -> Option<M> {
         self.cache.get(pattern).map(|r| r.clone())
     }
 
     /// Insert a matcher, evicting random entry if at capacity
-    fn insert(&self, pattern: String, matcher: GlobMatcher) {
+    fn insert(&self, pattern: String, matcher: M) {
         // Simple capacity control: remove one random entry if full
         if self.cache.len() >= self.capacity {
             if let Some(entry) = self.cache.iter().next() {
@@ -82,13 +116,37 @@ impl GlobCache {
         }
         self.cache.insert(pattern, matcher);
     }
-}
 
-/// In-memory file index using DashMap for concurrent access
-pub struct FileIndex {
-    /// Root directory being indexed
-    root: PathBuf,
+    /// Patterns currently cached
+    fn patterns(&self) -> Vec<String> {
+        self.cache.iter().map(|entry| entry.key().clone()).collect()
+    }
+
+    /// Drop every cached matcher
+    fn clear(&self) {
+        self.cache.clear();
+    }
 
+    /// Estimated bytes held by the cache, see `FileIndex::memory_usage`
+    fn memory_usage(&self) -> usize {
+        self.cache
+            .iter()
+            .map(|entry| {
+                std::mem::size_of::<String>()
+                    + entry.key().capacity()
+                    + std::mem::size_of::<M>()
+                    + MAP_ENTRY_OVERHEAD
+            })
+            .sum()
+    }
+}
+
+/// One generation of indexed entries
+///
+/// Builds fill a fresh state and swap it in whole, so queries keep reading
+/// the previous generation until the new walk is complete.
+#[derive(Default)]
+struct IndexState {
     /// Main index: path -> metadata
     entries: DashMap<PathBuf, FileMetadata>,
 
@@ -98,6 +156,31 @@ pub struct FileIndex {
     /// All file paths (for fast iteration)
     all_files: RwLock<Vec<PathBuf>>,
 
+    /// Case-folded path -> path as stored in `entries` (case-insensitive only)
+    folded_keys: DashMap<PathBuf, PathBuf>,
+}
+
+/// Bookkeeping for a build in progress
+#[derive(Default)]
+struct PendingBuild {
+    /// Set by `cancel_build` to stop the walk and discard the shadow state
+    cancelled: AtomicBool,
+
+    /// Paths updated incrementally during the walk, replayed after the swap
+    touched: Mutex<Vec<PathBuf>>,
+}
+
+/// In-memory file index using DashMap for concurrent access
+pub struct FileIndex {
+    /// Root directory being indexed
+    root: PathBuf,
+
+    /// Indexed entries, replaced whole when a build completes
+    state: RwLock<Arc<IndexState>>,
+
+    /// The build currently walking into a shadow state, if any
+    pending_build: Mutex<Option<Arc<PendingBuild>>>,
+
     /// Whether the index has been built
     is_ready: AtomicBool,
 
@@ -106,53 +189,382 @@ pub struct FileIndex {
 
     /// Lock-free cache for compiled glob patterns
     glob_cache: GlobCache,
+
+    /// Lock-free cache for compiled path regexes
+    regex_cache: RegexCache,
+
+    /// Whether paths differing only in case refer to the same file
+    case_insensitive: bool,
+
+    /// Only index files with these extensions (None = all files)
+    extensions: Option<ExtensionFilter>,
+
+    /// Leave out directories with these names, and everything below them
+    ignored_dirs: Option<DirIgnore>,
+
+    /// Extra gitignore-syntax file names read in every directory walked
+    custom_ignore_files: Vec<String>,
+
+    /// Files above this size are assumed binary without reading them
+    binary_detection_limit: Option<u64>,
+
+    /// Extensions classified as binary or text without sniffing
+    binary_overrides: RwLock<BinaryOverrides>,
+
+    /// Line counts computed by `code_stats`, reused while files are unchanged
+    line_counts: DashMap<PathBuf, LineCount>,
+
+    /// Stop adding entries once the index holds this many (None = no cap)
+    max_entries: Option<usize>,
+
+    /// Whether entries were left out because `max_entries` was reached
+    truncated: AtomicBool,
 }
 
 impl FileIndex {
     /// Create a new file index for the given root directory
+    ///
+    /// Case sensitivity is detected from the filesystem holding `root`.
     pub fn new(root: PathBuf) -> Self {
+        let case_insensitive = is_case_insensitive_fs(&root);
+        Self::with_case_insensitive(root, case_insensitive)
+    }
+
+    /// Create a new file index with explicit case sensitivity
+    ///
+    /// When `case_insensitive` is true, lookups and updates match stored
+    /// paths ignoring case, so `Foo.txt` and `foo.txt` share one entry.
+    pub fn with_case_insensitive(root: PathBuf, case_insensitive: bool) -> Self {
         Self {
             root,
-            entries: DashMap::new(),
-            dir_children: DashMap::new(),
-            all_files: RwLock::new(Vec::new()),
+            state: RwLock::new(Arc::new(IndexState::default())),
+            pending_build: Mutex::new(None),
             is_ready: AtomicBool::new(false),
             is_building: AtomicBool::new(false),
             glob_cache: GlobCache::new(GLOB_CACHE_SIZE),
+            regex_cache: RegexCache::new(REGEX_CACHE_SIZE),
+            case_insensitive,
+            extensions: None,
+            ignored_dirs: None,
+            custom_ignore_files: Vec::new(),
+            binary_detection_limit: None,
+            binary_overrides: RwLock::new(BinaryOverrides::default()),
+            line_counts: DashMap::new(),
+            max_entries: None,
+            truncated: AtomicBool::new(false),
+        }
+    }
+
+    /// Restrict the index to files with the given extensions
+    ///
+    /// Directories are always indexed so every allowed file stays reachable.
+    pub fn with_extensions(mut self, extensions: ExtensionFilter) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Leave out directories with the given names on top of gitignore
+    ///
+    /// Walks do not descend into them, and unlike gitignore rules, which
+    /// shape walks only, they also drop watcher updates for paths below them.
+    pub fn with_ignored_dirs(mut self, ignored_dirs: DirIgnore) -> Self {
+        self.ignored_dirs = Some(ignored_dirs);
+        self
+    }
+
+    /// Also honour ignore files with these names, e.g. `.agentignore`
+    ///
+    /// They use gitignore syntax and take precedence over `.gitignore`, so
+    /// a `!` pattern in one re-includes a gitignored path. Like gitignore
+    /// they shape walks only; watcher updates are not filtered by them.
+    pub fn with_custom_ignore_files(mut self, names: Vec<String>) -> Self {
+        self.custom_ignore_files = names;
+        self
+    }
+
+    /// Assume files larger than `limit` bytes are binary instead of reading
+    /// their first bytes
+    ///
+    /// Saves a read per large file on asset-heavy trees. Such files are then
+    /// skipped by searches that skip binary files, even if they are text.
+    pub fn with_binary_detection_limit(mut self, limit: u64) -> Self {
+        self.binary_detection_limit = Some(limit);
+        self
+    }
+
+    /// Replace the per-extension binary overrides
+    ///
+    /// Indexed files whose extension was covered by the old or the new
+    /// overrides are reclassified right away, so searches see the change
+    /// without a rebuild.
+    ///
+    /// # Returns
+    /// Number of indexed files whose binary flag changed
+    pub fn set_binary_overrides(&self, overrides: BinaryOverrides) -> Result<usize> {
+        let state = self.state();
+        let previous = {
+            let mut guard = self.binary_overrides.write().map_err(|_| {
+                AgentGearError::Internal("Failed to acquire write lock".to_string())
+            })?;
+            std::mem::replace(&mut *guard, overrides)
+        };
+
+        // Sniff outside the map's locks, then apply the changed flags
+        let affected: Vec<(PathBuf, u64, bool)> = state
+            .entries
+            .iter()
+            .filter(|entry| !entry.value().is_dir)
+            .filter(|entry| {
+                previous.lookup(entry.key()).is_some()
+                    || self.binary_override(entry.key()).is_some()
+            })
+            .map(|entry| {
+                (
+                    entry.key().clone(),
+                    entry.value().size,
+                    entry.value().is_binary,
+                )
+            })
+            .collect();
+
+        let mut changed = 0;
+        for (path, size, was_binary) in affected {
+            let is_binary = self.detect_binary(&path, size);
+            if is_binary != was_binary {
+                if let Some(mut metadata) = state.entries.get_mut(&path) {
+                    metadata.is_binary = is_binary;
+                    changed += 1;
+                }
+            }
+        }
+        Ok(changed)
+    }
+
+    /// The forced binary classification of a path, if its extension has one
+    pub fn binary_override(&self, path: &Path) -> Option<bool> {
+        self.binary_overrides
+            .read()
+            .ok()
+            .and_then(|overrides| overrides.lookup(path))
+    }
+
+    /// Cap the number of entries the index holds
+    ///
+    /// Once the cap is reached, builds stop walking and further paths are
+    /// left out, so a huge tree yields a partial index instead of running
+    /// out of memory. `is_truncated` reports when that happened.
+    pub fn with_max_entries(mut self, limit: usize) -> Self {
+        self.max_entries = Some(limit);
+        self
+    }
+
+    /// Whether the entry cap left paths out of the index
+    ///
+    /// Stays set until a rebuild or restore fits within the cap.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated.load(Ordering::SeqCst)
+    }
+
+    /// Whether a new entry would exceed the entry cap
+    #[inline]
+    fn at_capacity(&self, len: usize) -> bool {
+        self.max_entries.is_some_and(|limit| len >= limit)
+    }
+
+    /// Whether a path is left out by the extension allowlist or lies in an
+    /// ignored directory
+    #[inline]
+    fn is_filtered(&self, path: &Path, is_dir: bool) -> bool {
+        if !is_dir && self.extensions.as_ref().is_some_and(|f| !f.allows(path)) {
+            return true;
+        }
+        self.ignored_dirs.as_ref().is_some_and(|d| {
+            let relative = path.strip_prefix(&self.root).unwrap_or(path);
+            d.skips(relative, is_dir)
+        })
+    }
+
+    /// Directory walker shared by `build` and `reconcile`
+    ///
+    /// Includes hidden files and respects gitignore, the global gitignore,
+    /// `.git/info/exclude` and custom ignore files, and prunes ignored
+    /// directories.
+    fn walk_builder(&self) -> ignore::WalkBuilder {
+        let mut builder = ignore::WalkBuilder::new(&self.root);
+        builder
+            .hidden(false)
+            .git_ignore(true)
+            .git_global(true)
+            .git_exclude(true);
+        for name in &self.custom_ignore_files {
+            builder.add_custom_ignore_filename(name);
+        }
+        if let Some(ignored_dirs) = self.ignored_dirs.clone() {
+            builder.filter_entry(move |entry| ignored_dirs.keeps_entry(entry));
+        }
+        builder
+    }
+
+    /// Estimate the bytes used by the index
+    ///
+    /// Sums, for every structure, the inline size of each key and value, the
+    /// heap bytes of each path (its length) and `MAP_ENTRY_OVERHEAD` per map
+    /// slot. Vectors count their capacity. Allocator padding and the heap
+    /// owned by compiled glob and regex matchers are not counted, so the
+    /// real footprint is somewhat higher; the estimate is meant for spotting
+    /// growth, not exact accounting.
+    pub fn memory_usage(&self) -> usize {
+        let state = self.state();
+        use std::mem::size_of;
+
+        let path_bytes = |path: &Path| size_of::<PathBuf>() + path.as_os_str().len();
+
+        let entries: usize = state
+            .entries
+            .iter()
+            .map(|entry| path_bytes(entry.key()) + size_of::<FileMetadata>() + MAP_ENTRY_OVERHEAD)
+            .sum();
+
+        let dir_children: usize = state
+            .dir_children
+            .iter()
+            .map(|entry| {
+                path_bytes(entry.key())
+                    + size_of::<Vec<PathBuf>>()
+                    + (entry.capacity() - entry.len()) * size_of::<PathBuf>()
+                    + entry.iter().map(|p| path_bytes(p)).sum::<usize>()
+                    + MAP_ENTRY_OVERHEAD
+            })
+            .sum();
+
+        let all_files = state
+            .all_files
+            .read()
+            .map(|files| {
+                (files.capacity() - files.len()) * size_of::<PathBuf>()
+                    + files.iter().map(|p| path_bytes(p)).sum::<usize>()
+            })
+            .unwrap_or(0);
+
+        let folded_keys: usize = state
+            .folded_keys
+            .iter()
+            .map(|entry| path_bytes(entry.key()) + path_bytes(entry.value()) + MAP_ENTRY_OVERHEAD)
+            .sum();
+
+        let line_counts: usize = self
+            .line_counts
+            .iter()
+            .map(|entry| path_bytes(entry.key()) + size_of::<LineCount>() + MAP_ENTRY_OVERHEAD)
+            .sum();
+
+        size_of::<Self>()
+            + entries
+            + dir_children
+            + all_files
+            + folded_keys
+            + line_counts
+            + self.glob_cache.memory_usage()
+            + self.regex_cache.memory_usage()
+    }
+
+    /// Whether the index matches paths case-insensitively
+    pub fn is_case_insensitive(&self) -> bool {
+        self.case_insensitive
+    }
+
+    /// Map a path to the key it is stored under
+    ///
+    /// On case-insensitive indexes this returns the stored spelling of a
+    /// path that differs only in case; otherwise the path is used as is.
+    #[inline]
+    fn canonical_key(&self, state: &IndexState, path: &Path) -> PathBuf {
+        if self.case_insensitive {
+            if let Some(stored) = state.folded_keys.get(&fold_case(path)) {
+                return stored.clone();
+            }
+        }
+        path.to_path_buf()
+    }
+
+    /// Record the stored spelling of a path (case-insensitive only)
+    #[inline]
+    fn track_key(&self, state: &IndexState, path: &Path) {
+        if self.case_insensitive {
+            state
+                .folded_keys
+                .insert(fold_case(path), path.to_path_buf());
+        }
+    }
+
+    /// The current generation of entries
+    ///
+    /// Callers hold the returned state for the whole operation, so a build
+    /// swapping in a new one never splits a query across generations.
+    fn state(&self) -> Arc<IndexState> {
+        match self.state.read() {
+            Ok(state) => Arc::clone(&state),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+
+    /// Record an incremental update so a build in progress replays it
+    ///
+    /// Must run before the update picks its state: a path recorded here is
+    /// re-read after the swap, and once the build has stopped recording the
+    /// new state is already live.
+    fn note_touched(&self, path: &Path) {
+        if let Ok(pending) = self.pending_build.lock() {
+            if let Some(build) = pending.as_ref() {
+                if let Ok(mut touched) = build.touched.lock() {
+                    touched.push(path.to_path_buf());
+                }
+            }
         }
     }
 
     /// Build the index by scanning the directory
-    pub fn build(&self) -> Result<()> {
+    ///
+    /// The walk fills a fresh state that replaces the current one only once
+    /// it is complete, so an index that is already ready keeps answering
+    /// queries from the previous entries meanwhile. Paths passed to
+    /// `add_path`, `update_path` or `remove_path` during the walk are
+    /// re-read after the swap.
+    ///
+    /// # Returns
+    /// Whether a new state was swapped in; false if another build was
+    /// already running or this one was cancelled
+    pub fn build(&self) -> Result<bool> {
         // Prevent concurrent builds
         if self
             .is_building
             .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
             .is_err()
         {
-            return Ok(()); // Already building
+            return Ok(false); // Already building
         }
 
-        // Clear existing entries
-        self.entries.clear();
-        self.dir_children.clear();
+        let pending = Arc::new(PendingBuild::default());
+        if let Ok(mut slot) = self.pending_build.lock() {
+            *slot = Some(Arc::clone(&pending));
+        }
 
+        let shadow = IndexState::default();
+        let truncated = AtomicBool::new(false);
         let mut all_files = Vec::new();
+        let reserved = AtomicUsize::new(0);
 
         // Use the `ignore` crate for fast, parallel directory walking
         // It automatically respects .gitignore files
-        let walker = ignore::WalkBuilder::new(&self.root)
-            .hidden(false) // Include hidden files
-            .git_ignore(true) // Respect .gitignore
-            .git_global(true) // Respect global gitignore
-            .git_exclude(true) // Respect .git/info/exclude
-            .build_parallel();
-
-        use std::sync::Mutex;
+        let walker = self.walk_builder().build_parallel();
+
         let all_files_mutex = Mutex::new(&mut all_files);
 
         walker.run(|| {
             Box::new(|entry| {
+                if pending.cancelled.load(Ordering::SeqCst) {
+                    return ignore::WalkState::Quit;
+                }
                 if let Ok(entry) = entry {
                     let path = entry.path().to_path_buf();
 
@@ -164,6 +576,14 @@ impl FileIndex {
                     // Get metadata
                     if let Ok(metadata) = entry.metadata() {
                         let is_dir = metadata.is_dir();
+                        if self.is_filtered(&path, is_dir) {
+                            return ignore::WalkState::Continue;
+                        }
+                        // Claim a slot before doing any work for the entry
+                        if self.at_capacity(reserved.fetch_add(1, Ordering::SeqCst)) {
+                            truncated.store(true, Ordering::SeqCst);
+                            return ignore::WalkState::Quit;
+                        }
                         let size = metadata.len();
                         let mtime = metadata
                             .modified()
@@ -173,11 +593,7 @@ impl FileIndex {
                             .unwrap_or(0.0);
 
                         // Simple binary detection: check first few bytes for null
-                        let is_binary = if !is_dir && size > 0 {
-                            Self::is_binary_file(&path)
-                        } else {
-                            false
-                        };
+                        let is_binary = !is_dir && self.detect_binary(&path, size);
 
                         let file_metadata = FileMetadata {
                             size,
@@ -186,11 +602,13 @@ impl FileIndex {
                             is_binary,
                         };
 
-                        self.entries.insert(path.clone(), file_metadata);
+                        self.track_key(&shadow, &path);
+                        shadow.entries.insert(path.clone(), file_metadata);
 
                         // Track directory children
                         if let Some(parent) = path.parent() {
-                            self.dir_children
+                            shadow
+                                .dir_children
                                 .entry(parent.to_path_buf())
                                 .or_default()
                                 .push(path.clone());
@@ -208,15 +626,130 @@ impl FileIndex {
             })
         });
 
-        // Update the all_files list
-        if let Ok(mut files) = self.all_files.write() {
+        if pending.cancelled.load(Ordering::SeqCst) {
+            self.finish_pending_build(&pending);
+            self.is_building.store(false, Ordering::SeqCst);
+            tracing::debug!("Build of {} cancelled", self.root.display());
+            return Ok(false);
+        }
+
+        if let Ok(mut files) = shadow.all_files.write() {
             *files = all_files;
         }
 
-        self.is_ready.store(true, Ordering::SeqCst);
+        let truncated = truncated.into_inner();
+        if truncated {
+            tracing::warn!(
+                "Index of {} truncated at {} entries; paths beyond the cap are not indexed",
+                self.root.display(),
+                shadow.entries.len()
+            );
+        }
+
+        self.swap_state(shadow, truncated);
+        let touched = self.finish_pending_build(&pending);
+        let replayed = self.refresh_paths(&touched);
+
         self.is_building.store(false, Ordering::SeqCst);
 
-        Ok(())
+        replayed.map(|_| true)
+    }
+
+    /// Make `shadow` the live state and mark the index ready
+    fn swap_state(&self, shadow: IndexState, truncated: bool) {
+        let shadow = Arc::new(shadow);
+        match self.state.write() {
+            Ok(mut state) => *state = shadow,
+            Err(poisoned) => *poisoned.into_inner() = shadow,
+        }
+        self.truncated.store(truncated, Ordering::SeqCst);
+        self.is_ready.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop recording incremental updates for `build`
+    ///
+    /// # Returns
+    /// The paths recorded during the walk, sorted and deduplicated
+    fn finish_pending_build(&self, build: &PendingBuild) -> Vec<PathBuf> {
+        match self.pending_build.lock() {
+            Ok(mut slot) => *slot = None,
+            Err(poisoned) => *poisoned.into_inner() = None,
+        }
+        let mut touched = match build.touched.lock() {
+            Ok(mut touched) => std::mem::take(&mut *touched),
+            Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+        };
+        touched.sort_unstable();
+        touched.dedup();
+        touched
+    }
+
+    /// Cancel the build in progress
+    ///
+    /// The walk stops at its next entry and its partial state is dropped;
+    /// the index keeps the entries it had before the build started. The
+    /// initial build is not cancelled: there are no earlier entries to fall
+    /// back on, and the index would stay not ready until a `refresh`.
+    ///
+    /// # Returns
+    /// Whether a refresh was running and is now cancelled
+    pub fn cancel_build(&self) -> bool {
+        if !self.is_ready() {
+            return false;
+        }
+        match self.pending_build.lock() {
+            Ok(pending) => pending.as_ref().is_some_and(|build| {
+                build.cancelled.store(true, Ordering::SeqCst);
+                true
+            }),
+            Err(_) => false,
+        }
+    }
+
+    /// Decide whether a file of the given size is binary
+    ///
+    /// Extension overrides win first. Otherwise files above the binary
+    /// detection limit are assumed binary without being opened; empty files
+    /// are text.
+    fn detect_binary(&self, path: &Path, size: u64) -> bool {
+        if let Some(is_binary) = self.binary_override(path) {
+            return is_binary;
+        }
+        match self.binary_detection_limit {
+            _ if size == 0 => false,
+            Some(limit) if size > limit => true,
+            _ => Self::is_binary_file(path),
+        }
+    }
+
+    /// Classify a file as "text", "binary", "empty" or "missing"
+    ///
+    /// The file is always stat'ed, so a deleted file reports "missing" even
+    /// before the watcher catches up. The indexed binary flag is reused when
+    /// the entry's size still matches; otherwise the file is sniffed with
+    /// the same null-byte heuristic and detection limit as indexing.
+    pub fn classify(&self, path: &Path) -> Result<&'static str> {
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok("missing"),
+            Err(e) => return Err(e.into()),
+        };
+        if metadata.is_dir() {
+            return Err(AgentGearError::Io(std::io::Error::other(format!(
+                "{} is a directory",
+                path.display()
+            ))));
+        }
+
+        let size = metadata.len();
+        if size == 0 {
+            return Ok("empty");
+        }
+        let is_binary = match self.get_metadata(path) {
+            Some(m) if !m.is_dir && m.size == size => m.is_binary,
+            _ => self.detect_binary(path, size),
+        };
+        Ok(if is_binary { "binary" } else { "text" })
     }
 
     /// Check if a file is binary by reading the first few bytes
@@ -238,9 +771,26 @@ impl FileIndex {
         self.is_ready.load(Ordering::SeqCst)
     }
 
+    /// Glob patterns currently held in the compiled-pattern cache, sorted
+    pub fn cached_globs(&self) -> Vec<String> {
+        let mut patterns = self.glob_cache.patterns();
+        patterns.sort_unstable();
+        patterns
+    }
+
+    /// Empty the compiled glob cache
+    pub fn clear_glob_cache(&self) {
+        self.glob_cache.clear();
+    }
+
     /// Refresh the index
-    pub fn refresh(&self) -> Result<()> {
-        self.is_ready.store(false, Ordering::SeqCst);
+    ///
+    /// The index stays ready and serves the previous entries until the new
+    /// walk is swapped in.
+    ///
+    /// # Returns
+    /// Whether the rebuild completed, as for `build`
+    pub fn refresh(&self) -> Result<bool> {
         self.build()
     }
 
@@ -248,6 +798,8 @@ impl FileIndex {
     pub fn list(&self, pattern: &str, only_files: bool) -> Result<Vec<String>> {
         use rayon::prelude::*;
 
+        let state = self.state();
+
         if !self.is_ready() {
             return Err(AgentGearError::IndexNotReady);
         }
@@ -255,7 +807,7 @@ impl FileIndex {
         // Fast path: "**/*" matches everything
         let match_all = pattern == "**/*" || pattern == "**";
 
-        let files = self
+        let files = state
             .all_files
             .read()
             .map_err(|_| AgentGearError::Internal("Failed to acquire read lock".to_string()))?;
@@ -303,13 +855,15 @@ impl FileIndex {
                 }
             }
         } else if match_all {
-            self.entries
+            state
+                .entries
                 .iter()
                 .map(|entry| self.relative_path_fast(entry.key()))
                 .collect()
         } else {
             let matcher = self.compile_glob(pattern)?;
-            self.entries
+            state
+                .entries
                 .iter()
                 .filter_map(|entry| {
                     let relative = self.relative_path_fast(entry.key());
@@ -344,6 +898,8 @@ impl FileIndex {
     ) -> Result<Vec<PathBuf>> {
         use rayon::prelude::*;
 
+        let state = self.state();
+
         if !self.is_ready() {
             return Err(AgentGearError::IndexNotReady);
         }
@@ -351,7 +907,7 @@ impl FileIndex {
         // Fast path: "**/*" matches everything
         let match_all = pattern == "**/*" || pattern == "**";
 
-        let files = self
+        let files = state
             .all_files
             .read()
             .map_err(|_| AgentGearError::Internal("Failed to acquire read lock".to_string()))?;
@@ -363,7 +919,7 @@ impl FileIndex {
             if skip_binary {
                 // Filter out binary files using index metadata
                 let filter_fn = |path: &&PathBuf| {
-                    !self
+                    !state
                         .entries
                         .get(*path)
                         .map(|m| m.is_binary)
@@ -385,7 +941,7 @@ impl FileIndex {
                     return false;
                 }
                 if skip_binary {
-                    !self
+                    !state
                         .entries
                         .get(*path)
                         .map(|m| m.is_binary)
@@ -404,114 +960,717 @@ impl FileIndex {
         Ok(results)
     }
 
-    /// Get metadata for a path
-    pub fn get_metadata(&self, path: &Path) -> Option<FileMetadata> {
-        self.entries.get(path).map(|entry| entry.clone())
-    }
+    /// Match files against several globs, grouping the results by pattern
+    ///
+    /// Every pattern is compiled once (through the glob cache) and the file
+    /// list is scanned in a single pass, testing each file against all
+    /// patterns. Patterns that match nothing map to an empty list; each list
+    /// is sorted.
+    pub fn glob_grouped(&self, patterns: &[String]) -> Result<HashMap<String, Vec<String>>> {
+        use rayon::prelude::*;
 
-    /// Get the relative path from the root (optimized version)
-    #[inline]
-    fn relative_path_fast(&self, path: &Path) -> String {
-        // Fast path: try to use the path directly if it's valid UTF-8
-        if let Ok(relative) = path.strip_prefix(&self.root) {
-            if let Some(s) = relative.to_str() {
-                return s.to_owned();
-            }
+        let state = self.state();
+
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
         }
-        // Fallback for non-UTF8 paths
-        path.strip_prefix(&self.root)
-            .unwrap_or(path)
-            .to_string_lossy()
-            .into_owned()
-    }
 
-    /// Get the relative path from the root
-    #[allow(dead_code)]
-    fn relative_path(&self, path: &Path) -> String {
-        self.relative_path_fast(path)
-    }
+        let matchers = patterns
+            .iter()
+            .map(|p| self.compile_glob(p))
+            .collect::<Result<Vec<_>>>()?;
 
-    /// Compile a glob pattern with lock-free caching
-    #[inline]
-    fn compile_glob(&self, pattern: &str) -> Result<GlobMatcher> {
-        // Fast path: lock-free cache lookup
-        if let Some(matcher) = self.glob_cache.get(pattern) {
-            return Ok(matcher);
-        }
+        let files = state
+            .all_files
+            .read()
+            .map_err(|_| AgentGearError::Internal("Failed to acquire read lock".to_string()))?;
 
-        // Cache miss: compile and store
-        let matcher = Glob::new(pattern)
-            .map(|g| g.compile_matcher())
-            .map_err(AgentGearError::Glob)?;
+        let collect = |mut groups: Vec<Vec<String>>, path: &PathBuf| {
+            let relative = self.relative_path_fast(path);
+            for (group, matcher) in groups.iter_mut().zip(&matchers) {
+                if matcher.is_match(&relative) {
+                    group.push(relative.clone());
+                }
+            }
+            groups
+        };
+        let empty = || vec![Vec::new(); matchers.len()];
+
+        let groups = if files.len() >= PARALLEL_ITER_THRESHOLD {
+            files
+                .par_iter()
+                .fold(empty, collect)
+                .reduce(empty, |mut a, b| {
+                    for (left, right) in a.iter_mut().zip(b) {
+                        left.extend(right);
+                    }
+                    a
+                })
+        } else {
+            files.iter().fold(empty(), collect)
+        };
 
-        self.glob_cache.insert(pattern.to_string(), matcher.clone());
-        Ok(matcher)
+        Ok(patterns
+            .iter()
+            .cloned()
+            .zip(groups)
+            .map(|(pattern, mut paths)| {
+                paths.sort_unstable();
+                (pattern, paths)
+            })
+            .collect())
     }
 
-    /// Get the root directory
-    pub fn root(&self) -> &Path {
-        &self.root
-    }
+    /// Resolve file specs mixing literal paths and globs to a file list
+    ///
+    /// A spec containing a glob metacharacter (`*`, `?`, `[`, `{`) is
+    /// expanded against the indexed files, all globs in one pass via
+    /// `glob_grouped`. Any other spec is a literal path, relative to root or
+    /// absolute, kept if it is an indexed file or a file on disk, so a file
+    /// named explicitly resolves even when ignore rules keep it out of the
+    /// index. Results follow spec order, each glob's matches sorted, with
+    /// duplicates dropped after their first appearance.
+    pub fn expand_specs(&self, specs: &[String]) -> Result<Vec<String>> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
 
-    /// Get the number of indexed entries
-    #[allow(dead_code)]
-    pub fn len(&self) -> usize {
-        self.entries.len()
-    }
+        let is_glob = |spec: &str| spec.contains(['*', '?', '[', '{']);
+        let globs: Vec<String> = specs.iter().filter(|s| is_glob(s)).cloned().collect();
+        let mut grouped = self.glob_grouped(&globs)?;
+
+        let mut seen = std::collections::HashSet::new();
+        let mut expanded = Vec::new();
+        for spec in specs {
+            if is_glob(spec) {
+                let matches = grouped.remove(spec).unwrap_or_default();
+                expanded.extend(matches.into_iter().filter(|p| seen.insert(p.clone())));
+                continue;
+            }
 
-    /// Check if the index is empty
-    #[allow(dead_code)]
-    pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+            // Collecting components drops `.` segments and trailing slashes
+            let path: PathBuf = self.root.join(spec).components().collect();
+            let key = self.canonical_key(&state, &path);
+            let found = match state.entries.get(&key) {
+                Some(entry) => !entry.is_dir,
+                None => path.is_file(),
+            };
+            if found {
+                let relative = self.relative_path_fast(&key);
+                if seen.insert(relative.clone()) {
+                    expanded.push(relative);
+                }
+            }
+        }
+        Ok(expanded)
     }
 
-    // ========== Incremental update methods ==========
+    /// List paths whose relative path matches a regex
+    ///
+    /// The regex is unanchored (use `^`/`$` to anchor) and is matched
+    /// against the `/`-separated path relative to root.
+    pub fn list_regex(&self, path_regex: &str, only_files: bool) -> Result<Vec<String>> {
+        use rayon::prelude::*;
 
-    /// Add a new path to the index
-    pub fn add_path(&self, path: &Path) -> Result<()> {
-        // Skip if path doesn't exist
-        if !path.exists() {
-            return Ok(());
-        }
+        let state = self.state();
 
-        // Get metadata
-        let metadata = std::fs::metadata(path)?;
-        let is_dir = metadata.is_dir();
-        let size = metadata.len();
-        let mtime = metadata
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs_f64())
-            .unwrap_or(0.0);
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
 
-        let is_binary = if !is_dir && size > 0 {
-            Self::is_binary_file(path)
-        } else {
-            false
+        let regex = self.compile_regex(path_regex)?;
+        let matches = |path: &Path| {
+            let relative = self.relative_path_fast(path);
+            if regex.is_match(&relative) {
+                Some(relative)
+            } else {
+                None
+            }
         };
 
-        let file_metadata = FileMetadata {
-            size,
-            mtime,
-            is_dir,
-            is_binary,
+        let results = if only_files {
+            let files = state
+                .all_files
+                .read()
+                .map_err(|_| AgentGearError::Internal("Failed to acquire read lock".to_string()))?;
+            if files.len() >= PARALLEL_ITER_THRESHOLD {
+                files.par_iter().filter_map(|p| matches(p)).collect()
+            } else {
+                files.iter().filter_map(|p| matches(p)).collect()
+            }
+        } else {
+            state
+                .entries
+                .iter()
+                .filter_map(|entry| matches(entry.key()))
+                .collect()
         };
 
-        // Add to entries
-        self.entries.insert(path.to_path_buf(), file_metadata);
+        Ok(results)
+    }
 
-        // Update directory children
-        if let Some(parent) = path.parent() {
-            self.dir_children
-                .entry(parent.to_path_buf())
-                .or_default()
-                .push(path.to_path_buf());
+    /// Find every indexed path whose final component equals `name`
+    ///
+    /// Only the basename is compared, so `Cargo.toml` never matches
+    /// `Cargo.toml.bak` or `MyCargo.toml`. Directories are included.
+    pub fn find_by_name(&self, name: &str, case_insensitive: bool) -> Result<Vec<String>> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
         }
 
-        // Update all_files if it's a file
-        if !is_dir {
-            if let Ok(mut files) = self.all_files.write() {
+        let wanted = if case_insensitive {
+            name.to_lowercase()
+        } else {
+            name.to_owned()
+        };
+
+        let mut results: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|entry| {
+                entry
+                    .key()
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| {
+                        if case_insensitive {
+                            n.to_lowercase() == wanted
+                        } else {
+                            n == wanted
+                        }
+                    })
+            })
+            .map(|entry| self.relative_path_fast(entry.key()))
+            .collect();
+
+        results.sort_unstable();
+        Ok(results)
+    }
+
+    /// Every indexed zero-byte file, sorted
+    pub fn empty_files(&self) -> Result<Vec<String>> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let mut results: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir && entry.size == 0)
+            .map(|entry| self.relative_path_fast(entry.key()))
+            .collect();
+
+        results.sort_unstable();
+        Ok(results)
+    }
+
+    /// Every indexed directory with no indexed children, sorted
+    ///
+    /// Emptiness is judged from the index alone: a directory holding only
+    /// ignored, hidden-by-filter or extension-filtered files counts as empty.
+    pub fn empty_dirs(&self) -> Result<Vec<String>> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let mut results: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|entry| {
+                entry.is_dir
+                    && !state
+                        .dir_children
+                        .get(entry.key())
+                        .is_some_and(|children| !children.is_empty())
+            })
+            .map(|entry| self.relative_path_fast(entry.key()))
+            .collect();
+
+        results.sort_unstable();
+        Ok(results)
+    }
+
+    /// A BLAKE3 hash of every indexed file's path, size and mtime
+    ///
+    /// Files are hashed in path order with root-relative paths, so two
+    /// indexes of the same unchanged tree agree, and adding, removing or
+    /// modifying a file changes the result. Directories are left out.
+    pub fn fingerprint(&self) -> Result<String> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let mut files: Vec<(String, u64, f64)> = state
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| {
+                (
+                    self.relative_path_fast(entry.key()),
+                    entry.size,
+                    entry.mtime,
+                )
+            })
+            .collect();
+        files.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+        let mut hasher = blake3::Hasher::new();
+        for (path, size, mtime) in &files {
+            // The separator keeps "ab" + "c" distinct from "a" + "bc"
+            hasher.update(path.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(&size.to_le_bytes());
+            hasher.update(&mtime.to_bits().to_le_bytes());
+        }
+        Ok(hasher.finalize().to_hex().to_string())
+    }
+
+    /// Compare a list of file paths against the indexed files
+    ///
+    /// Returns `(missing, untracked)`: listed paths with no indexed file,
+    /// and indexed files absent from the list. Both are sorted, relative to
+    /// root, and deduplicated. A listed directory counts as missing, since
+    /// only files are compared. Paths outside root stay absolute.
+    pub fn diff_paths(&self, paths: &[PathBuf]) -> Result<(Vec<String>, Vec<String>)> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let listed: std::collections::HashSet<PathBuf> = paths
+            .iter()
+            .map(|path| self.canonical_key(&state, path))
+            .collect();
+
+        let mut missing: Vec<String> = listed
+            .iter()
+            .filter(|path| !state.entries.get(*path).is_some_and(|entry| !entry.is_dir))
+            .map(|path| self.relative_path_fast(path))
+            .collect();
+
+        let mut untracked: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir && !listed.contains(entry.key()))
+            .map(|entry| self.relative_path_fast(entry.key()))
+            .collect();
+
+        missing.sort_unstable();
+        untracked.sort_unstable();
+        Ok((missing, untracked))
+    }
+
+    /// Directories directly containing a file that matches `pattern`
+    ///
+    /// Each matching file maps to its parent directory, `.` for files in
+    /// root itself. Ancestors further up are not added. Sorted, without
+    /// duplicates.
+    pub fn dirs_with_matches(&self, pattern: &str) -> Result<Vec<String>> {
+        let dirs: std::collections::BTreeSet<String> = self
+            .list(pattern, true)?
+            .into_iter()
+            .map(|file| match file.rfind('/') {
+                Some(slash) => file[..slash].to_string(),
+                None => ".".to_string(),
+            })
+            .collect();
+        Ok(dirs.into_iter().collect())
+    }
+
+    /// Whether a glob pattern matches a path, as `list` would match it
+    ///
+    /// `path` is relative to root, or absolute; absolute paths outside root
+    /// never match. The path need not exist or be indexed, and the compiled
+    /// pattern is cached like any other glob.
+    pub fn glob_matches(&self, pattern: &str, path: &Path) -> Result<bool> {
+        let matcher = self.compile_glob(pattern)?;
+        let relative = match path.strip_prefix(&self.root) {
+            Ok(relative) => relative,
+            Err(_) if path.is_absolute() => return Ok(false),
+            Err(_) => path.strip_prefix(".").unwrap_or(path),
+        };
+        Ok(matcher.is_match(to_posix_string(relative)))
+    }
+
+    /// Files, lines and bytes per detected language
+    ///
+    /// Aggregates every indexed text file whose language `language::detect`
+    /// recognises; binary files and unknown kinds are left out. Line counts
+    /// are read from disk on first use and cached until the file's mtime or
+    /// size changes, so repeated calls only read files that were modified.
+    /// Files that cannot be read are skipped.
+    pub fn code_stats(&self) -> Result<HashMap<String, LanguageStats>> {
+        use rayon::prelude::*;
+
+        let state = self.state();
+
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let files: Vec<(PathBuf, &'static str, FileMetadata)> = state
+            .entries
+            .iter()
+            .filter(|entry| !entry.is_dir && !entry.is_binary)
+            .filter_map(|entry| {
+                let language = language::detect(entry.key())?;
+                Some((entry.key().clone(), language, entry.value().clone()))
+            })
+            .collect();
+
+        let counted: Vec<(&'static str, u64, u64)> = files
+            .par_iter()
+            .filter_map(|(path, language, metadata)| {
+                let lines = self.line_count(path, metadata)?;
+                Some((*language, lines, metadata.size))
+            })
+            .collect();
+
+        // Drop counts for files that left the index by a rebuild
+        self.line_counts
+            .retain(|path, _| state.entries.contains_key(path));
+
+        let mut stats: HashMap<String, LanguageStats> = HashMap::new();
+        for (language, lines, bytes) in counted {
+            let totals = stats.entry(language.to_string()).or_default();
+            totals.0 += 1;
+            totals.1 += lines;
+            totals.2 += bytes;
+        }
+        Ok(stats)
+    }
+
+    /// Number of indexed files matching a glob pattern
+    pub fn count_files(&self, pattern: &str) -> Result<usize> {
+        Ok(self.glob_paths_with_options(pattern, false)?.len())
+    }
+
+    /// Total lines across the text files matching a glob pattern
+    ///
+    /// Shares the per-file line count cache with `code_stats`, reading only
+    /// files not yet counted or changed since. Binary files and files that
+    /// cannot be read add nothing.
+    pub fn count_lines(&self, pattern: &str) -> Result<u64> {
+        use rayon::prelude::*;
+
+        let state = self.state();
+
+        let files = self.glob_paths(pattern)?;
+        Ok(files
+            .par_iter()
+            .filter_map(|path| {
+                let metadata = state.entries.get(path)?.clone();
+                self.line_count(path, &metadata)
+            })
+            .sum())
+    }
+
+    /// Number of lines in a file, from the cache when its metadata matches
+    ///
+    /// A final line without a trailing newline still counts. The file is
+    /// streamed through a fixed buffer, so large files are never held in
+    /// memory.
+    fn line_count(&self, path: &Path, metadata: &FileMetadata) -> Option<u64> {
+        use std::io::Read;
+
+        if let Some(cached) = self.line_counts.get(path) {
+            if cached.mtime == metadata.mtime && cached.size == metadata.size {
+                return Some(cached.lines);
+            }
+        }
+
+        let (newlines, last) = {
+            let _permit = limit::acquire();
+            let mut file = std::fs::File::open(path).ok()?;
+            let mut buffer = vec![0u8; LINE_COUNT_CHUNK];
+            let mut newlines = 0u64;
+            let mut last = None;
+            loop {
+                let n = match file.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(_) => return None,
+                };
+                newlines += buffer[..n].iter().filter(|&&b| b == b'\n').count() as u64;
+                last = Some(buffer[n - 1]);
+            }
+            (newlines, last)
+        };
+        let lines = newlines + u64::from(last.is_some_and(|b| b != b'\n'));
+
+        self.line_counts.insert(
+            path.to_path_buf(),
+            LineCount {
+                mtime: metadata.mtime,
+                size: metadata.size,
+                lines,
+            },
+        );
+        Some(lines)
+    }
+
+    /// The `n` most recently modified files, newest first
+    ///
+    /// Scans the indexed metadata, keeping only the current top `n` in a
+    /// min-heap, so memory stays bounded by `n` rather than the tree size.
+    /// Files with equal mtimes are ordered by path.
+    pub fn recent_files(&self, n: usize, pattern: Option<&str>) -> Result<Vec<(String, f64)>> {
+        use std::cmp::{Ordering as CmpOrdering, Reverse};
+        use std::collections::BinaryHeap;
+
+        /// Heap entry ranked by mtime; on equal mtimes the smaller path ranks higher
+        struct Recent(f64, String);
+
+        impl Ord for Recent {
+            fn cmp(&self, other: &Self) -> CmpOrdering {
+                self.0
+                    .total_cmp(&other.0)
+                    .then_with(|| other.1.cmp(&self.1))
+            }
+        }
+
+        impl PartialOrd for Recent {
+            fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl PartialEq for Recent {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == CmpOrdering::Equal
+            }
+        }
+
+        impl Eq for Recent {}
+
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let matcher = pattern
+            .filter(|p| *p != "**/*" && *p != "**")
+            .map(|p| self.compile_glob(p))
+            .transpose()?;
+
+        if n == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut heap: BinaryHeap<Reverse<Recent>> = BinaryHeap::with_capacity(n + 1);
+        for entry in state.entries.iter() {
+            if entry.is_dir {
+                continue;
+            }
+            let mtime = entry.mtime;
+            // Skip the path conversion for files that cannot make the cut
+            if heap.len() == n && heap.peek().is_some_and(|Reverse(min)| mtime < min.0) {
+                continue;
+            }
+
+            let relative = self.relative_path_fast(entry.key());
+            if matcher.as_ref().is_some_and(|m| !m.is_match(&relative)) {
+                continue;
+            }
+
+            heap.push(Reverse(Recent(mtime, relative)));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        // Ascending order of Reverse is descending order of Recent
+        Ok(heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(Recent(mtime, path))| (path, mtime))
+            .collect())
+    }
+
+    /// Whether any indexed entry at or under `dir` has `mtime >= timestamp`
+    ///
+    /// Walks only that subtree through the directory children cache and
+    /// stops at the first hit. Directories count too, so a file removed
+    /// from the index still shows through its parent's mtime once the index
+    /// has refreshed that directory. A path the index does not hold has no
+    /// entries and yields false.
+    pub fn changed_under(&self, dir: &Path, timestamp: f64) -> Result<bool> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let start = self.canonical_key(&state, dir);
+        if state
+            .entries
+            .get(&start)
+            .is_some_and(|entry| entry.mtime >= timestamp)
+        {
+            return Ok(true);
+        }
+
+        let mut pending = vec![start];
+        while let Some(current) = pending.pop() {
+            let Some(children) = state.dir_children.get(&current) else {
+                continue;
+            };
+            for child in children.iter() {
+                let Some(entry) = state.entries.get(child) else {
+                    continue;
+                };
+                if entry.mtime >= timestamp {
+                    return Ok(true);
+                }
+                if entry.is_dir {
+                    pending.push(child.clone());
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Get metadata for a path
+    pub fn get_metadata(&self, path: &Path) -> Option<FileMetadata> {
+        let state = self.state();
+        state
+            .entries
+            .get(&self.canonical_key(&state, path))
+            .map(|entry| entry.clone())
+    }
+
+    /// Get the relative path from the root (optimized version)
+    ///
+    /// Always uses `/` separators, regardless of platform.
+    #[inline]
+    fn relative_path_fast(&self, path: &Path) -> String {
+        // Valid UTF-8 borrows without copying; non-UTF8 paths are converted lossily
+        to_posix_string(path.strip_prefix(&self.root).unwrap_or(path))
+    }
+
+    /// Get the relative path from the root
+    #[allow(dead_code)]
+    fn relative_path(&self, path: &Path) -> String {
+        self.relative_path_fast(path)
+    }
+
+    /// Compile a glob pattern with lock-free caching
+    #[inline]
+    fn compile_glob(&self, pattern: &str) -> Result<GlobMatcher> {
+        // Fast path: lock-free cache lookup
+        if let Some(matcher) = self.glob_cache.get(pattern) {
+            return Ok(matcher);
+        }
+
+        // Cache miss: compile and store
+        let matcher = Glob::new(pattern)
+            .map(|g| g.compile_matcher())
+            .map_err(AgentGearError::Glob)?;
+
+        self.glob_cache.insert(pattern.to_string(), matcher.clone());
+        Ok(matcher)
+    }
+
+    /// Compile a path regex with lock-free caching
+    #[inline]
+    fn compile_regex(&self, pattern: &str) -> Result<regex::Regex> {
+        if let Some(regex) = self.regex_cache.get(pattern) {
+            return Ok(regex);
+        }
+
+        let regex = regex::Regex::new(pattern).map_err(|e| AgentGearError::Regex(e.to_string()))?;
+
+        self.regex_cache.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    /// Get the root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the number of indexed entries
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        let state = self.state();
+        state.entries.len()
+    }
+
+    /// Check if the index is empty
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        let state = self.state();
+        state.entries.is_empty()
+    }
+
+    // ========== Incremental update methods ==========
+
+    /// Add a new path to the index
+    pub fn add_path(&self, path: &Path) -> Result<()> {
+        self.note_touched(path);
+        let state = self.state();
+        // Skip if path doesn't exist
+        if !path.exists() {
+            return Ok(());
+        }
+
+        // A stored path differing only in case is replaced by the new
+        // spelling, which covers case-only renames
+        if self.case_insensitive {
+            let stored = self.canonical_key(&state, path);
+            if stored != path {
+                self.remove_path(&stored);
+            }
+        }
+
+        // Get metadata
+        let metadata = std::fs::metadata(path)?;
+        let is_dir = metadata.is_dir();
+        if self.is_filtered(path, is_dir) {
+            return Ok(());
+        }
+        if self.at_capacity(state.entries.len()) && !state.entries.contains_key(path) {
+            self.truncated.store(true, Ordering::SeqCst);
+            return Ok(());
+        }
+        let size = metadata.len();
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        let is_binary = !is_dir && self.detect_binary(path, size);
+
+        let file_metadata = FileMetadata {
+            size,
+            mtime,
+            is_dir,
+            is_binary,
+        };
+
+        // Add to entries
+        self.track_key(&state, path);
+        state.entries.insert(path.to_path_buf(), file_metadata);
+
+        // Update directory children
+        if let Some(parent) = path.parent() {
+            state
+                .dir_children
+                .entry(self.canonical_key(&state, parent))
+                .or_default()
+                .push(path.to_path_buf());
+        }
+
+        // Update all_files if it's a file
+        if !is_dir {
+            if let Ok(mut files) = state.all_files.write() {
                 if !files.contains(&path.to_path_buf()) {
                     files.push(path.to_path_buf());
                 }
@@ -523,6 +1682,8 @@ impl FileIndex {
 
     /// Update metadata for an existing path
     pub fn update_path(&self, path: &Path) -> Result<()> {
+        self.note_touched(path);
+        let state = self.state();
         // Skip if path doesn't exist
         if !path.exists() {
             return Ok(());
@@ -531,6 +1692,9 @@ impl FileIndex {
         // Get updated metadata
         let metadata = std::fs::metadata(path)?;
         let is_dir = metadata.is_dir();
+        if self.is_filtered(path, is_dir) {
+            return Ok(());
+        }
         let size = metadata.len();
         let mtime = metadata
             .modified()
@@ -539,11 +1703,7 @@ impl FileIndex {
             .map(|d| d.as_secs_f64())
             .unwrap_or(0.0);
 
-        let is_binary = if !is_dir && size > 0 {
-            Self::is_binary_file(path)
-        } else {
-            false
-        };
+        let is_binary = !is_dir && self.detect_binary(path, size);
 
         let file_metadata = FileMetadata {
             size,
@@ -553,40 +1713,266 @@ impl FileIndex {
         };
 
         // Update entry
-        self.entries.insert(path.to_path_buf(), file_metadata);
+        state
+            .entries
+            .insert(self.canonical_key(&state, path), file_metadata);
 
         Ok(())
     }
 
+    /// Bring the index back in line with the disk without rebuilding it
+    ///
+    /// Walks the tree with the same ignore rules as `build`, adding paths the
+    /// index is missing, re-reading files whose size or mtime changed and
+    /// dropping entries that no longer exist. Unlike `refresh`, unchanged
+    /// files are neither re-read nor copied into a new state. The walk is
+    /// serial to keep the background cost low.
+    ///
+    /// # Returns
+    /// Number of entries added, updated or removed
+    pub fn reconcile(&self) -> Result<usize> {
+        let state = self.state();
+        if !self.is_ready() || self.is_building.load(Ordering::SeqCst) {
+            return Ok(0);
+        }
+
+        let walker = self.walk_builder().build();
+
+        let mut seen = std::collections::HashSet::new();
+        let mut fixed = 0;
+
+        for entry in walker.flatten() {
+            let path = entry.path();
+            if path == self.root {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if self.is_filtered(path, metadata.is_dir()) {
+                continue;
+            }
+
+            let mtime = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0);
+
+            // Directory mtimes change with their children, so only the
+            // kind is compared for them
+            let stored = state
+                .entries
+                .get(&self.canonical_key(&state, path))
+                .map(|m| {
+                    (
+                        m.is_dir != metadata.is_dir(),
+                        !m.is_dir && (m.size != metadata.len() || m.mtime != mtime),
+                    )
+                });
+
+            match stored {
+                None => {
+                    self.add_path(path)?;
+                    // A path refused by the entry cap is not a fix
+                    if state.entries.contains_key(path) {
+                        fixed += 1;
+                    }
+                }
+                Some((true, _)) => {
+                    self.remove_path(path);
+                    self.add_path(path)?;
+                    fixed += 1;
+                }
+                Some((false, true)) => {
+                    self.update_path(path)?;
+                    fixed += 1;
+                }
+                Some((false, false)) => {}
+            }
+
+            seen.insert(self.canonical_key(&state, path));
+        }
+
+        let stale: Vec<PathBuf> = state
+            .entries
+            .iter()
+            .filter(|entry| !seen.contains(entry.key()))
+            .map(|entry| entry.key().clone())
+            .collect();
+        fixed += stale.len();
+        for path in stale {
+            self.remove_path(&path);
+        }
+
+        Ok(fixed)
+    }
+
+    /// Bring specific paths in line with the disk
+    ///
+    /// The targeted counterpart to `reconcile` for callers that know which
+    /// files changed: each path is added, re-read or removed depending on
+    /// whether it exists and is already indexed, and a path whose kind
+    /// changed between file and directory is re-added. Paths are handled in
+    /// parallel and the index stays ready throughout.
+    ///
+    /// # Returns
+    /// Number of paths that were already indexed or now are
+    pub fn refresh_paths(&self, paths: &[PathBuf]) -> Result<usize> {
+        use rayon::prelude::*;
+
+        paths
+            .par_iter()
+            .map(|path| {
+                if !path.exists() {
+                    self.remove_path(path);
+                    return Ok(0);
+                }
+                let is_dir = path.is_dir();
+                match self.get_metadata(path) {
+                    Some(m) if m.is_dir == is_dir => self.update_path(path)?,
+                    Some(_) => {
+                        self.remove_path(path);
+                        self.add_path(path)?;
+                    }
+                    None => self.add_path(path)?,
+                }
+                Ok(usize::from(self.get_metadata(path).is_some()))
+            })
+            .try_reduce(|| 0, |a, b| Ok(a + b))
+    }
+
     /// Remove a path from the index
     pub fn remove_path(&self, path: &Path) {
+        self.note_touched(path);
+        let state = self.state();
+        let path = &self.canonical_key(&state, path);
+        if self.case_insensitive {
+            state.folded_keys.remove(&fold_case(path));
+        }
+
         // Remove from entries
-        let removed = self.entries.remove(path);
+        let removed = state.entries.remove(path);
+        self.line_counts.remove(path);
 
         // Update directory children
         if let Some(parent) = path.parent() {
-            if let Some(mut children) = self.dir_children.get_mut(&parent.to_path_buf()) {
+            if let Some(mut children) = state
+                .dir_children
+                .get_mut(&self.canonical_key(&state, parent))
+            {
                 children.retain(|p| p != path);
             }
         }
 
         // Remove from dir_children if it was a directory
-        self.dir_children.remove(path);
+        state.dir_children.remove(path);
 
         // Update all_files if it was a file
         if let Some((_, metadata)) = removed {
             if !metadata.is_dir {
-                if let Ok(mut files) = self.all_files.write() {
+                if let Ok(mut files) = state.all_files.write() {
                     files.retain(|p| p != path);
                 }
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // ========== Snapshot methods ==========
+
+    /// Capture the current index as a serializable snapshot
+    pub fn snapshot(&self) -> Result<Snapshot> {
+        let state = self.state();
+        if !self.is_ready() {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let entries = state
+            .entries
+            .iter()
+            .map(|entry| {
+                let metadata = entry.value();
+                SnapshotEntry {
+                    path: self.relative_path_fast(entry.key()),
+                    size: metadata.size,
+                    mtime: metadata.mtime,
+                    is_dir: metadata.is_dir,
+                    is_binary: metadata.is_binary,
+                }
+            })
+            .collect();
+
+        Ok(Snapshot::new(entries))
+    }
+
+    /// Replace the index contents with a previously captured snapshot
+    ///
+    /// Paths are rebased onto this index's root. The snapshot is trusted as-is;
+    /// changes made on disk since it was saved are not reflected until the
+    /// watcher reports them or `refresh()` is called.
+    pub fn restore(&self, snapshot: Snapshot) -> Result<()> {
+        // Share the build guard so a restore never interleaves with a walk
+        if self
+            .is_building
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(AgentGearError::IndexNotReady);
+        }
+
+        let shadow = IndexState::default();
+        let mut truncated = false;
+        let mut all_files = Vec::new();
+
+        for entry in snapshot.entries {
+            let path = self.root.join(&entry.path);
+            if self.is_filtered(&path, entry.is_dir) {
+                continue;
+            }
+            if self.at_capacity(shadow.entries.len()) {
+                truncated = true;
+                break;
+            }
+
+            if let Some(parent) = path.parent() {
+                shadow
+                    .dir_children
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(path.clone());
+            }
+
+            if !entry.is_dir {
+                all_files.push(path.clone());
+            }
+
+            self.track_key(&shadow, &path);
+            shadow.entries.insert(
+                path,
+                FileMetadata {
+                    size: entry.size,
+                    mtime: entry.mtime,
+                    is_dir: entry.is_dir,
+                    is_binary: entry.is_binary,
+                },
+            );
+        }
+
+        if let Ok(mut files) = shadow.all_files.write() {
+            *files = all_files;
+        }
+
+        self.swap_state(shadow, truncated);
+        self.is_building.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     use tempfile::tempdir;
 
     fn create_test_files(dir: &Path) {
@@ -638,6 +2024,27 @@ mod tests {
         assert_eq!(src_files.len(), 2); // main.rs, lib.rs
     }
 
+    #[test]
+    fn test_glob_brace_alternation() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        let mut files = index.glob("**/*.{rs,md}").unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["README.md", "src/lib.rs", "src/main.rs", "tests/test.rs"]
+        );
+
+        // Nested groups are expanded too
+        let mut files = index.glob("{src/{main,lib},README}.*").unwrap();
+        files.sort();
+        assert_eq!(files, vec!["README.md", "src/lib.rs", "src/main.rs"]);
+    }
+
     #[test]
     fn test_metadata() {
         let dir = tempdir().unwrap();
@@ -653,4 +2060,810 @@ mod tests {
         assert!(!metadata.is_binary);
         assert!(metadata.size > 0);
     }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+        let snapshot = index.snapshot().unwrap();
+
+        let restored = FileIndex::new(dir.path().to_path_buf());
+        restored.restore(snapshot).unwrap();
+
+        assert!(restored.is_ready());
+        assert_eq!(restored.len(), index.len());
+        assert_eq!(restored.glob("**/*.rs").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_find_by_name() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("src/main.rs.bak"), "").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        let found = index.find_by_name("main.rs", false).unwrap();
+        assert_eq!(found, vec!["src/main.rs"]);
+
+        assert!(index.find_by_name("readme.md", false).unwrap().is_empty());
+        assert_eq!(
+            index.find_by_name("readme.md", true).unwrap(),
+            vec!["README.md"]
+        );
+
+        // Directories match too
+        assert_eq!(index.find_by_name("src", false).unwrap(), vec!["src"]);
+    }
+
+    #[test]
+    fn test_list_regex() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("src/v2024.rs"), "").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        let found = index.list_regex(r"\d{4}", true).unwrap();
+        assert_eq!(found, vec!["src/v2024.rs"]);
+
+        let mut rs = index.list_regex(r"^src/.*\.rs$", true).unwrap();
+        rs.sort();
+        assert_eq!(rs, vec!["src/lib.rs", "src/main.rs", "src/v2024.rs"]);
+
+        // Directories are only included when only_files is false
+        assert!(index.list_regex("^tests$", true).unwrap().is_empty());
+        assert_eq!(index.list_regex("^tests$", false).unwrap(), vec!["tests"]);
+
+        assert!(matches!(
+            index.list_regex("(", true),
+            Err(AgentGearError::Regex(_))
+        ));
+    }
+
+    #[test]
+    fn test_binary_detection_limit() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("big.txt"), "x".repeat(100)).unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf()).with_binary_detection_limit(50);
+        index.build().unwrap();
+
+        let metadata = |name: &str| index.get_metadata(&dir.path().join(name)).unwrap();
+        assert!(metadata("big.txt").is_binary);
+        assert!(!metadata("README.md").is_binary);
+
+        // add_path applies the same limit
+        std::fs::write(dir.path().join("big2.txt"), "y".repeat(100)).unwrap();
+        index.add_path(&dir.path().join("big2.txt")).unwrap();
+        assert!(metadata("big2.txt").is_binary);
+    }
+
+    #[test]
+    fn test_binary_overrides() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("trace.fixture"), b"a\0b").unwrap();
+        std::fs::write(dir.path().join("model.dat"), "plain text").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+        let metadata = |name: &str| index.get_metadata(&dir.path().join(name)).unwrap();
+        assert!(metadata("trace.fixture").is_binary);
+        assert!(!metadata("model.dat").is_binary);
+
+        let overrides = BinaryOverrides::new(["dat"], ["fixture"]);
+        assert_eq!(index.set_binary_overrides(overrides).unwrap(), 2);
+        assert!(!metadata("trace.fixture").is_binary);
+        assert!(metadata("model.dat").is_binary);
+        assert!(!metadata("README.md").is_binary);
+
+        // Dropping the overrides sniffs the files again
+        assert_eq!(
+            index
+                .set_binary_overrides(BinaryOverrides::default())
+                .unwrap(),
+            2
+        );
+        assert!(metadata("trace.fixture").is_binary);
+    }
+
+    #[test]
+    fn test_glob_grouped() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        let patterns = ["src/*.rs", "**/*.rs", "*.py"].map(String::from);
+        let groups = index.glob_grouped(&patterns).unwrap();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups["src/*.rs"], vec!["src/lib.rs", "src/main.rs"]);
+        assert_eq!(
+            groups["**/*.rs"],
+            vec!["src/lib.rs", "src/main.rs", "tests/test.rs"]
+        );
+        assert!(groups["*.py"].is_empty());
+
+        assert!(matches!(
+            index.glob_grouped(&["[".to_string()]),
+            Err(AgentGearError::Glob(_))
+        ));
+    }
+
+    #[test]
+    fn test_glob_cache_introspection() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+        assert!(index.cached_globs().is_empty());
+
+        index.glob("src/*").unwrap();
+        index.glob("**/*.rs").unwrap();
+        index.glob("**/*").unwrap(); // fast path, never compiled
+        assert_eq!(index.cached_globs(), vec!["**/*.rs", "src/*"]);
+
+        index.clear_glob_cache();
+        assert!(index.cached_globs().is_empty());
+    }
+
+    #[test]
+    fn test_case_insensitive_lookup() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("src/Foo.txt"), "foo").unwrap();
+
+        let index = FileIndex::with_case_insensitive(dir.path().to_path_buf(), true);
+        index.build().unwrap();
+
+        // Mixed-case lookups find the stored entry
+        assert!(index
+            .get_metadata(&dir.path().join("SRC/foo.TXT"))
+            .is_some());
+
+        // Re-adding under another spelling does not duplicate the entry
+        index.add_path(&dir.path().join("src/Foo.txt")).unwrap();
+        index.update_path(&dir.path().join("src/FOO.txt")).unwrap();
+        assert_eq!(
+            index.find_by_name("foo.txt", true).unwrap(),
+            vec!["src/Foo.txt"]
+        );
+
+        // A case-only rename replaces the old spelling
+        std::fs::rename(
+            dir.path().join("src/Foo.txt"),
+            dir.path().join("src/foo.txt"),
+        )
+        .unwrap();
+        index.add_path(&dir.path().join("src/foo.txt")).unwrap();
+        assert_eq!(index.list("**/*.txt", true).unwrap(), vec!["src/foo.txt"]);
+
+        index.remove_path(&dir.path().join("src/FOO.TXT"));
+        assert!(index.list("**/*.txt", true).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_case_sensitive_lookup() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("Foo.txt"), "foo").unwrap();
+
+        let index = FileIndex::with_case_insensitive(dir.path().to_path_buf(), false);
+        index.build().unwrap();
+
+        assert!(index.get_metadata(&dir.path().join("Foo.txt")).is_some());
+        assert!(index.get_metadata(&dir.path().join("foo.txt")).is_none());
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+        assert_eq!(index.reconcile().unwrap(), 0);
+
+        // Changes the watcher never reported
+        std::fs::write(dir.path().join("src/new.rs"), "fn new() {}").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() { run() }").unwrap();
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+
+        assert_eq!(index.reconcile().unwrap(), 3);
+        assert!(index.is_ready());
+
+        let files = index.list("**/*", true).unwrap();
+        assert!(files.contains(&"src/new.rs".to_string()));
+        assert!(!files.contains(&"README.md".to_string()));
+        assert_eq!(
+            index
+                .get_metadata(&dir.path().join("src/main.rs"))
+                .unwrap()
+                .size,
+            19
+        );
+        assert_eq!(index.reconcile().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_refresh_paths() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        std::fs::write(dir.path().join("src/new.rs"), "fn new() {}").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() { run() }").unwrap();
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+
+        let paths = vec![
+            dir.path().join("src/new.rs"),
+            dir.path().join("src/main.rs"),
+            dir.path().join("README.md"),
+        ];
+        assert_eq!(index.refresh_paths(&paths).unwrap(), 2);
+
+        let files = index.list("**/*", true).unwrap();
+        assert!(files.contains(&"src/new.rs".to_string()));
+        assert!(!files.contains(&"README.md".to_string()));
+        assert_eq!(
+            index
+                .get_metadata(&dir.path().join("src/main.rs"))
+                .unwrap()
+                .size,
+            19
+        );
+
+        // Already in line with the disk, so nothing is reported as stale
+        assert_eq!(index.reconcile().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_refresh_swaps_state() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        assert!(index.build().unwrap());
+        assert!(!index.cancel_build());
+
+        std::fs::write(dir.path().join("src/new.rs"), "fn new() {}").unwrap();
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+
+        // A query holding the old state keeps its view across the swap
+        let old = index.state();
+        assert!(index.refresh().unwrap());
+        assert!(index.is_ready());
+        assert!(old.entries.contains_key(&dir.path().join("README.md")));
+
+        let files = index.list("**/*", true).unwrap();
+        assert!(files.contains(&"src/new.rs".to_string()));
+        assert!(!files.contains(&"README.md".to_string()));
+    }
+
+    #[test]
+    fn test_cancel_build() {
+        let dir = tempdir().unwrap();
+        for i in 0..2000 {
+            std::fs::write(dir.path().join(format!("file{}.txt", i)), "x").unwrap();
+        }
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+        std::fs::write(dir.path().join("late.txt"), "x").unwrap();
+
+        let swapped = std::thread::scope(|scope| {
+            let refresh = scope.spawn(|| index.refresh().unwrap());
+            while !refresh.is_finished() && !index.cancel_build() {
+                std::thread::yield_now();
+            }
+            refresh.join().unwrap()
+        });
+
+        // A cancelled walk leaves the previous entries in place
+        let files = index.list("**/*", true).unwrap();
+        assert_eq!(files.contains(&"late.txt".to_string()), swapped);
+        assert_eq!(files.len(), if swapped { 2001 } else { 2000 });
+        assert!(index.is_ready());
+        assert!(!index.cancel_build());
+
+        // The initial build runs to completion
+        let fresh = FileIndex::new(dir.path().to_path_buf());
+        let built = std::thread::scope(|scope| {
+            let build = scope.spawn(|| fresh.build().unwrap());
+            while !build.is_finished() {
+                assert!(!fresh.cancel_build());
+                std::thread::yield_now();
+            }
+            build.join().unwrap()
+        });
+        assert!(built);
+        assert!(fresh.is_ready());
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf()).with_max_entries(3);
+        index.build().unwrap();
+        assert!(index.is_ready());
+        assert!(index.is_truncated());
+        assert_eq!(index.len(), 3);
+
+        // New paths stay out while at the cap, existing ones still update
+        std::fs::write(dir.path().join("new.rs"), "").unwrap();
+        index.add_path(&dir.path().join("new.rs")).unwrap();
+        assert!(index.get_metadata(&dir.path().join("new.rs")).is_none());
+        assert_eq!(index.len(), 3);
+
+        let index = FileIndex::new(dir.path().to_path_buf()).with_max_entries(100);
+        index.build().unwrap();
+        assert!(!index.is_truncated());
+    }
+
+    #[test]
+    fn test_include_extensions() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("src/logo.png"), [0u8, 1, 2]).unwrap();
+
+        let index =
+            FileIndex::new(dir.path().to_path_buf()).with_extensions(ExtensionFilter::new(["rs"]));
+        index.build().unwrap();
+
+        let mut files = index.list("**/*", true).unwrap();
+        files.sort();
+        assert_eq!(files, vec!["src/lib.rs", "src/main.rs", "tests/test.rs"]);
+        assert!(index.get_metadata(&dir.path().join("src")).is_some());
+
+        std::fs::write(dir.path().join("notes.txt"), "skip").unwrap();
+        std::fs::write(dir.path().join("src/new.rs"), "fn new() {}").unwrap();
+        index.add_path(&dir.path().join("notes.txt")).unwrap();
+        index.update_path(&dir.path().join("notes.txt")).unwrap();
+        index.add_path(&dir.path().join("src/new.rs")).unwrap();
+
+        assert!(index.get_metadata(&dir.path().join("notes.txt")).is_none());
+        assert!(index.get_metadata(&dir.path().join("src/new.rs")).is_some());
+        assert_eq!(index.reconcile().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_ignored_dirs() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::create_dir_all(dir.path().join("web/node_modules/react")).unwrap();
+        std::fs::write(dir.path().join("web/node_modules/react/index.js"), "x").unwrap();
+        std::fs::write(dir.path().join("web/app.js"), "x").unwrap();
+        std::fs::write(dir.path().join("src/build"), "not a directory").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf())
+            .with_ignored_dirs(DirIgnore::new(["node_modules", "build"]));
+        index.build().unwrap();
+
+        assert!(index.list("**/node_modules/**", false).unwrap().is_empty());
+        assert!(index
+            .get_metadata(&dir.path().join("web/node_modules"))
+            .is_none());
+        assert!(index.get_metadata(&dir.path().join("web/app.js")).is_some());
+        assert!(index.get_metadata(&dir.path().join("src/build")).is_some());
+
+        // Watcher updates below an ignored directory are dropped too
+        let new_file = dir.path().join("web/node_modules/react/new.js");
+        std::fs::write(&new_file, "x").unwrap();
+        index.add_path(&new_file).unwrap();
+        assert!(index.get_metadata(&new_file).is_none());
+        assert_eq!(index.reconcile().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_custom_ignore_files() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::create_dir_all(dir.path().join("scratch")).unwrap();
+        std::fs::write(dir.path().join("scratch/notes.md"), "x").unwrap();
+        std::fs::write(dir.path().join("src/a.gen.rs"), "x").unwrap();
+        std::fs::write(dir.path().join("src/keep.gen.rs"), "x").unwrap();
+        std::fs::write(dir.path().join(".ignore"), "*.gen.rs\n").unwrap();
+        std::fs::write(dir.path().join(".agentignore"), "scratch/\n!keep.gen.rs\n").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf())
+            .with_custom_ignore_files(vec![".agentignore".to_string()]);
+        index.build().unwrap();
+
+        assert!(index.list("scratch/**", false).unwrap().is_empty());
+        // The custom file overrides .ignore
+        assert_eq!(
+            index.list("**/*.gen.rs", true).unwrap(),
+            vec!["src/keep.gen.rs"]
+        );
+        assert!(index
+            .get_metadata(&dir.path().join("src/main.rs"))
+            .is_some());
+    }
+
+    #[test]
+    fn test_empty_files_and_dirs() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        std::fs::create_dir_all(dir.path().join("empty/nested")).unwrap();
+        std::fs::write(dir.path().join("a/b/blank.txt"), "").unwrap();
+        std::fs::write(dir.path().join("a/full.txt"), "x").unwrap();
+        std::fs::write(dir.path().join("zero.rs"), "").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        assert!(matches!(
+            index.empty_files(),
+            Err(AgentGearError::IndexNotReady)
+        ));
+        index.build().unwrap();
+
+        assert_eq!(
+            index.empty_files().unwrap(),
+            vec!["a/b/blank.txt", "zero.rs"]
+        );
+        // A directory holding only an empty directory is not itself empty
+        assert_eq!(index.empty_dirs().unwrap(), vec!["empty/nested"]);
+
+        index.remove_path(&dir.path().join("a/b/blank.txt"));
+        assert_eq!(index.empty_dirs().unwrap(), vec!["a/b", "empty/nested"]);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        assert!(matches!(
+            index.fingerprint(),
+            Err(AgentGearError::IndexNotReady)
+        ));
+        index.build().unwrap();
+        let fingerprint = index.fingerprint().unwrap();
+        assert_eq!(fingerprint.len(), 64);
+
+        // A second index of the same tree agrees
+        let other = FileIndex::new(dir.path().to_path_buf());
+        other.build().unwrap();
+        assert_eq!(other.fingerprint().unwrap(), fingerprint);
+
+        let new_file = dir.path().join("src/new.rs");
+        std::fs::write(&new_file, "x").unwrap();
+        index.add_path(&new_file).unwrap();
+        let added = index.fingerprint().unwrap();
+        assert_ne!(added, fingerprint);
+
+        std::fs::write(&new_file, "xy").unwrap();
+        index.update_path(&new_file).unwrap();
+        assert_ne!(index.fingerprint().unwrap(), added);
+
+        index.remove_path(&new_file);
+        std::fs::remove_file(&new_file).unwrap();
+        assert_eq!(index.fingerprint().unwrap(), fingerprint);
+    }
+
+    #[test]
+    fn test_diff_paths() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        let listed: Vec<PathBuf> = ["src/main.rs", "./src/lib.rs", "src/gone.rs", "tests"]
+            .iter()
+            .map(|p| dir.path().join(p))
+            .collect();
+        let (missing, untracked) = index.diff_paths(&listed).unwrap();
+
+        // Directories are not files, so "tests" is missing too
+        assert_eq!(missing, vec!["src/gone.rs", "tests"]);
+        assert_eq!(untracked, vec!["README.md", "tests/test.rs"]);
+
+        let (missing, untracked) = index.diff_paths(&[]).unwrap();
+        assert!(missing.is_empty());
+        assert_eq!(untracked.len(), 4);
+    }
+
+    #[test]
+    fn test_expand_specs() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join(".ignore"), "secret.rs\n").unwrap();
+        std::fs::write(dir.path().join("secret.rs"), "x").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        let specs = |specs: &[&str]| specs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(matches!(
+            index.expand_specs(&specs(&["src/main.rs"])),
+            Err(AgentGearError::IndexNotReady)
+        ));
+        index.build().unwrap();
+        assert!(index.get_metadata(&dir.path().join("secret.rs")).is_none());
+
+        let absolute = dir.path().join("README.md").display().to_string();
+        let expanded = index
+            .expand_specs(&specs(&[
+                "tests/test.rs",
+                "src/*.rs",
+                "./src/main.rs",
+                &absolute,
+                "secret.rs",
+                "src/gone.rs",
+                "src",
+                "**/*.{md,txt}",
+            ]))
+            .unwrap();
+        // Literals in order, glob matches sorted, duplicates and missing
+        // paths dropped; an ignored file named literally still resolves
+        assert_eq!(
+            expanded,
+            vec![
+                "tests/test.rs",
+                "src/lib.rs",
+                "src/main.rs",
+                "README.md",
+                "secret.rs"
+            ]
+        );
+
+        assert!(index.expand_specs(&specs(&["src/[unclosed"])).is_err());
+    }
+
+    #[test]
+    fn test_changed_under() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        assert!(matches!(
+            index.changed_under(dir.path(), 0.0),
+            Err(AgentGearError::IndexNotReady)
+        ));
+        index.build().unwrap();
+        assert!(index.changed_under(&dir.path().join("src"), 0.0).unwrap());
+
+        // Push one file's mtime well past everything else in the tree
+        let future = SystemTime::now() + std::time::Duration::from_secs(3600);
+        let new_file = dir.path().join("src/new.rs");
+        std::fs::File::create(&new_file)
+            .unwrap()
+            .set_modified(future)
+            .unwrap();
+        index.add_path(&new_file).unwrap();
+        let since = future
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_secs_f64()
+            - 60.0;
+
+        assert!(index.changed_under(&dir.path().join("src"), since).unwrap());
+        assert!(index.changed_under(dir.path(), since).unwrap());
+        assert!(index.changed_under(&new_file, since).unwrap());
+        assert!(!index
+            .changed_under(&dir.path().join("tests"), since)
+            .unwrap());
+        assert!(!index
+            .changed_under(&dir.path().join("missing"), 0.0)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_code_stats() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}\n\nfn b() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("tool.py"), "print(1)\n").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not code\n").unwrap();
+        std::fs::write(dir.path().join("logo.png"), [0u8, 1, 2, 3]).unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        assert!(matches!(
+            index.code_stats(),
+            Err(AgentGearError::IndexNotReady)
+        ));
+        index.build().unwrap();
+
+        let stats = index.code_stats().unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["Rust"], (2, 4, 33));
+        assert_eq!(stats["Python"], (1, 1, 9));
+
+        // Counts are cached per file and recomputed once it changes
+        let lib = dir.path().join("src/lib.rs");
+        std::fs::write(&lib, "fn a() {}\n").unwrap();
+        index.update_path(&lib).unwrap();
+        assert_eq!(index.code_stats().unwrap()["Rust"], (2, 2, 22));
+
+        index.remove_path(&lib);
+        assert_eq!(index.code_stats().unwrap()["Rust"], (1, 1, 12));
+        assert_eq!(index.line_counts.len(), 2);
+    }
+
+    #[test]
+    fn test_count_files_and_lines() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "fn a() {}\n\nfn b() {}\n").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(dir.path().join("logo.png"), [0u8, 1, 2, 3]).unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        assert!(matches!(
+            index.count_files("**/*"),
+            Err(AgentGearError::IndexNotReady)
+        ));
+        index.build().unwrap();
+
+        assert_eq!(index.count_files("**/*").unwrap(), 4);
+        assert_eq!(index.count_files("**/*.rs").unwrap(), 2);
+        assert_eq!(index.count_lines("**/*.rs").unwrap(), 4);
+        assert_eq!(index.count_lines("**/*").unwrap(), 6);
+        assert_eq!(index.line_counts.len(), 3);
+
+        // Lines spanning read chunks, with an unterminated last line
+        let big = dir.path().join("big.txt");
+        let line = "x".repeat(99) + "\n";
+        std::fs::write(&big, line.repeat(2000) + "tail").unwrap();
+        index.build().unwrap();
+        assert_eq!(index.count_lines("big.txt").unwrap(), 2001);
+    }
+
+    #[test]
+    fn test_classify() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::write(dir.path().join("empty.txt"), "").unwrap();
+        std::fs::write(dir.path().join("logo.png"), [0u8, 1, 2, 3]).unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        // Works before the index is built by sniffing the file
+        assert_eq!(
+            index.classify(&dir.path().join("logo.png")).unwrap(),
+            "binary"
+        );
+        index.build().unwrap();
+
+        assert_eq!(
+            index.classify(&dir.path().join("src/main.rs")).unwrap(),
+            "text"
+        );
+        assert_eq!(
+            index.classify(&dir.path().join("logo.png")).unwrap(),
+            "binary"
+        );
+        assert_eq!(
+            index.classify(&dir.path().join("empty.txt")).unwrap(),
+            "empty"
+        );
+        assert_eq!(
+            index.classify(&dir.path().join("nope.rs")).unwrap(),
+            "missing"
+        );
+        assert!(index.classify(&dir.path().join("src")).is_err());
+
+        // A stale entry is not trusted once the size differs
+        std::fs::write(dir.path().join("src/main.rs"), [b'a', 0, b'b']).unwrap();
+        assert_eq!(
+            index.classify(&dir.path().join("src/main.rs")).unwrap(),
+            "binary"
+        );
+        std::fs::remove_file(dir.path().join("README.md")).unwrap();
+        assert_eq!(
+            index.classify(&dir.path().join("README.md")).unwrap(),
+            "missing"
+        );
+    }
+
+    #[test]
+    fn test_dirs_with_matches() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+        std::fs::create_dir_all(dir.path().join("src/nested/deep")).unwrap();
+        std::fs::write(dir.path().join("src/nested/deep/x.rs"), "").unwrap();
+        std::fs::write(dir.path().join("build.rs"), "").unwrap();
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        assert_eq!(
+            index.dirs_with_matches("**/*.rs").unwrap(),
+            vec![".", "src", "src/nested/deep", "tests"]
+        );
+        assert_eq!(index.dirs_with_matches("**/*.md").unwrap(), vec!["."]);
+        assert!(index.dirs_with_matches("**/*.py").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        let index = FileIndex::with_case_insensitive(PathBuf::from("/repo"), false);
+        assert!(index
+            .glob_matches("**/*.rs", Path::new("src/foo.rs"))
+            .unwrap());
+        assert!(index
+            .glob_matches("src/*.rs", Path::new("./src/foo.rs"))
+            .unwrap());
+        assert!(index
+            .glob_matches("**/*.rs", Path::new("/repo/src/foo.rs"))
+            .unwrap());
+        assert!(!index
+            .glob_matches("**/*.rs", Path::new("/elsewhere/foo.rs"))
+            .unwrap());
+        assert!(!index
+            .glob_matches("src/*.rs", Path::new("tests/foo.rs"))
+            .unwrap());
+        assert!(index.glob_matches("[", Path::new("src/foo.rs")).is_err());
+        assert_eq!(index.cached_globs().len(), 2);
+    }
+
+    #[test]
+    fn test_recent_files() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let base = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for (i, name) in ["README.md", "src/lib.rs", "tests/test.rs", "src/main.rs"]
+            .iter()
+            .enumerate()
+        {
+            let file = std::fs::File::options()
+                .write(true)
+                .open(dir.path().join(name))
+                .unwrap();
+            file.set_modified(base + std::time::Duration::from_secs(i as u64 * 60))
+                .unwrap();
+        }
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        index.build().unwrap();
+
+        let recent = index.recent_files(2, None).unwrap();
+        let paths: Vec<&str> = recent.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["src/main.rs", "tests/test.rs"]);
+        assert!(recent[0].1 > recent[1].1);
+
+        let recent = index.recent_files(10, Some("src/*")).unwrap();
+        let paths: Vec<&str> = recent.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, vec!["src/main.rs", "src/lib.rs"]);
+
+        assert!(index.recent_files(0, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let dir = tempdir().unwrap();
+        create_test_files(dir.path());
+
+        let index = FileIndex::new(dir.path().to_path_buf());
+        let empty = index.memory_usage();
+        index.build().unwrap();
+        let built = index.memory_usage();
+        assert!(built > empty);
+
+        // Every indexed path contributes at least its own length
+        let path_len: usize = index
+            .list("**/*", false)
+            .unwrap()
+            .iter()
+            .map(|p| dir.path().join(p).as_os_str().len())
+            .sum();
+        assert!(built - empty >= path_len);
+
+        index.glob("**/*.rs").unwrap();
+        assert!(index.memory_usage() > built);
+    }
 }
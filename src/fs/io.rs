@@ -5,28 +5,571 @@
 //! - Atomic file writing
 //! - Text replacement with safety checks
 
+use dashmap::DashMap;
 use pyo3::prelude::*;
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::index::FileMetadata;
 
 use crate::utils::error::{AgentGearError, Result};
+use crate::utils::limit;
+
+/// Default cap on the size of a file read whole into memory (1 GiB)
+pub const DEFAULT_MAX_READ_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Refuse a whole-file read of `size` bytes above `limit`
+///
+/// Called with the size from a stat, before any buffer is allocated.
+#[inline]
+fn check_read_size(path: &Path, size: u64, limit: Option<u64>) -> Result<()> {
+    match limit {
+        Some(limit) if size > limit => Err(AgentGearError::FileTooLarge {
+            path: path.display().to_string(),
+            size,
+            limit,
+        }),
+        _ => Ok(()),
+    }
+}
 
 /// Read a single file as text
 ///
 /// # Arguments
 /// * `py` - Python GIL token
 /// * `path` - Path to the file
-/// * `encoding` - Text encoding (currently only utf-8 is fully supported)
-pub fn read_file(py: Python<'_>, path: &Path, _encoding: &str) -> PyResult<String> {
-    py.allow_threads(|| {
-        std::fs::read_to_string(path).map_err(|e| {
+/// * `encoding` - Text encoding label (e.g. "utf-8", "latin-1")
+/// * `max_bytes` - Fail with `FileTooLarge` instead of reading a larger file
+///   (None = no limit)
+pub fn read_file(
+    py: Python<'_>,
+    path: &Path,
+    encoding: &str,
+    max_bytes: Option<u64>,
+) -> PyResult<String> {
+    use std::io::Read;
+
+    py.allow_threads(|| -> Result<String> {
+        let mut file = std::fs::File::open(path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 AgentGearError::PathNotFound(path.display().to_string())
             } else {
                 AgentGearError::Io(e)
             }
-        })
+        })?;
+
+        let size = file.metadata()?.len();
+        check_read_size(path, size, max_bytes)?;
+
+        if !super::encoding::is_utf8(encoding) {
+            let mut bytes = Vec::with_capacity(size as usize);
+            file.read_to_end(&mut bytes)?;
+            return super::encoding::decode(&bytes, encoding);
+        }
+
+        let mut content = String::with_capacity(size as usize);
+        file.read_to_string(&mut content)?;
+        Ok(content)
+    })
+    .map_err(|e| e.into())
+}
+
+/// Default entry budget of a `ReadCache`
+pub const DEFAULT_READ_CACHE_ENTRIES: usize = 256;
+
+/// Default content budget of a `ReadCache` (32 MiB)
+pub const DEFAULT_READ_CACHE_BYTES: usize = 32 * 1024 * 1024;
+
+/// A cached file body and the metadata it was read with
+struct CachedRead {
+    mtime: f64,
+    size: u64,
+    content: Arc<String>,
+    /// Value of the cache clock at the last hit, for LRU eviction
+    last_used: u64,
+}
+
+/// Size-bounded read-through cache of recently read files
+///
+/// An entry is served only while the file's mtime and size still match the
+/// values it was cached with, so edits made behind the watcher's back are
+/// picked up on the next read. A rewrite that keeps both the size and the
+/// mtime (within the filesystem's timestamp granularity) is not detected;
+/// `invalidate` covers the changes the watcher reports.
+pub struct ReadCache {
+    entries: DashMap<PathBuf, CachedRead>,
+    max_entries: usize,
+    max_bytes: usize,
+    bytes: AtomicUsize,
+    clock: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ReadCache {
+    /// Create a cache holding at most `max_entries` files and `max_bytes`
+    /// of content; larger files are read but never cached
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            max_entries,
+            max_bytes,
+            bytes: AtomicUsize::new(0),
+            clock: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Read a file as text, serving it from the cache while it is unchanged
+    ///
+    /// Files larger than `max_bytes` fail with `FileTooLarge`, cached or not.
+    pub fn read(&self, path: &Path, max_bytes: Option<u64>) -> Result<Arc<String>> {
+        let not_found = |e: std::io::Error| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        };
+
+        let metadata = std::fs::metadata(path).map_err(not_found)?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        let size = metadata.len();
+        check_read_size(path, size, max_bytes)?;
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(mut entry) = self.entries.get_mut(path) {
+            if entry.mtime == mtime && entry.size == size {
+                entry.last_used = tick;
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Arc::clone(&entry.content));
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let content = Arc::new(std::fs::read_to_string(path).map_err(not_found)?);
+        if content.len() <= self.max_bytes {
+            self.bytes.fetch_add(content.len(), Ordering::Relaxed);
+            let replaced = self.entries.insert(
+                path.to_path_buf(),
+                CachedRead {
+                    mtime,
+                    size,
+                    content: Arc::clone(&content),
+                    last_used: tick,
+                },
+            );
+            // A stale entry, or one inserted by a concurrent miss on the
+            // same path, leaves the budget with the entry it was
+            if let Some(old) = replaced {
+                self.bytes.fetch_sub(old.content.len(), Ordering::Relaxed);
+            }
+            self.evict();
+        }
+        Ok(content)
+    }
+
+    /// Drop a path from the cache
+    pub fn invalidate(&self, path: &Path) {
+        if let Some((_, entry)) = self.entries.remove(path) {
+            self.bytes.fetch_sub(entry.content.len(), Ordering::Relaxed);
+        }
+    }
+
+    /// Evict least recently used entries until both budgets are met
+    fn evict(&self) {
+        while self.entries.len() > self.max_entries
+            || self.bytes.load(Ordering::Relaxed) > self.max_bytes
+        {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|entry| entry.last_used)
+                .map(|entry| entry.key().clone());
+            match oldest {
+                Some(path) => self.invalidate(&path),
+                None => break,
+            }
+        }
+    }
+
+    /// Number of reads served from the cache and reads that went to disk
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Default entry budget of a `LineOffsetCache`
+pub const DEFAULT_LINE_OFFSET_ENTRIES: usize = 64;
+
+/// Byte offset of the start of each line
+///
+/// Lines end at `\n` as for `str::lines`, so a trailing newline does not
+/// start another line and empty input has no lines.
+pub fn line_offsets(bytes: &[u8]) -> Vec<u64> {
+    if bytes.is_empty() {
+        return Vec::new();
+    }
+    std::iter::once(0)
+        .chain(
+            bytes
+                .iter()
+                .enumerate()
+                .filter(|&(i, &b)| b == b'\n' && i + 1 < bytes.len())
+                .map(|(i, _)| (i + 1) as u64),
+        )
+        .collect()
+}
+
+/// Character offset of the start of each line
+///
+/// Counts Unicode code points rather than bytes, so entries index a decoded
+/// string (a Python `str`) directly. Lines end as in `line_offsets`.
+pub fn char_line_offsets(content: &str) -> Vec<u64> {
+    if content.is_empty() {
+        return Vec::new();
+    }
+    let bytes = content.as_bytes();
+    let mut offsets = vec![0];
+    let mut chars = 0u64;
+    for (i, &b) in bytes.iter().enumerate() {
+        // Continuation bytes do not start a character
+        if b & 0xC0 != 0x80 {
+            chars += 1;
+        }
+        if b == b'\n' && i + 1 < bytes.len() {
+            offsets.push(chars);
+        }
+    }
+    offsets
+}
+
+/// Line offsets of a file, scanned over an mmap
+///
+/// # Returns
+/// `(offsets, mtime, size)`, with the metadata taken from the open handle
+fn scan_line_offsets(path: &Path) -> Result<(Vec<u64>, f64, u64)> {
+    use memmap2::Mmap;
+
+    let file = std::fs::File::open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AgentGearError::PathNotFound(path.display().to_string())
+        } else {
+            AgentGearError::Io(e)
+        }
+    })?;
+    let metadata = file.metadata()?;
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0);
+    let size = metadata.len();
+
+    // Empty files cannot be mapped on every platform
+    if size == 0 {
+        return Ok((Vec::new(), mtime, size));
+    }
+    let mmap = unsafe { Mmap::map(&file) }.map_err(AgentGearError::Io)?;
+    Ok((line_offsets(&mmap), mtime, size))
+}
+
+/// Line offsets of a file at the time of the call
+pub fn file_line_offsets(py: Python<'_>, path: &Path) -> PyResult<Vec<u64>> {
+    py.allow_threads(|| scan_line_offsets(path).map(|(offsets, _, _)| offsets))
+        .map_err(|e| e.into())
+}
+
+/// Which byte sequences end a line for `count_lines_exact`
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `\n` only, as the cached line counts see it (`\r\n` ends in `\n`)
+    #[pyo3(name = "LF")]
+    Lf,
+    /// `\r\n` only; a bare `\n` or `\r` is part of the line
+    #[pyo3(name = "CRLF")]
+    Crlf,
+    /// `\r\n`, `\n` or a lone `\r` (classic Mac), each one break
+    #[pyo3(name = "ANY")]
+    Any,
+}
+
+/// Number of lines in `bytes` when lines end as `ending` says
+///
+/// A final line without a terminator still counts, and empty input has
+/// no lines. The scan is over bytes, so it holds for UTF-8 and other
+/// ASCII-compatible encodings but not for UTF-16.
+pub fn count_lines_with(bytes: &[u8], ending: LineEnding) -> u64 {
+    let mut breaks = 0u64;
+    let mut line_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = match (ending, bytes[i]) {
+            (LineEnding::Lf, b'\n') => 1,
+            (LineEnding::Crlf | LineEnding::Any, b'\r') if bytes.get(i + 1) == Some(&b'\n') => 2,
+            (LineEnding::Any, b'\n' | b'\r') => 1,
+            _ => 0,
+        };
+        if len == 0 {
+            i += 1;
+            continue;
+        }
+        breaks += 1;
+        i += len;
+        line_start = i;
+    }
+    breaks + u64::from(line_start < bytes.len())
+}
+
+/// Count the lines of a file under a line ending convention
+///
+/// Scans the file's bytes over an mmap instead of decoding it, so it is
+/// not bounded by the read size limit and reads nothing into memory.
+pub fn count_lines_exact(py: Python<'_>, path: &Path, ending: LineEnding) -> PyResult<u64> {
+    use memmap2::Mmap;
+
+    py.allow_threads(|| -> Result<u64> {
+        let file = std::fs::File::open(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        })?;
+        // Empty files cannot be mapped on every platform
+        if file.metadata()?.len() == 0 {
+            return Ok(0);
+        }
+        let mmap = unsafe { Mmap::map(&file) }.map_err(AgentGearError::Io)?;
+        Ok(count_lines_with(&mmap, ending))
+    })
+    .map_err(|e| e.into())
+}
+
+/// Cached line offsets and the metadata they were scanned with
+struct CachedOffsets {
+    mtime: f64,
+    size: u64,
+    offsets: Arc<Vec<u64>>,
+    /// Value of the cache clock at the last hit, for LRU eviction
+    last_used: u64,
+}
+
+/// Per-file cache of line start offsets, for seeking straight to a line
+///
+/// Like `ReadCache`, an entry is used only while the file's mtime and size
+/// match the values it was scanned with.
+pub struct LineOffsetCache {
+    entries: DashMap<PathBuf, CachedOffsets>,
+    max_entries: usize,
+    clock: AtomicU64,
+}
+
+impl LineOffsetCache {
+    /// Create a cache holding the offsets of at most `max_entries` files
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: DashMap::new(),
+            max_entries,
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    /// Line offsets of a file, rescanned only when it changed
+    pub fn offsets(&self, path: &Path) -> Result<Arc<Vec<u64>>> {
+        let metadata = std::fs::metadata(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        })?;
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(mut entry) = self.entries.get_mut(path) {
+            if entry.mtime == mtime && entry.size == metadata.len() {
+                entry.last_used = tick;
+                return Ok(Arc::clone(&entry.offsets));
+            }
+        }
+
+        let (offsets, mtime, size) = scan_line_offsets(path)?;
+        let offsets = Arc::new(offsets);
+        self.entries.insert(
+            path.to_path_buf(),
+            CachedOffsets {
+                mtime,
+                size,
+                offsets: Arc::clone(&offsets),
+                last_used: tick,
+            },
+        );
+        self.evict();
+        Ok(offsets)
+    }
+
+    /// Read a range of lines like `read_lines`, seeking to the first one
+    ///
+    /// Only the bytes of the requested lines are read, however far into
+    /// the file they start.
+    pub fn read_lines(
+        &self,
+        path: &Path,
+        start_line: usize,
+        count: Option<usize>,
+        max_bytes: Option<usize>,
+    ) -> Result<Vec<String>> {
+        use std::io::{Read, Seek, SeekFrom};
+
+        let offsets = self.offsets(path)?;
+        if start_line >= offsets.len() {
+            return Ok(Vec::new());
+        }
+        let end_line = count.map_or(offsets.len(), |n| {
+            start_line.saturating_add(n).min(offsets.len())
+        });
+        let start = offsets[start_line];
+        let len = offsets.get(end_line).map_or(u64::MAX, |&end| end - start);
+
+        let mut file = std::fs::File::open(path)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut bytes = Vec::new();
+        file.take(len).read_to_end(&mut bytes)?;
+        let text = String::from_utf8(bytes)
+            .map_err(|e| AgentGearError::Internal(format!("Invalid UTF-8: {}", e)))?;
+
+        Ok(take_lines(
+            text.lines().map(str::to_string),
+            None,
+            max_bytes,
+        ))
+    }
+
+    /// Drop a path from the cache
+    pub fn invalidate(&self, path: &Path) {
+        self.entries.remove(path);
+    }
+
+    /// Evict least recently used entries until the budget is met
+    fn evict(&self) {
+        while self.entries.len() > self.max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|entry| entry.last_used)
+                .map(|entry| entry.key().clone());
+            match oldest {
+                Some(path) => self.invalidate(&path),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Hash a file's content into a stable hex id (BLAKE3, 64 hex digits)
+///
+/// The id depends only on the bytes, so it survives renames and moves,
+/// changes on any edit, and is shared by files with identical content.
+/// The file is streamed, so memory use does not grow with its size.
+pub fn content_id(path: &Path) -> Result<String> {
+    let file = std::fs::File::open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AgentGearError::PathNotFound(path.display().to_string())
+        } else {
+            AgentGearError::Io(e)
+        }
+    })?;
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(file)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Read a file as text together with its metadata
+///
+/// The metadata comes from the open file handle, so it describes exactly
+/// the content returned, and the known size decides whether to mmap
+/// without an extra stat.
+///
+/// # Arguments
+/// * `py` - Python GIL token
+/// * `path` - Path to the file
+/// * `max_bytes` - Fail with `FileTooLarge` instead of reading a larger file
+///   (None = no limit)
+///
+/// # Returns
+/// `(content, metadata)` for the file
+pub fn read_with_metadata(
+    py: Python<'_>,
+    path: &Path,
+    max_bytes: Option<u64>,
+) -> PyResult<(String, FileMetadata)> {
+    use memmap2::Mmap;
+    use std::io::Read;
+
+    py.allow_threads(|| -> Result<(String, FileMetadata)> {
+        let mut file = std::fs::File::open(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        })?;
+
+        let fs_metadata = file.metadata()?;
+        let size = fs_metadata.len();
+        check_read_size(path, size, max_bytes)?;
+
+        // Use mmap for large files (> 1MB), a single sized read for smaller
+        let content = if size > 1024 * 1024 {
+            let mmap = unsafe { Mmap::map(&file) }.map_err(AgentGearError::Io)?;
+            std::str::from_utf8(&mmap)
+                .map_err(|e| AgentGearError::Internal(format!("Invalid UTF-8: {}", e)))?
+                .to_owned()
+        } else {
+            let mut content = String::with_capacity(size as usize);
+            file.read_to_string(&mut content)?;
+            content
+        };
+
+        let mtime = fs_metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+
+        let bytes = content.as_bytes();
+        let metadata = FileMetadata {
+            size,
+            mtime,
+            is_dir: false,
+            is_binary: bytes[..bytes.len().min(512)].contains(&0),
+        };
+
+        Ok((content, metadata))
     })
     .map_err(|e| e.into())
 }
@@ -41,55 +584,102 @@ const PARALLEL_READ_THRESHOLD: usize = 30;
 ///
 /// # Arguments
 /// * `py` - Python GIL token
-/// * `paths` - Slice of file paths to read
+/// * `paths` - `(key, path)` pairs: the caller's original path string and
+///   the resolved path to read
+/// * `max_file_size` - Skip files larger than this many bytes (None = no limit)
+/// * `max_read_bytes` - Fail the whole batch with `FileTooLarge` if a file
+///   not skipped by `max_file_size` is larger than this (None = no limit)
 ///
 /// # Returns
-/// HashMap mapping file path strings to their contents
+/// HashMap mapping each key to its file's contents
 pub fn read_batch(
     py: Python<'_>,
-    paths: &[std::path::PathBuf],
+    paths: &[(String, PathBuf)],
+    max_file_size: Option<u64>,
+    max_read_bytes: Option<u64>,
 ) -> PyResult<HashMap<String, String>> {
-    let result = py.allow_threads(|| {
+    py.allow_threads(|| {
         // Use serial read for small batches to avoid Rayon overhead
         if paths.len() < PARALLEL_READ_THRESHOLD {
-            read_batch_serial(paths)
+            read_batch_serial(paths, max_file_size, max_read_bytes)
         } else {
-            read_batch_parallel(paths)
+            read_batch_parallel(paths, max_file_size, max_read_bytes)
         }
-    });
-    Ok(result)
+    })
+    .map_err(|e| e.into())
 }
 
-/// Serial batch read for small file counts
+/// Read one file for a batch, logging and skipping failures and oversized files
+///
+/// Only a file over `max_read_bytes` is an error; every other failure skips.
 #[inline]
-fn read_batch_serial(paths: &[std::path::PathBuf]) -> HashMap<String, String> {
-    let mut result = HashMap::with_capacity(paths.len());
-    for path in paths {
-        match std::fs::read_to_string(path) {
-            Ok(content) => {
-                result.insert(path.display().to_string(), content);
+fn read_batch_entry(
+    path: &Path,
+    max_file_size: Option<u64>,
+    max_read_bytes: Option<u64>,
+) -> Result<Option<String>> {
+    let _permit = limit::acquire();
+
+    if max_file_size.is_some() || max_read_bytes.is_some() {
+        // Check the size before reading so oversized files are never loaded
+        match std::fs::metadata(path) {
+            Ok(metadata) if max_file_size.is_some_and(|limit| metadata.len() > limit) => {
+                tracing::warn!(
+                    "Skipping {}: {} bytes exceeds limit of {} bytes",
+                    path.display(),
+                    metadata.len(),
+                    max_file_size.unwrap_or_default()
+                );
+                return Ok(None);
             }
+            Ok(metadata) => check_read_size(path, metadata.len(), max_read_bytes)?,
             Err(e) => {
                 tracing::warn!("Failed to read {}: {}", path.display(), e);
+                return Ok(None);
             }
         }
     }
-    result
+
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) => {
+            tracing::warn!("Failed to read {}: {}", path.display(), e);
+            Ok(None)
+        }
+    }
+}
+
+/// Serial batch read for small file counts
+#[inline]
+fn read_batch_serial(
+    paths: &[(String, PathBuf)],
+    max_file_size: Option<u64>,
+    max_read_bytes: Option<u64>,
+) -> Result<HashMap<String, String>> {
+    let mut result = HashMap::with_capacity(paths.len());
+    for (key, path) in paths {
+        if let Some(content) = read_batch_entry(path, max_file_size, max_read_bytes)? {
+            result.insert(key.clone(), content);
+        }
+    }
+    Ok(result)
 }
 
 /// Parallel batch read using Rayon
 #[inline]
-fn read_batch_parallel(paths: &[std::path::PathBuf]) -> HashMap<String, String> {
+fn read_batch_parallel(
+    paths: &[(String, PathBuf)],
+    max_file_size: Option<u64>,
+    max_read_bytes: Option<u64>,
+) -> Result<HashMap<String, String>> {
     paths
         .par_iter()
-        .filter_map(|path| match std::fs::read_to_string(path) {
-            Ok(content) => Some((path.display().to_string(), content)),
-            Err(e) => {
-                tracing::warn!("Failed to read {}: {}", path.display(), e);
-                None
-            }
+        .filter_map(|(key, path)| {
+            read_batch_entry(path, max_file_size, max_read_bytes)
+                .map(|content| content.map(|content| (key.clone(), content)))
+                .transpose()
         })
-        .collect::<HashMap<String, String>>()
+        .collect::<Result<HashMap<String, String>>>()
 }
 
 /// Write content to a file atomically
@@ -100,8 +690,35 @@ fn read_batch_parallel(paths: &[std::path::PathBuf]) -> HashMap<String, String>
 /// * `py` - Python GIL token
 /// * `path` - Target file path
 /// * `content` - Content to write
-pub fn write_file(py: Python<'_>, path: &Path, content: &str) -> PyResult<()> {
-    py.allow_threads(|| super::atomic::atomic_write(path, content.as_bytes()))
+/// * `encoding` - Output encoding label (e.g. "utf-8", "shift_jis")
+/// * `replace_unencodable` - Write `?` for unencodable characters instead of
+///   failing
+/// * `verify` - Read the file back after the rename and check it matches
+pub fn write_file(
+    py: Python<'_>,
+    path: &Path,
+    content: &str,
+    encoding: &str,
+    replace_unencodable: bool,
+    verify: bool,
+) -> PyResult<()> {
+    py.allow_threads(|| -> Result<()> {
+        let bytes = super::encoding::encode(content, encoding, replace_unencodable)?;
+        super::atomic::atomic_write(path, &bytes)?;
+        if verify {
+            super::atomic::verify_content(path, &bytes)?;
+        }
+        Ok(())
+    })
+    .map_err(|e| e.into())
+}
+
+/// Create a new file atomically, leaving any existing file untouched
+///
+/// # Returns
+/// `true` if the file was created, `false` if it already existed
+pub fn write_new(py: Python<'_>, path: &Path, content: &str) -> PyResult<bool> {
+    py.allow_threads(|| super::atomic::atomic_write_new(path, content.as_bytes()))
         .map_err(|e| e.into())
 }
 
@@ -141,6 +758,64 @@ pub fn write_file_fast(py: Python<'_>, path: &Path, content: &str) -> PyResult<(
     .map_err(|e| e.into())
 }
 
+/// Apply the end-of-line fixups agents commonly need before writing
+///
+/// `strip_trailing_whitespace` trims spaces and tabs from the end of every
+/// line; `ensure_trailing_newline` appends one line ending if the content
+/// does not already end with one, using `\r\n` when the content already
+/// uses it. Line endings are otherwise preserved, and empty content stays
+/// empty.
+pub fn normalize_text(
+    content: &str,
+    ensure_trailing_newline: bool,
+    strip_trailing_whitespace: bool,
+) -> Cow<'_, str> {
+    let mut text = Cow::Borrowed(content);
+
+    if strip_trailing_whitespace {
+        let mut out = String::with_capacity(content.len());
+        for line in content.split_inclusive('\n') {
+            let (body, ending) = match line.strip_suffix("\r\n") {
+                Some(body) => (body, "\r\n"),
+                None => match line.strip_suffix('\n') {
+                    Some(body) => (body, "\n"),
+                    None => (line, ""),
+                },
+            };
+            out.push_str(body.trim_end_matches([' ', '\t']));
+            out.push_str(ending);
+        }
+        if out != content {
+            text = Cow::Owned(out);
+        }
+    }
+
+    if ensure_trailing_newline && !text.is_empty() && !text.ends_with('\n') {
+        let ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        text.to_mut().push_str(ending);
+    }
+
+    text
+}
+
+/// Byte span covering 1-indexed lines `first..=last`, line endings included
+///
+/// Lines past the end of the content clamp to its end, so an out-of-range
+/// span is empty.
+fn line_range_bounds(content: &str, first: usize, last: usize) -> (usize, usize) {
+    let line_start = |n: usize| -> usize {
+        if n <= 1 {
+            return 0;
+        }
+        content
+            .match_indices('\n')
+            .nth(n - 2)
+            .map_or(content.len(), |(i, _)| i + 1)
+    };
+    let start = line_start(first);
+    (start, line_start(last.saturating_add(1)).max(start))
+}
+
 /// Replace text in a file
 ///
 /// # Arguments
@@ -149,34 +824,60 @@ pub fn write_file_fast(py: Python<'_>, path: &Path, content: &str) -> PyResult<(
 /// * `old_text` - Text to find
 /// * `new_text` - Replacement text
 /// * `strict` - If true, error on non-unique or missing match
+/// * `encoding` - Encoding the file is read and written back in
+/// * `replace_unencodable` - Write `?` for unencodable characters instead of
+///   failing
+/// * `ensure_trailing_newline`, `strip_trailing_whitespace` - Fixups applied
+///   to the edited content, see `normalize_text`
+/// * `max_replacements` - Replace at most this many occurrences, first
+///   ones first (None = all)
+/// * `line_range` - Only consider occurrences lying entirely within these
+///   1-indexed lines, inclusive (None = whole file). Occurrences outside
+///   are neither counted nor replaced
 ///
 /// # Returns
-/// True if replacement was made, false if old_text was not found (when strict=false)
+/// Number of occurrences replaced; 0 if old_text was not found (when
+/// strict=false), in which case the file is not rewritten
+#[allow(clippy::too_many_arguments)]
 pub fn edit_replace(
     py: Python<'_>,
     path: &Path,
     old_text: &str,
     new_text: &str,
     strict: bool,
-) -> PyResult<bool> {
-    py.allow_threads(|| -> Result<bool> {
+    encoding: &str,
+    replace_unencodable: bool,
+    ensure_trailing_newline: bool,
+    strip_trailing_whitespace: bool,
+    max_replacements: Option<usize>,
+    line_range: Option<(usize, usize)>,
+) -> PyResult<usize> {
+    py.allow_threads(|| -> Result<usize> {
         // Read the file
-        let content = std::fs::read_to_string(path).map_err(|e| {
+        let bytes = std::fs::read(path).map_err(|e| {
             if e.kind() == std::io::ErrorKind::NotFound {
                 AgentGearError::PathNotFound(path.display().to_string())
             } else {
                 AgentGearError::Io(e)
             }
         })?;
+        let content = super::encoding::decode(&bytes, encoding)?;
+
+        // Occurrences outside the line range are invisible
+        let (region_start, region_end) = match line_range {
+            Some((first, last)) => line_range_bounds(&content, first, last),
+            None => (0, content.len()),
+        };
+        let region = &content[region_start..region_end];
 
         // Count occurrences
-        let count = content.matches(old_text).count();
+        let count = region.matches(old_text).count();
 
         if count == 0 {
             if strict {
                 return Err(AgentGearError::TextNotFound);
             }
-            return Ok(false);
+            return Ok(0);
         }
 
         if count > 1 && strict {
@@ -184,12 +885,59 @@ pub fn edit_replace(
         }
 
         // Perform replacement
-        let new_content = content.replace(old_text, new_text);
+        let replaced = max_replacements.map_or(count, |max| count.min(max));
+        if replaced == 0 {
+            return Ok(0);
+        }
+        let new_content = format!(
+            "{}{}{}",
+            &content[..region_start],
+            region.replacen(old_text, new_text, replaced),
+            &content[region_end..]
+        );
+        let new_content = normalize_text(
+            &new_content,
+            ensure_trailing_newline,
+            strip_trailing_whitespace,
+        );
 
         // Write atomically
-        super::atomic::atomic_write(path, new_content.as_bytes())?;
+        let bytes = super::encoding::encode(&new_content, encoding, replace_unencodable)?;
+        super::atomic::atomic_write(path, &bytes)?;
+
+        Ok(replaced)
+    })
+    .map_err(|e| e.into())
+}
+
+/// Apply a unified diff to a file and write the result atomically
+///
+/// See `diff::apply_patch` for how hunks are matched. Nothing is written
+/// when a strict patch fails or when no hunk applied.
+///
+/// # Returns
+/// Whether every hunk applied
+pub fn apply_patch(
+    py: Python<'_>,
+    path: &Path,
+    patch: &str,
+    strict: bool,
+    fuzzy: bool,
+) -> PyResult<bool> {
+    py.allow_threads(|| -> Result<bool> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                AgentGearError::PathNotFound(path.display().to_string())
+            } else {
+                AgentGearError::Io(e)
+            }
+        })?;
 
-        Ok(true)
+        let (patched, skipped) = super::diff::apply_patch(&content, patch, strict, fuzzy)?;
+        if patched != content {
+            super::atomic::atomic_write(path, patched.as_bytes())?;
+        }
+        Ok(skipped == 0)
     })
     .map_err(|e| e.into())
 }
@@ -204,6 +952,8 @@ pub fn edit_replace(
 /// * `path` - File path
 /// * `start_line` - Starting line number (0-indexed)
 /// * `count` - Number of lines to read (None = read to end)
+/// * `max_bytes` - Stop before the line that would push the summed length of
+///   the returned lines past this budget (None = no budget)
 ///
 /// # Returns
 /// Vector of line strings (without trailing newlines)
@@ -212,47 +962,288 @@ pub fn read_lines(
     path: &Path,
     start_line: usize,
     count: Option<usize>,
+    max_bytes: Option<usize>,
 ) -> PyResult<Vec<String>> {
-    use memmap2::Mmap;
-    use std::io::{BufRead, BufReader};
+    py.allow_threads(|| read_lines_internal(path, start_line, count, max_bytes))
+        .map_err(|e| e.into())
+}
 
-    py.allow_threads(|| -> Result<Vec<String>> {
-        let file = std::fs::File::open(path).map_err(|e| {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                AgentGearError::PathNotFound(path.display().to_string())
-            } else {
-                AgentGearError::Io(e)
-            }
-        })?;
+/// Read a range of lines paired with their 1-indexed line numbers
+///
+/// Built on the same machinery as `read_lines`, so large files are mmapped.
+///
+/// # Arguments
+/// * `py` - Python GIL token
+/// * `path` - File path
+/// * `start_line` - Starting line number (0-indexed)
+/// * `count` - Number of lines to read (None = read to end)
+///
+/// # Returns
+/// Vector of `(line_number, text)` pairs, where `line_number` is 1-indexed
+pub fn read_numbered(
+    py: Python<'_>,
+    path: &Path,
+    start_line: usize,
+    count: Option<usize>,
+) -> PyResult<Vec<(usize, String)>> {
+    py.allow_threads(|| -> Result<Vec<(usize, String)>> {
+        let lines = read_lines_internal(path, start_line, count, None)?;
+        Ok(lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| (start_line + i + 1, line))
+            .collect())
+    })
+    .map_err(|e| e.into())
+}
+
+/// Read the lines within `radius` of a 1-indexed center line
+///
+/// The window `[center_line - radius, center_line + radius]` is clamped to
+/// the file, so a center near either end simply yields fewer lines.
+///
+/// # Arguments
+/// * `py` - Python GIL token
+/// * `path` - File path
+/// * `center_line` - Line to center on (1-indexed, must not be 0)
+/// * `radius` - Number of lines to include on each side
+///
+/// # Returns
+/// Vector of `(line_number, text)` pairs, where `line_number` is 1-indexed
+pub fn read_around(
+    py: Python<'_>,
+    path: &Path,
+    center_line: usize,
+    radius: usize,
+) -> PyResult<Vec<(usize, String)>> {
+    let (start_line, count) = around_window(center_line, radius);
+    read_numbered(py, path, start_line, Some(count))
+}
 
-        let metadata = file.metadata()?;
-        let file_size = metadata.len() as usize;
+/// Convert a center line and radius into a 0-indexed start and line count
+pub fn around_window(center_line: usize, radius: usize) -> (usize, usize) {
+    let first = center_line.saturating_sub(radius).max(1);
+    let last = center_line.saturating_add(radius);
+    (first - 1, last - first + 1)
+}
 
-        // Use mmap for large files (> 1MB), buffered read for smaller
-        let lines: Vec<String> = if file_size > 1024 * 1024 {
-            // Memory-mapped approach for large files
-            let mmap = unsafe { Mmap::map(&file) }.map_err(AgentGearError::Io)?;
-            let content = std::str::from_utf8(&mmap)
-                .map_err(|e| AgentGearError::Internal(format!("Invalid UTF-8: {}", e)))?;
+/// Split text into pages of at most `page_chars` characters
+///
+/// Pages break after a newline whenever a whole line still fits; a line
+/// longer than a page is cut at character boundaries, so multibyte
+/// characters are never split. Concatenating the pages gives back the
+/// original text.
+///
+/// # Returns
+/// Vector of `(start_line, text)` pairs, where `start_line` is the 1-indexed
+/// line the page begins on
+pub fn paginate(content: &str, page_chars: usize) -> Vec<(usize, String)> {
+    let mut pages = Vec::new();
+    let mut page = String::new();
+    let mut page_len = 0;
+    let mut page_line = 1;
 
-            let line_iter = content.lines().skip(start_line);
-            match count {
-                Some(n) => line_iter.take(n).map(|s| s.to_string()).collect(),
-                None => line_iter.map(|s| s.to_string()).collect(),
-            }
+    for (i, line) in content.split_inclusive('\n').enumerate() {
+        let line_number = i + 1;
+        let mut rest = line;
+        let mut rest_len = rest.chars().count();
+
+        if page_len > 0 && page_len + rest_len > page_chars {
+            pages.push((page_line, std::mem::take(&mut page)));
+            page_len = 0;
+        }
+        if page_len == 0 {
+            page_line = line_number;
+        }
+
+        // An overlong line fills whole pages on its own
+        while rest_len > page_chars {
+            let split = rest
+                .char_indices()
+                .nth(page_chars)
+                .map_or(rest.len(), |(offset, _)| offset);
+            pages.push((line_number, rest[..split].to_string()));
+            rest = &rest[split..];
+            rest_len -= page_chars;
+        }
+        page.push_str(rest);
+        page_len += rest_len;
+    }
+    if !page.is_empty() {
+        pages.push((page_line, page));
+    }
+    pages
+}
+
+/// Read only the first line of a file
+///
+/// Stops at the first newline instead of loading the whole file, which makes
+/// shebang and generated-file header checks cheap.
+///
+/// # Arguments
+/// * `py` - Python GIL token
+/// * `path` - File path
+///
+/// # Returns
+/// The first line without its terminator, or None for an empty file
+pub fn read_first_line(py: Python<'_>, path: &Path) -> PyResult<Option<String>> {
+    py.allow_threads(|| read_first_line_internal(path))
+        .map_err(|e| e.into())
+}
+
+fn read_first_line_internal(path: &Path) -> Result<Option<String>> {
+    use std::io::{BufRead, BufReader};
+
+    let file = std::fs::File::open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AgentGearError::PathNotFound(path.display().to_string())
+        } else {
+            AgentGearError::Io(e)
+        }
+    })?;
+
+    let mut line = String::new();
+    if BufReader::new(file).read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    // Strip the terminator, matching `str::lines`
+    if line.ends_with('\n') {
+        line.pop();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+    }
+
+    Ok(Some(line))
+}
+
+/// Read up to `max_lines` lines starting at a byte offset
+///
+/// Seeks to `cursor` and reads forward line by line, so memory stays
+/// bounded by the page however large the file is. Pass the returned cursor
+/// back in to continue exactly where the page ended.
+///
+/// # Arguments
+/// * `py` - Python GIL token
+/// * `path` - File path
+/// * `cursor` - Byte offset to start at (0 = start of file)
+/// * `max_lines` - Maximum number of lines to return
+///
+/// # Returns
+/// The lines without terminators, and the cursor of the next page, or None
+/// once the end of the file is reached
+pub fn read_page(
+    py: Python<'_>,
+    path: &Path,
+    cursor: u64,
+    max_lines: usize,
+) -> PyResult<(Vec<String>, Option<u64>)> {
+    py.allow_threads(|| read_page_internal(path, cursor, max_lines))
+        .map_err(|e| e.into())
+}
+
+fn read_page_internal(
+    path: &Path,
+    cursor: u64,
+    max_lines: usize,
+) -> Result<(Vec<String>, Option<u64>)> {
+    use std::io::{BufRead, BufReader, Seek, SeekFrom};
+
+    let mut file = std::fs::File::open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AgentGearError::PathNotFound(path.display().to_string())
         } else {
-            // Buffered read for smaller files
-            let reader = BufReader::new(file);
-            let line_iter = reader.lines().skip(start_line).filter_map(|l| l.ok());
-            match count {
-                Some(n) => line_iter.take(n).collect(),
-                None => line_iter.collect(),
+            AgentGearError::Io(e)
+        }
+    })?;
+    file.seek(SeekFrom::Start(cursor))?;
+
+    let mut reader = BufReader::new(file);
+    let mut position = cursor;
+    let mut lines = Vec::new();
+    let mut bytes = Vec::new();
+    while lines.len() < max_lines {
+        bytes.clear();
+        let read = reader.read_until(b'\n', &mut bytes)?;
+        if read == 0 {
+            return Ok((lines, None));
+        }
+        position += read as u64;
+
+        // Strip the terminator, matching `str::lines`
+        if bytes.ends_with(b"\n") {
+            bytes.pop();
+            if bytes.ends_with(b"\r") {
+                bytes.pop();
             }
-        };
+        }
+        let line = String::from_utf8(std::mem::take(&mut bytes))
+            .map_err(|e| AgentGearError::Internal(format!("Invalid UTF-8: {}", e)))?;
+        lines.push(line);
+    }
 
-        Ok(lines)
-    })
-    .map_err(|e| e.into())
+    let at_end = reader.fill_buf()?.is_empty();
+    Ok((lines, (!at_end).then_some(position)))
+}
+
+/// Shared line-range reader used by `read_lines` and `read_numbered`
+fn read_lines_internal(
+    path: &Path,
+    start_line: usize,
+    count: Option<usize>,
+    max_bytes: Option<usize>,
+) -> Result<Vec<String>> {
+    use memmap2::Mmap;
+    use std::io::{BufRead, BufReader};
+
+    let file = std::fs::File::open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AgentGearError::PathNotFound(path.display().to_string())
+        } else {
+            AgentGearError::Io(e)
+        }
+    })?;
+
+    let metadata = file.metadata()?;
+    let file_size = metadata.len() as usize;
+
+    // Use mmap for large files (> 1MB), buffered read for smaller
+    let lines: Vec<String> = if file_size > 1024 * 1024 {
+        // Memory-mapped approach for large files
+        let mmap = unsafe { Mmap::map(&file) }.map_err(AgentGearError::Io)?;
+        let content = std::str::from_utf8(&mmap)
+            .map_err(|e| AgentGearError::Internal(format!("Invalid UTF-8: {}", e)))?;
+
+        let line_iter = content.lines().skip(start_line).map(|s| s.to_string());
+        take_lines(line_iter, count, max_bytes)
+    } else {
+        // Buffered read for smaller files
+        let reader = BufReader::new(file);
+        let line_iter = reader.lines().skip(start_line).filter_map(|l| l.ok());
+        take_lines(line_iter, count, max_bytes)
+    };
+
+    Ok(lines)
+}
+
+/// Collect up to `count` whole lines whose summed length fits in `max_bytes`
+fn take_lines(
+    lines: impl Iterator<Item = String>,
+    count: Option<usize>,
+    max_bytes: Option<usize>,
+) -> Vec<String> {
+    let mut budget = max_bytes.unwrap_or(usize::MAX);
+    lines
+        .take(count.unwrap_or(usize::MAX))
+        .take_while(|line| match budget.checked_sub(line.len()) {
+            Some(left) => {
+                budget = left;
+                true
+            }
+            None => false,
+        })
+        .collect()
 }
 
 /// Read file with offset and limit (for large files)
@@ -299,10 +1290,10 @@ mod tests {
             let file_path = dir.path().join("test.txt");
 
             // Write
-            write_file(py, &file_path, "Hello, World!").unwrap();
+            write_file(py, &file_path, "Hello, World!", "utf-8", false, false).unwrap();
 
             // Read
-            let content = read_file(py, &file_path, "utf-8").unwrap();
+            let content = read_file(py, &file_path, "utf-8", None).unwrap();
             assert_eq!(content, "Hello, World!");
         });
     }
@@ -315,18 +1306,242 @@ mod tests {
             let file_path = dir.path().join("test.txt");
 
             // Write initial content
-            write_file(py, &file_path, "Hello, World!").unwrap();
+            write_file(py, &file_path, "Hello, World!", "utf-8", false, false).unwrap();
 
             // Replace
-            let result = edit_replace(py, &file_path, "World", "Rust", true).unwrap();
-            assert!(result);
+            let result = edit_replace(
+                py, &file_path, "World", "Rust", true, "utf-8", false, false, false, None, None,
+            )
+            .unwrap();
+            assert_eq!(result, 1);
 
             // Verify
-            let content = read_file(py, &file_path, "utf-8").unwrap();
+            let content = read_file(py, &file_path, "utf-8", None).unwrap();
             assert_eq!(content, "Hello, Rust!");
         });
     }
 
+    #[test]
+    fn test_edit_replace_line_range() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+            std::fs::write(&file_path, "x = 1\ny = 2\nx = 1\nz = 3\nx = 1\n").unwrap();
+
+            // Unique within lines 2-4, though the file holds three copies
+            let result = edit_replace(
+                py,
+                &file_path,
+                "x = 1",
+                "x = 9",
+                true,
+                "utf-8",
+                false,
+                false,
+                false,
+                None,
+                Some((2, 4)),
+            )
+            .unwrap();
+            assert_eq!(result, 1);
+            assert_eq!(
+                std::fs::read_to_string(&file_path).unwrap(),
+                "x = 1\ny = 2\nx = 9\nz = 3\nx = 1\n"
+            );
+
+            // An occurrence crossing the range edge is not counted
+            let result = edit_replace(
+                py,
+                &file_path,
+                "x = 9\nz = 3",
+                "",
+                true,
+                "utf-8",
+                false,
+                false,
+                false,
+                None,
+                Some((2, 3)),
+            );
+            assert!(result.is_err());
+
+            // Beyond the end of the file nothing is visible
+            let result = edit_replace(
+                py,
+                &file_path,
+                "x = 1",
+                "",
+                false,
+                "utf-8",
+                false,
+                false,
+                false,
+                None,
+                Some((10, 20)),
+            )
+            .unwrap();
+            assert_eq!(result, 0);
+        });
+    }
+
+    #[test]
+    fn test_line_range_bounds() {
+        let content = "a\nbb\nccc";
+        assert_eq!(line_range_bounds(content, 1, 1), (0, 2));
+        assert_eq!(line_range_bounds(content, 2, 3), (2, 8));
+        assert_eq!(line_range_bounds(content, 3, usize::MAX), (5, 8));
+        assert_eq!(line_range_bounds(content, 5, 6), (8, 8));
+    }
+
+    #[test]
+    fn test_content_id() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "same").unwrap();
+        std::fs::write(&b, "same").unwrap();
+
+        let id = content_id(&a).unwrap();
+        assert_eq!(id.len(), 64);
+        assert_eq!(id, blake3::hash(b"same").to_hex().as_str());
+        assert_eq!(content_id(&b).unwrap(), id);
+
+        // Moving keeps the id, editing changes it
+        let moved = dir.path().join("moved.txt");
+        std::fs::rename(&a, &moved).unwrap();
+        assert_eq!(content_id(&moved).unwrap(), id);
+        std::fs::write(&moved, "changed").unwrap();
+        assert_ne!(content_id(&moved).unwrap(), id);
+
+        assert!(matches!(
+            content_id(&a),
+            Err(AgentGearError::PathNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_read_cache() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.txt");
+        let b = dir.path().join("b.txt");
+        std::fs::write(&a, "aaaa").unwrap();
+        std::fs::write(&b, "bbbb").unwrap();
+
+        let cache = ReadCache::new(1, 1024);
+        assert_eq!(*cache.read(&a, None).unwrap(), "aaaa");
+        assert_eq!(*cache.read(&a, None).unwrap(), "aaaa");
+        assert_eq!(cache.stats(), (1, 1));
+
+        // A size change is a miss even if the mtime did not move
+        std::fs::write(&a, "aaaaa").unwrap();
+        assert_eq!(*cache.read(&a, None).unwrap(), "aaaaa");
+        assert_eq!(cache.stats(), (1, 2));
+
+        // The one-entry budget evicts a.txt
+        cache.read(&b, None).unwrap();
+        cache.read(&a, None).unwrap();
+        assert_eq!(cache.stats(), (1, 4));
+
+        cache.invalidate(&a);
+        cache.read(&a, None).unwrap();
+        assert_eq!(cache.stats(), (1, 5));
+
+        assert!(matches!(
+            cache.read(&dir.path().join("missing"), None),
+            Err(AgentGearError::PathNotFound(_))
+        ));
+
+        // Concurrent misses on one path leave the byte count matching
+        let cache = ReadCache::new(16, 1024);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| cache.read(&b, None).unwrap());
+            }
+        });
+        let cached: usize = cache.entries.iter().map(|e| e.content.len()).sum();
+        assert_eq!(cache.bytes.load(Ordering::Relaxed), cached);
+    }
+
+    #[test]
+    fn test_count_lines_with() {
+        let count = |bytes: &[u8]| {
+            [LineEnding::Lf, LineEnding::Crlf, LineEnding::Any].map(|e| count_lines_with(bytes, e))
+        };
+        assert_eq!(count(b""), [0, 0, 0]);
+        assert_eq!(count(b"a"), [1, 1, 1]);
+        assert_eq!(count(b"a\nb\n"), [2, 1, 2]);
+        assert_eq!(count(b"a\r\nb\r\n"), [2, 2, 2]);
+        assert_eq!(count(b"a\rb\rc"), [1, 1, 3]);
+        // Mixed endings: CRLF, LF, lone CR, then an unterminated line
+        assert_eq!(count(b"a\r\nb\nc\rd"), [3, 2, 4]);
+        assert_eq!(count(b"\r\n\r\n"), [2, 2, 2]);
+        assert_eq!(count(b"\n\r"), [2, 1, 2]);
+    }
+
+    #[test]
+    fn test_line_offsets() {
+        assert_eq!(line_offsets(b""), Vec::<u64>::new());
+        assert_eq!(line_offsets(b"a"), vec![0]);
+        assert_eq!(line_offsets(b"ab\ncd\n"), vec![0, 3]);
+        assert_eq!(line_offsets(b"\n\nx"), vec![0, 1, 2]);
+
+        assert_eq!(char_line_offsets(""), Vec::<u64>::new());
+        assert_eq!(char_line_offsets("ab\ncd\n"), vec![0, 3]);
+        let text = "é☃\n日本\nx";
+        let offsets = char_line_offsets(text);
+        assert_eq!(offsets, vec![0, 3, 6]);
+        let chars: Vec<char> = text.chars().collect();
+        assert_eq!(
+            chars[offsets[1] as usize..offsets[2] as usize],
+            ['日', '本', '\n']
+        );
+    }
+
+    #[test]
+    fn test_line_offset_cache() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("a.txt");
+        std::fs::write(&path, "zero\r\none\ntwo\nthree\n").unwrap();
+
+        let cache = LineOffsetCache::new(4);
+        assert_eq!(*cache.offsets(&path).unwrap(), vec![0, 6, 10, 14]);
+        assert_eq!(
+            cache.read_lines(&path, 1, Some(2), None).unwrap(),
+            vec!["one", "two"]
+        );
+        assert_eq!(
+            cache.read_lines(&path, 0, None, None).unwrap(),
+            read_lines_internal(&path, 0, None, None).unwrap()
+        );
+        assert_eq!(
+            cache.read_lines(&path, 2, None, Some(3)).unwrap(),
+            vec!["two"]
+        );
+        assert!(cache.read_lines(&path, 9, None, None).unwrap().is_empty());
+
+        // A size change rescans the file
+        std::fs::write(&path, "a\nbb\n").unwrap();
+        assert_eq!(*cache.offsets(&path).unwrap(), vec![0, 2]);
+        assert_eq!(cache.read_lines(&path, 1, None, None).unwrap(), vec!["bb"]);
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert!(matches!(
+            normalize_text("a\n", true, true),
+            Cow::Borrowed(_)
+        ));
+        assert_eq!(normalize_text("a", true, false), "a\n");
+        assert_eq!(normalize_text("a\r\nb", true, false), "a\r\nb\r\n");
+        assert_eq!(normalize_text("", true, false), "");
+        assert_eq!(
+            normalize_text("a  \r\nb\t\n c \t", false, true),
+            "a\r\nb\n c"
+        );
+        assert_eq!(normalize_text("a \nb ", true, true), "a\nb\n");
+    }
+
     #[test]
     fn test_edit_replace_not_found() {
         pyo3::prepare_freethreaded_python();
@@ -334,15 +1549,78 @@ mod tests {
             let dir = tempdir().unwrap();
             let file_path = dir.path().join("test.txt");
 
-            write_file(py, &file_path, "Hello, World!").unwrap();
+            write_file(py, &file_path, "Hello, World!", "utf-8", false, false).unwrap();
 
             // Should fail in strict mode
-            let result = edit_replace(py, &file_path, "NotFound", "Replacement", true);
+            let result = edit_replace(
+                py,
+                &file_path,
+                "NotFound",
+                "Replacement",
+                true,
+                "utf-8",
+                false,
+                false,
+                false,
+                None,
+                None,
+            );
             assert!(result.is_err());
 
             // Should return false in non-strict mode
-            let result = edit_replace(py, &file_path, "NotFound", "Replacement", false).unwrap();
-            assert!(!result);
+            let result = edit_replace(
+                py,
+                &file_path,
+                "NotFound",
+                "Replacement",
+                false,
+                "utf-8",
+                false,
+                false,
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+            assert_eq!(result, 0);
+        });
+    }
+
+    #[test]
+    fn test_edit_replace_encoding() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("sjis.txt");
+
+            write_file(py, &file_path, "名前 = 太郎", "shift_jis", false, false).unwrap();
+            assert!(std::fs::read_to_string(&file_path).is_err());
+
+            edit_replace(
+                py,
+                &file_path,
+                "太郎",
+                "花子",
+                true,
+                "shift_jis",
+                false,
+                false,
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+            let bytes = std::fs::read(&file_path).unwrap();
+            assert_eq!(
+                super::super::encoding::decode(&bytes, "shift_jis").unwrap(),
+                "名前 = 花子"
+            );
+
+            // Reading with the wrong encoding fails instead of mangling the file
+            assert!(edit_replace(
+                py, &file_path, "a", "b", false, "utf-8", false, false, false, None, None
+            )
+            .is_err());
         });
     }
 
@@ -353,18 +1631,308 @@ mod tests {
             let dir = tempdir().unwrap();
             let file_path = dir.path().join("test.txt");
 
-            write_file(py, &file_path, "Hello Hello Hello").unwrap();
+            write_file(py, &file_path, "Hello Hello Hello", "utf-8", false, false).unwrap();
 
             // Should fail in strict mode
-            let result = edit_replace(py, &file_path, "Hello", "Hi", true);
+            let result = edit_replace(
+                py, &file_path, "Hello", "Hi", true, "utf-8", false, false, false, None, None,
+            );
             assert!(result.is_err());
 
             // Should succeed in non-strict mode (replaces all)
-            let result = edit_replace(py, &file_path, "Hello", "Hi", false).unwrap();
-            assert!(result);
+            let result = edit_replace(
+                py, &file_path, "Hello", "Hi", false, "utf-8", false, false, false, None, None,
+            )
+            .unwrap();
+            assert_eq!(result, 3);
 
-            let content = read_file(py, &file_path, "utf-8").unwrap();
+            let content = read_file(py, &file_path, "utf-8", None).unwrap();
             assert_eq!(content, "Hi Hi Hi");
         });
     }
+
+    #[test]
+    fn test_edit_replace_max_replacements() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+            write_file(py, &file_path, "a a a a", "utf-8", false, false).unwrap();
+
+            let replace = |max| {
+                edit_replace(
+                    py, &file_path, "a", "b", false, "utf-8", false, false, false, max, None,
+                )
+                .unwrap()
+            };
+
+            assert_eq!(replace(Some(2)), 2);
+            assert_eq!(read_file(py, &file_path, "utf-8", None).unwrap(), "b b a a");
+
+            // Fewer occurrences than the limit replaces what there is
+            assert_eq!(replace(Some(5)), 2);
+            assert_eq!(read_file(py, &file_path, "utf-8", None).unwrap(), "b b b b");
+
+            assert_eq!(replace(Some(0)), 0);
+        });
+    }
+
+    #[test]
+    fn test_read_numbered() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+
+            write_file(py, &file_path, "a\nb\nc\nd\n", "utf-8", false, false).unwrap();
+
+            let lines = read_numbered(py, &file_path, 1, Some(2)).unwrap();
+            assert_eq!(lines, vec![(2, "b".to_string()), (3, "c".to_string())]);
+
+            let lines = read_numbered(py, &file_path, 0, None).unwrap();
+            assert_eq!(lines.len(), 4);
+            assert_eq!(lines[0], (1, "a".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+            write_file(py, &file_path, "a\nb\nc\n", "utf-8", false, false).unwrap();
+
+            let patch = "@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n";
+            assert!(apply_patch(py, &file_path, patch, true, false).unwrap());
+            assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "a\nB\nc\n");
+
+            // The context no longer matches, so nothing is written
+            assert!(apply_patch(py, &file_path, patch, true, false).is_err());
+            assert!(!apply_patch(py, &file_path, patch, false, false).unwrap());
+            assert_eq!(std::fs::read_to_string(&file_path).unwrap(), "a\nB\nc\n");
+        });
+    }
+
+    #[test]
+    fn test_read_around() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+
+            write_file(py, &file_path, "a\nb\nc\nd\ne\n", "utf-8", false, false).unwrap();
+
+            let lines = read_around(py, &file_path, 3, 1).unwrap();
+            assert_eq!(
+                lines,
+                vec![
+                    (2, "b".to_string()),
+                    (3, "c".to_string()),
+                    (4, "d".to_string())
+                ]
+            );
+
+            // Clamped at both ends of the file
+            let lines = read_around(py, &file_path, 1, 2).unwrap();
+            assert_eq!(lines.first(), Some(&(1, "a".to_string())));
+            assert_eq!(lines.len(), 3);
+            let lines = read_around(py, &file_path, 5, 10).unwrap();
+            assert_eq!(lines.len(), 5);
+        });
+    }
+
+    #[test]
+    fn test_around_window() {
+        assert_eq!(around_window(10, 3), (6, 7));
+        assert_eq!(around_window(2, 5), (0, 7));
+        assert_eq!(around_window(4, 0), (3, 1));
+        assert_eq!(around_window(usize::MAX, 1).0, usize::MAX - 2);
+    }
+
+    #[test]
+    fn test_paginate() {
+        let pages = paginate("ab\ncd\nef\n", 6);
+        assert_eq!(
+            pages,
+            vec![(1, "ab\ncd\n".to_string()), (3, "ef\n".to_string())]
+        );
+
+        // Overlong lines are cut by characters, not bytes
+        let content = "ééééé\nx";
+        let pages = paginate(content, 2);
+        assert_eq!(
+            pages,
+            vec![
+                (1, "éé".to_string()),
+                (1, "éé".to_string()),
+                (1, "é\n".to_string()),
+                (2, "x".to_string()),
+            ]
+        );
+        let joined: String = pages.into_iter().map(|(_, text)| text).collect();
+        assert_eq!(joined, content);
+
+        assert!(paginate("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_read_batch_max_file_size() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let small = dir.path().join("small.txt");
+            let large = dir.path().join("large.txt");
+
+            write_file(py, &small, "tiny", "utf-8", false, false).unwrap();
+            write_file(py, &large, &"x".repeat(1024), "utf-8", false, false).unwrap();
+
+            let paths = vec![
+                ("small.txt".to_string(), small.clone()),
+                ("large.txt".to_string(), large.clone()),
+            ];
+            let result = read_batch(py, &paths, Some(100), None).unwrap();
+            assert_eq!(result.len(), 1);
+            assert_eq!(result["small.txt"], "tiny");
+
+            let result = read_batch(py, &paths, None, None).unwrap();
+            assert_eq!(result.len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_read_size_limit() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let small = dir.path().join("small.txt");
+            let large = dir.path().join("large.txt");
+
+            write_file(py, &small, "tiny", "utf-8", false, false).unwrap();
+            write_file(py, &large, &"x".repeat(1024), "utf-8", false, false).unwrap();
+
+            assert_eq!(read_file(py, &small, "utf-8", Some(100)).unwrap(), "tiny");
+            let err = read_file(py, &large, "utf-8", Some(100)).unwrap_err();
+            assert!(err.to_string().contains("1024 bytes"));
+
+            let cache = ReadCache::new(8, 4096);
+            assert!(matches!(
+                cache.read(&large, Some(100)),
+                Err(AgentGearError::FileTooLarge {
+                    size: 1024,
+                    limit: 100,
+                    ..
+                })
+            ));
+
+            // A batch fails on an oversized file, unless max_file_size skips it
+            let paths = vec![
+                ("small.txt".to_string(), small.clone()),
+                ("large.txt".to_string(), large.clone()),
+            ];
+            assert!(read_batch(py, &paths, None, Some(100)).is_err());
+            let result = read_batch(py, &paths, Some(100), Some(100)).unwrap();
+            assert_eq!(result.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_read_page() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let path = dir.path().join("log.txt");
+            std::fs::write(&path, "one\r\ntwo\nthree\nfour").unwrap();
+
+            let (lines, cursor) = read_page(py, &path, 0, 2).unwrap();
+            assert_eq!(lines, vec!["one", "two"]);
+            assert_eq!(cursor, Some(9));
+
+            // Resumes exactly where the last page ended
+            let (lines, cursor) = read_page(py, &path, cursor.unwrap(), 2).unwrap();
+            assert_eq!(lines, vec!["three", "four"]);
+            assert_eq!(cursor, None);
+
+            // A page ending on the last line reports the end of the file
+            std::fs::write(&path, "one\ntwo\n").unwrap();
+            assert_eq!(read_page(py, &path, 0, 2).unwrap().1, None);
+            assert_eq!(read_page(py, &path, 100, 2).unwrap(), (Vec::new(), None));
+        });
+    }
+
+    #[test]
+    fn test_read_first_line() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+
+            let script = dir.path().join("run");
+            std::fs::write(&script, "#!/usr/bin/env python3\r\nprint(1)\n").unwrap();
+            assert_eq!(
+                read_first_line(py, &script).unwrap().as_deref(),
+                Some("#!/usr/bin/env python3")
+            );
+
+            let single = dir.path().join("single.txt");
+            std::fs::write(&single, "no newline").unwrap();
+            assert_eq!(
+                read_first_line(py, &single).unwrap().as_deref(),
+                Some("no newline")
+            );
+
+            let empty = dir.path().join("empty.txt");
+            std::fs::write(&empty, "").unwrap();
+            assert_eq!(read_first_line(py, &empty).unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_read_with_metadata() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+            write_file(py, &file_path, "Hello, World!", "utf-8", false, false).unwrap();
+
+            let (content, metadata) = read_with_metadata(py, &file_path, None).unwrap();
+            assert_eq!(content, "Hello, World!");
+            assert_eq!(metadata.size, 13);
+            assert!(metadata.mtime > 0.0);
+            assert!(!metadata.is_dir);
+            assert!(!metadata.is_binary);
+
+            let missing = read_with_metadata(py, &dir.path().join("missing.txt"), None);
+            assert!(missing.is_err());
+
+            // Refused from the handle's size, before anything is read
+            let err = read_with_metadata(py, &file_path, Some(12)).unwrap_err();
+            assert!(err.is_instance_of::<pyo3::exceptions::PyIOError>(py));
+            assert!(read_with_metadata(py, &file_path, Some(13)).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_read_lines_max_bytes() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("test.txt");
+            let long = "x".repeat(100);
+            std::fs::write(&file_path, format!("ab\ncd\n{}\nef\n", long)).unwrap();
+
+            // Stops before the line that would exceed the budget
+            let lines = read_lines(py, &file_path, 0, None, Some(10)).unwrap();
+            assert_eq!(lines, vec!["ab", "cd"]);
+
+            // Composes with count, whichever limit is hit first
+            let lines = read_lines(py, &file_path, 0, Some(1), Some(10)).unwrap();
+            assert_eq!(lines, vec!["ab"]);
+            let lines = read_lines(py, &file_path, 3, Some(5), Some(2)).unwrap();
+            assert_eq!(lines, vec!["ef"]);
+
+            // A line longer than the whole budget yields nothing
+            let lines = read_lines(py, &file_path, 2, None, Some(10)).unwrap();
+            assert!(lines.is_empty());
+        });
+    }
 }
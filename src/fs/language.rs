@@ -0,0 +1,92 @@
+//! Language detection
+//!
+//! Maps a file to a language name from its extension, or from its whole
+//! file name for build files that have none (`Makefile`, `Dockerfile`).
+//! Like `symbols`, this is a lookup table rather than content sniffing, so
+//! a shebang script without an extension is not recognised.
+
+use std::path::Path;
+
+/// Languages recognised by exact file name
+const FILE_NAMES: &[(&str, &str)] = &[
+    ("makefile", "Makefile"),
+    ("gnumakefile", "Makefile"),
+    ("dockerfile", "Dockerfile"),
+    ("cmakelists.txt", "CMake"),
+    ("rakefile", "Ruby"),
+    ("gemfile", "Ruby"),
+];
+
+/// Detect the language of a file from its name
+///
+/// Returns None for files whose kind is unknown.
+pub fn detect(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if let Some(&(_, language)) = FILE_NAMES.iter().find(|(n, _)| *n == name) {
+        return Some(language);
+    }
+
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "Rust",
+        "py" | "pyi" | "pyw" => "Python",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "scala" | "sc" => "Scala",
+        "cs" => "C#",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "C++",
+        "m" | "mm" => "Objective-C",
+        "swift" => "Swift",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "lua" => "Lua",
+        "pl" | "pm" => "Perl",
+        "r" => "R",
+        "dart" => "Dart",
+        "ex" | "exs" => "Elixir",
+        "erl" | "hrl" => "Erlang",
+        "hs" => "Haskell",
+        "ml" | "mli" => "OCaml",
+        "clj" | "cljs" | "cljc" => "Clojure",
+        "zig" => "Zig",
+        "sh" | "bash" | "zsh" => "Shell",
+        "ps1" => "PowerShell",
+        "sql" => "SQL",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "scss" | "sass" => "SCSS",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "json" => "JSON",
+        "yaml" | "yml" => "YAML",
+        "toml" => "TOML",
+        "xml" => "XML",
+        "md" | "markdown" => "Markdown",
+        "rst" => "reStructuredText",
+        "proto" => "Protobuf",
+        "cmake" => "CMake",
+        "tf" => "Terraform",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(detect(Path::new("src/main.rs")), Some("Rust"));
+        assert_eq!(detect(Path::new("App.TSX")), Some("TypeScript"));
+        assert_eq!(detect(Path::new("include/util.hpp")), Some("C++"));
+        assert_eq!(detect(Path::new("build/Makefile")), Some("Makefile"));
+        assert_eq!(detect(Path::new("CMakeLists.txt")), Some("CMake"));
+        assert_eq!(detect(Path::new("notes.txt")), None);
+        assert_eq!(detect(Path::new("LICENSE")), None);
+    }
+}
@@ -5,24 +5,46 @@
 //! - `index`: In-memory file indexing
 //! - `searcher`: Grep-like search engine
 //! - `atomic`: Atomic file write operations
+//! - `diff`: Line-level diffs against proposed content
+//! - `encoding`: Text encoding conversion for non-UTF8 files
+//! - `follow`: Streaming lines appended to a file (`tail -f`)
+//! - `language`: Language detection by file name for `code_stats`
+//! - `symbols`: Enclosing function/class lookup for search results
 //! - `watcher`: File system watching with debouncing
+//! - `snapshot`: Index persistence with optional zstd compression
 
 pub mod atomic;
+pub mod diff;
+pub mod encoding;
+pub mod follow;
 pub mod index;
 pub mod io;
+pub mod language;
 pub mod searcher;
+pub mod snapshot;
+pub mod symbols;
 pub mod watcher;
 
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use rayon::prelude::*;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::Duration;
 
 use crate::utils::error::AgentGearError;
+use crate::utils::path::{
+    canonicalize_paths, common_prefix, to_posix_string, BinaryOverrides, DirIgnore,
+    ExtensionFilter, WritePolicy, DEFAULT_IGNORED_DIRS,
+};
 use index::FileIndex;
-use searcher::{SearchOptions, SearchResult, Searcher};
-use watcher::{ChangeKind, FileWatcher};
+use io::LineEnding;
+use searcher::{
+    BinaryMode, Location, Matcher, SearchIter, SearchOptions, SearchOrder, SearchResult,
+    SearchSummary, Searcher,
+};
+use watcher::{ChangeKind, FileWatcher, WatchEvent, WatchHandle, WatchMode};
 
 /// High-performance file system interface
 ///
@@ -33,8 +55,23 @@ pub struct FileSystem {
     index: Arc<FileIndex>,
     searcher: Searcher,
     watcher: Option<Arc<FileWatcher>>,
+    read_cache: Option<Arc<io::ReadCache>>,
+    /// Line start offsets of recently read files, for `read_lines` seeks
+    line_offsets: Option<Arc<io::LineOffsetCache>>,
+    write_policy: Option<WritePolicy>,
+    max_read_bytes: Option<u64>,
+    /// Backend and poll interval for watchers this instance starts
+    watch_config: (WatchMode, Duration),
+    /// Extra watches started by `watch_subpath`, stopped on close
+    sub_watchers: parking_lot::Mutex<Vec<Weak<watcher::WatchSlot>>>,
+    /// Changes the watcher loop has applied, for `poll_changes`
+    change_log: Arc<watcher::ChangeLog>,
+    /// File contents saved by `snapshot_file` for `restore_file`
+    file_stash: atomic::FileStash,
     #[allow(dead_code)]
     watcher_thread: Option<std::thread::JoinHandle<()>>,
+    #[allow(dead_code)]
+    refresh_thread: Option<std::thread::JoinHandle<()>>,
     stop_flag: Arc<AtomicBool>,
 }
 
@@ -45,9 +82,107 @@ impl FileSystem {
     /// Args:
     ///     root: Root directory path
     ///     auto_watch: Whether to automatically watch for file changes
+    ///     index_snapshot: Optional snapshot file (see `save_index`) to load
+    ///         instead of walking the tree; falls back to a full build if it
+    ///         cannot be read
+    ///     case_insensitive: Treat paths differing only in case as the same
+    ///         file (None = detect from the filesystem holding root)
+    ///     auto_refresh_secs: Reconcile the index with the disk every N
+    ///         seconds, catching events the watcher missed (None = never).
+    ///         Each pass re-walks the tree serially and stats every entry, so
+    ///         pick an interval well above the walk time for large trees.
+    ///     include_extensions: Only index and watch files with these
+    ///         extensions, e.g. ["py", "rs"] (None = all files). Directories
+    ///         are always traversed.
+    ///     watch_recursive: Watch the whole tree (default). When false only
+    ///         root itself is watched: changes inside subdirectories are not
+    ///         detected and the index will not track them until `refresh()`
+    ///     cache_reads: Keep recently read files in memory (up to 256 files
+    ///         and 32 MiB) and serve `read_file` from there while the file's
+    ///         mtime and size are unchanged. Watched changes evict entries.
+    ///         Only UTF-8 reads are cached
+    ///     skip_binary_detection_above: Assume files larger than this many
+    ///         bytes are binary instead of reading their first 512 bytes
+    ///         while indexing (None = always read). Cuts indexing I/O on
+    ///         asset-heavy trees; such files are skipped by default searches
+    ///     watch_debounce_ms: How long a path must be quiet before its watched
+    ///         change is applied to the index (default 100). 0 selects raw
+    ///         mode: every event is applied as soon as it is seen, unmerged,
+    ///         for the lowest latency. Raw mode is noisy: a save can apply as
+    ///         several events, and a file created and deleted in quick
+    ///         succession is added and removed rather than skipped
+    ///     writable_extensions: Only allow writes to files with these
+    ///         extensions, e.g. ["py", "md"] (None = no restriction). Write
+    ///         methods raise PermissionError for any other target before
+    ///         touching the disk. A policy guardrail, not a sandbox
+    ///     allow_extensionless_writes: With writable_extensions set, also
+    ///         allow targets without an extension (Makefile, dotfiles such
+    ///         as .bashrc, directory names). Refused by default
+    ///     max_read_bytes: Refuse to load files larger than this many bytes
+    ///         in `read_file`, `read_batch` and `open`, raising IOError after
+    ///         a stat instead (default 1 GiB, None = no limit). Guards against
+    ///         pulling a huge artifact into memory; `read_lines` and
+    ///         `read_file_range` are not limited
+    ///     watch_mode: Watcher backend. AUTO (default) uses native
+    ///         notifications and polls only if they fail to start; NATIVE
+    ///         never polls; POLL rescans the tree every
+    ///         watch_poll_interval_ms. Native notifications can start fine
+    ///         yet deliver nothing on network shares, FUSE and some Docker
+    ///         bind mounts; `watcher_healthy()` cannot tell, so probe by
+    ///         writing a file and checking that `get_metadata` sees it
+    ///         within a second, and use POLL if it does not
+    ///     watch_poll_interval_ms: Interval between scans when polling
+    ///         (default 1000). Each scan stats the whole watched tree
+    ///     max_index_entries: Stop indexing once this many files and
+    ///         directories are held (None = no limit), keeping memory bounded
+    ///         on gigantic trees. The index still becomes ready, but only
+    ///         partially; `is_truncated()` reports this so callers can fall
+    ///         back to direct disk access for paths the index lacks
+    ///     use_default_ignores: Also skip the vendor and build directories
+    ///         in DEFAULT_IGNORED_DIRS (node_modules, target, .git, ...)
+    ///         wherever they appear, on top of gitignore. Off by default
+    ///     ignored_dirs: Further directory names to skip the same way (None
+    ///         = none). To replace the default list rather than extend it,
+    ///         leave use_default_ignores off and pass an edited copy of
+    ///         DEFAULT_IGNORED_DIRS here. Searches with include_ignored see
+    ///         these directories again
+    ///     cache_line_offsets: Remember where each line starts in files read
+    ///         with `read_lines`, `read_numbered` or `read_around` (up to 64
+    ///         files), so later range reads of an unchanged file seek straight
+    ///         to the first requested line instead of scanning from the top.
+    ///         Entries are reused while the file's mtime and size match
+    ///     custom_ignore_files: Further ignore file names to honour in every
+    ///         directory, e.g. [".agentignore"], so agent-visible scope can be
+    ///         managed apart from .gitignore (None = none). They use gitignore
+    ///         syntax and take precedence over .gitignore and .ignore: a `!`
+    ///         pattern in one re-includes a gitignored path, and later names
+    ///         in the list beat earlier ones. They apply outside git
+    ///         repositories too. Searches with include_ignored skip them
     #[new]
-    #[pyo3(signature = (root, auto_watch = true))]
-    pub fn new(root: String, auto_watch: bool) -> PyResult<Self> {
+    #[pyo3(signature = (root, auto_watch = true, index_snapshot = None, case_insensitive = None, auto_refresh_secs = None, include_extensions = None, watch_recursive = true, cache_reads = false, skip_binary_detection_above = None, watch_debounce_ms = 100, writable_extensions = None, allow_extensionless_writes = false, max_read_bytes = Some(io::DEFAULT_MAX_READ_BYTES), watch_mode = WatchMode::Auto, watch_poll_interval_ms = 1000, max_index_entries = None, use_default_ignores = false, ignored_dirs = None, cache_line_offsets = false, custom_ignore_files = None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        root: String,
+        auto_watch: bool,
+        index_snapshot: Option<String>,
+        case_insensitive: Option<bool>,
+        auto_refresh_secs: Option<u64>,
+        include_extensions: Option<Vec<String>>,
+        watch_recursive: bool,
+        cache_reads: bool,
+        skip_binary_detection_above: Option<u64>,
+        watch_debounce_ms: u64,
+        writable_extensions: Option<Vec<String>>,
+        allow_extensionless_writes: bool,
+        max_read_bytes: Option<u64>,
+        watch_mode: WatchMode,
+        watch_poll_interval_ms: u64,
+        max_index_entries: Option<usize>,
+        use_default_ignores: bool,
+        ignored_dirs: Option<Vec<String>>,
+        cache_line_offsets: bool,
+        custom_ignore_files: Option<Vec<String>>,
+    ) -> PyResult<Self> {
         let root_path = PathBuf::from(&root);
 
         if !root_path.exists() {
@@ -60,13 +195,81 @@ impl FileSystem {
             );
         }
 
-        let index = Arc::new(FileIndex::new(root_path.clone()));
-        let searcher = Searcher::new(root_path.clone());
+        if auto_refresh_secs == Some(0) {
+            return Err(PyValueError::new_err("auto_refresh_secs must be positive"));
+        }
+
+        if watch_poll_interval_ms == 0 {
+            return Err(PyValueError::new_err(
+                "watch_poll_interval_ms must be positive",
+            ));
+        }
+
+        let extensions = include_extensions.map(ExtensionFilter::new);
+        let defaults = use_default_ignores.then_some(DEFAULT_IGNORED_DIRS);
+        let dir_ignore = (defaults.is_some() || ignored_dirs.is_some()).then(|| {
+            let defaults = defaults.into_iter().flatten().map(|n| n.to_string());
+            DirIgnore::new(defaults.chain(ignored_dirs.into_iter().flatten()))
+        });
+        let write_policy = writable_extensions.map(|allowed| {
+            WritePolicy::new(ExtensionFilter::new(allowed), allow_extensionless_writes)
+        });
+
+        let mut index = match case_insensitive {
+            Some(flag) => FileIndex::with_case_insensitive(root_path.clone(), flag),
+            None => FileIndex::new(root_path.clone()),
+        };
+        if let Some(ref filter) = extensions {
+            index = index.with_extensions(filter.clone());
+        }
+        if let Some(limit) = skip_binary_detection_above {
+            index = index.with_binary_detection_limit(limit);
+        }
+        if let Some(limit) = max_index_entries {
+            index = index.with_max_entries(limit);
+        }
+        if let Some(ref ignore) = dir_ignore {
+            index = index.with_ignored_dirs(ignore.clone());
+        }
+        if let Some(ref names) = custom_ignore_files {
+            index = index.with_custom_ignore_files(names.clone());
+        }
+        let index = Arc::new(index);
+        let mut searcher = Searcher::new(root_path.clone()).with_index(Arc::clone(&index));
+        if let Some(ignore) = dir_ignore {
+            searcher = searcher.with_ignored_dirs(ignore);
+        }
+        if let Some(names) = custom_ignore_files {
+            searcher = searcher.with_custom_ignore_files(names);
+        }
         let stop_flag = Arc::new(AtomicBool::new(false));
+        let change_log = Arc::new(watcher::ChangeLog::new(watcher::DEFAULT_CHANGE_LOG_ENTRIES));
+        let read_cache = cache_reads.then(|| {
+            Arc::new(io::ReadCache::new(
+                io::DEFAULT_READ_CACHE_ENTRIES,
+                io::DEFAULT_READ_CACHE_BYTES,
+            ))
+        });
+        let line_offsets = cache_line_offsets
+            .then(|| Arc::new(io::LineOffsetCache::new(io::DEFAULT_LINE_OFFSET_ENTRIES)));
 
-        // Start background indexing
+        // Start background indexing, preferring a saved snapshot when given
         let index_clone = Arc::clone(&index);
+        let snapshot_path = index_snapshot.map(|p| {
+            let p = PathBuf::from(p);
+            if p.is_absolute() {
+                p
+            } else {
+                root_path.join(p)
+            }
+        });
         std::thread::spawn(move || {
+            if let Some(path) = snapshot_path {
+                match snapshot::read_snapshot(&path).and_then(|s| index_clone.restore(s)) {
+                    Ok(()) => return,
+                    Err(e) => tracing::warn!("Failed to load index snapshot: {}", e),
+                }
+            }
             if let Err(e) = index_clone.build() {
                 tracing::error!("Failed to build index: {}", e);
             }
@@ -74,15 +277,32 @@ impl FileSystem {
 
         // Optionally start file watcher
         let (watcher, watcher_thread) = if auto_watch {
-            match FileWatcher::new(root_path.clone(), Duration::from_millis(100)) {
-                Ok(w) => {
+            match FileWatcher::with_mode(
+                root_path.clone(),
+                Duration::from_millis(watch_debounce_ms),
+                watch_recursive,
+                watch_mode,
+                Duration::from_millis(watch_poll_interval_ms),
+            ) {
+                Ok(mut w) => {
+                    if let Some(filter) = extensions {
+                        w = w.with_extensions(filter);
+                    }
                     let watcher = Arc::new(w);
                     let watcher_clone = Arc::clone(&watcher);
                     let index_clone = Arc::clone(&index);
                     let stop_flag_clone = Arc::clone(&stop_flag);
+                    let cache_clone = read_cache.clone();
+                    let log_clone = Arc::clone(&change_log);
 
                     let handle = std::thread::spawn(move || {
-                        Self::watcher_loop(watcher_clone, index_clone, stop_flag_clone);
+                        Self::watcher_loop(
+                            watcher_clone,
+                            index_clone,
+                            cache_clone,
+                            log_clone,
+                            stop_flag_clone,
+                        );
                     });
 
                     (Some(watcher), Some(handle))
@@ -96,12 +316,33 @@ impl FileSystem {
             (None, None)
         };
 
+        // Optionally start periodic reconciliation as a safety net
+        let refresh_thread = auto_refresh_secs.map(|secs| {
+            let index_clone = Arc::clone(&index);
+            let stop_flag_clone = Arc::clone(&stop_flag);
+            std::thread::spawn(move || {
+                Self::refresh_loop(index_clone, stop_flag_clone, Duration::from_secs(secs));
+            })
+        });
+
         Ok(Self {
             root: root_path,
             index,
             searcher,
             watcher,
+            read_cache,
+            line_offsets,
+            write_policy,
+            max_read_bytes,
+            watch_config: (watch_mode, Duration::from_millis(watch_poll_interval_ms)),
+            sub_watchers: parking_lot::Mutex::new(Vec::new()),
+            change_log,
+            file_stash: atomic::FileStash::new(
+                atomic::DEFAULT_STASH_ENTRIES,
+                atomic::DEFAULT_STASH_BYTES,
+            ),
             watcher_thread,
+            refresh_thread,
             stop_flag,
         })
     }
@@ -109,268 +350,2171 @@ impl FileSystem {
     /// List files matching the given pattern from memory index
     ///
     /// Args:
-    ///     pattern: Glob pattern (default: "**/*")
+    ///     pattern: Glob pattern (default: "**/*"). Brace alternation works
+    ///         as in the shell, including nested groups: "**/*.{rs,toml}"
     ///     only_files: If true, only return files (not directories)
+    ///     canonicalize: Resolve symlinks in each returned path, as
+    ///         described under glob()
     ///
     /// Returns:
     ///     List of file paths relative to root
-    #[pyo3(signature = (pattern = "**/*", only_files = true))]
-    pub fn list(&self, pattern: &str, only_files: bool) -> PyResult<Vec<String>> {
-        self.index.list(pattern, only_files).map_err(|e| e.into())
+    #[pyo3(signature = (pattern = "**/*", only_files = true, canonicalize = false))]
+    pub fn list(
+        &self,
+        py: Python<'_>,
+        pattern: &str,
+        only_files: bool,
+        canonicalize: bool,
+    ) -> PyResult<Vec<String>> {
+        let mut paths = self.index.list(pattern, only_files)?;
+        if canonicalize {
+            py.allow_threads(|| canonicalize_paths(&self.root, paths.par_iter_mut()));
+        }
+        Ok(paths)
     }
 
     /// Match files using glob pattern
     ///
     /// Args:
     ///     pattern: Glob pattern
+    ///     canonicalize: Resolve symlinks in each returned path
     ///
     /// Returns:
     ///     List of matching file paths
-    pub fn glob(&self, pattern: &str) -> PyResult<Vec<String>> {
-        self.index.glob(pattern).map_err(|e| e.into())
+    ///
+    /// Note:
+    ///     With canonicalize, each path is resolved by one
+    ///     `std::fs::canonicalize` call, run in parallel. A path that stays
+    ///     inside root is returned relative to it; one whose symlink points
+    ///     elsewhere becomes absolute and may lie outside root, so passing
+    ///     it back in is subject to allow_external. Two links to the same
+    ///     file yield the same path, and paths that cannot be resolved are
+    ///     returned unchanged.
+    #[pyo3(signature = (pattern, canonicalize = false))]
+    pub fn glob(&self, py: Python<'_>, pattern: &str, canonicalize: bool) -> PyResult<Vec<String>> {
+        self.list(py, pattern, true, canonicalize)
     }
 
-    /// Read a single file
+    /// Match files against several globs, keeping the results per pattern
+    ///
+    /// Unlike globbing each pattern and merging, this shows which pattern
+    /// matched what, including patterns that matched nothing.
     ///
     /// Args:
-    ///     path: File path (relative to root or absolute)
-    ///     encoding: Text encoding (default: "utf-8")
+    ///     patterns: Glob patterns to match
     ///
     /// Returns:
-    ///     File content as string
-    #[pyo3(signature = (path, encoding = "utf-8"))]
-    pub fn read_file(&self, py: Python<'_>, path: &str, encoding: &str) -> PyResult<String> {
-        let full_path = self.resolve_path(path);
-        io::read_file(py, &full_path, encoding)
+    ///     Dict mapping each pattern to its sorted matching file paths
+    pub fn glob_grouped(
+        &self,
+        py: Python<'_>,
+        patterns: Vec<String>,
+    ) -> PyResult<std::collections::HashMap<String, Vec<String>>> {
+        py.allow_threads(|| self.index.glob_grouped(&patterns))
+            .map_err(|e| e.into())
     }
 
-    /// Read multiple files in parallel
+    /// Resolve file specs mixing literal paths and globs to a file list
+    ///
+    /// A spec containing `*`, `?`, `[` or `{` is a glob expanded against
+    /// the index; any other spec is a literal path, kept if it names an
+    /// existing file, even one the ignore rules leave out of the index.
     ///
     /// Args:
-    ///     paths: List of file paths
+    ///     specs: Literal paths (relative to root or absolute) and globs
+    ///         (relative to root), e.g. ["src/main.rs", "tests/**/*.rs"]
     ///
     /// Returns:
-    ///     Dict mapping path to content
-    pub fn read_batch(
-        &self,
-        py: Python<'_>,
-        paths: Vec<String>,
-    ) -> PyResult<std::collections::HashMap<String, String>> {
-        let full_paths: Vec<PathBuf> = paths.iter().map(|p| self.resolve_path(p)).collect();
-        io::read_batch(py, &full_paths)
+    ///     Paths relative to root in spec order, each glob's matches sorted,
+    ///     without duplicates. Specs matching nothing contribute nothing
+    ///
+    /// Raises:
+    ///     ValueError: If a glob spec is invalid
+    ///     RuntimeError: If the index is not ready yet
+    pub fn expand_specs(&self, py: Python<'_>, specs: Vec<String>) -> PyResult<Vec<String>> {
+        py.allow_threads(|| self.index.expand_specs(&specs))
+            .map_err(|e| e.into())
     }
 
-    /// Read specific lines from a file (for large files)
+    /// List files whose relative path matches a regex
     ///
-    /// Efficiently reads a range of lines without loading the entire file.
-    /// Uses memory-mapped I/O for large files (> 1MB).
+    /// For matches globs cannot express, e.g. `r"\d{4}"` or
+    /// `r"(^|/)test_\w+\.py$"`. The regex is unanchored and is matched
+    /// against the `/`-separated path relative to root.
     ///
     /// Args:
-    ///     path: File path
-    ///     start_line: Starting line number (0-indexed)
-    ///     count: Number of lines to read (None = read to end)
+    ///     path_regex: Regular expression to match against relative paths
+    ///     only_files: If true, only return files (not directories)
     ///
     /// Returns:
-    ///     List of line strings (without trailing newlines)
-    #[pyo3(signature = (path, start_line = 0, count = None))]
-    pub fn read_lines(
-        &self,
-        py: Python<'_>,
-        path: &str,
-        start_line: usize,
-        count: Option<usize>,
-    ) -> PyResult<Vec<String>> {
-        let full_path = self.resolve_path(path);
-        io::read_lines(py, &full_path, start_line, count)
+    ///     List of matching paths relative to root
+    #[pyo3(signature = (path_regex, only_files = true))]
+    pub fn list_regex(&self, path_regex: &str, only_files: bool) -> PyResult<Vec<String>> {
+        self.index
+            .list_regex(path_regex, only_files)
+            .map_err(|e| e.into())
     }
 
-    /// Read a byte range from a file
+    /// Find the deepest directory containing every given path
+    ///
+    /// Paths are compared component-wise, so `src/foo` and `src/foobar`
+    /// share `src`. A single path yields its parent; if the shared prefix is
+    /// itself a file, its parent is used.
     ///
     /// Args:
-    ///     path: File path
-    ///     offset: Byte offset to start reading from
-    ///     limit: Maximum bytes to read
+    ///     paths: File or directory paths (relative to root or absolute)
     ///
     /// Returns:
-    ///     Content as string
-    #[pyo3(signature = (path, offset, limit))]
-    pub fn read_file_range(
+    ///     The common directory relative to root ("." for root itself), or
+    ///     an absolute path if it lies outside root. Empty input returns "."
+    pub fn common_ancestor(&self, paths: Vec<String>) -> String {
+        let resolved: Vec<PathBuf> = paths.iter().map(|p| self.resolve_path(p)).collect();
+
+        let ancestor = match common_prefix(&resolved) {
+            None => self.root.clone(),
+            Some(prefix) if resolved.len() == 1 || prefix.is_file() => {
+                prefix.parent().map(|p| p.to_path_buf()).unwrap_or(prefix)
+            }
+            Some(prefix) => prefix,
+        };
+
+        match ancestor.strip_prefix(&self.root) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => to_posix_string(relative),
+            Err(_) => to_posix_string(&ancestor),
+        }
+    }
+
+    /// Compare a list of file paths against the indexed files
+    ///
+    /// For checking a build manifest or lockfile against the tree. This is
+    /// a set operation over the in-memory index; the disk is not touched.
+    ///
+    /// Args:
+    ///     paths: File paths (relative to root or absolute)
+    ///
+    /// Returns:
+    ///     Tuple of (missing_on_disk, untracked_in_list): listed paths the
+    ///     index has no file for, and indexed files the list leaves out.
+    ///     Both are sorted and relative to root. A listed directory counts
+    ///     as missing, since only files are compared
+    pub fn diff_paths(
         &self,
         py: Python<'_>,
-        path: &str,
-        offset: u64,
-        limit: usize,
-    ) -> PyResult<String> {
-        let full_path = self.resolve_path(path);
-        io::read_file_range(py, &full_path, offset, limit)
+        paths: Vec<String>,
+    ) -> PyResult<(Vec<String>, Vec<String>)> {
+        let resolved: Vec<PathBuf> = paths.iter().map(|p| self.resolve_path(p)).collect();
+        py.allow_threads(|| self.index.diff_paths(&resolved))
+            .map_err(|e| e.into())
     }
 
-    /// Write content to file atomically
+    /// Find paths whose filename exactly equals `name`
     ///
     /// Args:
-    ///     path: File path
-    ///     content: Content to write
+    ///     name: Basename to look for (e.g. "Cargo.toml")
+    ///     case_insensitive: Compare names ignoring case
     ///
     /// Returns:
-    ///     True if successful
-    pub fn write_file(&self, py: Python<'_>, path: &str, content: &str) -> PyResult<bool> {
-        let full_path = self.resolve_path(path);
-        io::write_file(py, &full_path, content)?;
-        Ok(true)
+    ///     Sorted list of matching paths relative to root
+    #[pyo3(signature = (name, case_insensitive = false))]
+    pub fn find_by_name(&self, name: &str, case_insensitive: bool) -> PyResult<Vec<String>> {
+        self.index
+            .find_by_name(name, case_insensitive)
+            .map_err(|e| e.into())
     }
 
-    /// Write content to file without atomicity guarantee (fast mode)
+    /// List zero-byte files
     ///
-    /// Much faster than write_file() but does not guarantee data integrity
-    /// on crash. Use for temporary files or when speed is critical.
+    /// Answered from the in-memory index without touching the disk.
     ///
-    /// Warning:
-    ///     This operation is not atomic. Also, index updates rely on the
-    ///     asynchronous file watcher, so `list()` might not immediately
-    ///     reflect the new file.
+    /// Returns:
+    ///     Sorted list of empty file paths relative to root
+    pub fn empty_files(&self) -> PyResult<Vec<String>> {
+        self.index.empty_files().map_err(|e| e.into())
+    }
+
+    /// List directories with no entries
     ///
-    /// Args:
-    ///     path: File path
-    ///     content: Content to write
+    /// Answered from the in-memory index without touching the disk, so a
+    /// directory containing only ignored or filtered-out files is listed
+    /// too. A directory whose only child is an empty directory is not.
     ///
     /// Returns:
-    ///     True if successful
-    pub fn write_file_fast(&self, py: Python<'_>, path: &str, content: &str) -> PyResult<bool> {
-        let full_path = self.resolve_path(path);
-        io::write_file_fast(py, &full_path, content)?;
-        Ok(true)
+    ///     Sorted list of empty directory paths relative to root
+    pub fn empty_dirs(&self) -> PyResult<Vec<String>> {
+        self.index.empty_dirs().map_err(|e| e.into())
     }
 
-    /// Replace text in file
+    /// List the directories that directly contain matching files
     ///
-    /// Args:
-    ///     path: File path
-    ///     old_text: Text to find
-    ///     new_text: Replacement text
-    ///     strict: If true, error if old_text is not unique or not found
+    /// The "which folders have Python files" query for directory pickers:
+    /// files are globbed from the index and reduced to their parent
+    /// directories, without returning the files themselves.
     ///
-    /// Warning:
-    ///     This operation is NOT atomic across processes. It performs a
-    ///     read-modify-write cycle. If multiple processes modify the file
-    ///     concurrently, changes may be lost.
+    /// Args:
+    ///     pattern: Glob pattern for files, e.g. "**/*.py"
     ///
     /// Returns:
-    ///     True if replacement was made
-    #[pyo3(signature = (path, old_text, new_text, strict = true))]
-    pub fn edit_replace(
-        &self,
-        py: Python<'_>,
-        path: &str,
-        old_text: &str,
-        new_text: &str,
-        strict: bool,
-    ) -> PyResult<bool> {
-        let full_path = self.resolve_path(path);
-        io::edit_replace(py, &full_path, old_text, new_text, strict)
+    ///     Sorted, deduplicated directory paths relative to root ("." for
+    ///     root itself). Ancestors of those directories are not added
+    pub fn list_dirs_with_matches(&self, py: Python<'_>, pattern: &str) -> PyResult<Vec<String>> {
+        py.allow_threads(|| self.index.dirs_with_matches(pattern))
+            .map_err(|e| e.into())
     }
 
-    /// Search files for content matching query
+    /// Check whether a glob pattern matches a path
+    ///
+    /// Tests the pattern exactly as `list` and `glob` would apply it, without
+    /// listing anything; handy for checking include rules before using
+    /// them. As there, `*` also matches across `/`. The path does not have
+    /// to exist, and the compiled pattern is cached.
     ///
     /// Args:
-    ///     query: Search pattern (regex)
-    ///     glob_pattern: File pattern to search in
-    ///     case_sensitive: Case sensitive search
-    ///     max_results: Maximum number of results
+    ///     pattern: Glob pattern, e.g. "src/**/*.rs"
+    ///     path: Path relative to root, or absolute (absolute paths outside
+    ///         root never match)
     ///
     /// Returns:
-    ///     List of SearchResult objects
-    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000))]
-    pub fn grep(
-        &self,
-        py: Python<'_>,
-        query: &str,
-        glob_pattern: &str,
-        case_sensitive: bool,
-        max_results: usize,
-    ) -> PyResult<Vec<SearchResult>> {
-        let options = SearchOptions {
-            case_sensitive,
-            max_results,
-            max_file_size: 10 * 1024 * 1024, // 10MB
-            context_lines: 0,
-        };
-
-        // Use index if ready, otherwise fall back to directory scan
-        if self.index.is_ready() {
-            match self.index.glob_paths(glob_pattern) {
-                Ok(files) => {
-                    return self.searcher.grep_with_files(py, query, files, &options);
-                }
-                Err(_) => {
-                    // Fall back to standard grep
-                }
-            }
-        }
-
-        self.searcher.grep(py, query, glob_pattern, &options)
+    ///     True if the path matches the pattern
+    ///
+    /// Raises:
+    ///     ValueError: If the pattern is invalid
+    pub fn glob_matches(&self, pattern: &str, path: &str) -> PyResult<bool> {
+        self.index
+            .glob_matches(pattern, std::path::Path::new(path))
+            .map_err(|e| e.into())
     }
 
-    /// Get file metadata
+    /// List the most recently modified files
+    ///
+    /// Uses the mtimes already held by the index, so no file is stat'ed,
+    /// and keeps only the top `n` while scanning.
     ///
     /// Args:
-    ///     path: File path
+    ///     n: Maximum number of files to return
+    ///     pattern: Only consider files matching this glob (None = all files)
     ///
     /// Returns:
-    ///     FileMetadata object
-    pub fn get_metadata(&self, path: &str) -> PyResult<index::FileMetadata> {
-        let full_path = self.resolve_path(path);
+    ///     List of (path, mtime) tuples, newest first; mtime is a Unix
+    ///     timestamp
+    #[pyo3(signature = (n = 20, pattern = None))]
+    pub fn recent_files(&self, n: usize, pattern: Option<String>) -> PyResult<Vec<(String, f64)>> {
         self.index
-            .get_metadata(&full_path)
-            .ok_or_else(|| AgentGearError::PathNotFound(path.to_string()).into())
+            .recent_files(n, pattern.as_deref())
+            .map_err(|e| e.into())
     }
 
-    /// Force refresh the file index
-    pub fn refresh(&self) -> PyResult<()> {
-        self.index.refresh().map_err(|e| e.into())
+    /// Check whether anything under a subtree changed since a timestamp
+    ///
+    /// Walks only the indexed entries below `subpath` and stops at the first
+    /// one modified at or after `timestamp`, so a yes/no answer for one
+    /// directory is cheaper than listing recent files for the whole tree.
+    /// Directory mtimes count, which is how removals show up.
+    ///
+    /// Args:
+    ///     subpath: Directory (or file) to check, relative to root or absolute
+    ///     timestamp: Unix timestamp, e.g. from time.time() on a previous run
+    ///
+    /// Returns:
+    ///     True if an entry has mtime >= timestamp; False otherwise,
+    ///     including for paths the index does not hold
+    ///
+    /// Raises:
+    ///     RuntimeError: If the index is not ready yet
+    pub fn changed_under(&self, py: Python<'_>, subpath: &str, timestamp: f64) -> PyResult<bool> {
+        let dir = self.resolve_path(subpath);
+        py.allow_threads(|| self.index.changed_under(&dir, timestamp))
+            .map_err(|e| e.into())
     }
 
-    /// Check if the index is ready
-    pub fn is_ready(&self) -> bool {
-        self.index.is_ready()
+    /// Summarise the indexed code by language, a lightweight `cloc`
+    ///
+    /// Languages are detected from file names; binary files and files of
+    /// unknown kind are not counted. Only total lines are reported, with
+    /// no blank or comment distinction. Line counts are cached per file
+    /// and reused until the file changes, so after the first call only
+    /// modified files are read again.
+    ///
+    /// Returns:
+    ///     Dict mapping language name to a (files, lines, bytes) tuple
+    pub fn code_stats(
+        &self,
+        py: Python<'_>,
+    ) -> PyResult<std::collections::HashMap<String, index::LanguageStats>> {
+        py.allow_threads(|| self.index.code_stats())
+            .map_err(|e| e.into())
     }
 
-    /// Close the filesystem and release resources
-    pub fn close(&self) {
-        // Signal the watcher thread to stop
-        self.stop_flag.store(true, Ordering::SeqCst);
+    /// Count the indexed files matching a glob pattern
+    ///
+    /// Args:
+    ///     pattern: Glob pattern (default: "**/*")
+    ///
+    /// Returns:
+    ///     Number of matching files
+    #[pyo3(signature = (pattern = "**/*"))]
+    pub fn count_files(&self, py: Python<'_>, pattern: &str) -> PyResult<usize> {
+        py.allow_threads(|| self.index.count_files(pattern))
+            .map_err(|e| e.into())
+    }
 
-        // Stop the watcher
-        if let Some(ref watcher) = self.watcher {
-            watcher.stop();
-        }
+    /// Total lines across the text files matching a glob pattern
+    ///
+    /// Uses the same per-file line count cache as code_stats(), so only
+    /// files not yet counted or modified since are read. Binary files are
+    /// skipped.
+    ///
+    /// Args:
+    ///     pattern: Glob pattern (default: "**/*")
+    ///
+    /// Returns:
+    ///     Sum of line counts
+    #[pyo3(signature = (pattern = "**/*"))]
+    pub fn count_lines(&self, py: Python<'_>, pattern: &str) -> PyResult<u64> {
+        py.allow_threads(|| self.index.count_lines(pattern))
+            .map_err(|e| e.into())
     }
 
-    /// Check if file watching is active
-    pub fn is_watching(&self) -> bool {
-        self.watcher.is_some() && !self.stop_flag.load(Ordering::SeqCst)
+    /// Count a file's lines under an explicit line ending convention
+    ///
+    /// For matching the count another tool reports. The cached counts of
+    /// count_lines() and code_stats() split on `\n` only, the same as LF
+    /// here, and read_lines() splits like `str::lines` on `\n` and `\r\n`;
+    /// neither breaks at a lone `\r`. This always reads the file, with a
+    /// byte scan over a memory map, and skips the cache.
+    ///
+    /// Args:
+    ///     path: File path (relative to root or absolute)
+    ///     convention: LineEnding deciding what ends a line (default: LF).
+    ///         CRLF counts only `\r\n`; ANY counts `\r\n`, `\n` and a lone
+    ///         `\r` each as one break
+    ///
+    /// Returns:
+    ///     Number of lines, counting a final line without a terminator;
+    ///     0 for an empty file
+    ///
+    /// Raises:
+    ///     ValueError: If the file does not exist
+    #[pyo3(signature = (path, convention = LineEnding::Lf))]
+    pub fn count_lines_exact(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        convention: LineEnding,
+    ) -> PyResult<u64> {
+        io::count_lines_exact(py, &self.resolve_path(path), convention)
     }
 
-    /// Get the number of pending file change events
-    pub fn pending_changes(&self) -> usize {
-        if let Some(ref watcher) = self.watcher {
-            // Process events and return count
-            let events = watcher.process_events();
-            events.len()
-        } else {
-            0
+    /// Read a single file
+    ///
+    /// Args:
+    ///     path: File path (relative to root or absolute)
+    ///     encoding: Text encoding (default: "utf-8")
+    ///     max_read_bytes: Override the instance's max_read_bytes for this
+    ///         call (None = use the instance limit, 0 = no limit)
+    ///
+    /// Returns:
+    ///     File content as string
+    ///
+    /// Raises:
+    ///     IOError: If the file is larger than the read limit; the size is
+    ///         checked before anything is loaded
+    #[pyo3(signature = (path, encoding = "utf-8", max_read_bytes = None))]
+    pub fn read_file(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        encoding: &str,
+        max_read_bytes: Option<u64>,
+    ) -> PyResult<String> {
+        let full_path = self.resolve_path(path);
+        let limit = self.read_limit(max_read_bytes);
+        // Cached bodies are UTF-8, so other encodings bypass the cache
+        match self.read_cache {
+            Some(ref cache) if encoding::is_utf8(encoding) => py
+                .allow_threads(|| cache.read(&full_path, limit))
+                .map(|content| content.as_ref().clone())
+                .map_err(|e| e.into()),
+            _ => io::read_file(py, &full_path, encoding, limit),
         }
     }
 
-    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
-        slf
+    /// Classify a file by how an agent should treat it
+    ///
+    /// Combines the existence check, the empty-file case and the null-byte
+    /// binary heuristic in one call. The indexed binary flag is reused when
+    /// it is current; files outside the index or changed since are sniffed
+    /// directly.
+    ///
+    /// Args:
+    ///     path: File path (relative to root or absolute)
+    ///
+    /// Returns:
+    ///     "text", "binary", "empty" or "missing"
+    ///
+    /// Raises:
+    ///     IOError: If the path is a directory or cannot be stat'ed
+    pub fn classify(&self, py: Python<'_>, path: &str) -> PyResult<&'static str> {
+        let full_path = self.resolve_path(path);
+        py.allow_threads(|| self.index.classify(&full_path))
+            .map_err(|e| e.into())
     }
 
-    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
-    fn __exit__(
-        &self,
-        _exc_type: Option<&Bound<'_, PyAny>>,
-        _exc_value: Option<&Bound<'_, PyAny>>,
-        _traceback: Option<&Bound<'_, PyAny>>,
+    /// Override binary detection by file extension
+    ///
+    /// Files with an always_binary extension are marked binary without
+    /// being read; files with an always_text extension are treated as text,
+    /// and so searched, even if they contain null bytes. The overrides are
+    /// consulted before the null-byte sniff by indexing, classify() and the
+    /// candidate collection of searches that walk the disk. Each call
+    /// replaces the previous overrides; pass two empty lists to clear them.
+    ///
+    /// Args:
+    ///     always_binary: Extensions to treat as binary, e.g. ["dat"]
+    ///     always_text: Extensions to treat as text; wins over always_binary
+    ///         for an extension given in both
+    ///
+    /// Returns:
+    ///     Number of indexed files whose binary flag changed
+    pub fn set_binary_extensions(
+        &self,
+        py: Python<'_>,
+        always_binary: Vec<String>,
+        always_text: Vec<String>,
+    ) -> PyResult<usize> {
+        let overrides = BinaryOverrides::new(always_binary, always_text);
+        py.allow_threads(|| self.index.set_binary_overrides(overrides))
+            .map_err(|e| e.into())
+    }
+
+    /// Get a stable, content-based id for a file
+    ///
+    /// The id is the BLAKE3 hash of the file's bytes, so it survives
+    /// renames and moves and can key references across sessions. Files with
+    /// identical content share an id, and any edit changes it. Computed on
+    /// demand by streaming the file.
+    ///
+    /// Args:
+    ///     path: File path (relative to root or absolute)
+    ///
+    /// Returns:
+    ///     64-character lowercase hex string
+    pub fn file_id(&self, py: Python<'_>, path: &str) -> PyResult<String> {
+        let full_path = self.resolve_path(path);
+        py.allow_threads(|| io::content_id(&full_path))
+            .map_err(|e| e.into())
+    }
+
+    /// Read a file and its metadata in one call
+    ///
+    /// The metadata is taken from the opened file, so size and mtime match
+    /// the returned content even if the index has not caught up yet.
+    ///
+    /// Args:
+    ///     path: File path (relative to root or absolute)
+    ///     max_read_bytes: Override the instance's max_read_bytes for this
+    ///         call (None = use the instance limit, 0 = no limit)
+    ///
+    /// Returns:
+    ///     Tuple of (content, FileMetadata)
+    ///
+    /// Raises:
+    ///     IOError: If the file is larger than the read limit; the size is
+    ///         checked before anything is loaded
+    #[pyo3(signature = (path, max_read_bytes = None))]
+    pub fn open(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        max_read_bytes: Option<u64>,
+    ) -> PyResult<(String, index::FileMetadata)> {
+        let full_path = self.resolve_path(path);
+        io::read_with_metadata(py, &full_path, self.read_limit(max_read_bytes))
+    }
+
+    /// Read multiple files in parallel
+    ///
+    /// Files that cannot be read, or that exceed `max_file_size`, are skipped
+    /// and absent from the result.
+    ///
+    /// Args:
+    ///     paths: List of file paths
+    ///     max_file_size: Skip files larger than this many bytes (None = no limit)
+    ///     max_read_bytes: Override the instance's max_read_bytes for this
+    ///         call (None = use the instance limit, 0 = no limit)
+    ///
+    /// Returns:
+    ///     Dict mapping each path, exactly as given, to its content
+    ///
+    /// Raises:
+    ///     IOError: If a file not skipped by max_file_size is larger than the
+    ///         read limit
+    #[pyo3(signature = (paths, max_file_size = None, max_read_bytes = None))]
+    pub fn read_batch(
+        &self,
+        py: Python<'_>,
+        paths: Vec<String>,
+        max_file_size: Option<u64>,
+        max_read_bytes: Option<u64>,
+    ) -> PyResult<std::collections::HashMap<String, String>> {
+        // Key results by the caller's own strings so lookups use what was passed in
+        let keyed_paths: Vec<(String, PathBuf)> = paths
+            .into_iter()
+            .map(|p| {
+                let full_path = self.resolve_path(&p);
+                (p, full_path)
+            })
+            .collect();
+        io::read_batch(
+            py,
+            &keyed_paths,
+            max_file_size,
+            self.read_limit(max_read_bytes),
+        )
+    }
+
+    /// Read specific lines from a file (for large files)
+    ///
+    /// Efficiently reads a range of lines without loading the entire file.
+    /// Uses memory-mapped I/O for large files (> 1MB).
+    ///
+    /// Args:
+    ///     path: File path
+    ///     start_line: Starting line number (0-indexed)
+    ///     count: Number of lines to read (None = read to end)
+    ///     max_bytes: Byte budget for the returned lines; reading stops before
+    ///         the first line that would exceed it, so only whole lines are
+    ///         returned (None = no budget)
+    ///
+    /// Returns:
+    ///     List of line strings (without trailing newlines); shorter than
+    ///     count when the budget or the end of file is reached
+    #[pyo3(signature = (path, start_line = 0, count = None, max_bytes = None))]
+    pub fn read_lines(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        start_line: usize,
+        count: Option<usize>,
+        max_bytes: Option<usize>,
+    ) -> PyResult<Vec<String>> {
+        let full_path = self.resolve_path(path);
+        self.read_line_range(py, &full_path, start_line, count, max_bytes)
+    }
+
+    /// Read the first line of a file
+    ///
+    /// Only reads up to the first newline, so it is cheap even for huge files.
+    /// Useful for shebang detection and generated-file headers.
+    ///
+    /// Args:
+    ///     path: File path
+    ///
+    /// Returns:
+    ///     The first line without its terminator, or None for an empty file
+    pub fn read_first_line(&self, py: Python<'_>, path: &str) -> PyResult<Option<String>> {
+        let full_path = self.resolve_path(path);
+        io::read_first_line(py, &full_path)
+    }
+
+    /// Read specific lines paired with their line numbers
+    ///
+    /// Returns the same range as `read_lines`, but each line is paired with
+    /// its 1-indexed line number, ready to be shown to a model.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     start_line: Starting line number (0-indexed)
+    ///     count: Number of lines to read (None = read to end)
+    ///
+    /// Returns:
+    ///     List of (line_number, text) tuples, line_number is 1-indexed
+    #[pyo3(signature = (path, start_line = 0, count = None))]
+    pub fn read_numbered(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        start_line: usize,
+        count: Option<usize>,
+    ) -> PyResult<Vec<(usize, String)>> {
+        let full_path = self.resolve_path(path);
+        if self.line_offsets.is_none() {
+            return io::read_numbered(py, &full_path, start_line, count);
+        }
+        let lines = self.read_line_range(py, &full_path, start_line, count, None)?;
+        Ok(lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| (start_line + i + 1, line))
+            .collect())
+    }
+
+    /// Read the numbered lines around a given line
+    ///
+    /// Returns lines `[center_line - radius, center_line + radius]`, clamped
+    /// to the file, for showing the code around a grep match. Uses the same
+    /// path as `read_lines`, so large files are memory-mapped.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     center_line: Line to center on (1-indexed, as in grep results)
+    ///     radius: Number of lines to include before and after center_line
+    ///
+    /// Returns:
+    ///     List of (line_number, text) tuples, line_number is 1-indexed
+    ///
+    /// Raises:
+    ///     ValueError: If center_line is 0
+    #[pyo3(signature = (path, center_line, radius = 10))]
+    pub fn read_around(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        center_line: usize,
+        radius: usize,
+    ) -> PyResult<Vec<(usize, String)>> {
+        if center_line == 0 {
+            return Err(PyValueError::new_err("center_line is 1-indexed"));
+        }
+        let (start_line, count) = io::around_window(center_line, radius);
+        self.read_numbered(py, path, start_line, Some(count))
+    }
+
+    /// Byte offset of the start of each line of a file
+    ///
+    /// Found with a newline scan over a memory map. Offsets follow
+    /// read_lines' numbering: entry i is where line i (0-indexed) starts,
+    /// and a trailing newline does not start another line. With
+    /// cache_line_offsets the result is cached and later range reads of the
+    /// file seek with it.
+    ///
+    /// Args:
+    ///     path: File path
+    ///
+    /// Returns:
+    ///     List of byte offsets, empty for an empty file
+    pub fn line_offsets(&self, py: Python<'_>, path: &str) -> PyResult<Vec<u64>> {
+        let full_path = self.resolve_path(path);
+        match self.line_offsets {
+            Some(ref cache) => py
+                .allow_threads(|| cache.offsets(&full_path))
+                .map(|offsets| offsets.as_ref().clone())
+                .map_err(|e| e.into()),
+            None => io::file_line_offsets(py, &full_path),
+        }
+    }
+
+    /// Read a file together with the character offset of each line start
+    ///
+    /// The offsets are computed from the returned content itself, so unlike
+    /// read_file() followed by line_offsets() the two always agree even if
+    /// the file changes between calls. Reads go through the read cache and
+    /// the read limit like read_file().
+    ///
+    /// Args:
+    ///     path: File path (relative to root or absolute)
+    ///     max_read_bytes: Override the instance's max_read_bytes for this
+    ///         call (None = use the instance limit, 0 = no limit)
+    ///
+    /// Returns:
+    ///     Tuple of (content, offsets). Entry i is where line i (0-indexed)
+    ///     starts, counted in characters so content[offsets[i]:offsets[i + 1]]
+    ///     is the line; unlike line_offsets() they are not byte offsets
+    ///
+    /// Raises:
+    ///     IOError: If the file is larger than the read limit
+    #[pyo3(signature = (path, max_read_bytes = None))]
+    pub fn read_indexed(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        max_read_bytes: Option<u64>,
+    ) -> PyResult<(String, Vec<u64>)> {
+        let content = self.read_file(py, path, "utf-8", max_read_bytes)?;
+        let offsets = py.allow_threads(|| io::char_line_offsets(&content));
+        Ok((content, offsets))
+    }
+
+    /// Read a file split into pages of bounded size
+    ///
+    /// Pages hold at most page_chars characters and break after a newline
+    /// whenever a whole line fits; only lines longer than a page are cut,
+    /// and never inside a multibyte character. Joining the page texts gives
+    /// back the file. The file is subject to max_read_bytes like read_file.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     page_chars: Maximum characters per page
+    ///
+    /// Returns:
+    ///     List of (start_line, text) tuples, start_line is the 1-indexed
+    ///     line each page begins on
+    ///
+    /// Raises:
+    ///     ValueError: If page_chars is 0
+    pub fn read_paged(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        page_chars: usize,
+    ) -> PyResult<Vec<(usize, String)>> {
+        if page_chars == 0 {
+            return Err(PyValueError::new_err("page_chars must be positive"));
+        }
+        let content = self.read_file(py, path, "utf-8", None)?;
+        Ok(py.allow_threads(|| io::paginate(&content, page_chars)))
+    }
+
+    /// Read a page of lines starting at a cursor
+    ///
+    /// For paging through files too large to load: each call seeks to the
+    /// cursor and reads forward, so memory is bounded by the page and no
+    /// earlier lines are rescanned, unlike read_lines with a growing
+    /// start_line. The cursor is a byte offset; treat it as opaque and only
+    /// pass back values returned by an earlier call.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     cursor: Where to resume (None = start of file)
+    ///     max_lines: Maximum number of lines to return
+    ///
+    /// Returns:
+    ///     Tuple of (lines, next_cursor); lines are without terminators and
+    ///     next_cursor is None once the end of the file is reached
+    ///
+    /// Raises:
+    ///     ValueError: If max_lines is 0
+    #[pyo3(signature = (path, cursor = None, max_lines = 1000))]
+    pub fn read_page(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        cursor: Option<u64>,
+        max_lines: usize,
+    ) -> PyResult<(Vec<String>, Option<u64>)> {
+        if max_lines == 0 {
+            return Err(PyValueError::new_err("max_lines must be positive"));
+        }
+        let full_path = self.resolve_path(path);
+        io::read_page(py, &full_path, cursor.unwrap_or(0), max_lines)
+    }
+
+    /// Read a byte range from a file
+    ///
+    /// Args:
+    ///     path: File path
+    ///     offset: Byte offset to start reading from
+    ///     limit: Maximum bytes to read
+    ///
+    /// Returns:
+    ///     Content as string
+    #[pyo3(signature = (path, offset, limit))]
+    pub fn read_file_range(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        offset: u64,
+        limit: usize,
+    ) -> PyResult<String> {
+        let full_path = self.resolve_path(path);
+        io::read_file_range(py, &full_path, offset, limit)
+    }
+
+    /// Write content to file atomically
+    ///
+    /// Args:
+    ///     path: File path
+    ///     content: Content to write
+    ///     encoding: Output encoding (e.g. "utf-8", "shift_jis", "gbk")
+    ///     replace_unencodable: Write "?" for characters the encoding cannot
+    ///         represent instead of raising
+    ///     verify: Read the file back after the rename and compare it with
+    ///         what was written. Doubles the I/O, so reserve it for critical
+    ///         writes on storage that may corrupt data silently
+    ///     ensure_trailing_newline: Append a line ending if the content does
+    ///         not end with one (CRLF if the content uses CRLF)
+    ///     strip_trailing_whitespace: Trim spaces and tabs from the end of
+    ///         every line
+    ///
+    /// Returns:
+    ///     True if successful
+    ///
+    /// Raises:
+    ///     ValueError: If the encoding is unknown, or content is unencodable
+    ///         and replace_unencodable is false
+    ///     WriteVerificationError: If verify is set and the file on disk
+    ///         differs from the written content
+    ///     PermissionError: If writable_extensions does not allow the path
+    #[pyo3(signature = (path, content, encoding = "utf-8", replace_unencodable = false, verify = false, ensure_trailing_newline = false, strip_trailing_whitespace = false))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_file(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        content: &str,
+        encoding: &str,
+        replace_unencodable: bool,
+        verify: bool,
+        ensure_trailing_newline: bool,
+        strip_trailing_whitespace: bool,
+    ) -> PyResult<bool> {
+        let full_path = self.writable_path(path)?;
+        let content =
+            io::normalize_text(content, ensure_trailing_newline, strip_trailing_whitespace);
+        io::write_file(
+            py,
+            &full_path,
+            &content,
+            encoding,
+            replace_unencodable,
+            verify,
+        )?;
+        Ok(true)
+    }
+
+    /// Create a file only if it does not already exist
+    ///
+    /// The existence check and the create are a single exclusive rename, so
+    /// two agents scaffolding the same path cannot overwrite each other.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     content: Content to write
+    ///
+    /// Returns:
+    ///     True if the file was created, False if it already existed
+    pub fn write_new(&self, py: Python<'_>, path: &str, content: &str) -> PyResult<bool> {
+        let full_path = self.writable_path(path)?;
+        io::write_new(py, &full_path, content)
+    }
+
+    /// Write several files with all-or-nothing semantics
+    ///
+    /// Every file is first written and fsynced to a temp file beside its
+    /// target; if any of that fails the temps are deleted and no target is
+    /// changed. Only then are the temps renamed into place, in path order.
+    ///
+    /// Warning:
+    ///     The rename phase is not atomic across files. A crash, or a rename
+    ///     that fails, partway through leaves the earlier files replaced and
+    ///     the rest unchanged. The window is short (renames only, all data
+    ///     already on disk) but a journal would be needed to close it.
+    ///     Missing parent directories are created while staging and are
+    ///     kept on failure.
+    ///
+    /// Args:
+    ///     files: Dict mapping each file path to its new content
+    ///
+    /// Returns:
+    ///     True if every file was written
+    ///
+    /// Raises:
+    ///     IOError: If staging fails (nothing changed) or a rename fails (the
+    ///         message says how many files were already replaced)
+    ///     PermissionError: If writable_extensions does not allow one of the
+    ///         paths (nothing is written)
+    pub fn write_transaction(
+        &self,
+        py: Python<'_>,
+        files: std::collections::HashMap<String, String>,
+    ) -> PyResult<bool> {
+        let mut staged: Vec<(PathBuf, Vec<u8>)> = files
+            .into_iter()
+            .map(|(path, content)| Ok((self.writable_path(&path)?, content.into_bytes())))
+            .collect::<PyResult<_>>()?;
+        staged.sort_by(|a, b| a.0.cmp(&b.0));
+
+        py.allow_threads(|| atomic::atomic_write_all(&staged))?;
+        Ok(true)
+    }
+
+    /// Write content to file without atomicity guarantee (fast mode)
+    ///
+    /// Much faster than write_file() but does not guarantee data integrity
+    /// on crash. Use for temporary files or when speed is critical.
+    ///
+    /// Warning:
+    ///     This operation is not atomic. Also, index updates rely on the
+    ///     asynchronous file watcher, so `list()` might not immediately
+    ///     reflect the new file.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     content: Content to write
+    ///
+    /// Returns:
+    ///     True if successful
+    pub fn write_file_fast(&self, py: Python<'_>, path: &str, content: &str) -> PyResult<bool> {
+        let full_path = self.writable_path(path)?;
+        io::write_file_fast(py, &full_path, content)?;
+        Ok(true)
+    }
+
+    /// Write content to a staged file beside a target, without touching it
+    ///
+    /// The first half of a two-phase single-file commit: stage now, then
+    /// `promote(staged, target_path)` to swap it in atomically, or delete
+    /// the staged file to abandon the edit. Staged files are named
+    /// `.<target name>.<random>.agstaged` in the target's directory;
+    /// orphans left by a crash can be found with the glob `**/*.agstaged`.
+    ///
+    /// Args:
+    ///     target_path: Path the content is meant to replace
+    ///     content: Content to write
+    ///
+    /// Returns:
+    ///     Path of the staged file, relative to root when inside it
+    pub fn write_staged(
+        &self,
+        py: Python<'_>,
+        target_path: &str,
+        content: &str,
+    ) -> PyResult<String> {
+        let target = self.writable_path(target_path)?;
+        let staged = py.allow_threads(|| atomic::write_staged(&target, content.as_bytes()))?;
+
+        Ok(match staged.strip_prefix(&self.root) {
+            Ok(relative) => to_posix_string(relative),
+            Err(_) => to_posix_string(&staged),
+        })
+    }
+
+    /// Atomically move a staged file into its final location
+    ///
+    /// Performs a single `rename(temp_path, final_path)`, replacing any
+    /// existing file at `final_path`, and updates the index accordingly.
+    ///
+    /// Args:
+    ///     temp_path: Staged file to promote
+    ///     final_path: Destination path
+    ///
+    /// Returns:
+    ///     True if successful
+    ///
+    /// Raises:
+    ///     IOError: If the paths are on different filesystems, where a rename
+    ///         is not atomic. Copy the content with write_file() instead.
+    pub fn promote(&self, py: Python<'_>, temp_path: &str, final_path: &str) -> PyResult<bool> {
+        let from = self.resolve_path(temp_path);
+        let to = self.writable_path(final_path)?;
+
+        py.allow_threads(|| -> crate::utils::error::Result<()> {
+            atomic::promote(&from, &to)?;
+
+            self.index.remove_path(&from);
+            if self.index.get_metadata(&to).is_some() {
+                self.index.update_path(&to)
+            } else {
+                self.index.add_path(&to)
+            }
+        })?;
+
+        Ok(true)
+    }
+
+    /// Save a file's current content in memory for a later restore_file
+    ///
+    /// A single-level undo for speculative edits: snapshot, edit, and
+    /// restore if the edit turns out wrong. No backup file is written. The
+    /// 32 most recent snapshots are kept, up to 64MB in total; older ones
+    /// are evicted and their tokens stop working.
+    ///
+    /// Args:
+    ///     path: File path
+    ///
+    /// Returns:
+    ///     Token to pass to restore_file
+    ///
+    /// Raises:
+    ///     IOError: If the file is larger than the 64MB budget
+    pub fn snapshot_file(&self, py: Python<'_>, path: &str) -> PyResult<u64> {
+        let full_path = self.resolve_path(path);
+        py.allow_threads(|| self.file_stash.stash(&full_path))
+            .map_err(|e| e.into())
+    }
+
+    /// Atomically rewrite a file to the content saved by snapshot_file
+    ///
+    /// Each snapshot restores once. If the write fails the snapshot is
+    /// kept, so the restore can be retried.
+    ///
+    /// Args:
+    ///     token: Token returned by snapshot_file
+    ///
+    /// Returns:
+    ///     The restored path, relative to root
+    ///
+    /// Raises:
+    ///     ValueError: If the token was already restored or has been evicted
+    ///     PermissionError: If writable_extensions does not allow the path
+    pub fn restore_file(&self, py: Python<'_>, token: u64) -> PyResult<String> {
+        let unknown = || {
+            PyValueError::new_err(format!(
+                "Unknown snapshot token {}: already restored or evicted",
+                token
+            ))
+        };
+        let path = self.file_stash.path(token).ok_or_else(unknown)?;
+        let relative = to_posix_string(path.strip_prefix(&self.root).unwrap_or(&path));
+        self.check_writable(&relative)?;
+
+        match py.allow_threads(|| self.file_stash.restore(token))? {
+            Some(_) => Ok(relative),
+            None => Err(unknown()),
+        }
+    }
+
+    /// Replace text in file
+    ///
+    /// Args:
+    ///     path: File path
+    ///     old_text: Text to find
+    ///     new_text: Replacement text
+    ///     strict: If true, error if old_text is not unique or not found
+    ///     encoding: Encoding the file is read in and written back in
+    ///     replace_unencodable: Write "?" for characters the encoding cannot
+    ///         represent instead of raising
+    ///     ensure_trailing_newline: Make the edited file end with a line ending
+    ///     strip_trailing_whitespace: Trim trailing spaces and tabs from every
+    ///         line of the edited file, not just the replaced text
+    ///     max_replacements: In non-strict mode, replace only the first N
+    ///         occurrences and leave the rest (None = all)
+    ///     line_start: First line (1-indexed) of the region to edit in
+    ///         (None = line 1)
+    ///     line_end: Last line of the region, inclusive (None = end of file)
+    ///
+    /// With a line range, only occurrences lying entirely inside it are
+    /// visible: strict counts uniqueness within the range, so old_text may
+    /// repeat elsewhere in the file, and raises if it is absent from the
+    /// range even when it occurs outside. Non-strict mode replaces only the
+    /// occurrences in the range.
+    ///
+    /// Warning:
+    ///     This operation is NOT atomic across processes. It performs a
+    ///     read-modify-write cycle. If multiple processes modify the file
+    ///     concurrently, changes may be lost.
+    ///
+    /// Returns:
+    ///     Number of occurrences replaced (0 if old_text was not found and
+    ///     strict is false)
+    ///
+    /// Raises:
+    ///     ValueError: If line_start is 0 or line_end is before line_start
+    #[pyo3(signature = (path, old_text, new_text, strict = true, encoding = "utf-8", replace_unencodable = false, ensure_trailing_newline = false, strip_trailing_whitespace = false, max_replacements = None, line_start = None, line_end = None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn edit_replace(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        old_text: &str,
+        new_text: &str,
+        strict: bool,
+        encoding: &str,
+        replace_unencodable: bool,
+        ensure_trailing_newline: bool,
+        strip_trailing_whitespace: bool,
+        max_replacements: Option<usize>,
+        line_start: Option<usize>,
+        line_end: Option<usize>,
+    ) -> PyResult<usize> {
+        let line_range = match (line_start, line_end) {
+            (None, None) => None,
+            (Some(0), _) => return Err(PyValueError::new_err("line_start is 1-indexed")),
+            (first, last) => {
+                let first = first.unwrap_or(1);
+                let last = last.unwrap_or(usize::MAX);
+                if last < first {
+                    return Err(PyValueError::new_err("line_end is before line_start"));
+                }
+                Some((first, last))
+            }
+        };
+
+        let full_path = self.writable_path(path)?;
+        io::edit_replace(
+            py,
+            &full_path,
+            old_text,
+            new_text,
+            strict,
+            encoding,
+            replace_unencodable,
+            ensure_trailing_newline,
+            strip_trailing_whitespace,
+            max_replacements,
+            line_range,
+        )
+    }
+
+    /// Apply a unified diff to a file
+    ///
+    /// Parses the hunks of a single-file unified diff (as produced by
+    /// `diff -u` or `git diff`), checks that each hunk's context and removed
+    /// lines match the file at the line its header names, applies the
+    /// changes in memory and writes the result atomically. File headers are
+    /// optional; only the `@@` hunks are needed.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     unified_diff: The patch text
+    ///     strict: If true, raise if any hunk does not match and write
+    ///         nothing; otherwise skip hunks that do not match and apply
+    ///         the rest
+    ///     fuzzy: Also accept a hunk whose context sits at a different line
+    ///         (the nearest match wins) or differs in trailing whitespace,
+    ///         for patches made against a slightly older version of the file
+    ///
+    /// Returns:
+    ///     True if every hunk applied, False if some were skipped
+    ///
+    /// Raises:
+    ///     ValueError: If the diff is malformed, touches more than one file,
+    ///         or (when strict) a hunk does not match
+    #[pyo3(signature = (path, unified_diff, strict = true, fuzzy = false))]
+    pub fn apply_patch(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        unified_diff: &str,
+        strict: bool,
+        fuzzy: bool,
+    ) -> PyResult<bool> {
+        let full_path = self.writable_path(path)?;
+        io::apply_patch(py, &full_path, unified_diff, strict, fuzzy)
+    }
+
+    /// Stream lines appended to a file, like `tail -f`
+    ///
+    /// The file is polled every 100ms on a background thread and each new
+    /// complete line is passed to `callback`. If the file is truncated or
+    /// replaced (log rotation), following restarts from its beginning.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     callback: Called with each new line (without its line ending),
+    ///         from a background thread. If it raises, following stops
+    ///     from_end: Only report lines appended from now on; when false the
+    ///         existing content is reported first
+    ///
+    /// Returns:
+    ///     FollowHandle; call `stop()` on it (or close the FileSystem) to
+    ///     stop following
+    #[pyo3(signature = (path, callback, from_end = true))]
+    pub fn follow(
+        &self,
+        path: &str,
+        callback: Py<PyAny>,
+        from_end: bool,
+    ) -> PyResult<follow::FollowHandle> {
+        let tail = follow::LineTail::new(self.resolve_path(path), from_end)?;
+        Ok(follow::FollowHandle::spawn(
+            tail,
+            callback,
+            Arc::clone(&self.stop_flag),
+        ))
+    }
+
+    /// Compare a file with proposed content, line by line
+    ///
+    /// Nothing is written; use this to review a whole-file rewrite hunk by
+    /// hunk before calling `write_file`.
+    ///
+    /// Args:
+    ///     path: File path
+    ///     new_content: Proposed full content of the file
+    ///
+    /// Returns:
+    ///     List of DiffHunk objects in file order (empty if unchanged)
+    pub fn diff_content(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        new_content: &str,
+    ) -> PyResult<Vec<diff::DiffHunk>> {
+        let full_path = self.resolve_path(path);
+        let old_content = io::read_file(py, &full_path, "utf-8", self.max_read_bytes)?;
+        Ok(py.allow_threads(|| diff::diff_lines(&old_content, new_content)))
+    }
+
+    /// Search files for content matching query
+    ///
+    /// Args:
+    ///     query: Search pattern (regex)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of results
+    ///     first_per_file_with_count: Return only the first match per file,
+    ///         with `file_match_count` set to the file's total matches
+    ///     include_hidden: Search hidden files (dotfiles)
+    ///     include_ignored: Search files excluded by .gitignore/.ignore rules
+    ///     max_files_scanned: Stop after searching this many files, bounding
+    ///         latency on huge trees (None = no limit)
+    ///     order_by: SearchOrder applied to every match before truncation
+    ///         to max_results, so the top results are the best across the
+    ///         tree rather than the first found. Reaching max_results does
+    ///         not end an ordered search early, though only the best
+    ///         max_results are held at a time; max_files_scanned still
+    ///         bounds it (None = unspecified order)
+    ///     binary: BinaryMode for binary and non-UTF8 files (default: SKIP).
+    ///         TEXT decodes lossily, STRINGS searches printable runs; line
+    ///         numbers for binary content are approximate.
+    ///     normalize_whitespace: Collapse runs of whitespace in each line to a
+    ///         single space before matching, so `foo  (bar)` matches the
+    ///         query `foo \(bar\)`. Applies to file content only; the regex
+    ///         is used as given. Returned lines keep their original spacing.
+    ///     head_bytes: Search only the first N bytes of each file, for
+    ///         headers such as licenses, shebangs or front matter. A line cut
+    ///         by the region edge is skipped.
+    ///     tail_bytes: Search only the last N bytes of each file, for
+    ///         trailers. Line numbers stay file-relative, which costs a
+    ///         newline count over the skipped prefix. Mutually exclusive with
+    ///         `head_bytes`.
+    ///     symbol_context: Set each result's `symbol` to the name of the
+    ///         nearest enclosing function, class or method, found by scanning
+    ///         upward for a declaration. Best-effort and only for recognised
+    ///         extensions (Rust, Python, JS/TS, Go, Java/Kotlin/C#, C/C++,
+    ///         Ruby, PHP); other files get None.
+    ///     include_metadata: Set each result's `metadata` to the file's
+    ///         indexed FileMetadata (size, mtime, is_binary), looked up once
+    ///         per file. None for files not yet in the index.
+    ///     options: A fully configured SearchOptions, reaching settings that
+    ///         have no argument here (context_lines, max_file_size,
+    ///         per_file_timeout_ms, encoding). When given it takes
+    ///         precedence: every other argument except query, glob_pattern
+    ///         and canonicalize is ignored.
+    ///     canonicalize: Resolve symlinks in each result's file path, with
+    ///         the same rules and cost as glob(canonicalize=True)
+    ///     capture_group: Report this capture group of the query as each
+    ///         result's content instead of the whole line, with its columns
+    ///         in capture_span, so `version = "(.*)"` with capture_group=1
+    ///         extracts the version string. Lines where the group did not
+    ///         participate are skipped. With normalize_whitespace the group
+    ///         is taken from the normalized line
+    ///     languages: Search only files whose detected language is one of
+    ///         these names, compared case-insensitively, e.g. ["python"].
+    ///         Detection is by file name, as in `code_stats`, and always
+    ///         available; files of an unrecognised kind never match. Applied
+    ///         on top of glob_pattern and the ignore rules
+    ///
+    /// Returns:
+    ///     List of SearchResult objects, each with matched_glob set to the
+    ///     glob_pattern its file was selected by
+    ///
+    /// Warning:
+    ///     `include_ignored` widens the search to everything on disk under
+    ///     root, including secrets files (`.env`), build output, vendored
+    ///     dependencies and `.git` internals. Results may expose content the
+    ///     project deliberately keeps out of version control.
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, first_per_file_with_count = false, include_hidden = true, include_ignored = false, max_files_scanned = None, order_by = None, binary = BinaryMode::Skip, normalize_whitespace = false, head_bytes = None, tail_bytes = None, symbol_context = false, include_metadata = false, options = None, canonicalize = false, capture_group = None, languages = None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn grep(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        first_per_file_with_count: bool,
+        include_hidden: bool,
+        include_ignored: bool,
+        max_files_scanned: Option<usize>,
+        order_by: Option<SearchOrder>,
+        binary: BinaryMode,
+        normalize_whitespace: bool,
+        head_bytes: Option<u64>,
+        tail_bytes: Option<u64>,
+        symbol_context: bool,
+        include_metadata: bool,
+        options: Option<SearchOptions>,
+        canonicalize: bool,
+        capture_group: Option<usize>,
+        languages: Option<Vec<String>>,
+    ) -> PyResult<Vec<SearchResult>> {
+        let mut results = self
+            .grep_summary(
+                py,
+                query,
+                glob_pattern,
+                case_sensitive,
+                max_results,
+                first_per_file_with_count,
+                include_hidden,
+                include_ignored,
+                max_files_scanned,
+                order_by,
+                binary,
+                normalize_whitespace,
+                head_bytes,
+                tail_bytes,
+                symbol_context,
+                include_metadata,
+                options,
+                capture_group,
+                languages,
+            )?
+            .results;
+        if canonicalize {
+            py.allow_threads(|| {
+                canonicalize_paths(&self.root, results.par_iter_mut().map(|r| &mut r.file))
+            });
+        }
+        Ok(results)
+    }
+
+    /// Search files for content, yielding results as they are found
+    ///
+    /// Unlike `grep`, this returns immediately: the search runs in the
+    /// background and results can be processed, or the loop left early,
+    /// before it completes. Breaking out of the loop and dropping the
+    /// iterator cancels the remaining search.
+    ///
+    /// Args:
+    ///     query: Search pattern (regex)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of results
+    ///     options: A fully configured SearchOptions; when given, every other
+    ///         argument except query and glob_pattern is ignored
+    ///
+    /// Returns:
+    ///     SearchIter yielding SearchResult objects in completion order
+    ///     (`order_by` does not apply)
+    ///
+    /// Raises:
+    ///     ValueError: If the query or glob pattern is invalid
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, options = None))]
+    pub fn grep_iter(
+        &self,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        options: Option<SearchOptions>,
+    ) -> PyResult<SearchIter> {
+        let options = options.unwrap_or(SearchOptions {
+            case_sensitive,
+            max_results,
+            ..SearchOptions::default()
+        });
+        Self::check_search_options(&options)?;
+
+        let matcher = Matcher::single(query, options.case_sensitive)?;
+        if let Some(group) = options.capture_group {
+            matcher.check_capture_group(group)?;
+        }
+        let files = self.indexed_files(glob_pattern, &options);
+        self.searcher
+            .stream(matcher, glob_pattern, files, options)
+            .map_err(|e| e.into())
+    }
+
+    /// Search files for content, returning results with search statistics
+    ///
+    /// Takes the same arguments as `grep`, but also reports how many files
+    /// were searched, whether the search stopped early, and which files
+    /// were skipped because reading them failed or exceeded
+    /// `per_file_timeout_ms`.
+    ///
+    /// Returns:
+    ///     SearchSummary object
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, first_per_file_with_count = false, include_hidden = true, include_ignored = false, max_files_scanned = None, order_by = None, binary = BinaryMode::Skip, normalize_whitespace = false, head_bytes = None, tail_bytes = None, symbol_context = false, include_metadata = false, options = None, capture_group = None, languages = None))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn grep_summary(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        first_per_file_with_count: bool,
+        include_hidden: bool,
+        include_ignored: bool,
+        max_files_scanned: Option<usize>,
+        order_by: Option<SearchOrder>,
+        binary: BinaryMode,
+        normalize_whitespace: bool,
+        head_bytes: Option<u64>,
+        tail_bytes: Option<u64>,
+        symbol_context: bool,
+        include_metadata: bool,
+        options: Option<SearchOptions>,
+        capture_group: Option<usize>,
+        languages: Option<Vec<String>>,
+    ) -> PyResult<SearchSummary> {
+        let options = options.unwrap_or(SearchOptions {
+            case_sensitive,
+            max_results,
+            max_file_size: 10 * 1024 * 1024, // 10MB
+            context_lines: 0,
+            first_per_file_with_count,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            normalize_whitespace,
+            head_bytes,
+            tail_bytes,
+            symbol_context,
+            per_file_timeout_ms: None,
+            include_metadata,
+            encoding: None,
+            capture_group,
+            max_matches_per_dir: None,
+            languages,
+        });
+
+        let matcher = Matcher::single(query, options.case_sensitive)?;
+        self.run_search(py, &matcher, glob_pattern, &options)
+    }
+
+    /// Search files and return ripgrep-style text
+    ///
+    /// Each match is formatted as `path:line:content`; with context, context
+    /// lines use `path-line-content` and `--` separates groups. Formatting
+    /// in Rust avoids building a Python object per result.
+    ///
+    /// Args:
+    ///     query: Search pattern (regex supported)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of matches
+    ///     context_lines: Lines of context around each match
+    ///
+    /// Returns:
+    ///     Formatted results, one line per output line, sorted by path
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, context_lines = 0))]
+    pub fn grep_text(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        context_lines: usize,
+    ) -> PyResult<String> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results,
+            context_lines,
+            order_by: Some(SearchOrder::FilePath),
+            ..Default::default()
+        };
+
+        let matcher = Matcher::single(query, case_sensitive)?;
+        let summary = self.run_search(py, &matcher, glob_pattern, &options)?;
+        Ok(py.allow_threads(|| searcher::format_results(&summary.results, context_lines)))
+    }
+
+    /// Search files and return the results as parallel lists
+    ///
+    /// For large result sets: three lists cross into Python far more
+    /// cheaply than one SearchResult object per match. Entry i of each list
+    /// describes the same match, so zip them to iterate. Context lines,
+    /// symbols and metadata are not included.
+    ///
+    /// Args:
+    ///     query: Search pattern (regex)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of results
+    ///     options: A fully configured SearchOptions; when given, every other
+    ///         argument except query and glob_pattern is ignored
+    ///
+    /// Returns:
+    ///     Tuple of (files, line_numbers, contents)
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, options = None))]
+    pub fn grep_columnar(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        options: Option<SearchOptions>,
+    ) -> PyResult<searcher::Columns> {
+        let options = options.unwrap_or(SearchOptions {
+            case_sensitive,
+            max_results,
+            ..SearchOptions::default()
+        });
+
+        let matcher = Matcher::single(query, options.case_sensitive)?;
+        let summary = self.run_search(py, &matcher, glob_pattern, &options)?;
+        Ok(searcher::to_columns(summary.results))
+    }
+
+    /// Search files and return each match as a source range
+    ///
+    /// One Location per regex match (a line matching twice yields two), in
+    /// path order, ready for editor quickfix lists and diagnostics.
+    ///
+    /// Args:
+    ///     query: Search pattern (regex)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of matching lines; the number of
+    ///         locations can be higher
+    ///
+    /// Returns:
+    ///     List of Location objects with 1-indexed lines and character
+    ///     columns; end_col is exclusive
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, max_results = 1000))]
+    pub fn grep_locations(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+    ) -> PyResult<Vec<Location>> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results,
+            order_by: Some(SearchOrder::FilePath),
+            ..Default::default()
+        };
+
+        let regex = searcher::build_regex(query, case_sensitive)?;
+        let matcher = Matcher::Single(regex.clone());
+        let summary = self.run_search(py, &matcher, glob_pattern, &options)?;
+        Ok(py.allow_threads(|| searcher::to_locations(&summary.results, &regex)))
+    }
+
+    /// Count matching lines per directory
+    ///
+    /// Every candidate file is searched (there is no result limit) and each
+    /// file's matching lines are added to its parent directory, giving a
+    /// quick map of where a concept lives without listing every hit.
+    ///
+    /// Args:
+    ///     query: Search pattern (regex supported)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     include_hidden: Search dotfiles and dot-directories
+    ///     include_ignored: Search files excluded by ignore rules
+    ///
+    /// Returns:
+    ///     Dict mapping directories relative to root ("." for root itself)
+    ///     to their number of matching lines; directories without matches
+    ///     are omitted
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false, include_hidden = true, include_ignored = false))]
+    pub fn grep_count_by_dir(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        include_hidden: bool,
+        include_ignored: bool,
+    ) -> PyResult<std::collections::HashMap<String, usize>> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results: usize::MAX,
+            first_per_file_with_count: true,
+            include_hidden,
+            include_ignored,
+            ..Default::default()
+        };
+
+        let matcher = Matcher::single(query, case_sensitive)?;
+        let summary = self.run_search(py, &matcher, glob_pattern, &options)?;
+        Ok(py.allow_threads(|| searcher::count_by_dir(&summary.results)))
+    }
+
+    /// Check whether any file contains a match
+    ///
+    /// Candidate files are searched in parallel and the first match flips
+    /// the shared cancel flag, which also stops files already in flight.
+    ///
+    /// Args:
+    ///     query: Search pattern (regex supported)
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///
+    /// Returns:
+    ///     True as soon as one match is found, False if no file matches
+    #[pyo3(signature = (query, glob_pattern = "**/*", case_sensitive = false))]
+    pub fn any_match(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        case_sensitive: bool,
+    ) -> PyResult<bool> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results: 1,
+            ..Default::default()
+        };
+
+        let matcher = Matcher::single(query, case_sensitive)?;
+        self.run_search(py, &matcher, glob_pattern, &options)
+            .map(|summary| !summary.results.is_empty())
+    }
+
+    /// Find files matching both a path glob and a content query
+    ///
+    /// The glob narrows candidates through the index, then each candidate is
+    /// searched only until its first matching line, so neither the full glob
+    /// listing nor per-line results are handed back and intersected.
+    ///
+    /// Args:
+    ///     path_glob: Glob pattern for candidate files, e.g. "src/**/*.py"
+    ///     content_query: Search pattern (regex) a file must contain
+    ///     options: SearchOptions for the content search; max_results caps
+    ///         the number of files returned (default: 1000)
+    ///
+    /// Returns:
+    ///     Sorted, deduplicated file paths relative to root
+    ///
+    /// Raises:
+    ///     ValueError: If the query, glob pattern or options are invalid
+    #[pyo3(signature = (path_glob, content_query, options = None))]
+    pub fn find(
+        &self,
+        py: Python<'_>,
+        path_glob: &str,
+        content_query: &str,
+        options: Option<SearchOptions>,
+    ) -> PyResult<Vec<String>> {
+        let options = options.unwrap_or_default();
+        Self::check_search_options(&options)?;
+
+        let matcher = Matcher::single(content_query, options.case_sensitive)?;
+        let files = self.indexed_files(path_glob, &options);
+        py.allow_threads(|| {
+            self.searcher
+                .matching_files(&matcher, path_glob, files, &options)
+        })
+        .map_err(|e| e.into())
+    }
+
+    /// Search files for several independent patterns in one pass
+    ///
+    /// Each result's `pattern_index` identifies which entry of `patterns`
+    /// matched. A line matching several patterns yields one result per
+    /// pattern. Files matching none of the patterns are rejected with a
+    /// single combined check before any line is examined.
+    ///
+    /// Args:
+    ///     patterns: Regex patterns to search for
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum total number of results
+    ///     include_hidden: Search dotfiles and dot-directories
+    ///     include_ignored: Search files excluded by ignore rules
+    ///     max_files_scanned: Stop after searching this many files (None = no limit)
+    ///     order_by: SearchOrder applied to the results (None = unspecified order)
+    ///     binary: BinaryMode for binary and non-UTF8 files (default: SKIP)
+    ///
+    /// Returns:
+    ///     List of SearchResult objects
+    #[pyo3(signature = (patterns, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, include_hidden = true, include_ignored = false, max_files_scanned = None, order_by = None, binary = BinaryMode::Skip))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn grep_multi(
+        &self,
+        py: Python<'_>,
+        patterns: Vec<String>,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        include_hidden: bool,
+        include_ignored: bool,
+        max_files_scanned: Option<usize>,
+        order_by: Option<SearchOrder>,
+        binary: BinaryMode,
+    ) -> PyResult<Vec<SearchResult>> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results,
+            max_file_size: 10 * 1024 * 1024, // 10MB
+            context_lines: 0,
+            first_per_file_with_count: false,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            normalize_whitespace: false,
+            head_bytes: None,
+            tail_bytes: None,
+            symbol_context: false,
+            per_file_timeout_ms: None,
+            include_metadata: false,
+            encoding: None,
+            capture_group: None,
+            max_matches_per_dir: None,
+            languages: None,
+        };
+
+        let matcher = Matcher::multi(&patterns, case_sensitive)?;
+        self.run_search(py, &matcher, glob_pattern, &options)
+            .map(|summary| summary.results)
+    }
+
+    /// Search for lines containing every one of several keywords
+    ///
+    /// Keywords are plain substrings, not regexes, so `grep_all_keywords(
+    /// ["error", "timeout"])` finds lines mentioning both, in any order.
+    /// Files lacking any keyword are rejected before lines are examined.
+    ///
+    /// Args:
+    ///     keywords: Substrings that must all appear on a line
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of results
+    ///     include_hidden: Search dotfiles and dot-directories
+    ///     include_ignored: Search files excluded by ignore rules
+    ///     max_files_scanned: Stop after searching this many files (None = no limit)
+    ///     order_by: SearchOrder applied to the results (None = unspecified order)
+    ///     binary: BinaryMode for binary and non-UTF8 files (default: SKIP)
+    ///
+    /// Returns:
+    ///     List of SearchResult objects
+    ///
+    /// Raises:
+    ///     ValueError: If no keywords are given
+    #[pyo3(signature = (keywords, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, include_hidden = true, include_ignored = false, max_files_scanned = None, order_by = None, binary = BinaryMode::Skip))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn grep_all_keywords(
+        &self,
+        py: Python<'_>,
+        keywords: Vec<String>,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        include_hidden: bool,
+        include_ignored: bool,
+        max_files_scanned: Option<usize>,
+        order_by: Option<SearchOrder>,
+        binary: BinaryMode,
+    ) -> PyResult<Vec<SearchResult>> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            ..Default::default()
+        };
+
+        let matcher = Matcher::keywords(&keywords, true, case_sensitive)?;
+        self.run_search(py, &matcher, glob_pattern, &options)
+            .map(|summary| summary.results)
+    }
+
+    /// Search for lines containing at least one of several keywords
+    ///
+    /// The OR counterpart of `grep_all_keywords`: keywords are plain
+    /// substrings and a line matching several is reported once.
+    ///
+    /// Args:
+    ///     keywords: Substrings of which at least one must appear on a line
+    ///     glob_pattern: File pattern to search in
+    ///     case_sensitive: Case sensitive search
+    ///     max_results: Maximum number of results
+    ///     include_hidden: Search dotfiles and dot-directories
+    ///     include_ignored: Search files excluded by ignore rules
+    ///     max_files_scanned: Stop after searching this many files (None = no limit)
+    ///     order_by: SearchOrder applied to the results (None = unspecified order)
+    ///     binary: BinaryMode for binary and non-UTF8 files (default: SKIP)
+    ///
+    /// Returns:
+    ///     List of SearchResult objects
+    ///
+    /// Raises:
+    ///     ValueError: If no keywords are given
+    #[pyo3(signature = (keywords, glob_pattern = "**/*", case_sensitive = false, max_results = 1000, include_hidden = true, include_ignored = false, max_files_scanned = None, order_by = None, binary = BinaryMode::Skip))]
+    #[allow(clippy::too_many_arguments)]
+    pub fn grep_any_keyword(
+        &self,
+        py: Python<'_>,
+        keywords: Vec<String>,
+        glob_pattern: &str,
+        case_sensitive: bool,
+        max_results: usize,
+        include_hidden: bool,
+        include_ignored: bool,
+        max_files_scanned: Option<usize>,
+        order_by: Option<SearchOrder>,
+        binary: BinaryMode,
+    ) -> PyResult<Vec<SearchResult>> {
+        let options = SearchOptions {
+            case_sensitive,
+            max_results,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            ..Default::default()
+        };
+
+        let matcher = Matcher::keywords(&keywords, false, case_sensitive)?;
+        self.run_search(py, &matcher, glob_pattern, &options)
+            .map(|summary| summary.results)
+    }
+
+    /// Get file metadata
+    ///
+    /// Args:
+    ///     path: File path
+    ///
+    /// Returns:
+    ///     FileMetadata object
+    pub fn get_metadata(&self, path: &str) -> PyResult<index::FileMetadata> {
+        let full_path = self.resolve_path(path);
+        self.index
+            .get_metadata(&full_path)
+            .ok_or_else(|| AgentGearError::PathNotFound(path.to_string()).into())
+    }
+
+    /// Force refresh the file index
+    ///
+    /// The tree is re-walked into a separate index that replaces the current
+    /// one when complete, so queries keep answering from the previous
+    /// entries meanwhile. Changes the watcher reports during the walk are
+    /// applied after the swap.
+    ///
+    /// Returns:
+    ///     True if the new index was swapped in, False if the refresh was
+    ///     cancelled or another build was already running
+    pub fn refresh(&self, py: Python<'_>) -> PyResult<bool> {
+        py.allow_threads(|| self.index.refresh())
+            .map_err(|e| e.into())
+    }
+
+    /// Cancel a refresh in progress
+    ///
+    /// The walk stops and its partial results are dropped; the index keeps
+    /// the entries it had before the refresh started. The initial build
+    /// cannot be cancelled, since the index would be left not ready.
+    ///
+    /// Returns:
+    ///     True if a refresh was running, False if none was or the initial
+    ///     build is still in progress
+    pub fn cancel_refresh(&self) -> bool {
+        self.index.cancel_build()
+    }
+
+    /// Refresh specific paths in the index without a rebuild
+    ///
+    /// The targeted counterpart to refresh() for files an agent or external
+    /// tool just changed: each path is added, updated or removed from the
+    /// index depending on whether it exists, so the change is visible
+    /// immediately instead of after the watcher catches up. Paths are
+    /// handled in parallel; cached reads of them are dropped.
+    ///
+    /// Args:
+    ///     paths: File or directory paths, relative to root or absolute
+    ///
+    /// Returns:
+    ///     Number of the given paths present in the index afterwards
+    pub fn refresh_paths(&self, py: Python<'_>, paths: Vec<String>) -> PyResult<usize> {
+        let full_paths: Vec<PathBuf> = paths.iter().map(|p| self.resolve_path(p)).collect();
+        if let Some(ref cache) = self.read_cache {
+            for path in &full_paths {
+                cache.invalidate(path);
+            }
+        }
+        if let Some(ref cache) = self.line_offsets {
+            for path in &full_paths {
+                cache.invalidate(path);
+            }
+        }
+        py.allow_threads(|| self.index.refresh_paths(&full_paths))
+            .map_err(|e| e.into())
+    }
+
+    /// Check if the index is ready
+    pub fn is_ready(&self) -> bool {
+        self.index.is_ready()
+    }
+
+    /// Confirm the extension is loaded and this object is alive
+    ///
+    /// Does no I/O and takes no locks, so health checks measure liveness
+    /// rather than index state; see is_ready() for that.
+    ///
+    /// Returns:
+    ///     "agent-gear <version> <root>"
+    pub fn ping(&self) -> String {
+        format!(
+            "{} {} {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            self.root.display()
+        )
+    }
+
+    /// Check whether max_index_entries left paths out of the index
+    ///
+    /// When true, list/glob/grep see only part of the tree; read and stat
+    /// paths directly instead of relying on the index to find them.
+    pub fn is_truncated(&self) -> bool {
+        self.index.is_truncated()
+    }
+
+    /// Fingerprint the indexed tree for cache keys
+    ///
+    /// A BLAKE3 hash over every indexed file's path, size and mtime in
+    /// path order, so two processes indexing the same unchanged tree get
+    /// the same value and adding, removing or modifying a file changes it.
+    /// File contents are not read: an edit that keeps both size and mtime
+    /// goes unnoticed, and directories do not contribute.
+    ///
+    /// Returns:
+    ///     64 hex digits
+    ///
+    /// Raises:
+    ///     RuntimeError: If the index is not ready yet
+    pub fn index_fingerprint(&self, py: Python<'_>) -> PyResult<String> {
+        py.allow_threads(|| self.index.fingerprint())
+            .map_err(|e| e.into())
+    }
+
+    /// Estimate the memory used by the file index, in bytes
+    ///
+    /// Sums path lengths plus the inline size of each entry and a fixed
+    /// per-slot map overhead across the entries, the file list, the
+    /// directory children and the pattern caches. Allocator padding and
+    /// compiled matcher internals are not counted, so treat it as a lower
+    /// bound for capacity planning rather than an exact figure.
+    pub fn memory_usage(&self) -> usize {
+        self.index.memory_usage()
+    }
+
+    /// Check whether paths are matched case-insensitively
+    pub fn is_case_insensitive(&self) -> bool {
+        self.index.is_case_insensitive()
+    }
+
+    /// Check a path against the writable_extensions policy, without I/O
+    ///
+    /// Every write method runs this check itself; call it directly to vet a
+    /// path up front. Always passes when writable_extensions is not set.
+    ///
+    /// Raises:
+    ///     PermissionError: If the policy does not allow writing to the path
+    pub fn check_writable(&self, path: &str) -> PyResult<()> {
+        match self.write_policy {
+            Some(ref policy) if !policy.allows(std::path::Path::new(path)) => {
+                let reason = if std::path::Path::new(path).extension().is_some() {
+                    "extension not in writable_extensions"
+                } else {
+                    "no extension and allow_extensionless_writes is false"
+                };
+                Err(AgentGearError::WriteNotAllowed(format!("{} ({})", path, reason)).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// List the glob patterns currently held in the compiled-pattern cache
+    ///
+    /// Useful for diagnosing cache thrashing: a hot pattern that keeps
+    /// disappearing means the cache is too small for the workload.
+    ///
+    /// Returns:
+    ///     Sorted list of cached pattern strings
+    pub fn cached_globs(&self) -> Vec<String> {
+        self.index.cached_globs()
+    }
+
+    /// Empty the compiled glob cache to reclaim memory
+    pub fn clear_glob_cache(&self) {
+        self.index.clear_glob_cache()
+    }
+
+    /// Save the file index to a snapshot file
+    ///
+    /// Args:
+    ///     path: Snapshot file path
+    ///     compression_level: zstd compression level (1-22), or None to
+    ///         store the snapshot uncompressed
+    #[pyo3(signature = (path, compression_level = None))]
+    pub fn save_index(
+        &self,
+        py: Python<'_>,
+        path: &str,
+        compression_level: Option<i32>,
+    ) -> PyResult<()> {
+        let full_path = self.resolve_path(path);
+        py.allow_threads(|| {
+            let snapshot = self.index.snapshot()?;
+            snapshot::write_snapshot(&full_path, &snapshot, compression_level)
+        })
+        .map_err(|e| e.into())
+    }
+
+    /// Replace the file index with a saved snapshot
+    ///
+    /// Compressed and uncompressed snapshots are detected automatically.
+    ///
+    /// Args:
+    ///     path: Snapshot file path
+    pub fn load_index(&self, py: Python<'_>, path: &str) -> PyResult<()> {
+        let full_path = self.resolve_path(path);
+        py.allow_threads(|| {
+            let snapshot = snapshot::read_snapshot(&full_path)?;
+            self.index.restore(snapshot)
+        })
+        .map_err(|e| e.into())
+    }
+
+    /// Close the filesystem and release resources
+    pub fn close(&self) {
+        // Signal the watcher and refresh threads to stop
+        self.stop_flag.store(true, Ordering::SeqCst);
+
+        // Stop the watcher
+        if let Some(ref watcher) = self.watcher {
+            watcher.stop();
+        }
+
+        // Release subtree watches whose handles are still alive
+        for slot in self.sub_watchers.lock().drain(..) {
+            if let Some(slot) = slot.upgrade() {
+                slot.lock().take();
+            }
+        }
+    }
+
+    /// Watch a subtree separately, with its own debounce and event queue
+    ///
+    /// Starts an extra watcher on `subpath`, independent of the main one,
+    /// so a hot directory can be watched with a short debounce while the
+    /// rest of the tree keeps the default. Uses the same backend as the
+    /// main watcher (see `watch_mode`). Events only go to the handle; the
+    /// index is still updated by the main watcher.
+    ///
+    /// Args:
+    ///     subpath: Directory to watch (relative to root or absolute)
+    ///     debounce_ms: How long a path must be quiet before its change is
+    ///         reported (default 100, 0 = raw mode)
+    ///
+    /// Returns:
+    ///     WatchHandle; poll `events()` on it. The watch stops when the
+    ///     handle is stopped or garbage collected, or on `close()`
+    ///
+    /// Raises:
+    ///     ValueError: If subpath is not a directory or the FileSystem is
+    ///         closed
+    #[pyo3(signature = (subpath, debounce_ms = 100))]
+    pub fn watch_subpath(&self, subpath: &str, debounce_ms: u64) -> PyResult<WatchHandle> {
+        if self.stop_flag.load(Ordering::SeqCst) {
+            return Err(PyValueError::new_err("FileSystem is closed"));
+        }
+        let full_path = self.resolve_path(subpath);
+        if !full_path.is_dir() {
+            return Err(PyValueError::new_err(format!(
+                "Not a directory: {}",
+                subpath
+            )));
+        }
+
+        let (mode, poll_interval) = self.watch_config;
+        let mode = self.watch_mode().unwrap_or(mode);
+        let watcher = FileWatcher::with_mode(
+            full_path,
+            Duration::from_millis(debounce_ms),
+            true,
+            mode,
+            poll_interval,
+        )?;
+        let handle = WatchHandle::new(watcher, self.root.clone());
+
+        let mut sub_watchers = self.sub_watchers.lock();
+        sub_watchers.retain(|slot| slot.strong_count() > 0);
+        sub_watchers.push(handle.slot());
+        Ok(handle)
+    }
+
+    /// Check if file watching is active
+    pub fn is_watching(&self) -> bool {
+        !self.stop_flag.load(Ordering::SeqCst) && self.watcher_healthy()
+    }
+
+    /// Check whether the watcher is still delivering events
+    ///
+    /// Returns False if there is no watcher, or if its backend died (for
+    /// example because the root directory was deleted or unmounted). The
+    /// index is then frozen; recreate the FileSystem to resume watching.
+    /// A native backend that starts but never receives events, as on some
+    /// network and bind mounts, still reports healthy; see `watch_mode`.
+    pub fn watcher_healthy(&self) -> bool {
+        self.watcher.as_ref().is_some_and(|w| w.is_healthy())
+    }
+
+    /// The watcher backend in use
+    ///
+    /// NATIVE or POLL, so a FileSystem created with AUTO reports whether it
+    /// fell back to polling. None if nothing is being watched.
+    pub fn watch_mode(&self) -> Option<WatchMode> {
+        self.watcher.as_ref().map(|w| w.mode())
+    }
+
+    /// List paths with changes the index has not applied yet
+    ///
+    /// These are events waiting out the debounce window; their metadata in
+    /// the index may be stale. Peeking does not flush them, so the index
+    /// settles on its normal schedule.
+    ///
+    /// Returns:
+    ///     Sorted list of paths relative to root (absolute if outside root);
+    ///     empty when there is no watcher. A rename lists both paths.
+    pub fn pending_paths(&self) -> Vec<String> {
+        let Some(ref watcher) = self.watcher else {
+            return Vec::new();
+        };
+        watcher
+            .pending_paths()
+            .iter()
+            .map(|path| to_posix_string(path.strip_prefix(&self.root).unwrap_or(path)))
+            .collect()
+    }
+
+    /// Get the number of applied changes waiting in the change log
+    ///
+    /// Counts changes the watcher has already applied to the index that
+    /// `poll_changes` has not taken yet. Counting does not consume them,
+    /// and changes still in the debounce window are not included (see
+    /// `pending_paths`).
+    pub fn pending_changes(&self) -> usize {
+        self.change_log.len()
+    }
+
+    /// Take the changes the watcher has applied to the index
+    ///
+    /// The watcher loop is the only consumer of watcher events; it updates
+    /// the index first and then logs each change here, so every returned
+    /// change is already reflected in the index. The log keeps the most
+    /// recent 10000 changes.
+    ///
+    /// Returns:
+    ///     List of WatchEvent objects, oldest first, with paths relative
+    ///     to root; empty when there is no watcher
+    pub fn poll_changes(&self) -> Vec<WatchEvent> {
+        self.change_log
+            .take()
+            .iter()
+            .map(|change| WatchEvent::from_change(change, &self.root))
+            .collect()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
     ) -> bool {
         self.close();
         false
@@ -378,6 +2522,90 @@ impl FileSystem {
 }
 
 impl FileSystem {
+    /// Run a search, using the index for candidate files when possible
+    fn run_search(
+        &self,
+        py: Python<'_>,
+        matcher: &Matcher,
+        glob_pattern: &str,
+        options: &SearchOptions,
+    ) -> PyResult<SearchSummary> {
+        Self::check_search_options(options)?;
+        if let Some(group) = options.capture_group {
+            matcher.check_capture_group(group)?;
+        }
+
+        let files = self.indexed_files(glob_pattern, options);
+        self.searcher
+            .search_matcher(py, matcher, glob_pattern, files, options)
+    }
+
+    /// Read a line range, seeking with cached line offsets when enabled
+    fn read_line_range(
+        &self,
+        py: Python<'_>,
+        full_path: &std::path::Path,
+        start_line: usize,
+        count: Option<usize>,
+        max_bytes: Option<usize>,
+    ) -> PyResult<Vec<String>> {
+        match self.line_offsets {
+            Some(ref cache) => py
+                .allow_threads(|| cache.read_lines(full_path, start_line, count, max_bytes))
+                .map_err(|e| e.into()),
+            None => io::read_lines(py, full_path, start_line, count, max_bytes),
+        }
+    }
+
+    /// Reject option combinations a search cannot honour
+    fn check_search_options(options: &SearchOptions) -> PyResult<()> {
+        if options.head_bytes.is_some() && options.tail_bytes.is_some() {
+            return Err(PyValueError::new_err(
+                "head_bytes and tail_bytes are mutually exclusive",
+            ));
+        }
+        if let Some(label) = &options.encoding {
+            encoding::lookup(label)?;
+        }
+        Ok(())
+    }
+
+    /// Candidate files for a search, taken from the index when it can serve it
+    ///
+    /// Returns None when the search has to walk the directory instead.
+    fn indexed_files(&self, glob_pattern: &str, options: &SearchOptions) -> Option<Vec<PathBuf>> {
+        // The index includes hidden files and respects gitignore, so any
+        // deviation from that scope has to walk the directory directly
+        let index_scope_matches = options.include_hidden && !options.include_ignored;
+
+        // Use index if ready, otherwise fall back to directory scan
+        if !index_scope_matches || !self.index.is_ready() {
+            return None;
+        }
+        let skip_binary = options.binary == BinaryMode::Skip;
+        self.index
+            .glob_paths_with_options(glob_pattern, skip_binary)
+            .ok()
+    }
+
+    /// Resolve a path for writing, refusing it if the write policy does not
+    /// allow its extension
+    fn writable_path(&self, path: &str) -> PyResult<PathBuf> {
+        self.check_writable(path)?;
+        Ok(self.resolve_path(path))
+    }
+
+    /// Read limit of one call: its override, or the instance limit if none
+    ///
+    /// An override of 0 lifts the limit for the call.
+    fn read_limit(&self, max_read_bytes: Option<u64>) -> Option<u64> {
+        match max_read_bytes {
+            Some(0) => None,
+            Some(limit) => Some(limit),
+            None => self.max_read_bytes,
+        }
+    }
+
     /// Resolve a path relative to the root directory
     fn resolve_path(&self, path: &str) -> PathBuf {
         let path = PathBuf::from(path);
@@ -389,7 +2617,20 @@ impl FileSystem {
     }
 
     /// Background watcher loop that processes file changes and updates the index
-    fn watcher_loop(watcher: Arc<FileWatcher>, index: Arc<FileIndex>, stop_flag: Arc<AtomicBool>) {
+    fn watcher_loop(
+        watcher: Arc<FileWatcher>,
+        index: Arc<FileIndex>,
+        read_cache: Option<Arc<io::ReadCache>>,
+        change_log: Arc<watcher::ChangeLog>,
+        stop_flag: Arc<AtomicBool>,
+    ) {
+        // Raw mode is chosen for latency, so poll far more often
+        let poll_interval = if watcher.is_raw() {
+            Duration::from_millis(5)
+        } else {
+            Duration::from_millis(50)
+        };
+
         loop {
             // Check if we should stop
             if stop_flag.load(Ordering::SeqCst) {
@@ -399,15 +2640,19 @@ impl FileSystem {
             // Process pending events
             let events = watcher.process_events();
 
-            for event in events {
-                match event.kind {
+            for event in &events {
+                if let Some(ref cache) = read_cache {
+                    cache.invalidate(&event.path);
+                }
+
+                match &event.kind {
                     ChangeKind::Created => {
                         // Add to index
                         if let Err(e) = index.add_path(&event.path) {
                             tracing::warn!("Failed to add path to index: {}", e);
                         }
                     }
-                    ChangeKind::Modified => {
+                    ChangeKind::Modified | ChangeKind::AttrChanged => {
                         // Update metadata in index
                         if let Err(e) = index.update_path(&event.path) {
                             tracing::warn!("Failed to update path in index: {}", e);
@@ -418,17 +2663,45 @@ impl FileSystem {
                         index.remove_path(&event.path);
                     }
                     ChangeKind::Renamed { from, to } => {
+                        // The rename may have replaced a cached target
+                        if let Some(ref cache) = read_cache {
+                            cache.invalidate(to);
+                        }
+
                         // Remove old path and add new path
-                        index.remove_path(&from);
-                        if let Err(e) = index.add_path(&to) {
+                        index.remove_path(from);
+                        if let Err(e) = index.add_path(to) {
                             tracing::warn!("Failed to add renamed path to index: {}", e);
                         }
                     }
                 }
             }
+            change_log.record(events);
 
             // Sleep briefly to avoid busy waiting
-            std::thread::sleep(Duration::from_millis(50));
+            std::thread::sleep(poll_interval);
+        }
+    }
+
+    /// Background loop that periodically reconciles the index with the disk
+    fn refresh_loop(index: Arc<FileIndex>, stop_flag: Arc<AtomicBool>, interval: Duration) {
+        // Sleep in short ticks so close() is not held up by a long interval
+        let tick = Duration::from_millis(100).min(interval);
+        let mut elapsed = Duration::ZERO;
+
+        while !stop_flag.load(Ordering::SeqCst) {
+            std::thread::sleep(tick);
+            elapsed += tick;
+            if elapsed < interval {
+                continue;
+            }
+            elapsed = Duration::ZERO;
+
+            match index.reconcile() {
+                Ok(0) => {}
+                Ok(n) => tracing::debug!("Auto-refresh corrected {} index entries", n),
+                Err(e) => tracing::warn!("Auto-refresh failed: {}", e),
+            }
         }
     }
 }
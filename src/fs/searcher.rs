@@ -2,16 +2,94 @@
 //!
 //! Provides grep-like search functionality using the ripgrep core libraries.
 
+use crossbeam::channel::{bounded, Receiver, RecvTimeoutError, SendTimeoutError, Sender};
+use dashmap::DashMap;
+use encoding_rs::{Encoding, UTF_8};
 use globset::{Glob, GlobMatcher};
 use memmap2::Mmap;
+use once_cell::sync::Lazy;
 use pyo3::prelude::*;
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::{BinaryHeap, HashMap};
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use super::encoding;
+use super::index::{FileIndex, FileMetadata};
+use super::language;
+use super::symbols;
 use crate::utils::error::{AgentGearError, Result};
+use crate::utils::limit;
+use crate::utils::path::{to_posix_string, DirIgnore};
+
+/// Ordering applied to search results after collection
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchOrder {
+    /// Lexicographic by file path, then line number
+    #[pyo3(name = "FILE_PATH")]
+    FilePath,
+    /// By line number, then file path
+    #[pyo3(name = "LINE_NUMBER")]
+    LineNumber,
+    /// Most useful first: files whose path matches the query, then smaller files
+    #[pyo3(name = "RELEVANCE")]
+    Relevance,
+}
+
+/// How binary and non-UTF8 files are handled by search
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryMode {
+    /// Skip binary and non-UTF8 files
+    #[pyo3(name = "SKIP")]
+    Skip,
+    /// Decode lossily (invalid bytes become U+FFFD) and search as text
+    #[pyo3(name = "TEXT")]
+    Text,
+    /// Search printable ASCII runs, like the `strings` tool; each run is
+    /// reported as its own line, numbered by run rather than by file line
+    #[pyo3(name = "STRINGS")]
+    Strings,
+}
+
+/// Minimum length of a printable run extracted in `BinaryMode::Strings`
+const MIN_STRING_LEN: usize = 4;
+
+/// Extract printable ASCII runs, one per line
+fn extract_strings(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for run in bytes.split(|b| !(b.is_ascii_graphic() || *b == b' ' || *b == b'\t')) {
+        if run.len() >= MIN_STRING_LEN {
+            // Printable ASCII is always valid UTF-8
+            out.push_str(std::str::from_utf8(run).unwrap_or_default());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Collapse every run of whitespace to a single space
+fn collapse_whitespace(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut in_space = false;
+    for c in line.chars() {
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
 
 /// Search options
 #[pyclass]
@@ -32,23 +110,145 @@ pub struct SearchOptions {
     /// Number of context lines before/after match
     #[pyo3(get, set)]
     pub context_lines: usize,
+
+    /// Report only the first match per file, annotated with the file's total match count
+    #[pyo3(get, set)]
+    pub first_per_file_with_count: bool,
+
+    /// Include hidden files (dotfiles) in the search
+    #[pyo3(get, set)]
+    pub include_hidden: bool,
+
+    /// Include files excluded by .gitignore/.ignore rules
+    #[pyo3(get, set)]
+    pub include_ignored: bool,
+
+    /// Stop after searching this many files (None = no limit)
+    #[pyo3(get, set)]
+    pub max_files_scanned: Option<usize>,
+
+    /// Result ordering (None = unspecified, fastest)
+    #[pyo3(get, set)]
+    pub order_by: Option<SearchOrder>,
+
+    /// Handling of binary and non-UTF8 files
+    #[pyo3(get, set)]
+    pub binary: BinaryMode,
+
+    /// Collapse runs of whitespace in each line to one space before matching
+    #[pyo3(get, set)]
+    pub normalize_whitespace: bool,
+
+    /// Search only the first N bytes of each file (None = whole file)
+    #[pyo3(get, set)]
+    pub head_bytes: Option<u64>,
+
+    /// Search only the last N bytes of each file (None = whole file)
+    #[pyo3(get, set)]
+    pub tail_bytes: Option<u64>,
+
+    /// Attach the enclosing function/class name to each result
+    #[pyo3(get, set)]
+    pub symbol_context: bool,
+
+    /// Give up reading a file after this many milliseconds and record it as
+    /// skipped (None = wait indefinitely)
+    ///
+    /// Timed reads run on a small pool of helper threads shared by every
+    /// search, and the wait for a free helper counts toward the timeout, so
+    /// while several files are stalled other files may time out too.
+    #[pyo3(get, set)]
+    pub per_file_timeout_ms: Option<u64>,
+
+    /// Attach the file's indexed metadata to each result
+    #[pyo3(get, set)]
+    pub include_metadata: bool,
+
+    /// Decode files from this encoding label before matching, e.g.
+    /// "shift_jis" or "latin1" (None = UTF-8)
+    ///
+    /// UTF-8 files are searched in place, straight from the read buffer or
+    /// mmap. Any other encoding converts each file to a new UTF-8 string
+    /// first, roughly one extra pass and copy of the file per search; a
+    /// file starting with a byte order mark is decoded by its mark instead.
+    #[pyo3(get, set)]
+    pub encoding: Option<String>,
+
+    /// Report this capture group of the query instead of the whole line,
+    /// skipping lines where the group did not participate (None = whole
+    /// line)
+    #[pyo3(get, set)]
+    pub capture_group: Option<usize>,
+
+    /// Stop reporting matches from a directory once it has this many, so
+    /// clusters such as generated code do not crowd out the rest of the
+    /// tree (None = no limit)
+    ///
+    /// Counts matches in the files directly inside each directory, not its
+    /// subdirectories. Which matches make the cut depends on the order
+    /// parallel workers reach them.
+    #[pyo3(get, set)]
+    pub max_matches_per_dir: Option<usize>,
+
+    /// Search only files whose detected language is one of these names,
+    /// compared case-insensitively, e.g. ["python", "rust"] (None = every
+    /// language)
+    ///
+    /// Uses the same name-based detection as `code_stats`, which needs no
+    /// setup and is always available; files of an unrecognised kind, such
+    /// as extensionless shebang scripts, never match.
+    #[pyo3(get, set)]
+    pub languages: Option<Vec<String>>,
 }
 
 #[pymethods]
 impl SearchOptions {
     #[new]
-    #[pyo3(signature = (case_sensitive = false, max_results = 1000, max_file_size = 10485760, context_lines = 0))]
+    #[pyo3(signature = (case_sensitive = false, max_results = 1000, max_file_size = 10485760, context_lines = 0, first_per_file_with_count = false, include_hidden = true, include_ignored = false, max_files_scanned = None, order_by = None, binary = BinaryMode::Skip, normalize_whitespace = false, head_bytes = None, tail_bytes = None, symbol_context = false, per_file_timeout_ms = None, include_metadata = false, encoding = None, capture_group = None, max_matches_per_dir = None, languages = None))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         case_sensitive: bool,
         max_results: usize,
         max_file_size: u64,
         context_lines: usize,
+        first_per_file_with_count: bool,
+        include_hidden: bool,
+        include_ignored: bool,
+        max_files_scanned: Option<usize>,
+        order_by: Option<SearchOrder>,
+        binary: BinaryMode,
+        normalize_whitespace: bool,
+        head_bytes: Option<u64>,
+        tail_bytes: Option<u64>,
+        symbol_context: bool,
+        per_file_timeout_ms: Option<u64>,
+        include_metadata: bool,
+        encoding: Option<String>,
+        capture_group: Option<usize>,
+        max_matches_per_dir: Option<usize>,
+        languages: Option<Vec<String>>,
     ) -> Self {
         Self {
             case_sensitive,
             max_results,
             max_file_size,
             context_lines,
+            first_per_file_with_count,
+            include_hidden,
+            include_ignored,
+            max_files_scanned,
+            order_by,
+            binary,
+            normalize_whitespace,
+            head_bytes,
+            tail_bytes,
+            symbol_context,
+            per_file_timeout_ms,
+            include_metadata,
+            encoding,
+            capture_group,
+            max_matches_per_dir,
+            languages,
         }
     }
 }
@@ -60,6 +260,22 @@ impl Default for SearchOptions {
             max_results: 1000,
             max_file_size: 10 * 1024 * 1024, // 10MB
             context_lines: 0,
+            first_per_file_with_count: false,
+            include_hidden: true,
+            include_ignored: false,
+            max_files_scanned: None,
+            order_by: None,
+            binary: BinaryMode::Skip,
+            normalize_whitespace: false,
+            head_bytes: None,
+            tail_bytes: None,
+            symbol_context: false,
+            per_file_timeout_ms: None,
+            include_metadata: false,
+            encoding: None,
+            capture_group: None,
+            max_matches_per_dir: None,
+            languages: None,
         }
     }
 }
@@ -76,7 +292,7 @@ pub struct SearchResult {
     #[pyo3(get)]
     pub line_number: u32,
 
-    /// The matching line content
+    /// The matching line content, or the captured text with capture_group
     #[pyo3(get)]
     pub content: String,
 
@@ -87,10 +303,63 @@ pub struct SearchResult {
     /// Context lines after the match
     #[pyo3(get)]
     pub context_after: Vec<String>,
+
+    /// Total matches in this file (only set in first-per-file mode)
+    #[pyo3(get)]
+    pub file_match_count: Option<usize>,
+
+    /// Index of the pattern that matched (only set by multi-pattern search)
+    #[pyo3(get)]
+    pub pattern_index: Option<usize>,
+
+    /// Name of the enclosing function, class or method (only set with
+    /// symbol context, and only for recognised languages)
+    #[pyo3(get)]
+    pub symbol: Option<String>,
+
+    /// Indexed metadata of the file (only set with include_metadata, and
+    /// only for files already in the index)
+    #[pyo3(get)]
+    pub metadata: Option<FileMetadata>,
+
+    /// Include glob the file was selected by (unset for in-memory search)
+    #[pyo3(get)]
+    pub matched_glob: Option<String>,
+
+    /// Columns of the captured group within the line, 1-indexed and
+    /// end-exclusive like `Location` (only set with capture_group)
+    #[pyo3(get)]
+    pub capture_span: Option<(u32, u32)>,
+}
+
+impl SearchResult {
+    /// Line number of the first `context_before` line
+    ///
+    /// Context lines are the ones directly around the match, so their
+    /// numbers follow from `line_number`.
+    fn context_start(&self) -> u32 {
+        self.line_number - self.context_before.len() as u32
+    }
 }
 
 #[pymethods]
 impl SearchResult {
+    /// Context lines before the match as (line_number, text) pairs
+    #[getter]
+    pub fn numbered_context_before(&self) -> Vec<(u32, String)> {
+        (self.context_start()..)
+            .zip(self.context_before.iter().cloned())
+            .collect()
+    }
+
+    /// Context lines after the match as (line_number, text) pairs
+    #[getter]
+    pub fn numbered_context_after(&self) -> Vec<(u32, String)> {
+        (self.line_number + 1..)
+            .zip(self.context_after.iter().cloned())
+            .collect()
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "SearchResult(file='{}', line={}, content='{}')",
@@ -105,396 +374,2834 @@ impl SearchResult {
     }
 }
 
-/// Search engine for file content
-pub struct Searcher {
-    root: PathBuf,
-}
+/// A match as a source range, for editor location lists
+///
+/// Lines and columns are 1-indexed; columns count characters and `end_col`
+/// is exclusive, so a match of `foo` at the start of a line spans columns
+/// 1 to 4.
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct Location {
+    /// File path relative to root
+    #[pyo3(get)]
+    pub file: String,
 
-impl Searcher {
-    /// Create a new searcher for the given root directory
-    pub fn new(root: PathBuf) -> Self {
-        Self { root }
-    }
+    /// Line where the match starts
+    #[pyo3(get)]
+    pub start_line: u32,
 
-    /// Search for a pattern in files matching the glob pattern
-    pub fn grep(
-        &self,
-        py: Python<'_>,
-        query: &str,
-        glob_pattern: &str,
-        options: &SearchOptions,
-    ) -> PyResult<Vec<SearchResult>> {
-        py.allow_threads(|| self.grep_internal(query, glob_pattern, options, None))
-            .map_err(|e| e.into())
-    }
+    /// Column where the match starts
+    #[pyo3(get)]
+    pub start_col: u32,
 
-    /// Search for a pattern using pre-collected files from index
-    pub fn grep_with_files(
-        &self,
-        py: Python<'_>,
-        query: &str,
-        files: Vec<PathBuf>,
-        options: &SearchOptions,
-    ) -> PyResult<Vec<SearchResult>> {
-        py.allow_threads(|| self.grep_internal(query, "**/*", options, Some(files)))
-            .map_err(|e| e.into())
-    }
+    /// Line where the match ends
+    #[pyo3(get)]
+    pub end_line: u32,
 
-    fn grep_internal(
-        &self,
-        query: &str,
-        glob_pattern: &str,
-        options: &SearchOptions,
-        pre_collected_files: Option<Vec<PathBuf>>,
-    ) -> Result<Vec<SearchResult>> {
-        // Build regex pattern
-        let regex = if options.case_sensitive {
-            regex::Regex::new(query)
-        } else {
-            regex::RegexBuilder::new(query)
-                .case_insensitive(true)
-                .build()
-        }
-        .map_err(|e| AgentGearError::Regex(e.to_string()))?;
+    /// Column just past the end of the match
+    #[pyo3(get)]
+    pub end_col: u32,
 
-        // Get files to search
-        let files = if let Some(files) = pre_collected_files {
-            files
-        } else {
-            let glob_matcher = Glob::new(glob_pattern)
-                .map(|g| g.compile_matcher())
-                .map_err(AgentGearError::Glob)?;
-            self.collect_files(&glob_matcher, options.max_file_size)?
-        };
+    /// The full line containing the match
+    #[pyo3(get)]
+    pub text: String,
+}
 
-        // Counter for limiting results
-        let result_count = Arc::new(AtomicUsize::new(0));
-        let max_results = options.max_results;
-        let cancelled = Arc::new(AtomicBool::new(false));
+#[pymethods]
+impl Location {
+    fn __repr__(&self) -> String {
+        format!(
+            "Location(file='{}', start={}:{}, end={}:{})",
+            self.file, self.start_line, self.start_col, self.end_line, self.end_col
+        )
+    }
+}
 
-        // Search files in parallel
-        let results: Vec<SearchResult> = files
-            .par_iter()
-            .flat_map(|path| {
-                if cancelled.load(Ordering::Relaxed) {
-                    return Vec::new();
-                }
+/// Search results together with statistics about the search itself
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct SearchSummary {
+    /// Matches found
+    #[pyo3(get)]
+    pub results: Vec<SearchResult>,
 
-                // Check if we've hit the limit
-                if result_count.load(Ordering::Relaxed) >= max_results {
-                    return Vec::new();
-                }
+    /// Number of files that were actually searched
+    #[pyo3(get)]
+    pub files_scanned: usize,
 
-                self.search_file(
-                    path,
-                    &regex,
-                    options,
-                    &result_count,
-                    &cancelled,
-                    max_results,
-                )
-                .unwrap_or_default()
-            })
-            .collect();
+    /// Whether the search stopped early (result or file limit reached),
+    /// so further matches may exist
+    #[pyo3(get)]
+    pub truncated: bool,
 
-        // Truncate to max_results (parallel collection may slightly exceed)
-        let results: Vec<SearchResult> = results.into_iter().take(max_results).collect();
+    /// Files that could not be searched, as (path, reason) pairs; a read
+    /// error or timeout skips the file instead of failing the search
+    #[pyo3(get)]
+    pub skipped: Vec<(String, String)>,
+}
 
-        Ok(results)
+#[pymethods]
+impl SearchSummary {
+    fn __repr__(&self) -> String {
+        format!(
+            "SearchSummary(results={}, files_scanned={}, truncated={}, skipped={})",
+            self.results.len(),
+            self.files_scanned,
+            self.truncated,
+            self.skipped.len()
+        )
     }
+}
 
-    /// Collect files matching the glob pattern
-    fn collect_files(&self, glob_matcher: &GlobMatcher, max_size: u64) -> Result<Vec<PathBuf>> {
-        use ignore::WalkState;
-        use std::sync::Mutex;
+/// Number of results buffered between a streaming search and its consumer
+const STREAM_CAPACITY: usize = 256;
+
+/// How long `SearchIter.__next__` waits before checking for signals, and
+/// a streaming worker blocked on a full buffer before checking for cancel
+const STREAM_SIGNAL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Upper bound on the threads of each streaming search's pool
+const STREAM_THREADS: usize = 4;
+
+/// Threads serving reads under `per_file_timeout_ms`
+///
+/// These reads take no `limit::acquire` permit; the pool size is their own
+/// budget of open files. A read abandoned on a stalled mount ties up one of
+/// these threads until it returns, never a slot other I/O waits for.
+const TIMED_READ_THREADS: usize = 8;
+
+/// Outcome of `Searcher::load`
+type Loaded = std::io::Result<Option<(String, usize)>>;
+
+/// A read queued on the timed read pool
+struct TimedRead {
+    path: PathBuf,
+    options: SearchOptions,
+    /// Set once the caller stops waiting, so a read still queued is dropped
+    abandoned: Arc<AtomicBool>,
+    reply: Sender<Loaded>,
+}
 
-        let files = Mutex::new(Vec::new());
+/// Queue of the timed read pool, whose threads start on first use
+static TIMED_READS: Lazy<Sender<TimedRead>> = Lazy::new(|| {
+    let (sender, receiver) = bounded::<TimedRead>(TIMED_READ_THREADS);
+    for i in 0..TIMED_READ_THREADS {
+        let receiver = receiver.clone();
+        // Without any threads, every timed read reports a timeout
+        let _ = std::thread::Builder::new()
+            .name(format!("agent-gear-timed-read-{i}"))
+            .spawn(move || {
+                for job in receiver {
+                    if !job.abandoned.load(Ordering::Relaxed) {
+                        let _ = job.reply.send(Searcher::load(&job.path, &job.options));
+                    }
+                }
+            });
+    }
+    sender
+});
+
+/// Iterator over the results of a search still running in the background
+///
+/// Workers pause once the buffer is full, so a consumer that stops early
+/// holds only a bounded number of results. Dropping the iterator, or
+/// calling `cancel()`, stops the search.
+#[pyclass]
+pub struct SearchIter {
+    receiver: Receiver<SearchResult>,
+    cancelled: Arc<AtomicBool>,
+}
 
-        let walker = ignore::WalkBuilder::new(&self.root)
-            .hidden(false)
-            .git_ignore(true)
-            .build_parallel();
+#[pymethods]
+impl SearchIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
 
-        walker.run(|| {
-            Box::new(|entry| {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(_) => return WalkState::Continue,
-                };
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<SearchResult>> {
+        loop {
+            // Wait without the GIL, then take it back to deliver the result
+            // or to let Ctrl-C interrupt a long wait
+            match py.allow_threads(|| self.receiver.recv_timeout(STREAM_SIGNAL_INTERVAL)) {
+                Ok(result) => return Ok(Some(result)),
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+                Err(RecvTimeoutError::Timeout) => py.check_signals()?,
+            }
+        }
+    }
 
-                let path = entry.path();
+    /// Stop the search; results already buffered are still yielded
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
 
-                // Skip directories
-                if path.is_dir() {
-                    return WalkState::Continue;
-                }
+    fn __repr__(&self) -> String {
+        format!("SearchIter(buffered={})", self.receiver.len())
+    }
+}
 
-                // Check file size
-                if let Ok(metadata) = entry.metadata() {
-                    if metadata.len() > max_size {
-                        return WalkState::Continue;
-                    }
+impl Drop for SearchIter {
+    fn drop(&mut self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Format results as ripgrep-style text
+///
+/// Matches are written as `path:line:content` and context lines as
+/// `path-line-content`. When searching with context, `--` separates
+/// non-adjacent groups. Results are expected in file/line order.
+pub fn format_results(results: &[SearchResult], context_lines: usize) -> String {
+    use std::collections::HashSet;
+    use std::fmt::Write;
+
+    let match_lines: HashSet<(&str, u32)> = results
+        .iter()
+        .map(|r| (r.file.as_str(), r.line_number))
+        .collect();
+
+    let mut out = String::new();
+    // Last line written, per file, so overlapping context is not repeated
+    let mut last: Option<(&str, u32)> = None;
+
+    for result in results {
+        let file = result.file.as_str();
+        let first = result.context_start();
+
+        let lines = result
+            .context_before
+            .iter()
+            .chain(std::iter::once(&result.content))
+            .chain(result.context_after.iter());
+
+        for (offset, text) in lines.enumerate() {
+            let number = first + offset as u32;
+
+            match last {
+                Some((last_file, last_line)) if last_file == file && number <= last_line => {
+                    continue;
                 }
+                Some((last_file, last_line))
+                    if context_lines > 0 && (last_file != file || number > last_line + 1) =>
+                {
+                    out.push_str("--\n");
+                }
+                _ => {}
+            }
 
-                // Check glob pattern
-                let relative = path.strip_prefix(&self.root).unwrap_or(path);
+            let sep = if match_lines.contains(&(file, number)) {
+                ':'
+            } else {
+                '-'
+            };
+            let _ = writeln!(out, "{}{}{}{}{}", file, sep, number, sep, text);
+            last = Some((file, number));
+        }
+    }
 
-                if glob_matcher.is_match(relative) && !Self::is_binary_file(path) {
-                    if let Ok(mut guard) = files.lock() {
-                        guard.push(path.to_path_buf());
-                    }
-                }
+    out
+}
 
-                WalkState::Continue
+/// Sum match counts per parent directory of each result's file
+///
+/// Results from a first-per-file search contribute their whole
+/// `file_match_count`; any other result counts once. Files directly under
+/// root are grouped under `"."`.
+pub fn count_by_dir(results: &[SearchResult]) -> HashMap<String, usize> {
+    let counts: DashMap<&str, usize> = DashMap::new();
+    results.par_iter().for_each(|result| {
+        let dir = match result.file.rfind('/') {
+            Some(i) => &result.file[..i],
+            None => ".",
+        };
+        *counts.entry(dir).or_insert(0) += result.file_match_count.unwrap_or(1);
+    });
+    counts
+        .into_iter()
+        .map(|(dir, count)| (dir.to_string(), count))
+        .collect()
+}
+
+/// Expand line results into one location per regex match on the line
+///
+/// Matching is per line, so every location starts and ends on the same line.
+/// Zero-width matches (e.g. `^`) yield an empty range.
+pub fn to_locations(results: &[SearchResult], regex: &regex::Regex) -> Vec<Location> {
+    let column = |line: &str, byte: usize| line[..byte].chars().count() as u32 + 1;
+
+    results
+        .iter()
+        .flat_map(|result| {
+            let line = result.content.as_str();
+            regex.find_iter(line).map(move |m| Location {
+                file: result.file.clone(),
+                start_line: result.line_number,
+                start_col: column(line, m.start()),
+                end_line: result.line_number,
+                end_col: column(line, m.end()),
+                text: result.content.clone(),
             })
-        });
+        })
+        .collect()
+}
 
-        Ok(files.into_inner().unwrap_or_default())
+/// Search results as parallel arrays: files, line numbers and contents
+pub type Columns = (Vec<String>, Vec<u32>, Vec<String>);
+
+/// Split results into parallel columns, moving the strings out
+///
+/// Three lists cross into Python far more cheaply than one pyclass
+/// instance per result. Context, symbols and metadata are dropped.
+pub fn to_columns(results: Vec<SearchResult>) -> Columns {
+    let mut files = Vec::with_capacity(results.len());
+    let mut line_numbers = Vec::with_capacity(results.len());
+    let mut contents = Vec::with_capacity(results.len());
+    for result in results {
+        files.push(result.file);
+        line_numbers.push(result.line_number);
+        contents.push(result.content);
     }
+    (files, line_numbers, contents)
+}
 
-    /// Search a single file for matches using mmap for large files
-    fn search_file(
-        &self,
-        path: &Path,
-        regex: &regex::Regex,
-        options: &SearchOptions,
-        result_count: &Arc<AtomicUsize>,
-        cancel_flag: &AtomicBool,
-        max_results: usize,
-    ) -> Result<Vec<SearchResult>> {
-        if cancel_flag.load(Ordering::Relaxed) {
-            return Ok(Vec::new());
-        }
+/// Compile a search regex, honouring case sensitivity
+pub(crate) fn build_regex(query: &str, case_sensitive: bool) -> Result<regex::Regex> {
+    if case_sensitive {
+        regex::Regex::new(query)
+    } else {
+        regex::RegexBuilder::new(query)
+            .case_insensitive(true)
+            .build()
+    }
+    .map_err(|e| AgentGearError::Regex(e.to_string()))
+}
 
-        // Get file size
-        let metadata = match std::fs::metadata(path) {
-            Ok(m) => m,
-            Err(_) => return Ok(Vec::new()),
-        };
+/// Compiled search pattern(s)
+pub enum Matcher {
+    /// A single regex
+    Single(regex::Regex),
+    /// Several independent regexes, reported by pattern index
+    Multi(regex::RegexSet),
+    /// Literal keywords; a line matches when all of them (or, if `all` is
+    /// false, any of them) appear in it
+    Keywords { set: regex::RegexSet, all: bool },
+}
 
-        let file_size = metadata.len() as usize;
+impl Matcher {
+    /// Compile a single search pattern
+    pub fn single(query: &str, case_sensitive: bool) -> Result<Self> {
+        build_regex(query, case_sensitive).map(Self::Single)
+    }
 
-        // Use mmap for larger files (> 32KB), regular read for smaller
-        let content: String = if file_size > 32 * 1024 {
-            // Memory-mapped read
-            let file = match File::open(path) {
-                Ok(f) => f,
-                Err(_) => return Ok(Vec::new()),
-            };
-            let mmap = match unsafe { Mmap::map(&file) } {
-                Ok(m) => m,
-                Err(_) => return Ok(Vec::new()),
-            };
-            match std::str::from_utf8(&mmap) {
-                Ok(s) => s.to_string(),
-                Err(_) => return Ok(Vec::new()), // Skip non-UTF8 files
-            }
-        } else {
-            // Regular read for small files
-            match std::fs::read_to_string(path) {
-                Ok(c) => c,
-                Err(_) => return Ok(Vec::new()),
-            }
-        };
+    /// Compile several patterns into one set
+    ///
+    /// Multi-line mode lets the set double as a whole-file prefilter: a
+    /// pattern that matches some line also matches the full content.
+    pub fn multi(patterns: &[String], case_sensitive: bool) -> Result<Self> {
+        regex::RegexSetBuilder::new(patterns)
+            .case_insensitive(!case_sensitive)
+            .multi_line(true)
+            .crlf(true)
+            .build()
+            .map(Self::Multi)
+            .map_err(|e| AgentGearError::Regex(e.to_string()))
+    }
 
-        let lines: Vec<&str> = content.lines().collect();
-        let mut results = Vec::new();
+    /// Compile literal keywords into a line-membership check
+    ///
+    /// Keywords are matched as plain substrings, not regexes.
+    pub fn keywords(keywords: &[String], all: bool, case_sensitive: bool) -> Result<Self> {
+        if keywords.is_empty() {
+            return Err(AgentGearError::Regex("no keywords given".to_string()));
+        }
+        regex::RegexSetBuilder::new(keywords.iter().map(|k| regex::escape(k)))
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map(|set| Self::Keywords { set, all })
+            .map_err(|e| AgentGearError::Regex(e.to_string()))
+    }
 
-        let relative_path = path
-            .strip_prefix(&self.root)
-            .unwrap_or(path)
-            .to_string_lossy()
-            .to_string();
+    /// Quick check whether a file's content can contain any match
+    #[inline]
+    fn may_match(&self, content: &str) -> bool {
+        match self {
+            Self::Single(_) => true,
+            Self::Multi(set) => set.is_match(content),
+            // Every keyword on one line implies every keyword in the file
+            Self::Keywords { set, all: true } => set.matches(content).matched_all(),
+            Self::Keywords { set, all: false } => set.is_match(content),
+        }
+    }
 
-        for (i, line) in lines.iter().enumerate() {
-            // Check if we've hit the limit
-            if cancel_flag.load(Ordering::Relaxed)
-                || result_count.load(Ordering::Relaxed) >= max_results
-            {
-                break;
-            }
+    /// Whether any pattern matches the line
+    #[inline]
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Self::Single(regex) => regex.is_match(line),
+            Self::Multi(set) => set.is_match(line),
+            Self::Keywords { set, all: true } => set.matches(line).matched_all(),
+            Self::Keywords { set, all: false } => set.is_match(line),
+        }
+    }
 
-            if regex.is_match(line) {
-                // Collect context lines
-                let context_before: Vec<String> = if options.context_lines > 0 {
-                    let start = i.saturating_sub(options.context_lines);
-                    lines[start..i].iter().map(|s| s.to_string()).collect()
-                } else {
-                    Vec::new()
-                };
+    /// Reject a capture group the pattern does not define
+    pub fn check_capture_group(&self, group: usize) -> Result<()> {
+        match self {
+            Self::Single(regex) if group < regex.captures_len() => Ok(()),
+            Self::Single(regex) => Err(AgentGearError::Regex(format!(
+                "capture group {} out of range; the pattern has {} groups",
+                group,
+                regex.captures_len() - 1
+            ))),
+            _ => Err(AgentGearError::Regex(
+                "capture_group needs a single regex pattern".to_string(),
+            )),
+        }
+    }
 
-                let context_after: Vec<String> = if options.context_lines > 0 {
-                    let end = (i + 1 + options.context_lines).min(lines.len());
-                    lines[(i + 1)..end].iter().map(|s| s.to_string()).collect()
+    /// Text and columns of a capture group in the first match on the line
+    /// where it participated (single pattern only)
+    fn capture(&self, line: &str, group: usize) -> Option<(String, (u32, u32))> {
+        let Self::Single(regex) = self else {
+            return None;
+        };
+        let m = regex.captures_iter(line).find_map(|c| c.get(group))?;
+        let column = |byte: usize| line[..byte].chars().count() as u32 + 1;
+        Some((m.as_str().to_string(), (column(m.start()), column(m.end()))))
+    }
+
+    /// Patterns matching the line, as pattern indices (None unless several
+    /// independent patterns were compiled)
+    fn line_hits(&self, line: &str) -> Vec<Option<usize>> {
+        match self {
+            Self::Single(_) | Self::Keywords { .. } => {
+                if self.is_match(line) {
+                    vec![None]
                 } else {
                     Vec::new()
-                };
-
-                let updated =
-                    result_count.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
-                        if current >= max_results {
-                            None
-                        } else {
-                            Some(current + 1)
-                        }
-                    });
-
-                match updated {
-                    Ok(prev) => {
-                        results.push(SearchResult {
-                            file: relative_path.clone(),
-                            line_number: (i + 1) as u32,
-                            content: line.to_string(),
-                            context_before,
-                            context_after,
-                        });
-
-                        if prev + 1 >= max_results {
-                            cancel_flag.store(true, Ordering::Relaxed);
-                            break;
-                        }
-                    }
-                    Err(_) => {
-                        cancel_flag.store(true, Ordering::Relaxed);
-                        break;
-                    }
                 }
             }
+            Self::Multi(set) => set.matches(line).iter().map(Some).collect(),
         }
-
-        Ok(results)
+    }
+}
+
+/// Compile a glob pattern into a matcher
+pub(crate) fn build_glob(pattern: &str) -> Result<GlobMatcher> {
+    Glob::new(pattern)
+        .map(|g| g.compile_matcher())
+        .map_err(AgentGearError::Glob)
+}
+
+/// Check that a glob pattern compiles, without running it
+///
+/// Args:
+///     pattern: Glob pattern to validate
+///
+/// Returns:
+///     True if the pattern is valid
+///
+/// Raises:
+///     ValueError: If the pattern is invalid
+#[pyfunction]
+pub fn validate_glob(pattern: &str) -> PyResult<bool> {
+    build_glob(pattern)?;
+    Ok(true)
+}
+
+/// Check that a regex pattern compiles, without running it
+///
+/// Args:
+///     pattern: Regex pattern to validate
+///     case_sensitive: Compile as a case-sensitive pattern
+///
+/// Returns:
+///     True if the pattern is valid
+///
+/// Raises:
+///     ValueError: If the pattern is invalid
+#[pyfunction]
+#[pyo3(signature = (pattern, case_sensitive = false))]
+pub fn validate_regex(pattern: &str, case_sensitive: bool) -> PyResult<bool> {
+    build_regex(pattern, case_sensitive)?;
+    Ok(true)
+}
+
+/// Search an in-memory string with the same line matching as file searches
+///
+/// Nothing touches the disk. Options that only concern reading files
+/// (`max_file_size`, `binary`, `head_bytes`, `tail_bytes`, hidden and
+/// ignored files, `per_file_timeout_ms`, `max_matches_per_dir`) have no
+/// effect; `include_metadata` leaves `metadata` unset.
+///
+/// Args:
+///     content: Text to search
+///     query: Search pattern (regex)
+///     label: Reported as each result's `file`; with `symbol_context` its
+///         extension picks the language, e.g. "reply.py"
+///     case_sensitive: Case sensitive search
+///     max_results: Maximum number of results
+///     options: A fully configured SearchOptions; when given, case_sensitive
+///         and max_results are ignored
+///
+/// Returns:
+///     List of SearchResult objects in line order
+///
+/// Raises:
+///     ValueError: If the query is invalid
+#[pyfunction]
+#[pyo3(signature = (content, query, label = "", case_sensitive = false, max_results = 1000, options = None))]
+pub fn grep_text_content(
+    py: Python<'_>,
+    content: &str,
+    query: &str,
+    label: &str,
+    case_sensitive: bool,
+    max_results: usize,
+    options: Option<SearchOptions>,
+) -> PyResult<Vec<SearchResult>> {
+    let options = options.unwrap_or(SearchOptions {
+        case_sensitive,
+        max_results,
+        ..SearchOptions::default()
+    });
+    let matcher = Matcher::single(query, options.case_sensitive)?;
+    if let Some(group) = options.capture_group {
+        matcher.check_capture_group(group)?;
+    }
+    let source = LineSource {
+        file: label.to_string(),
+        line_offset: 0,
+        symbol_patterns: if options.symbol_context {
+            symbols::patterns_for(Path::new(label))
+        } else {
+            None
+        },
+        metadata: None,
+        dir_matches: None,
+    };
+
+    Ok(py.allow_threads(|| {
+        match_lines(
+            content,
+            &source,
+            &matcher,
+            &options,
+            &AtomicUsize::new(0),
+            &AtomicBool::new(false),
+            options.max_results,
+        )
+    }))
+}
+
+/// Where the content handed to `match_lines` came from
+struct LineSource {
+    /// Value reported as `SearchResult.file`
+    file: String,
+    /// Lines preceding the content in its file (non-zero for `tail_bytes`)
+    line_offset: usize,
+    symbol_patterns: Option<&'static [regex::Regex]>,
+    metadata: Option<FileMetadata>,
+    /// Matches claimed so far in the file's directory, for
+    /// `max_matches_per_dir`
+    dir_matches: Option<Arc<AtomicUsize>>,
+}
+
+/// Per-directory match counters shared by the workers of one search
+type DirMatches = DashMap<PathBuf, Arc<AtomicUsize>>;
+
+/// Match loaded content line by line, building a result per hit
+///
+/// Shared by file searches and `grep_text_content`, so buffers and files get
+/// the same semantics. Every result claims a slot in `result_count`; once
+/// `max_results` are claimed `cancel_flag` is set to stop other workers.
+fn match_lines(
+    content: &str,
+    source: &LineSource,
+    matcher: &Matcher,
+    options: &SearchOptions,
+    result_count: &AtomicUsize,
+    cancel_flag: &AtomicBool,
+    max_results: usize,
+) -> Vec<SearchResult> {
+    // The whole-file prefilter sees unnormalized text, so it could
+    // reject files whose normalized lines match
+    if !options.normalize_whitespace && !matcher.may_match(content) {
+        return Vec::new();
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let haystacks: Vec<Cow<'_, str>> = if options.normalize_whitespace {
+        lines
+            .iter()
+            .map(|line| Cow::Owned(collapse_whitespace(line)))
+            .collect()
+    } else {
+        lines.iter().map(|line| Cow::Borrowed(*line)).collect()
+    };
+    let mut results = Vec::new();
+
+    'lines: for (i, line) in lines.iter().enumerate() {
+        // Check if we've hit the limit
+        if cancel_flag.load(Ordering::Relaxed)
+            || result_count.load(Ordering::Relaxed) >= max_results
+        {
+            break;
+        }
+
+        // One result per matching pattern on this line
+        for pattern_index in matcher.line_hits(&haystacks[i]) {
+            // A capture group replaces the line with its text; lines where
+            // the group did not participate are passed over
+            let (content, capture_span) = match options.capture_group {
+                Some(group) => match matcher.capture(&haystacks[i], group) {
+                    Some((text, span)) => (text, Some(span)),
+                    None => continue,
+                },
+                None => (line.to_string(), None),
+            };
+
+            // Collect context lines
+            let context_before: Vec<String> = if options.context_lines > 0 {
+                let start = i.saturating_sub(options.context_lines);
+                lines[start..i].iter().map(|s| s.to_string()).collect()
+            } else {
+                Vec::new()
+            };
+
+            let context_after: Vec<String> = if options.context_lines > 0 {
+                let end = (i + 1 + options.context_lines).min(lines.len());
+                lines[(i + 1)..end].iter().map(|s| s.to_string()).collect()
+            } else {
+                Vec::new()
+            };
+
+            // A full directory ends this file; other directories go on
+            if let (Some(cap), Some(dir_matches)) =
+                (options.max_matches_per_dir, &source.dir_matches)
+            {
+                let claimed = dir_matches.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                    (n < cap).then_some(n + 1)
+                });
+                if claimed.is_err() {
+                    break 'lines;
+                }
+            }
+
+            let updated =
+                result_count.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                    if current >= max_results {
+                        None
+                    } else {
+                        Some(current + 1)
+                    }
+                });
+
+            match updated {
+                Ok(prev) => {
+                    // In first-per-file mode, count the remaining hits without
+                    // emitting them and stop scanning this file
+                    let file_match_count = if options.first_per_file_with_count {
+                        Some(
+                            1 + haystacks[(i + 1)..]
+                                .iter()
+                                .filter(|l| matcher.is_match(l))
+                                .count(),
+                        )
+                    } else {
+                        None
+                    };
+
+                    results.push(SearchResult {
+                        file: source.file.clone(),
+                        line_number: (source.line_offset + i + 1) as u32,
+                        content,
+                        context_before,
+                        context_after,
+                        file_match_count,
+                        pattern_index,
+                        symbol: source
+                            .symbol_patterns
+                            .and_then(|p| symbols::enclosing_symbol(p, &lines, i)),
+                        metadata: source.metadata.clone(),
+                        matched_glob: None,
+                        capture_span,
+                    });
+
+                    if options.first_per_file_with_count {
+                        if prev + 1 >= max_results {
+                            cancel_flag.store(true, Ordering::Relaxed);
+                        }
+                        break 'lines;
+                    }
+
+                    if prev + 1 >= max_results {
+                        cancel_flag.store(true, Ordering::Relaxed);
+                        break 'lines;
+                    }
+                }
+                Err(_) => {
+                    cancel_flag.store(true, Ordering::Relaxed);
+                    break 'lines;
+                }
+            }
+        }
+    }
+
+    results
+}
+
+/// A result with its position under an ordered search
+///
+/// Compares by the file's rank (relevance only), then the line number when
+/// ordering by line, then file path and line.
+struct Ranked {
+    key: ((bool, u64), u32, String, u32),
+    result: SearchResult,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+/// The best `limit` results of an ordered search seen so far
+///
+/// A max-heap with the worst kept result on top, so memory stays bounded by
+/// `max_results` however many matches the tree holds.
+struct TopRanked {
+    limit: usize,
+    heap: BinaryHeap<Ranked>,
+    /// Whether a result was dropped for ranking below the kept ones
+    overflowed: bool,
+}
+
+impl TopRanked {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::new(),
+            overflowed: false,
+        }
+    }
+
+    fn push(&mut self, rank: (bool, u64), result: SearchResult, order: SearchOrder) {
+        let line = match order {
+            SearchOrder::LineNumber => result.line_number,
+            SearchOrder::FilePath | SearchOrder::Relevance => 0,
+        };
+        let ranked = Ranked {
+            key: (rank, line, result.file.clone(), result.line_number),
+            result,
+        };
+        self.insert(ranked);
+    }
+
+    fn insert(&mut self, ranked: Ranked) {
+        if self.heap.len() < self.limit {
+            self.heap.push(ranked);
+            return;
+        }
+        self.overflowed = true;
+        if let Some(mut worst) = self.heap.peek_mut() {
+            if ranked < *worst {
+                *worst = ranked;
+            }
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.overflowed |= other.overflowed;
+        for ranked in other.heap {
+            self.insert(ranked);
+        }
+        self
+    }
+
+    /// Kept results best first, and whether any were dropped
+    fn into_sorted(self) -> (Vec<SearchResult>, bool) {
+        let results = self
+            .heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.result)
+            .collect();
+        (results, self.overflowed)
+    }
+}
+
+/// Search engine for file content
+#[derive(Clone)]
+pub struct Searcher {
+    root: PathBuf,
+    /// Source of per-file metadata for `include_metadata`
+    index: Option<Arc<FileIndex>>,
+    /// Directory names pruned from walks unless `include_ignored` is set
+    ignored_dirs: Option<DirIgnore>,
+    /// Extra ignore file names honoured unless `include_ignored` is set
+    custom_ignore_files: Vec<String>,
+}
+
+impl Searcher {
+    /// Create a new searcher for the given root directory
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            index: None,
+            ignored_dirs: None,
+            custom_ignore_files: Vec::new(),
+        }
+    }
+
+    /// Skip directories with the given names when walking, on top of
+    /// gitignore
+    pub fn with_ignored_dirs(mut self, ignored_dirs: DirIgnore) -> Self {
+        self.ignored_dirs = Some(ignored_dirs);
+        self
+    }
+
+    /// Also honour ignore files with these names, with precedence over
+    /// gitignore
+    pub fn with_custom_ignore_files(mut self, names: Vec<String>) -> Self {
+        self.custom_ignore_files = names;
+        self
+    }
+
+    /// Attach results' file metadata from this index when requested
+    pub fn with_index(mut self, index: Arc<FileIndex>) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Search for a pattern in files matching the glob pattern
+    pub fn grep(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        options: &SearchOptions,
+    ) -> PyResult<Vec<SearchResult>> {
+        self.search(py, query, glob_pattern, options)
+            .map(|summary| summary.results)
+    }
+
+    /// Search for a pattern using pre-collected files from index
+    pub fn grep_with_files(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        files: Vec<PathBuf>,
+        options: &SearchOptions,
+    ) -> PyResult<Vec<SearchResult>> {
+        self.search_with_files(py, query, files, options)
+            .map(|summary| summary.results)
+    }
+
+    /// Search files matching the glob pattern, returning results with statistics
+    pub fn search(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        glob_pattern: &str,
+        options: &SearchOptions,
+    ) -> PyResult<SearchSummary> {
+        let matcher = Matcher::single(query, options.case_sensitive)?;
+        self.search_matcher(py, &matcher, glob_pattern, None, options)
+    }
+
+    /// Search pre-collected files from index, returning results with statistics
+    pub fn search_with_files(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        files: Vec<PathBuf>,
+        options: &SearchOptions,
+    ) -> PyResult<SearchSummary> {
+        let matcher = Matcher::single(query, options.case_sensitive)?;
+        self.search_matcher(py, &matcher, "**/*", Some(files), options)
+    }
+
+    /// Search with a compiled matcher, over pre-collected files if given or
+    /// otherwise over files matching the glob pattern
+    pub fn search_matcher(
+        &self,
+        py: Python<'_>,
+        matcher: &Matcher,
+        glob_pattern: &str,
+        files: Option<Vec<PathBuf>>,
+        options: &SearchOptions,
+    ) -> PyResult<SearchSummary> {
+        py.allow_threads(|| self.grep_internal(matcher, glob_pattern, options, files))
+            .map_err(|e| e.into())
+    }
+
+    /// Files with at least one matching line, relative to root and sorted
+    ///
+    /// Each candidate is scanned only up to its first matching line and no
+    /// results are built, so this is cheaper than a first-per-file search.
+    /// `max_results` caps the number of files returned and
+    /// `max_files_scanned` the number read. Files that fail to read are
+    /// left out.
+    pub fn matching_files(
+        &self,
+        matcher: &Matcher,
+        glob_pattern: &str,
+        files: Option<Vec<PathBuf>>,
+        options: &SearchOptions,
+    ) -> Result<Vec<String>> {
+        let mut files = match files {
+            Some(files) => files,
+            None => self.collect_files(&build_glob(glob_pattern)?, options)?,
+        };
+        Self::retain_languages(&mut files, options);
+
+        let found = AtomicUsize::new(0);
+        let cancelled = AtomicBool::new(false);
+        let files_scanned = AtomicUsize::new(0);
+        let files_capped = AtomicBool::new(false);
+
+        let mut matched: Vec<String> = files
+            .par_iter()
+            .filter(|path| {
+                if !Self::claim_file(options, &found, &cancelled, &files_scanned, &files_capped) {
+                    return false;
+                }
+                let Ok(Some((content, _))) = Self::read_candidate(path, options) else {
+                    return false;
+                };
+                let hit = if options.normalize_whitespace {
+                    content
+                        .lines()
+                        .any(|line| matcher.is_match(&collapse_whitespace(line)))
+                } else {
+                    matcher.may_match(&content)
+                        && content.lines().any(|line| matcher.is_match(line))
+                };
+                if hit {
+                    found.fetch_add(1, Ordering::Relaxed);
+                }
+                hit
+            })
+            .map(|path| to_posix_string(path.strip_prefix(&self.root).unwrap_or(path)))
+            .collect();
+
+        matched.sort_unstable();
+        matched.truncate(options.max_results);
+        Ok(matched)
+    }
+
+    /// Start a search on a background thread, yielding results as found
+    ///
+    /// Files are searched in parallel on a small pool of their own, at most
+    /// `STREAM_THREADS` threads, so a consumer that stops reading does not
+    /// stall searches on the global pool and many live iterators do not
+    /// multiply the core count. Results arrive in completion order;
+    /// `order_by` is ignored.
+    pub fn stream(
+        &self,
+        matcher: Matcher,
+        glob_pattern: &str,
+        files: Option<Vec<PathBuf>>,
+        options: SearchOptions,
+    ) -> Result<SearchIter> {
+        // Report a bad glob to the caller rather than ending the stream
+        let glob_matcher = match files {
+            Some(_) => None,
+            None => Some(build_glob(glob_pattern)?),
+        };
+
+        let (sender, receiver) = bounded(STREAM_CAPACITY);
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let searcher = self.clone();
+        let cancel_flag = Arc::clone(&cancelled);
+        let matched_glob = glob_pattern.to_string();
+        std::thread::spawn(move || {
+            let mut files = match (files, glob_matcher) {
+                (Some(files), _) => files,
+                (None, Some(glob_matcher)) => searcher
+                    .collect_files(&glob_matcher, &options)
+                    .unwrap_or_default(),
+                (None, None) => Vec::new(),
+            };
+            Self::retain_languages(&mut files, &options);
+
+            let result_count = Arc::new(AtomicUsize::new(0));
+            let sent = AtomicUsize::new(0);
+            let files_scanned = AtomicUsize::new(0);
+            let files_capped = AtomicBool::new(false);
+            // Set by search_file once max_results is reached; kept apart from
+            // the caller's flag so results already found are still sent
+            let exhausted = AtomicBool::new(false);
+            let dir_matches = DirMatches::new();
+
+            let run = || {
+                files.par_iter().for_each(|path| {
+                    if cancel_flag.load(Ordering::Relaxed)
+                        || !Self::claim_file(
+                            &options,
+                            &result_count,
+                            &exhausted,
+                            &files_scanned,
+                            &files_capped,
+                        )
+                    {
+                        return;
+                    }
+
+                    let results = searcher
+                        .search_file(
+                            path,
+                            &matcher,
+                            &options,
+                            &result_count,
+                            &exhausted,
+                            &dir_matches,
+                        )
+                        .unwrap_or_default();
+
+                    for mut result in results {
+                        result.matched_glob = Some(matched_glob.clone());
+                        // Parallel workers can overshoot max_results; only
+                        // the first max_results are sent
+                        if cancel_flag.load(Ordering::Relaxed)
+                            || sent.fetch_add(1, Ordering::Relaxed) >= options.max_results
+                        {
+                            return;
+                        }
+                        // Wait for room in slices, so a cancelled iterator
+                        // that is never dropped still releases its workers
+                        let mut pending = result;
+                        loop {
+                            match sender.send_timeout(pending, STREAM_SIGNAL_INTERVAL) {
+                                Ok(()) => break,
+                                Err(SendTimeoutError::Timeout(result))
+                                    if !cancel_flag.load(Ordering::Relaxed) =>
+                                {
+                                    pending = result;
+                                }
+                                // The iterator is gone or was cancelled
+                                Err(_) => {
+                                    cancel_flag.store(true, Ordering::Relaxed);
+                                    return;
+                                }
+                            }
+                        }
+                    }
+                });
+            };
+
+            let threads = std::thread::available_parallelism()
+                .map_or(1, |n| n.get())
+                .min(STREAM_THREADS);
+            match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => pool.install(run),
+                Err(_) => run(),
+            }
+        });
+
+        Ok(SearchIter {
+            receiver,
+            cancelled,
+        })
+    }
+
+    fn grep_internal(
+        &self,
+        matcher: &Matcher,
+        glob_pattern: &str,
+        options: &SearchOptions,
+        pre_collected_files: Option<Vec<PathBuf>>,
+    ) -> Result<SearchSummary> {
+        // Get files to search
+        let mut files = if let Some(files) = pre_collected_files {
+            files
+        } else {
+            let glob_matcher = build_glob(glob_pattern)?;
+            self.collect_files(&glob_matcher, options)?
+        };
+        Self::retain_languages(&mut files, options);
+
+        // Counter for limiting results
+        let result_count = Arc::new(AtomicUsize::new(0));
+        let max_results = options.max_results;
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        // Counter for bounding the number of files searched
+        let files_scanned = AtomicUsize::new(0);
+        let files_capped = AtomicBool::new(false);
+        let dir_matches = DirMatches::new();
+
+        // Files that failed to read, kept so one bad file is reported
+        // rather than failing the whole search
+        let skipped = std::sync::Mutex::new(Vec::new());
+
+        let search = |path: &PathBuf, result_count: &Arc<AtomicUsize>, cancelled: &AtomicBool| {
+            self.search_file(
+                path,
+                matcher,
+                options,
+                result_count,
+                cancelled,
+                &dir_matches,
+            )
+            .unwrap_or_else(|e| {
+                let relative = path.strip_prefix(&self.root).unwrap_or(path);
+                if let Ok(mut guard) = skipped.lock() {
+                    guard.push((to_posix_string(relative), e.to_string()));
+                }
+                Vec::new()
+            })
+        };
+
+        let (results, overflowed) = match options.order_by {
+            // Search files in parallel
+            None => {
+                let results: Vec<SearchResult> = files
+                    .par_iter()
+                    .flat_map(|path| {
+                        if !Self::claim_file(
+                            options,
+                            &result_count,
+                            &cancelled,
+                            &files_scanned,
+                            &files_capped,
+                        ) {
+                            return Vec::new();
+                        }
+                        search(path, &result_count, &cancelled)
+                    })
+                    .collect();
+                (results, false)
+            }
+            // An ordered search must rank every match, so files get their
+            // own result budget and only the best max_results are kept.
+            // Within a file every order ranks earlier lines first, so the
+            // file's first max_results matches are all that can make the cut
+            Some(order) => {
+                let top = files
+                    .par_iter()
+                    .fold(
+                        || TopRanked::new(max_results),
+                        |mut top, path| {
+                            if !Self::claim_file(
+                                options,
+                                &result_count,
+                                &cancelled,
+                                &files_scanned,
+                                &files_capped,
+                            ) {
+                                return top;
+                            }
+                            let results = search(
+                                path,
+                                &Arc::new(AtomicUsize::new(0)),
+                                &AtomicBool::new(false),
+                            );
+                            if let Some(first) = results.first() {
+                                let rank = Self::file_rank(path, &first.file, matcher, order);
+                                for result in results {
+                                    top.push(rank, result, order);
+                                }
+                            }
+                            top
+                        },
+                    )
+                    .reduce(|| TopRanked::new(max_results), TopRanked::merge);
+                top.into_sorted()
+            }
+        };
+
+        // Truncate to max_results (parallel collection may slightly exceed)
+        let results: Vec<SearchResult> = results
+            .into_iter()
+            .take(max_results)
+            .map(|mut result| {
+                result.matched_glob = Some(glob_pattern.to_string());
+                result
+            })
+            .collect();
+
+        Ok(SearchSummary {
+            results,
+            files_scanned: files_scanned.into_inner(),
+            truncated: files_capped.into_inner() || cancelled.load(Ordering::Relaxed) || overflowed,
+            skipped: skipped.into_inner().unwrap_or_default(),
+        })
+    }
+
+    /// Decide whether the next file should be searched
+    ///
+    /// Returns false once the search is cancelled, enough results were
+    /// found, or the `max_files_scanned` budget is spent (which also sets
+    /// `files_capped`). Otherwise a scan slot is claimed for the file.
+    fn claim_file(
+        options: &SearchOptions,
+        result_count: &AtomicUsize,
+        cancelled: &AtomicBool,
+        files_scanned: &AtomicUsize,
+        files_capped: &AtomicBool,
+    ) -> bool {
+        if cancelled.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        // Check if we've hit the limit
+        if result_count.load(Ordering::Relaxed) >= options.max_results {
+            return false;
+        }
+
+        // Claim a scan slot; once the budget is spent, skip remaining files
+        if let Some(max_files) = options.max_files_scanned {
+            let claimed = files_scanned.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                if n >= max_files {
+                    None
+                } else {
+                    Some(n + 1)
+                }
+            });
+            if claimed.is_err() {
+                files_capped.store(true, Ordering::Relaxed);
+                return false;
+            }
+        } else {
+            files_scanned.fetch_add(1, Ordering::Relaxed);
+        }
+
+        true
+    }
+
+    /// Drop candidates whose language is not in `options.languages`
+    ///
+    /// Applied to every candidate list, walked or taken from the index, so
+    /// the filter composes with the glob and ignore rules that built it.
+    fn retain_languages(files: &mut Vec<PathBuf>, options: &SearchOptions) {
+        let Some(languages) = &options.languages else {
+            return;
+        };
+        files.retain(|path| {
+            language::detect(path)
+                .is_some_and(|detected| languages.iter().any(|l| l.eq_ignore_ascii_case(detected)))
+        });
+    }
+
+    /// Rank shared by every result from one file under `order`
+    ///
+    /// Relevance ranks matches in files whose path also matches the query
+    /// first, then prefers smaller files, since huge files are usually
+    /// generated or vendored. The other orders need no per-file rank.
+    fn file_rank(path: &Path, file: &str, matcher: &Matcher, order: SearchOrder) -> (bool, u64) {
+        match order {
+            SearchOrder::FilePath | SearchOrder::LineNumber => (false, 0),
+            SearchOrder::Relevance => {
+                let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX);
+                (!matcher.is_match(file), size)
+            }
+        }
+    }
+
+    /// Collect files matching the glob pattern
+    ///
+    /// `include_ignored` disables every ignore source (.gitignore, global
+    /// gitignore, .git/info/exclude, .ignore), which also exposes build
+    /// artifacts, vendored trees and the `.git` directory itself. It also
+    /// lifts the ignored directories set by `with_ignored_dirs` and the
+    /// custom ignore files set by `with_custom_ignore_files`.
+    fn collect_files(
+        &self,
+        glob_matcher: &GlobMatcher,
+        options: &SearchOptions,
+    ) -> Result<Vec<PathBuf>> {
+        use ignore::WalkState;
+        use std::sync::Mutex;
+
+        let files = Mutex::new(Vec::new());
+        let max_size = options.max_file_size;
+
+        let mut builder = ignore::WalkBuilder::new(&self.root);
+        if options.include_ignored {
+            builder.standard_filters(false);
+        } else {
+            builder.git_ignore(true);
+            for name in &self.custom_ignore_files {
+                builder.add_custom_ignore_filename(name);
+            }
+        }
+        builder.hidden(!options.include_hidden);
+        if let Some(ignored_dirs) = self
+            .ignored_dirs
+            .clone()
+            .filter(|_| !options.include_ignored)
+        {
+            builder.filter_entry(move |entry| ignored_dirs.keeps_entry(entry));
+        }
+        let walker = builder.build_parallel();
+
+        walker.run(|| {
+            Box::new(|entry| {
+                let entry = match entry {
+                    Ok(e) => e,
+                    Err(_) => return WalkState::Continue,
+                };
+
+                let path = entry.path();
+
+                // Skip directories
+                if path.is_dir() {
+                    return WalkState::Continue;
+                }
+
+                // Check file size
+                if let Ok(metadata) = entry.metadata() {
+                    if metadata.len() > max_size {
+                        return WalkState::Continue;
+                    }
+                }
+
+                // Check glob pattern
+                let relative = path.strip_prefix(&self.root).unwrap_or(path);
+
+                if glob_matcher.is_match(relative)
+                    && (options.binary != BinaryMode::Skip || !self.is_binary_candidate(path))
+                {
+                    if let Ok(mut guard) = files.lock() {
+                        guard.push(path.to_path_buf());
+                    }
+                }
+
+                WalkState::Continue
+            })
+        });
+
+        Ok(files.into_inner().unwrap_or_default())
+    }
+
+    /// Search a single file for matches using mmap for large files
+    fn search_file(
+        &self,
+        path: &Path,
+        matcher: &Matcher,
+        options: &SearchOptions,
+        result_count: &Arc<AtomicUsize>,
+        cancel_flag: &AtomicBool,
+        dir_matches: &DirMatches,
+    ) -> Result<Vec<SearchResult>> {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return Ok(Vec::new());
+        }
+
+        // Non-UTF8 files are skipped unless a binary mode is set
+        let Some((content, line_offset)) = Self::read_candidate(path, options)? else {
+            return Ok(Vec::new());
+        };
+
+        let source = LineSource {
+            file: to_posix_string(path.strip_prefix(&self.root).unwrap_or(path)),
+            line_offset,
+            symbol_patterns: if options.symbol_context {
+                symbols::patterns_for(path)
+            } else {
+                None
+            },
+            // Shared by every result from this file, so looked up once
+            metadata: match &self.index {
+                Some(index) if options.include_metadata => index.get_metadata(path),
+                _ => None,
+            },
+            dir_matches: options.max_matches_per_dir.map(|_| {
+                let dir = path.parent().unwrap_or(path).to_path_buf();
+                Arc::clone(&dir_matches.entry(dir).or_default())
+            }),
+        };
+
+        Ok(match_lines(
+            &content,
+            &source,
+            matcher,
+            options,
+            result_count,
+            cancel_flag,
+            options.max_results,
+        ))
+    }
+
+    /// `load` under the open-file limit, or with the per-file timeout if set
+    fn read_candidate(path: &Path, options: &SearchOptions) -> Loaded {
+        match options.per_file_timeout_ms {
+            Some(ms) => Self::load_with_timeout(path, options, Duration::from_millis(ms)),
+            None => {
+                // Bound descriptors held across Rayon workers; released once
+                // the content is in memory
+                let _permit = limit::acquire();
+                Self::load(path, options)
+            }
+        }
+    }
+
+    /// Read and decode a file for searching
+    ///
+    /// Returns the text with the number of lines preceding it (non-zero
+    /// for `tail_bytes`), or None for content the binary mode skips.
+    fn load(path: &Path, options: &SearchOptions) -> Loaded {
+        let metadata = std::fs::metadata(path)?;
+        let file_size = metadata.len() as usize;
+
+        if let Some((bytes, offset)) = Self::read_region(path, metadata.len(), options)? {
+            return Ok(Self::decode(&bytes, options).map(|text| (text, offset)));
+        }
+
+        // Use mmap for larger files (> 32KB), regular read for smaller
+        let content = if file_size > 32 * 1024 {
+            // Memory-mapped read
+            let file = File::open(path)?;
+            let mmap = unsafe { Mmap::map(&file)? };
+            Self::decode(&mmap, options)
+        } else {
+            // Regular read for small files
+            let bytes = std::fs::read(path)?;
+            if options.binary == BinaryMode::Skip && options.encoding.is_none() {
+                String::from_utf8(bytes).ok()
+            } else {
+                Self::decode(&bytes, options)
+            }
+        };
+        Ok(content.map(|text| (text, 0)))
+    }
+
+    /// `load` on the timed read pool, giving up after `timeout`
+    ///
+    /// The timeout covers waiting for a free pool thread as well as the
+    /// read. A read stuck on a stalled mount cannot be interrupted, so on
+    /// timeout it is abandoned on its pool thread and the worker moves on
+    /// to the next file. Once every pool thread is stalled, timed reads all
+    /// time out until one returns, rather than blocking the search.
+    fn load_with_timeout(path: &Path, options: &SearchOptions, timeout: Duration) -> Loaded {
+        let deadline = Instant::now() + timeout;
+        let (reply, receiver) = bounded(1);
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let job = TimedRead {
+            path: path.to_path_buf(),
+            options: options.clone(),
+            abandoned: Arc::clone(&abandoned),
+            reply,
+        };
+
+        let outcome = match TIMED_READS.send_deadline(job, deadline) {
+            Ok(()) => receiver.recv_deadline(deadline),
+            Err(_) => Err(RecvTimeoutError::Timeout),
+        };
+        match outcome {
+            Ok(loaded) => loaded,
+            Err(RecvTimeoutError::Timeout) => {
+                abandoned.store(true, Ordering::Relaxed);
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    format!("read timed out after {} ms", timeout.as_millis()),
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(std::io::Error::other("read thread failed")),
+        }
+    }
+
+    /// Read the head or tail region of a file, if one is requested
+    ///
+    /// Returns None when no region is set or the region covers the whole
+    /// file. Lines cut by the region edge are dropped, so only complete
+    /// lines are searched. Alongside the bytes, returns the number of lines
+    /// before the region; for a tail region that means counting newlines in
+    /// the skipped prefix.
+    fn read_region(
+        path: &Path,
+        file_size: u64,
+        options: &SearchOptions,
+    ) -> std::io::Result<Option<(Vec<u8>, usize)>> {
+        use std::io::Read;
+
+        if let Some(n) = options.head_bytes.filter(|&n| n < file_size) {
+            let mut bytes = Vec::with_capacity(n as usize);
+            File::open(path)?.take(n).read_to_end(&mut bytes)?;
+            let end = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            bytes.truncate(end);
+            return Ok(Some((bytes, 0)));
+        }
+
+        if let Some(n) = options.tail_bytes.filter(|&n| n < file_size) {
+            // The file may have been truncated since `file_size` was taken,
+            // so the region is placed by the mapped length alone
+            let file = File::open(path)?;
+            if n >= file.metadata()?.len() {
+                return Ok(None);
+            }
+            let mmap = unsafe { Mmap::map(&file)? };
+            let Some(start) = mmap.len().checked_sub(n as usize).filter(|&s| s > 0) else {
+                return Ok(None);
+            };
+            let line_start = if mmap[start - 1] == b'\n' {
+                start
+            } else {
+                mmap[start..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(mmap.len(), |i| start + i + 1)
+            };
+            let offset = mmap[..line_start].iter().filter(|&&b| b == b'\n').count();
+            return Ok(Some((mmap[line_start..].to_vec(), offset)));
+        }
+
+        Ok(None)
+    }
+
+    /// Decode file bytes into searchable text according to the binary mode
+    /// and encoding
+    ///
+    /// With an encoding set, SKIP drops files that are malformed in it and
+    /// TEXT replaces the bad sequences; STRINGS ignores the encoding.
+    fn decode(bytes: &[u8], options: &SearchOptions) -> Option<String> {
+        let encoding = options
+            .encoding
+            .as_deref()
+            .and_then(|label| encoding::lookup(label).ok())
+            .filter(|&e| e != UTF_8 && options.binary != BinaryMode::Strings);
+        if let Some(encoding) = encoding {
+            let (encoding, bom_len) = Encoding::for_bom(bytes).unwrap_or((encoding, 0));
+            let bytes = &bytes[bom_len..];
+            return match options.binary {
+                BinaryMode::Skip => encoding
+                    .decode_without_bom_handling_and_without_replacement(bytes)
+                    .map(Cow::into_owned),
+                _ => Some(encoding.decode_without_bom_handling(bytes).0.into_owned()),
+            };
+        }
+
+        match options.binary {
+            BinaryMode::Skip => std::str::from_utf8(bytes).ok().map(str::to_owned),
+            BinaryMode::Text => Some(String::from_utf8_lossy(bytes).into_owned()),
+            BinaryMode::Strings => Some(extract_strings(bytes)),
+        }
+    }
+
+    /// Check if a walked file is binary, honouring the index's extension
+    /// overrides before sniffing its bytes
+    fn is_binary_candidate(&self, path: &Path) -> bool {
+        self.index
+            .as_ref()
+            .and_then(|index| index.binary_override(path))
+            .unwrap_or_else(|| Self::is_binary_file(path))
+    }
+
+    /// Check if a file appears to be binary
+    fn is_binary_file(path: &Path) -> bool {
+        use std::io::Read;
+
+        if let Ok(mut file) = std::fs::File::open(path) {
+            let mut buffer = [0u8; 512];
+            if let Ok(n) = file.read(&mut buffer) {
+                return buffer[..n].contains(&0);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::path::BinaryOverrides;
+    use tempfile::tempdir;
+
+    fn create_test_files(dir: &Path) {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+
+        std::fs::write(
+            dir.join("src/main.rs"),
+            r#"fn main() {
+    println!("Hello, World!");
+}
+"#,
+        )
+        .unwrap();
+
+        std::fs::write(
+            dir.join("src/lib.rs"),
+            r#"pub fn hello() {
+    println!("Hello from lib!");
+}
+
+pub fn goodbye() {
+    println!("Goodbye!");
+}
+"#,
+        )
+        .unwrap();
+
+        std::fs::write(dir.join("README.md"), "# Hello Project\n\nThis is a test.").unwrap();
+    }
+
+    #[test]
+    fn test_search_basic() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions::default();
+
+            let results = searcher.grep(py, "Hello", "**/*", &options).unwrap();
+            assert!(!results.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_search_glob_filter() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions::default();
+
+            // Only search .rs files
+            let results = searcher.grep(py, "println", "**/*.rs", &options).unwrap();
+            assert!(results.len() >= 2);
+
+            // Verify all results are from .rs files
+            for result in &results {
+                assert!(result.file.ends_with(".rs"));
+            }
+        });
+    }
+
+    #[test]
+    fn test_search_glob_brace_alternation() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions::default();
+
+            let results = searcher
+                .grep(py, "Hello", "**/*.{rs,md}", &options)
+                .unwrap();
+            assert!(results.iter().any(|r| r.file == "src/main.rs"));
+            assert!(results.iter().any(|r| r.file == "README.md"));
+            assert!(results
+                .iter()
+                .all(|r| r.matched_glob.as_deref() == Some("**/*.{rs,md}")));
+        });
+    }
+
+    #[test]
+    fn test_search_ignored_dirs() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+            std::fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+            std::fs::write(dir.path().join("node_modules/pkg/hello.js"), "Hello").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf())
+                .with_ignored_dirs(DirIgnore::new(["node_modules"]));
+            let results = searcher
+                .grep(py, "Hello", "**/*", &SearchOptions::default())
+                .unwrap();
+            assert!(!results.is_empty());
+            assert!(results.iter().all(|r| !r.file.starts_with("node_modules")));
+
+            let options = SearchOptions {
+                include_ignored: true,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "Hello", "**/*", &options).unwrap();
+            assert!(results
+                .iter()
+                .any(|r| r.file == "node_modules/pkg/hello.js"));
+        });
+    }
+
+    #[test]
+    fn test_search_custom_ignore_files() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+            std::fs::write(dir.path().join(".agentignore"), "src/\n").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf())
+                .with_custom_ignore_files(vec![".agentignore".to_string()]);
+            let results = searcher
+                .grep(py, "Hello", "**/*", &SearchOptions::default())
+                .unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].file, "README.md");
+
+            let options = SearchOptions {
+                include_ignored: true,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "Hello", "**/*", &options).unwrap();
+            assert!(results.iter().any(|r| r.file.starts_with("src/")));
+        });
+    }
+
+    #[test]
+    fn test_search_binary_overrides() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            std::fs::write(dir.path().join("trace.fixture"), b"Hello\0world").unwrap();
+            std::fs::write(dir.path().join("notes.dat"), "Hello").unwrap();
+
+            let index = Arc::new(FileIndex::new(dir.path().to_path_buf()));
+            index
+                .set_binary_overrides(BinaryOverrides::new(["dat"], ["fixture"]))
+                .unwrap();
+            let searcher = Searcher::new(dir.path().to_path_buf()).with_index(index);
+
+            let results = searcher
+                .grep(py, "Hello", "**/*", &SearchOptions::default())
+                .unwrap();
+            let files: Vec<&str> = results.iter().map(|r| r.file.as_str()).collect();
+            assert_eq!(files, vec!["trace.fixture"]);
+        });
+    }
+
+    #[test]
+    fn test_search_case_insensitive() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+
+            // Case insensitive (default)
+            let options = SearchOptions {
+                case_sensitive: false,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
+            assert!(!results.is_empty());
+
+            // Case sensitive
+            let options = SearchOptions {
+                case_sensitive: true,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
+            // "hello" (lowercase) should not match "Hello"
+            let hello_count = results
+                .iter()
+                .filter(|r| r.content.contains("hello"))
+                .count();
+            assert_eq!(hello_count, 0);
+        });
+    }
+
+    #[test]
+    fn test_search_max_results() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                max_results: 1,
+                ..Default::default()
+            };
+
+            let results = searcher.grep(py, "println", "**/*", &options).unwrap();
+            assert_eq!(results.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_search_first_per_file_with_count() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                first_per_file_with_count: true,
+                ..Default::default()
+            };
+
+            let results = searcher.grep(py, "println", "**/*.rs", &options).unwrap();
+            assert_eq!(results.len(), 2); // One per file
+
+            let lib = results.iter().find(|r| r.file.ends_with("lib.rs")).unwrap();
+            assert_eq!(lib.line_number, 2);
+            assert_eq!(lib.file_match_count, Some(2));
+
+            let main = results
+                .iter()
+                .find(|r| r.file.ends_with("main.rs"))
+                .unwrap();
+            assert_eq!(main.file_match_count, Some(1));
+        });
+    }
+
+    #[test]
+    fn test_search_hidden_and_ignored() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+            // .gitignore is only honored inside a git repository
+            std::fs::create_dir_all(dir.path().join(".git")).unwrap();
+            std::fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+            std::fs::create_dir_all(dir.path().join("build")).unwrap();
+            std::fs::write(dir.path().join("build/out.txt"), "SECRET=1").unwrap();
+            std::fs::write(dir.path().join(".env"), "SECRET=2").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+
+            // Default: hidden included, ignored excluded
+            let results = searcher
+                .grep(py, "SECRET", "**/*", &SearchOptions::default())
+                .unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].file, ".env");
+
+            let options = SearchOptions {
+                include_hidden: false,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "SECRET", "**/*", &options).unwrap();
+            assert!(results.is_empty());
+
+            let options = SearchOptions {
+                include_ignored: true,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "SECRET", "**/*", &options).unwrap();
+            assert_eq!(results.len(), 2);
+        });
+    }
+
+    #[test]
+    fn test_search_max_files_scanned() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                max_files_scanned: Some(1),
+                ..Default::default()
+            };
+
+            let summary = searcher.search(py, "Hello", "**/*", &options).unwrap();
+            assert_eq!(summary.files_scanned, 1);
+            assert!(summary.truncated);
+
+            let summary = searcher
+                .search(py, "Hello", "**/*", &SearchOptions::default())
+                .unwrap();
+            assert_eq!(summary.files_scanned, 3);
+            assert!(!summary.truncated);
+        });
+    }
+
+    #[test]
+    fn test_validate_patterns() {
+        assert!(build_glob("**/*.rs").is_ok());
+        assert!(matches!(
+            build_glob("src/[a-"),
+            Err(AgentGearError::Glob(_))
+        ));
+
+        assert!(build_regex(r"fn\s+\w+", true).is_ok());
+        assert!(matches!(
+            build_regex("fn (unclosed", false),
+            Err(AgentGearError::Regex(_))
+        ));
+    }
+
+    #[test]
+    fn test_search_multi_pattern() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                case_sensitive: true,
+                ..Default::default()
+            };
+            let patterns = vec!["println".to_string(), "^pub fn".to_string()];
+            let matcher = Matcher::multi(&patterns, options.case_sensitive).unwrap();
+            let summary = searcher
+                .search_matcher(py, &matcher, "**/*.rs", None, &options)
+                .unwrap();
+
+            let count = |idx| {
+                summary
+                    .results
+                    .iter()
+                    .filter(|r| r.pattern_index == Some(idx))
+                    .count()
+            };
+            assert_eq!(count(0), 3); // main.rs + hello + goodbye
+            assert_eq!(count(1), 2); // pub fn hello, pub fn goodbye
+            assert!(summary.results.iter().all(|r| r.file.ends_with(".rs")));
+        });
+    }
+
+    #[test]
+    fn test_numbered_context() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let content = "one\ntwo\nthree\nfour\nfive\n";
+            let options = SearchOptions {
+                context_lines: 2,
+                ..Default::default()
+            };
+            let results =
+                grep_text_content(py, content, "four", "", false, 1000, Some(options)).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(
+                results[0].numbered_context_before(),
+                vec![(2, "two".to_string()), (3, "three".to_string())]
+            );
+            assert_eq!(
+                results[0].numbered_context_after(),
+                vec![(5, "five".to_string())]
+            );
+
+            // Context is cut short at the start of the content
+            let options = SearchOptions {
+                context_lines: 2,
+                ..Default::default()
+            };
+            let results =
+                grep_text_content(py, content, "one", "", false, 1000, Some(options)).unwrap();
+            assert!(results[0].numbered_context_before().is_empty());
+            assert_eq!(results[0].numbered_context_after()[1].0, 3);
+        });
+    }
+
+    #[test]
+    fn test_grep_text_content() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let content = "fn main() {\n    let todo = 1;\n}\n\nfn helper() { // TODO\n}\n";
+
+            let results = grep_text_content(py, content, "todo", "", false, 1000, None).unwrap();
+            assert_eq!(results.len(), 2);
+            assert_eq!(results[0].file, "");
+            assert_eq!(results[0].line_number, 2);
+            assert_eq!(results[1].line_number, 5);
+
+            let options = SearchOptions {
+                case_sensitive: true,
+                context_lines: 1,
+                symbol_context: true,
+                ..Default::default()
+            };
+            let results =
+                grep_text_content(py, content, "TODO", "reply.rs", false, 1000, Some(options))
+                    .unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].file, "reply.rs");
+            assert_eq!(results[0].context_before, vec![""]);
+            assert_eq!(results[0].symbol.as_deref(), Some("helper"));
+
+            let results = grep_text_content(py, content, "fn", "", false, 1, None).unwrap();
+            assert_eq!(results.len(), 1);
+
+            assert!(grep_text_content(py, content, "(", "", false, 1000, None).is_err());
+        });
+    }
+
+    #[test]
+    fn test_search_keywords() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            std::fs::write(
+                dir.path().join("log.txt"),
+                "ERROR: request timeout\nerror: disk full\ntimeout (retrying)\nok\n",
+            )
+            .unwrap();
+            std::fs::write(dir.path().join("other.txt"), "error only\n").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                order_by: Some(SearchOrder::FilePath),
+                ..Default::default()
+            };
+            let keywords = vec!["error".to_string(), "timeout".to_string()];
+            let lines = |all: bool| {
+                let matcher = Matcher::keywords(&keywords, all, false).unwrap();
+                searcher
+                    .search_matcher(py, &matcher, "**/*", None, &options)
+                    .unwrap()
+                    .results
+                    .into_iter()
+                    .map(|r| (r.file, r.line_number))
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(lines(true), vec![("log.txt".to_string(), 1)]);
+            assert_eq!(
+                lines(false),
+                vec![
+                    ("log.txt".to_string(), 1),
+                    ("log.txt".to_string(), 2),
+                    ("log.txt".to_string(), 3),
+                    ("other.txt".to_string(), 1),
+                ]
+            );
+
+            // Keywords are literals, not regexes
+            let matcher = Matcher::keywords(&["(retrying)".to_string()], true, false).unwrap();
+            let summary = searcher
+                .search_matcher(py, &matcher, "**/*", None, &options)
+                .unwrap();
+            assert_eq!(summary.results.len(), 1);
+
+            assert!(Matcher::keywords(&[], true, false).is_err());
+        });
+    }
+
+    #[test]
+    fn test_search_records_skipped_files() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let files = vec![dir.path().join("src/lib.rs"), dir.path().join("gone.rs")];
+            let summary = searcher
+                .search_with_files(py, "Hello", files, &SearchOptions::default())
+                .unwrap();
+            assert!(!summary.results.is_empty());
+            assert_eq!(summary.skipped.len(), 1);
+            assert_eq!(summary.skipped[0].0, "gone.rs");
+        });
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_search_per_file_timeout() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+            // Opening a FIFO for reading blocks until a writer appears,
+            // standing in for a stalled mount
+            let fifo = dir.path().join("stalled.txt");
+            let status = std::process::Command::new("mkfifo")
+                .arg(&fifo)
+                .status()
+                .unwrap();
+            assert!(status.success());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                per_file_timeout_ms: Some(50),
+                ..Default::default()
+            };
+            let files = vec![fifo.clone(), dir.path().join("src/lib.rs")];
+            let summary = searcher
+                .search_with_files(py, "Hello", files, &options)
+                .unwrap();
+            assert!(!summary.results.is_empty());
+            assert_eq!(summary.skipped.len(), 1);
+            assert_eq!(summary.skipped[0].0, "stalled.txt");
+            assert!(summary.skipped[0].1.contains("timed out"));
+
+            // Release the abandoned reader
+            drop(std::fs::OpenOptions::new().write(true).open(&fifo));
+
+            // More stalled files than helper threads: the search still ends,
+            // and the abandoned reads hold none of the shared open-file slots
+            let fifos: Vec<PathBuf> = (0..TIMED_READ_THREADS * 3)
+                .map(|i| dir.path().join(format!("stalled{i}.txt")))
+                .collect();
+            for fifo in &fifos {
+                let status = std::process::Command::new("mkfifo")
+                    .arg(fifo)
+                    .status()
+                    .unwrap();
+                assert!(status.success());
+            }
+            let started = Instant::now();
+            let summary = searcher
+                .search_with_files(py, "Hello", fifos.clone(), &options)
+                .unwrap();
+            assert!(started.elapsed() < Duration::from_secs(10));
+            assert!(summary.results.is_empty());
+            assert_eq!(summary.skipped.len(), fifos.len());
+            assert!(summary.skipped.iter().all(|(_, e)| e.contains("timed out")));
+
+            let (done, held) = bounded(1);
+            std::thread::spawn(move || {
+                let permits: Vec<_> = (0..limit::max_open_files())
+                    .map(|_| limit::acquire())
+                    .collect();
+                let _ = done.send(permits.len());
+            });
+            assert_eq!(
+                held.recv_timeout(Duration::from_secs(10)),
+                Ok(limit::max_open_files())
+            );
+
+            // Opening read-write never blocks and wakes any stalled reader
+            for fifo in &fifos {
+                drop(
+                    std::fs::OpenOptions::new()
+                        .read(true)
+                        .write(true)
+                        .open(fifo),
+                );
+            }
+        });
     }
 
-    /// Check if a file appears to be binary
-    fn is_binary_file(path: &Path) -> bool {
-        use std::io::Read;
+    #[test]
+    fn test_search_order_by() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+            std::fs::write(dir.path().join("hello.txt"), "hello\n".repeat(50)).unwrap();
 
-        if let Ok(mut file) = std::fs::File::open(path) {
-            let mut buffer = [0u8; 512];
-            if let Ok(n) = file.read(&mut buffer) {
-                return buffer[..n].contains(&0);
+            let searcher = Searcher::new(dir.path().to_path_buf());
+
+            let options = SearchOptions {
+                order_by: Some(SearchOrder::FilePath),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
+            let keys: Vec<_> = results.iter().map(|r| (&r.file, r.line_number)).collect();
+            let mut sorted = keys.clone();
+            sorted.sort();
+            assert_eq!(keys, sorted);
+
+            let options = SearchOptions {
+                order_by: Some(SearchOrder::LineNumber),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
+            assert!(results
+                .windows(2)
+                .all(|w| w[0].line_number <= w[1].line_number));
+
+            // The file whose path matches the query ranks first
+            let options = SearchOptions {
+                order_by: Some(SearchOrder::Relevance),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
+            assert_eq!(results[0].file, "hello.txt");
+            assert_eq!(results.last().unwrap().file.as_str(), "src/lib.rs");
+        });
+    }
+
+    #[test]
+    fn test_search_order_by_ranks_past_max_results() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let mut files = Vec::new();
+            for i in 0..40 {
+                let path = dir.path().join(format!("z{i:02}.txt"));
+                std::fs::write(&path, "todo\ntodo\n").unwrap();
+                files.push(path);
             }
+            // The best-ranked file comes last, after max_results others
+            let best = dir.path().join("a.txt");
+            std::fs::write(&best, "todo\n").unwrap();
+            files.push(best);
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                max_results: 3,
+                order_by: Some(SearchOrder::FilePath),
+                ..Default::default()
+            };
+            let summary = searcher
+                .search_with_files(py, "todo", files, &options)
+                .unwrap();
+            let found: Vec<_> = summary
+                .results
+                .iter()
+                .map(|r| (r.file.as_str(), r.line_number))
+                .collect();
+            assert_eq!(found, [("a.txt", 1), ("z00.txt", 1), ("z00.txt", 2)]);
+            assert!(summary.truncated);
+            assert_eq!(summary.files_scanned, 41);
+
+            // Relevance puts the file whose path matches the query first
+            std::fs::write(dir.path().join("todo.md"), "todo\n").unwrap();
+            let options = SearchOptions {
+                max_results: 1,
+                order_by: Some(SearchOrder::Relevance),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "todo", "**/*", &options).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].file, "todo.md");
+        });
+    }
+
+    #[test]
+    fn test_top_ranked() {
+        let result = |file: &str, line_number| SearchResult {
+            file: file.to_string(),
+            line_number,
+            content: String::new(),
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            file_match_count: None,
+            pattern_index: None,
+            symbol: None,
+            metadata: None,
+            matched_glob: None,
+            capture_span: None,
+        };
+        let keys = |top: TopRanked| -> (Vec<(String, u32)>, bool) {
+            let (results, overflowed) = top.into_sorted();
+            let keys = results
+                .into_iter()
+                .map(|r| (r.file, r.line_number))
+                .collect();
+            (keys, overflowed)
+        };
+
+        // Never holds more than the limit, whatever arrives
+        let mut top = TopRanked::new(2);
+        for (file, line) in [("c", 1), ("b", 9), ("a", 5), ("b", 2), ("d", 1)] {
+            top.push((false, 0), result(file, line), SearchOrder::LineNumber);
+            assert!(top.heap.len() <= 2);
         }
-        false
+        assert_eq!(keys(top), (vec![("c".into(), 1), ("d".into(), 1)], true));
+
+        // Merging per-thread heaps keeps the best across both
+        let mut left = TopRanked::new(2);
+        left.push((true, 1), result("a", 1), SearchOrder::Relevance);
+        let mut right = TopRanked::new(2);
+        right.push((false, 9), result("z", 3), SearchOrder::Relevance);
+        assert_eq!(
+            keys(left.merge(right)),
+            (vec![("z".into(), 3), ("a".into(), 1)], false)
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::tempdir;
+    #[test]
+    fn test_search_binary_modes() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let mut blob = vec![0u8, 1, 2, 0xff];
+            blob.extend_from_slice(b"MAGIC_HEADER_v1\x00\x00ab\x00");
+            std::fs::write(dir.path().join("data.bin"), &blob).unwrap();
 
-    fn create_test_files(dir: &Path) {
-        std::fs::create_dir_all(dir.join("src")).unwrap();
+            let searcher = Searcher::new(dir.path().to_path_buf());
 
-        std::fs::write(
-            dir.join("src/main.rs"),
-            r#"fn main() {
-    println!("Hello, World!");
-}
-"#,
-        )
-        .unwrap();
+            let results = searcher
+                .grep(py, "MAGIC", "**/*", &SearchOptions::default())
+                .unwrap();
+            assert!(results.is_empty());
 
-        std::fs::write(
-            dir.join("src/lib.rs"),
-            r#"pub fn hello() {
-    println!("Hello from lib!");
-}
+            let options = SearchOptions {
+                binary: BinaryMode::Text,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "MAGIC", "**/*", &options).unwrap();
+            assert_eq!(results.len(), 1);
+            assert!(results[0].content.contains('\u{FFFD}'));
 
-pub fn goodbye() {
-    println!("Goodbye!");
-}
-"#,
+            let options = SearchOptions {
+                binary: BinaryMode::Strings,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "MAGIC", "**/*", &options).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].content, "MAGIC_HEADER_v1");
+
+            // Runs shorter than MIN_STRING_LEN are dropped
+            assert!(searcher
+                .grep(py, "^ab$", "**/*", &options)
+                .unwrap()
+                .is_empty());
+        });
+    }
+
+    #[test]
+    fn test_search_encoding() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            let sjis =
+                encoding::encode("// こんにちは\nfn main() {}\n", "shift_jis", false).unwrap();
+            std::fs::write(dir.path().join("hello.rs"), &sjis).unwrap();
+            std::fs::write(dir.path().join("caf\u{e9}.txt"), b"caf\xe9 au lait\n").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+
+            // Not valid UTF-8, so the default skips both files
+            let results = searcher
+                .grep(py, "こんにちは|caf", "**/*", &SearchOptions::default())
+                .unwrap();
+            assert!(results.is_empty());
+
+            let options = SearchOptions {
+                encoding: Some("shift_jis".to_string()),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "こんにちは", "**/*", &options).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].content, "// こんにちは");
+
+            let options = SearchOptions {
+                encoding: Some("latin1".to_string()),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "café", "**/*.txt", &options).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].content, "café au lait");
+        });
+    }
+
+    #[test]
+    fn test_search_max_matches_per_dir() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            std::fs::create_dir_all(dir.path().join("gen/nested")).unwrap();
+            for name in ["a.rs", "b.rs", "c.rs"] {
+                std::fs::write(dir.path().join("gen").join(name), "todo\ntodo\ntodo\n").unwrap();
+            }
+            std::fs::write(dir.path().join("gen/nested/d.rs"), "todo\n").unwrap();
+            std::fs::write(dir.path().join("main.rs"), "todo\n").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                max_matches_per_dir: Some(2),
+                ..Default::default()
+            };
+            let results = searcher.grep(py, "todo", "**/*.rs", &options).unwrap();
+
+            let in_dir = |prefix: &str| {
+                results
+                    .iter()
+                    .filter(|r| r.file.rsplit_once('/').map_or("", |(d, _)| d) == prefix)
+                    .count()
+            };
+            // Subdirectories have counters of their own
+            assert_eq!(in_dir("gen"), 2);
+            assert_eq!(in_dir("gen/nested"), 1);
+            assert_eq!(in_dir(""), 1);
+            assert_eq!(results.len(), 4);
+        });
+    }
+
+    #[test]
+    fn test_search_languages() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            std::fs::write(dir.path().join("app.py"), "todo\n").unwrap();
+            std::fs::write(dir.path().join("types.pyi"), "todo\n").unwrap();
+            std::fs::write(dir.path().join("main.rs"), "todo\n").unwrap();
+            std::fs::write(dir.path().join("Makefile"), "todo\n").unwrap();
+            std::fs::write(dir.path().join("notes.txt"), "todo\n").unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let search = |languages: &[&str], glob: &str| {
+                let options = SearchOptions {
+                    languages: Some(languages.iter().map(|l| l.to_string()).collect()),
+                    order_by: Some(SearchOrder::FilePath),
+                    ..Default::default()
+                };
+                let results = searcher.grep(py, "todo", glob, &options).unwrap();
+                results.into_iter().map(|r| r.file).collect::<Vec<_>>()
+            };
+
+            assert_eq!(search(&["python"], "**/*"), ["app.py", "types.pyi"]);
+            assert_eq!(
+                search(&["Rust", "makefile"], "**/*"),
+                ["Makefile", "main.rs"]
+            );
+            // Composes with the glob rather than replacing it
+            assert_eq!(search(&["python"], "*.py"), ["app.py"]);
+            assert!(search(&["cobol"], "**/*").is_empty());
+
+            // Pre-collected candidates are filtered too
+            let options = SearchOptions {
+                languages: Some(vec!["rust".to_string()]),
+                ..Default::default()
+            };
+            let files = vec![dir.path().join("app.py"), dir.path().join("main.rs")];
+            let results = searcher
+                .grep_with_files(py, "todo", files, &options)
+                .unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].file, "main.rs");
+        });
+    }
+
+    #[test]
+    fn test_search_capture_group() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            std::fs::write(
+                dir.path().join("Cargo.toml"),
+                "name = \"demo\"\nversion = \"1.2.3\"\nabout = \"version = x\"\n",
+            )
+            .unwrap();
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
+            let options = SearchOptions {
+                case_sensitive: true,
+                capture_group: Some(1),
+                ..Default::default()
+            };
+            let results = searcher
+                .grep(py, r#"^version = "(.*)"|version"#, "**/*", &options)
+                .unwrap();
+
+            // The last line matches only the second branch, so group 1 is absent
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].line_number, 2);
+            assert_eq!(results[0].content, "1.2.3");
+            assert_eq!(results[0].capture_span, Some((12, 17)));
+
+            let matcher = Matcher::single("version = (.*)", true).unwrap();
+            assert!(matcher.check_capture_group(1).is_ok());
+            assert!(matcher.check_capture_group(2).is_err());
+        });
+    }
+
+    #[test]
+    fn test_matching_files() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/api")).unwrap();
+        std::fs::create_dir_all(dir.path().join("tests")).unwrap();
+        std::fs::write(
+            dir.path().join("src/a.py"),
+            "import requests\nimport requests\n",
         )
         .unwrap();
+        std::fs::write(dir.path().join("src/api/b.py"), "x = 1\nimport requests\n").unwrap();
+        std::fs::write(dir.path().join("src/c.py"), "import os\n").unwrap();
+        std::fs::write(dir.path().join("src/notes.txt"), "import requests\n").unwrap();
+        std::fs::write(dir.path().join("tests/d.py"), "import requests\n").unwrap();
+
+        let searcher = Searcher::new(dir.path().to_path_buf());
+        let matcher = Matcher::single("^import requests", true).unwrap();
+        let options = SearchOptions::default();
+
+        let files = searcher
+            .matching_files(&matcher, "src/**/*.py", None, &options)
+            .unwrap();
+        assert_eq!(files, vec!["src/a.py", "src/api/b.py"]);
+
+        let capped = SearchOptions {
+            max_results: 1,
+            ..Default::default()
+        };
+        let files = searcher
+            .matching_files(&matcher, "**/*.py", None, &capped)
+            .unwrap();
+        assert_eq!(files.len(), 1);
+    }
 
-        std::fs::write(dir.join("README.md"), "# Hello Project\n\nThis is a test.").unwrap();
+    #[test]
+    fn test_format_results() {
+        let result = |line_number, content: &str, before: &[&str], after: &[&str]| SearchResult {
+            file: "src/lib.rs".to_string(),
+            line_number,
+            content: content.to_string(),
+            context_before: before.iter().map(|s| s.to_string()).collect(),
+            context_after: after.iter().map(|s| s.to_string()).collect(),
+            file_match_count: None,
+            pattern_index: None,
+            symbol: None,
+            metadata: None,
+            matched_glob: None,
+            capture_span: None,
+        };
+
+        let plain = [result(2, "fn a()", &[], &[]), result(7, "fn b()", &[], &[])];
+        assert_eq!(
+            format_results(&plain, 0),
+            "src/lib.rs:2:fn a()\nsrc/lib.rs:7:fn b()\n"
+        );
+
+        // Overlapping context is merged; a match inside context keeps ':'
+        let with_context = [
+            result(2, "fn a()", &["// a"], &["fn b()"]),
+            result(3, "fn b()", &["fn a()"], &["}"]),
+            result(9, "fn c()", &["// c"], &[]),
+        ];
+        assert_eq!(
+            format_results(&with_context, 1),
+            "src/lib.rs-1-// a\n\
+             src/lib.rs:2:fn a()\n\
+             src/lib.rs:3:fn b()\n\
+             src/lib.rs-4-}\n\
+             --\n\
+             src/lib.rs-8-// c\n\
+             src/lib.rs:9:fn c()\n"
+        );
     }
 
     #[test]
-    fn test_search_basic() {
+    fn test_search_normalize_whitespace() {
         pyo3::prepare_freethreaded_python();
         Python::with_gil(|py| {
             let dir = tempdir().unwrap();
-            create_test_files(dir.path());
+            std::fs::write(dir.path().join("a.py"), "x = foo(  bar,\tbaz )\n").unwrap();
 
             let searcher = Searcher::new(dir.path().to_path_buf());
-            let options = SearchOptions::default();
+            let query = r"foo\( bar, baz \)";
 
-            let results = searcher.grep(py, "Hello", "**/*", &options).unwrap();
-            assert!(!results.is_empty());
+            let results = searcher
+                .grep(py, query, "**/*", &SearchOptions::default())
+                .unwrap();
+            assert!(results.is_empty());
+
+            let options = SearchOptions {
+                normalize_whitespace: true,
+                ..Default::default()
+            };
+            let results = searcher.grep(py, query, "**/*", &options).unwrap();
+            assert_eq!(results.len(), 1);
+            assert_eq!(results[0].content, "x = foo(  bar,\tbaz )");
         });
     }
 
     #[test]
-    fn test_search_glob_filter() {
+    fn test_search_head_and_tail_regions() {
         pyo3::prepare_freethreaded_python();
         Python::with_gil(|py| {
             let dir = tempdir().unwrap();
-            create_test_files(dir.path());
+            std::fs::write(
+                dir.path().join("a.rs"),
+                "// license\nfn a() {}\n// marker mid\nfn b() {}\n// marker end\n",
+            )
+            .unwrap();
 
             let searcher = Searcher::new(dir.path().to_path_buf());
-            let options = SearchOptions::default();
+            let lines = |query: &str, options: &SearchOptions| -> Vec<u32> {
+                let results = searcher.grep(py, query, "**/*", options).unwrap();
+                results.iter().map(|r| r.line_number).collect()
+            };
 
-            // Only search .rs files
-            let results = searcher.grep(py, "println", "**/*.rs", &options).unwrap();
-            assert!(results.len() >= 2);
+            // The head region ends mid-way through line 2, which is skipped
+            let head = SearchOptions {
+                head_bytes: Some(15),
+                ..Default::default()
+            };
+            assert_eq!(lines("//|fn", &head), vec![1]);
 
-            // Verify all results are from .rs files
-            for result in &results {
-                assert!(result.file.ends_with(".rs"));
-            }
+            // The tail region starts inside line 4; line numbers stay absolute
+            let tail = SearchOptions {
+                tail_bytes: Some(20),
+                ..Default::default()
+            };
+            assert_eq!(lines("marker|fn", &tail), vec![5]);
+
+            let mut all = lines("marker", &SearchOptions::default());
+            all.sort();
+            assert_eq!(all, vec![3, 5]);
         });
     }
 
     #[test]
-    fn test_search_case_insensitive() {
+    fn test_tail_region_after_truncation() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("app.log");
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        let options = SearchOptions {
+            tail_bytes: Some(100),
+            ..Default::default()
+        };
+
+        // A size from before the file was truncated must not index past
+        // the end; the shrunken file is searched whole instead
+        assert!(Searcher::read_region(&path, 1000, &options)
+            .unwrap()
+            .is_none());
+
+        let options = SearchOptions {
+            tail_bytes: Some(4),
+            ..Default::default()
+        };
+        let (bytes, offset) = Searcher::read_region(&path, 1000, &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(bytes, b"two\n");
+        assert_eq!(offset, 1);
+    }
+
+    #[test]
+    fn test_count_by_dir() {
         pyo3::prepare_freethreaded_python();
         Python::with_gil(|py| {
             let dir = tempdir().unwrap();
-            create_test_files(dir.path());
+            std::fs::create_dir_all(dir.path().join("src/deep")).unwrap();
+            std::fs::write(dir.path().join("a.rs"), "todo\ntodo\n").unwrap();
+            std::fs::write(dir.path().join("src/b.rs"), "todo\n").unwrap();
+            std::fs::write(dir.path().join("src/c.rs"), "todo\ndone\n").unwrap();
+            std::fs::write(dir.path().join("src/deep/d.rs"), "todo\n").unwrap();
 
             let searcher = Searcher::new(dir.path().to_path_buf());
-
-            // Case insensitive (default)
             let options = SearchOptions {
-                case_sensitive: false,
+                first_per_file_with_count: true,
                 ..Default::default()
             };
-            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
-            assert!(!results.is_empty());
+            let results = searcher.grep(py, "todo", "**/*", &options).unwrap();
 
-            // Case sensitive
+            let counts = count_by_dir(&results);
+            assert_eq!(counts.len(), 3);
+            assert_eq!(counts["."], 2);
+            assert_eq!(counts["src"], 2);
+            assert_eq!(counts["src/deep"], 1);
+        });
+    }
+
+    #[test]
+    fn test_search_symbol_context() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let dir = tempdir().unwrap();
+            create_test_files(dir.path());
+
+            let searcher = Searcher::new(dir.path().to_path_buf());
             let options = SearchOptions {
                 case_sensitive: true,
+                symbol_context: true,
+                order_by: Some(SearchOrder::FilePath),
                 ..Default::default()
             };
-            let results = searcher.grep(py, "hello", "**/*", &options).unwrap();
-            // "hello" (lowercase) should not match "Hello"
-            let hello_count = results
+            let results = searcher
+                .grep(py, "Hello|Goodbye", "**/*", &options)
+                .unwrap();
+
+            let symbols: Vec<_> = results
                 .iter()
-                .filter(|r| r.content.contains("hello"))
-                .count();
-            assert_eq!(hello_count, 0);
+                .map(|r| (r.file.as_str(), r.symbol.as_deref()))
+                .collect();
+            assert_eq!(
+                symbols,
+                vec![
+                    ("README.md", None),
+                    ("src/lib.rs", Some("hello")),
+                    ("src/lib.rs", Some("goodbye")),
+                    ("src/main.rs", Some("main")),
+                ]
+            );
+
+            // Off by default
+            let results = searcher
+                .grep(py, "Hello", "**/*.rs", &SearchOptions::default())
+                .unwrap();
+            assert!(results.iter().all(|r| r.symbol.is_none()));
         });
     }
 
     #[test]
-    fn test_search_max_results() {
+    fn test_search_stream() {
+        let dir = tempdir().unwrap();
+        for i in 0..50 {
+            std::fs::write(
+                dir.path().join(format!("f{i}.txt")),
+                "needle\nhay\nneedle\n",
+            )
+            .unwrap();
+        }
+        let searcher = Searcher::new(dir.path().to_path_buf());
+
+        let stream = searcher
+            .stream(
+                Matcher::single("needle", false).unwrap(),
+                "**/*.txt",
+                None,
+                SearchOptions::default(),
+            )
+            .unwrap();
+        let results: Vec<_> = stream.receiver.iter().collect();
+        assert_eq!(results.len(), 100);
+
+        // max_results holds exactly despite parallel workers
+        let options = SearchOptions {
+            max_results: 7,
+            ..Default::default()
+        };
+        let stream = searcher
+            .stream(
+                Matcher::single("needle", false).unwrap(),
+                "**/*",
+                None,
+                options,
+            )
+            .unwrap();
+        assert_eq!(stream.receiver.iter().count(), 7);
+
+        // Dropping the iterator early must not leave workers blocked
+        let stream = searcher
+            .stream(
+                Matcher::single("needle", false).unwrap(),
+                "**/*",
+                None,
+                SearchOptions::default(),
+            )
+            .unwrap();
+        assert!(stream.receiver.recv().is_ok());
+        let cancelled = Arc::clone(&stream.cancelled);
+        drop(stream);
+        assert!(cancelled.load(Ordering::Relaxed));
+
+        // Cancelling without dropping must release workers waiting on a
+        // full buffer: once they give up, nothing beyond it is ever sent
+        for i in 50..400 {
+            std::fs::write(dir.path().join(format!("f{i}.txt")), "needle\n").unwrap();
+        }
+        let stream = searcher
+            .stream(
+                Matcher::single("needle", false).unwrap(),
+                "**/*",
+                None,
+                SearchOptions::default(),
+            )
+            .unwrap();
+        while stream.receiver.len() < STREAM_CAPACITY {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        stream.cancel();
+        std::thread::sleep(STREAM_SIGNAL_INTERVAL * 3);
+        let buffered = stream.receiver.len();
+        let mut drained = 0;
+        loop {
+            match stream.receiver.recv_timeout(Duration::from_secs(5)) {
+                Ok(_) => drained += 1,
+                Err(e) => {
+                    assert_eq!(e, RecvTimeoutError::Disconnected);
+                    break;
+                }
+            }
+        }
+        assert_eq!(drained, buffered);
+
+        assert!(searcher
+            .stream(
+                Matcher::single("x", false).unwrap(),
+                "[",
+                None,
+                SearchOptions::default()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_search_include_metadata() {
         pyo3::prepare_freethreaded_python();
         Python::with_gil(|py| {
             let dir = tempdir().unwrap();
             create_test_files(dir.path());
+            let index = Arc::new(FileIndex::new(dir.path().to_path_buf()));
+            index.build().unwrap();
 
-            let searcher = Searcher::new(dir.path().to_path_buf());
+            let searcher = Searcher::new(dir.path().to_path_buf()).with_index(index);
             let options = SearchOptions {
-                max_results: 1,
+                include_metadata: true,
                 ..Default::default()
             };
+            let results = searcher.grep(py, "Hello", "**/*.rs", &options).unwrap();
+            assert!(!results.is_empty());
+            for result in &results {
+                let metadata = result.metadata.as_ref().unwrap();
+                let on_disk = std::fs::metadata(dir.path().join(&result.file)).unwrap();
+                assert_eq!(metadata.size, on_disk.len());
+                assert!(!metadata.is_binary);
+            }
 
-            let results = searcher.grep(py, "println", "**/*", &options).unwrap();
-            assert_eq!(results.len(), 1);
+            // Off by default
+            let results = searcher
+                .grep(py, "Hello", "**/*.rs", &SearchOptions::default())
+                .unwrap();
+            assert!(results.iter().all(|r| r.metadata.is_none()));
         });
     }
+
+    #[test]
+    fn test_to_locations() {
+        let results = vec![SearchResult {
+            file: "src/main.rs".to_string(),
+            line_number: 3,
+            content: "é foo = foo()".to_string(),
+            context_before: Vec::new(),
+            context_after: Vec::new(),
+            file_match_count: None,
+            pattern_index: None,
+            symbol: None,
+            metadata: None,
+            matched_glob: None,
+            capture_span: None,
+        }];
+        let regex = build_regex("foo", true).unwrap();
+
+        let locations = to_locations(&results, &regex);
+        assert_eq!(locations.len(), 2);
+        // Columns count characters, not bytes
+        assert_eq!((locations[0].start_col, locations[0].end_col), (3, 6));
+        assert_eq!((locations[1].start_col, locations[1].end_col), (9, 12));
+        assert_eq!(locations[1].start_line, 3);
+        assert_eq!(locations[1].end_line, 3);
+        assert_eq!(locations[1].text, "é foo = foo()");
+    }
+
+    #[test]
+    fn test_to_columns() {
+        let result = |file: &str, line_number, content: &str| SearchResult {
+            file: file.to_string(),
+            line_number,
+            content: content.to_string(),
+            context_before: vec!["dropped".to_string()],
+            context_after: Vec::new(),
+            file_match_count: None,
+            pattern_index: None,
+            symbol: None,
+            metadata: None,
+            matched_glob: None,
+            capture_span: None,
+        };
+
+        let (files, line_numbers, contents) =
+            to_columns(vec![result("a.rs", 3, "x"), result("b.rs", 1, "y")]);
+        assert_eq!(files, vec!["a.rs", "b.rs"]);
+        assert_eq!(line_numbers, vec![3, 1]);
+        assert_eq!(contents, vec!["x", "y"]);
+        assert_eq!(to_columns(Vec::new()), (Vec::new(), Vec::new(), Vec::new()));
+    }
 }
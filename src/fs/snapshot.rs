@@ -0,0 +1,141 @@
+//! Index snapshot persistence
+//!
+//! Serializes the in-memory index to disk so a later session can start from
+//! a warm index instead of re-walking the whole tree. Snapshots can be
+//! compressed with zstd; compression is detected from the frame magic bytes
+//! on load, so uncompressed snapshots keep loading unchanged.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::utils::error::{AgentGearError, Result};
+
+/// Current snapshot format version
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// Magic bytes at the start of every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// A single indexed path, stored relative to the index root
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotEntry {
+    pub path: String,
+    pub size: u64,
+    pub mtime: f64,
+    pub is_dir: bool,
+    pub is_binary: bool,
+}
+
+/// Serializable form of the file index
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    pub version: u32,
+    pub entries: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    /// Create a snapshot of the current format version
+    pub fn new(entries: Vec<SnapshotEntry>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            entries,
+        }
+    }
+}
+
+/// Check whether the snapshot bytes are a zstd frame
+#[inline]
+fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+/// Write a snapshot to disk atomically
+///
+/// # Arguments
+/// * `path` - Target snapshot file
+/// * `snapshot` - Snapshot to write
+/// * `compression_level` - zstd level (1-22), or None to store uncompressed
+pub fn write_snapshot(
+    path: &Path,
+    snapshot: &Snapshot,
+    compression_level: Option<i32>,
+) -> Result<()> {
+    let json = serde_json::to_vec(snapshot)
+        .map_err(|e| AgentGearError::Internal(format!("Failed to serialize index: {}", e)))?;
+
+    let bytes = match compression_level {
+        Some(level) => zstd::encode_all(json.as_slice(), level)?,
+        None => json,
+    };
+
+    super::atomic::atomic_write(path, &bytes)
+}
+
+/// Read a snapshot from disk, decompressing it if needed
+pub fn read_snapshot(path: &Path) -> Result<Snapshot> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            AgentGearError::PathNotFound(path.display().to_string())
+        } else {
+            AgentGearError::Io(e)
+        }
+    })?;
+
+    let json = if is_compressed(&bytes) {
+        zstd::decode_all(bytes.as_slice())?
+    } else {
+        bytes
+    };
+
+    let snapshot: Snapshot = serde_json::from_slice(&json)
+        .map_err(|e| AgentGearError::Internal(format!("Invalid index snapshot: {}", e)))?;
+
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(AgentGearError::Internal(format!(
+            "Unsupported index snapshot version: {}",
+            snapshot.version
+        )));
+    }
+
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn sample() -> Snapshot {
+        Snapshot::new(vec![SnapshotEntry {
+            path: "src/main.rs".to_string(),
+            size: 12,
+            mtime: 1.5,
+            is_dir: false,
+            is_binary: false,
+        }])
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip_uncompressed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("index.snap");
+
+        write_snapshot(&path, &sample(), None).unwrap();
+        assert!(!is_compressed(&std::fs::read(&path).unwrap()));
+
+        let loaded = read_snapshot(&path).unwrap();
+        assert_eq!(loaded.entries, sample().entries);
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip_compressed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("index.snap.zst");
+
+        write_snapshot(&path, &sample(), Some(3)).unwrap();
+        assert!(is_compressed(&std::fs::read(&path).unwrap()));
+
+        let loaded = read_snapshot(&path).unwrap();
+        assert_eq!(loaded.entries, sample().entries);
+    }
+}
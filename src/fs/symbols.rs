@@ -0,0 +1,220 @@
+//! Enclosing symbol lookup
+//!
+//! Finds the function, class or method a line belongs to by scanning upward
+//! for the nearest declaration, using a few regexes per language chosen by
+//! file extension. This is a heuristic, not a parser: declarations are
+//! recognised line by line and enclosure is judged by indentation, so
+//! unusual formatting can yield the wrong name or none.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::path::Path;
+
+/// Declaration patterns for one language; group 1 captures the name
+type Patterns = Vec<Regex>;
+
+fn compile(patterns: &[&str]) -> Patterns {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).expect("invalid built-in symbol pattern"))
+        .collect()
+}
+
+static RUST: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:(?:async|const|unsafe|extern(?:\s+"[^"]*")?)\s+)*(?:fn|struct|enum|trait|union|mod)\s+(\w+)"#,
+        r"^\s*(?:unsafe\s+)?impl(?:<.*?>)?\s+(?:.*?\s+for\s+)?(\w+)",
+        r"^\s*macro_rules!\s*(\w+)",
+    ])
+});
+
+static PYTHON: Lazy<Patterns> = Lazy::new(|| compile(&[r"^\s*(?:async\s+)?(?:def|class)\s+(\w+)"]));
+
+static JAVASCRIPT: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(\w+)",
+        r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?(?:class|interface|enum)\s+(\w+)",
+        r"^\s*(?:export\s+)?(?:const|let|var)\s+(\w+)\s*(?::[^=]+)?=\s*(?:async\s+)?(?:function\b|\([^)]*\)[^=]*=>|\w+\s*=>)",
+        r"^\s*(?:(?:public|private|protected|static|async|get|set|readonly|override)\s+)*(\w+)\s*\([^)]*\)\s*(?::\s*[^{]+)?\{\s*$",
+    ])
+});
+
+static GO: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r"^func\s+(?:\([^)]*\)\s*)?(\w+)",
+        r"^type\s+(\w+)\s+(?:struct|interface)\b",
+    ])
+});
+
+static JVM: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|sealed|partial|open|data)\s+)*(?:class|interface|enum|record|struct|object)\s+(\w+)",
+        r"^\s*(?:(?:public|private|protected|internal|static|final|abstract|synchronized|override|virtual|async)\s+)+[\w<>\[\],.? ]+\s+(\w+)\s*\(",
+        r"^\s*(?:(?:public|private|protected|internal|override|open|suspend|inline)\s+)*fun\s+(?:<[^>]*>\s*)?(?:\w+\.)?(\w+)",
+    ])
+});
+
+static C: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r"^\s*(?:template\s*<.*>\s*)?(?:class|struct|namespace)\s+(\w+)[^;]*$",
+        r"^[A-Za-z_][\w\s\*&:<>,]*?\b(\w+)\s*\([^;]*$",
+    ])
+});
+
+static RUBY: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r"^\s*def\s+(?:self\.)?(\w+[?!=]?)",
+        r"^\s*(?:class|module)\s+((?:\w+::)*\w+)",
+    ])
+});
+
+static PHP: Lazy<Patterns> = Lazy::new(|| {
+    compile(&[
+        r"^\s*(?:(?:public|private|protected|static|abstract|final)\s+)*function\s+&?(\w+)",
+        r"^\s*(?:(?:abstract|final)\s+)?(?:class|interface|trait|enum)\s+(\w+)",
+    ])
+});
+
+/// Control-flow keywords that the looser patterns can mistake for names
+const KEYWORDS: &[&str] = &[
+    "if", "else", "for", "while", "do", "switch", "case", "catch", "return", "new", "sizeof",
+];
+
+/// Declaration patterns for a file, chosen by extension
+///
+/// Returns None for languages without patterns.
+pub fn patterns_for(path: &Path) -> Option<&'static [Regex]> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let patterns: &Lazy<Patterns> = match extension.as_str() {
+        "rs" => &RUST,
+        "py" | "pyi" => &PYTHON,
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => &JAVASCRIPT,
+        "go" => &GO,
+        "java" | "kt" | "kts" | "cs" | "scala" => &JVM,
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => &C,
+        "rb" => &RUBY,
+        "php" => &PHP,
+        _ => return None,
+    };
+    Some(patterns.as_slice())
+}
+
+/// Width of a line's leading whitespace, counting a tab as one column
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Name declared on a line, if it holds a declaration
+fn declared_name(patterns: &[Regex], line: &str) -> Option<String> {
+    patterns.iter().find_map(|regex| {
+        let name = regex.captures(line)?.get(1)?.as_str();
+        (!KEYWORDS.contains(&name)).then(|| name.to_string())
+    })
+}
+
+/// Find the name of the declaration enclosing `lines[index]`
+///
+/// A declaration on the line itself counts. Above it, only declarations
+/// indented less than the line qualify, so a sibling function that ended
+/// earlier is not reported for a line in the next one.
+pub fn enclosing_symbol(patterns: &[Regex], lines: &[&str], index: usize) -> Option<String> {
+    let line = lines.get(index)?;
+    if let Some(name) = declared_name(patterns, line) {
+        return Some(name);
+    }
+
+    // Blank lines carry no indentation, so measure from the nearest
+    // non-blank line at or above the match
+    let mut limit = lines[..=index]
+        .iter()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .map(|l| indent(l))?;
+
+    for candidate in lines[..index].iter().rev() {
+        if candidate.trim().is_empty() {
+            continue;
+        }
+        let depth = indent(candidate);
+        if depth >= limit {
+            continue;
+        }
+        if let Some(name) = declared_name(patterns, candidate) {
+            return Some(name);
+        }
+        // Anything shallower is still a candidate, but nothing deeper
+        // than this line can enclose the match
+        limit = depth;
+        if limit == 0 {
+            break;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol_at(file: &str, content: &str, line_number: usize) -> Option<String> {
+        let patterns = patterns_for(Path::new(file))?;
+        let lines: Vec<&str> = content.lines().collect();
+        enclosing_symbol(patterns, &lines, line_number - 1)
+    }
+
+    #[test]
+    fn test_rust_symbols() {
+        let source = "\
+impl Server {
+    pub async fn handle_request(&self) {
+        if ready {
+            dispatch();
+        }
+    }
+
+    fn helper() {}
+}
+";
+        assert_eq!(
+            symbol_at("src/server.rs", source, 4).as_deref(),
+            Some("handle_request")
+        );
+        assert_eq!(
+            symbol_at("src/server.rs", source, 8).as_deref(),
+            Some("helper")
+        );
+        assert_eq!(
+            symbol_at("src/server.rs", source, 7).as_deref(),
+            Some("Server")
+        );
+    }
+
+    #[test]
+    fn test_python_symbols() {
+        let source = "\
+class Handler:
+    def first(self):
+        return 1
+
+    value = compute()
+
+def later():
+    pass
+";
+        assert_eq!(symbol_at("app.py", source, 3).as_deref(), Some("first"));
+        // A sibling method at the same depth does not enclose the line
+        assert_eq!(symbol_at("app.py", source, 5).as_deref(), Some("Handler"));
+        assert_eq!(symbol_at("app.py", source, 8).as_deref(), Some("later"));
+    }
+
+    #[test]
+    fn test_keywords_and_unknown_languages() {
+        let source = "function run() {\n  if (x) {\n    go();\n  }\n}\n";
+        assert_eq!(symbol_at("a.js", source, 3).as_deref(), Some("run"));
+
+        assert!(patterns_for(Path::new("notes.txt")).is_none());
+        assert!(patterns_for(Path::new("Makefile")).is_none());
+        assert_eq!(symbol_at("notes.txt", "def not_code():\n  x\n", 2), None);
+    }
+}
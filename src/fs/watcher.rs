@@ -3,18 +3,65 @@
 //! Provides real-time file system monitoring with debouncing to keep
 //! the in-memory index synchronized with disk changes.
 
-use crossbeam::channel::{unbounded, Receiver, Sender};
+use crossbeam::channel::{unbounded, Receiver, Sender, TryRecvError};
 use notify::{
     event::{CreateKind, ModifyKind, RemoveKind, RenameMode},
-    Config, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    Config, Event, EventKind, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher,
 };
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use pyo3::prelude::*;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
 use crate::utils::error::{AgentGearError, Result};
+use crate::utils::path::{to_posix_string, ExtensionFilter};
+
+/// Default interval between scans in `WatchMode::Poll`
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default number of applied changes a `ChangeLog` keeps
+pub const DEFAULT_CHANGE_LOG_ENTRIES: usize = 10_000;
+
+/// How a FileWatcher receives changes from the operating system
+#[pyclass(eq, eq_int)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Native notifications, falling back to polling if they cannot start
+    #[pyo3(name = "AUTO")]
+    Auto,
+    /// Native notifications only (inotify, FSEvents, ReadDirectoryChangesW)
+    #[pyo3(name = "NATIVE")]
+    Native,
+    /// Periodically rescan the tree and diff it against the last scan
+    #[pyo3(name = "POLL")]
+    Poll,
+}
+
+/// The notify backend feeding a FileWatcher, kept alive while it runs
+#[allow(dead_code)]
+enum Backend {
+    Native(RecommendedWatcher),
+    Poll(PollWatcher),
+}
+
+/// Create a notify watcher of type `W` sending its events to `tx`
+fn start_backend<W: Watcher>(
+    tx: Sender<notify::Result<Event>>,
+    config: Config,
+    root: &Path,
+    mode: RecursiveMode,
+) -> notify::Result<W> {
+    let mut watcher = W::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        config,
+    )?;
+    watcher.watch(root, mode)?;
+    Ok(watcher)
+}
 
 /// File change event types
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -23,6 +70,8 @@ pub enum ChangeKind {
     Created,
     /// File content was modified
     Modified,
+    /// File attributes (permissions, ownership, timestamps) changed
+    AttrChanged,
     /// File or directory was deleted
     Deleted,
     /// File or directory was renamed
@@ -38,14 +87,17 @@ pub struct FileChange {
     pub kind: ChangeKind,
     /// Timestamp of the change
     pub timestamp: Instant,
+    /// Number of raw events merged into this change (1 if none were merged);
+    /// a high count marks a file that is churning
+    pub merge_count: usize,
 }
 
 /// Debouncer for file system events
 ///
 /// Collects events over a time window and merges them to reduce noise.
 pub struct Debouncer {
-    /// Pending events by path
-    pending: HashMap<PathBuf, (ChangeKind, Instant)>,
+    /// Pending events by path: kind, last event time, raw event count
+    pending: HashMap<PathBuf, (ChangeKind, Instant, usize)>,
     /// Debounce duration
     duration: Duration,
 }
@@ -65,22 +117,29 @@ impl Debouncer {
 
         // Merge events: later events override earlier ones
         // Exception: Delete after Create = no event
-        if let Some((existing_kind, _)) = self.pending.get(&path) {
-            match (existing_kind, &kind) {
+        let mut merge_count = 1;
+        if let Some((existing_kind, _, count)) = self.pending.get_mut(&path) {
+            match (&*existing_kind, &kind) {
                 // Created then deleted = nothing happened
                 (ChangeKind::Created, ChangeKind::Deleted) => {
                     self.pending.remove(&path);
                     return;
                 }
                 // Created then modified = still created
-                (ChangeKind::Created, ChangeKind::Modified) => {
+                (ChangeKind::Created, ChangeKind::Modified | ChangeKind::AttrChanged) => {
+                    *count += 1;
                     return; // Keep Created
                 }
-                _ => {}
+                // Modified then attributes changed = still modified
+                (ChangeKind::Modified, ChangeKind::AttrChanged) => {
+                    *count += 1;
+                    return; // Keep Modified
+                }
+                _ => merge_count = *count + 1,
             }
         }
 
-        self.pending.insert(path, (kind, now));
+        self.pending.insert(path, (kind, now, merge_count));
     }
 
     /// Get events that have been stable for the debounce duration
@@ -89,12 +148,13 @@ impl Debouncer {
         let mut ready = Vec::new();
         let mut to_remove = Vec::new();
 
-        for (path, (kind, timestamp)) in self.pending.iter() {
+        for (path, (kind, timestamp, merge_count)) in self.pending.iter() {
             if now.duration_since(*timestamp) >= self.duration {
                 ready.push(FileChange {
                     path: path.clone(),
                     kind: kind.clone(),
                     timestamp: *timestamp,
+                    merge_count: *merge_count,
                 });
                 to_remove.push(path.clone());
             }
@@ -112,15 +172,37 @@ impl Debouncer {
         let events: Vec<FileChange> = self
             .pending
             .drain()
-            .map(|(path, (kind, timestamp))| FileChange {
+            .map(|(path, (kind, timestamp, merge_count))| FileChange {
                 path,
                 kind,
                 timestamp,
+                merge_count,
             })
             .collect();
         events
     }
 
+    /// Paths with pending events, without flushing them
+    ///
+    /// A rename reports both its source and its destination. Sorted and
+    /// free of duplicates.
+    pub fn pending_paths(&self) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .flat_map(|(path, (kind, _, _))| {
+                let target = match kind {
+                    ChangeKind::Renamed { to, .. } => Some(to.clone()),
+                    _ => None,
+                };
+                std::iter::once(path.clone()).chain(target)
+            })
+            .collect();
+        paths.sort_unstable();
+        paths.dedup();
+        paths
+    }
+
     /// Check if there are pending events
     pub fn has_pending(&self) -> bool {
         !self.pending.is_empty()
@@ -130,7 +212,7 @@ impl Debouncer {
 /// File system watcher with debouncing
 pub struct FileWatcher {
     /// The underlying notify watcher
-    _watcher: RecommendedWatcher,
+    backend: Backend,
     /// Channel receiver for events
     event_rx: Receiver<notify::Result<Event>>,
     /// Root directory being watched
@@ -139,62 +221,213 @@ pub struct FileWatcher {
     debouncer: RwLock<Debouncer>,
     /// Whether the watcher is running
     running: Arc<std::sync::atomic::AtomicBool>,
+    /// Cleared once the backend stops delivering events (disconnected
+    /// channel, backend error, or the root directory disappearing)
+    healthy: std::sync::atomic::AtomicBool,
+    /// Only report changes to files with these extensions (None = all)
+    extensions: Option<ExtensionFilter>,
+    /// Forward every event as it arrives, bypassing the debouncer
+    raw: bool,
 }
 
 impl FileWatcher {
     /// Create a new file watcher for the given root directory
-    pub fn new(root: PathBuf, debounce_duration: Duration) -> Result<Self> {
+    ///
+    /// With `recursive` false only the root directory itself is watched:
+    /// changes inside subdirectories are never reported, which keeps a flat
+    /// drop folder cheap to watch regardless of what is nested below it.
+    ///
+    /// A zero `debounce_duration` selects raw mode: `process_events`
+    /// returns every mapped event as soon as it is seen, unmerged, so the
+    /// consumer has to cope with duplicates and short-lived files (such as
+    /// a create immediately followed by a delete).
+    ///
+    /// Uses `WatchMode::Auto`; see `with_mode` to pick the backend.
+    pub fn new(root: PathBuf, debounce_duration: Duration, recursive: bool) -> Result<Self> {
+        Self::with_mode(
+            root,
+            debounce_duration,
+            recursive,
+            WatchMode::Auto,
+            DEFAULT_POLL_INTERVAL,
+        )
+    }
+
+    /// Create a new file watcher using the given backend
+    ///
+    /// `WatchMode::Poll` rescans the tree every `poll_interval`, stat'ing
+    /// every entry, so it costs CPU and I/O in proportion to the tree size
+    /// but works where native notifications silently deliver nothing
+    /// (network shares, some FUSE and container bind mounts). Changes are
+    /// seen up to one interval late. `WatchMode::Auto` uses native
+    /// notifications and only falls back to polling when they fail to
+    /// start, e.g. when the inotify watch limit is exhausted; it cannot
+    /// detect a native backend that starts but stays silent.
+    pub fn with_mode(
+        root: PathBuf,
+        debounce_duration: Duration,
+        recursive: bool,
+        mode: WatchMode,
+        poll_interval: Duration,
+    ) -> Result<Self> {
         let (tx, rx): (
             Sender<notify::Result<Event>>,
             Receiver<notify::Result<Event>>,
         ) = unbounded();
 
-        // Create the watcher with a simple event handler
-        let mut watcher = RecommendedWatcher::new(
-            move |res| {
-                let _ = tx.send(res);
-            },
-            Config::default().with_poll_interval(Duration::from_millis(100)),
-        )
-        .map_err(|e| AgentGearError::Internal(format!("Failed to create watcher: {}", e)))?;
-
-        // Start watching the root directory recursively
-        watcher
-            .watch(&root, RecursiveMode::Recursive)
-            .map_err(|e| AgentGearError::Internal(format!("Failed to watch directory: {}", e)))?;
+        let recursive_mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        let native_config = Config::default().with_poll_interval(Duration::from_millis(100));
+        let poll_config = Config::default().with_poll_interval(poll_interval);
+        let watch_error = |e: notify::Error| {
+            AgentGearError::Internal(format!("Failed to watch directory: {}", e))
+        };
+
+        let backend = match mode {
+            WatchMode::Native => Backend::Native(
+                start_backend(tx, native_config, &root, recursive_mode).map_err(watch_error)?,
+            ),
+            WatchMode::Poll => Backend::Poll(
+                start_backend(tx, poll_config, &root, recursive_mode).map_err(watch_error)?,
+            ),
+            WatchMode::Auto => {
+                match start_backend(tx.clone(), native_config, &root, recursive_mode) {
+                    Ok(watcher) => Backend::Native(watcher),
+                    Err(e) => {
+                        tracing::warn!(
+                            "Native file watching unavailable ({}), falling back to polling",
+                            e
+                        );
+                        Backend::Poll(
+                            start_backend(tx, poll_config, &root, recursive_mode)
+                                .map_err(watch_error)?,
+                        )
+                    }
+                }
+            }
+        };
 
         Ok(Self {
-            _watcher: watcher,
+            backend,
             event_rx: rx,
             root,
             debouncer: RwLock::new(Debouncer::new(debounce_duration)),
             running: Arc::new(std::sync::atomic::AtomicBool::new(true)),
+            healthy: std::sync::atomic::AtomicBool::new(true),
+            extensions: None,
+            raw: debounce_duration.is_zero(),
         })
     }
 
+    /// Drop events for files outside the given extensions
+    ///
+    /// Paths without an extension and existing directories are still
+    /// reported, since they may be directories the index has to track.
+    pub fn with_extensions(mut self, extensions: ExtensionFilter) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// Whether an event for this path should reach the debouncer
+    #[inline]
+    fn is_relevant(&self, path: &Path) -> bool {
+        match self.extensions {
+            Some(ref filter) => path.extension().is_none() || filter.allows(path) || path.is_dir(),
+            None => true,
+        }
+    }
+
     /// Process pending events from the watcher
     ///
     /// This should be called periodically to collect and debounce events.
+    /// In raw mode every event received since the last call is returned,
+    /// in arrival order.
     pub fn process_events(&self) -> Vec<FileChange> {
+        if self.raw {
+            let now = Instant::now();
+            let mut changes = Vec::new();
+            self.ingest(|path, kind| {
+                changes.push(FileChange {
+                    path,
+                    kind,
+                    timestamp: now,
+                    merge_count: 1,
+                })
+            });
+            return changes;
+        }
+
+        let mut debouncer = self.debouncer.write();
+        self.ingest(|path, kind| debouncer.add_event(path, kind));
+
+        // Flush debounced events
+        debouncer.flush()
+    }
+
+    /// Whether events bypass the debouncer
+    pub fn is_raw(&self) -> bool {
+        self.raw
+    }
+
+    /// The backend in use: `Native` or `Poll`, never `Auto`
+    pub fn mode(&self) -> WatchMode {
+        match self.backend {
+            Backend::Native(_) => WatchMode::Native,
+            Backend::Poll(_) => WatchMode::Poll,
+        }
+    }
+
+    /// Paths with changes that have not been flushed to the index yet
+    ///
+    /// Raw events still queued from the backend are moved into the
+    /// debouncer first, so a change made just now is included. Nothing is
+    /// flushed. Always empty in raw mode, where nothing waits in a window.
+    pub fn pending_paths(&self) -> Vec<PathBuf> {
+        if self.raw {
+            return Vec::new();
+        }
+        let mut debouncer = self.debouncer.write();
+        self.ingest(|path, kind| debouncer.add_event(path, kind));
+        debouncer.pending_paths()
+    }
+
+    /// Drain raw backend events, passing each mapped change to `emit`
+    fn ingest(&self, mut emit: impl FnMut(PathBuf, ChangeKind)) {
         // Collect all pending raw events to minimize lock duration/frequency
         let mut raw_events = Vec::new();
-        while let Ok(event_result) = self.event_rx.try_recv() {
-            if let Ok(event) = event_result {
-                raw_events.push(event);
+        loop {
+            match self.event_rx.try_recv() {
+                Ok(Ok(event)) => raw_events.push(event),
+                Ok(Err(e)) => {
+                    tracing::warn!("File watcher backend error: {}", e);
+                    self.mark_unhealthy();
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.mark_unhealthy();
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
             }
         }
-
-        let mut debouncer = self.debouncer.write();
+        if !self.root.is_dir() {
+            self.mark_unhealthy();
+        }
 
         for event in raw_events {
             let kind = match event.kind {
                 EventKind::Create(CreateKind::File | CreateKind::Folder) => ChangeKind::Created,
                 EventKind::Create(CreateKind::Any) => ChangeKind::Created,
                 EventKind::Modify(ModifyKind::Data(_)) => ChangeKind::Modified,
+                EventKind::Modify(ModifyKind::Metadata(_)) => ChangeKind::AttrChanged,
                 EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
                     // Handle rename: paths[0] = from, paths[1] = to
-                    if event.paths.len() >= 2 {
-                        debouncer.add_event(
+                    if event.paths.len() >= 2
+                        && (self.is_relevant(&event.paths[0]) || self.is_relevant(&event.paths[1]))
+                    {
+                        emit(
                             event.paths[0].clone(),
                             ChangeKind::Renamed {
                                 from: event.paths[0].clone(),
@@ -212,12 +445,11 @@ impl FileWatcher {
             };
 
             for path in event.paths {
-                debouncer.add_event(path, kind.clone());
+                if self.is_relevant(&path) {
+                    emit(path, kind.clone());
+                }
             }
         }
-
-        // Flush debounced events
-        debouncer.flush()
     }
 
     /// Get the root directory being watched
@@ -230,6 +462,22 @@ impl FileWatcher {
         self.running.load(std::sync::atomic::Ordering::SeqCst)
     }
 
+    /// Check whether the backend is still delivering events
+    ///
+    /// Once this returns false it stays false: the index is no longer kept
+    /// in sync and the watcher has to be recreated.
+    pub fn is_healthy(&self) -> bool {
+        if !self.root.is_dir() {
+            self.mark_unhealthy();
+        }
+        self.healthy.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn mark_unhealthy(&self) {
+        self.healthy
+            .store(false, std::sync::atomic::Ordering::SeqCst);
+    }
+
     /// Stop the watcher
     pub fn stop(&self) {
         self.running
@@ -243,6 +491,181 @@ impl Drop for FileWatcher {
     }
 }
 
+/// A change reported by a `WatchHandle`
+#[pyclass]
+#[derive(Clone, Debug)]
+pub struct WatchEvent {
+    /// "created", "modified", "attr_changed", "deleted" or "renamed"
+    #[pyo3(get)]
+    pub kind: String,
+
+    /// Path that changed, relative to the FileSystem root (absolute if
+    /// outside it); the old path for renames
+    #[pyo3(get)]
+    pub path: String,
+
+    /// New path of a rename, None for every other kind
+    #[pyo3(get)]
+    pub dest_path: Option<String>,
+
+    /// Number of raw events merged into this one by the debouncer
+    #[pyo3(get)]
+    pub merge_count: usize,
+}
+
+impl WatchEvent {
+    /// Describe a change with paths relative to `base`
+    pub fn from_change(change: &FileChange, base: &Path) -> Self {
+        let relative = |path: &Path| to_posix_string(path.strip_prefix(base).unwrap_or(path));
+        let (kind, dest_path) = match change.kind {
+            ChangeKind::Created => ("created", None),
+            ChangeKind::Modified => ("modified", None),
+            ChangeKind::AttrChanged => ("attr_changed", None),
+            ChangeKind::Deleted => ("deleted", None),
+            ChangeKind::Renamed { ref to, .. } => ("renamed", Some(relative(to))),
+        };
+        Self {
+            kind: kind.to_string(),
+            path: relative(&change.path),
+            dest_path,
+            merge_count: change.merge_count,
+        }
+    }
+}
+
+#[pymethods]
+impl WatchEvent {
+    fn __repr__(&self) -> String {
+        match self.dest_path {
+            Some(ref dest) => format!("WatchEvent({} '{}' -> '{}')", self.kind, self.path, dest),
+            None => format!("WatchEvent({} '{}')", self.kind, self.path),
+        }
+    }
+}
+
+/// Changes the watcher loop has applied to the index, oldest first
+///
+/// The loop is the only consumer of a watcher's events. Readers such as
+/// `pending_changes` look here instead of draining the watcher themselves,
+/// so counting changes never takes them away from index maintenance, and
+/// every change in the log is already reflected in the index. Once full,
+/// the oldest changes are dropped.
+pub struct ChangeLog {
+    changes: Mutex<VecDeque<FileChange>>,
+    capacity: usize,
+}
+
+impl ChangeLog {
+    /// Create an empty log keeping at most `capacity` changes
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            changes: Mutex::new(VecDeque::new()),
+            capacity,
+        }
+    }
+
+    /// Append changes that have been applied to the index
+    pub fn record(&self, applied: impl IntoIterator<Item = FileChange>) {
+        let mut changes = self.changes.lock();
+        changes.extend(applied);
+        let excess = changes.len().saturating_sub(self.capacity);
+        changes.drain(..excess);
+    }
+
+    /// Number of changes waiting to be taken
+    pub fn len(&self) -> usize {
+        self.changes.lock().len()
+    }
+
+    /// Whether no changes are waiting
+    pub fn is_empty(&self) -> bool {
+        self.changes.lock().is_empty()
+    }
+
+    /// Remove and return every waiting change, oldest first
+    pub fn take(&self) -> Vec<FileChange> {
+        self.changes.lock().drain(..).collect()
+    }
+}
+
+/// Watcher slot shared by a `WatchHandle` and its owner; emptying it stops
+/// the watch
+pub type WatchSlot = Mutex<Option<FileWatcher>>;
+
+/// Handle to an extra watch on a subtree, with its own event queue
+///
+/// Events are collected by the backend and debounced independently of the
+/// FileSystem's main watcher; `events()` drains the ones whose debounce
+/// window has passed. The watch stops when the handle is stopped, dropped,
+/// or its FileSystem is closed.
+#[pyclass]
+pub struct WatchHandle {
+    watcher: Arc<WatchSlot>,
+    /// Paths in events are reported relative to this directory
+    base: PathBuf,
+}
+
+impl WatchHandle {
+    /// Wrap a running watcher, reporting paths relative to `base`
+    pub fn new(watcher: FileWatcher, base: PathBuf) -> Self {
+        Self {
+            watcher: Arc::new(Mutex::new(Some(watcher))),
+            base,
+        }
+    }
+
+    /// A weak reference the owner can use to stop the watch
+    pub fn slot(&self) -> Weak<WatchSlot> {
+        Arc::downgrade(&self.watcher)
+    }
+}
+
+#[pymethods]
+impl WatchHandle {
+    /// Take the changes whose debounce window has passed
+    ///
+    /// Non-blocking; returns an empty list when nothing is ready or the
+    /// watch has stopped.
+    pub fn events(&self) -> Vec<WatchEvent> {
+        let guard = self.watcher.lock();
+        let Some(ref watcher) = *guard else {
+            return Vec::new();
+        };
+        watcher
+            .process_events()
+            .iter()
+            .map(|change| WatchEvent::from_change(change, &self.base))
+            .collect()
+    }
+
+    /// Stop watching and release the backend
+    ///
+    /// Pending events are discarded. Calling it again is a no-op.
+    pub fn stop(&self) {
+        self.watcher.lock().take();
+    }
+
+    /// Check whether the watch is running and its backend is healthy
+    pub fn is_active(&self) -> bool {
+        self.watcher.lock().as_ref().is_some_and(|w| w.is_healthy())
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        self.stop();
+        false
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,6 +690,43 @@ mod tests {
         assert_eq!(events[0].path, PathBuf::from("/test/file.txt"));
     }
 
+    #[test]
+    fn test_change_log() {
+        let change = |name: &str, kind: ChangeKind| FileChange {
+            path: PathBuf::from("/root").join(name),
+            kind,
+            timestamp: Instant::now(),
+            merge_count: 1,
+        };
+        let log = ChangeLog::new(2);
+        assert!(log.is_empty());
+
+        log.record(vec![change("a.rs", ChangeKind::Created)]);
+        log.record(vec![
+            change("b.rs", ChangeKind::Modified),
+            change(
+                "c.rs",
+                ChangeKind::Renamed {
+                    from: PathBuf::from("/root/c.rs"),
+                    to: PathBuf::from("/root/d.rs"),
+                },
+            ),
+        ]);
+
+        // The oldest change is dropped at capacity
+        assert_eq!(log.len(), 2);
+        let events: Vec<WatchEvent> = log
+            .take()
+            .iter()
+            .map(|c| WatchEvent::from_change(c, Path::new("/root")))
+            .collect();
+        assert_eq!(events[0].kind, "modified");
+        assert_eq!(events[0].path, "b.rs");
+        assert_eq!(events[1].kind, "renamed");
+        assert_eq!(events[1].dest_path.as_deref(), Some("d.rs"));
+        assert!(log.is_empty());
+    }
+
     #[test]
     fn test_debouncer_merge_create_delete() {
         let mut debouncer = Debouncer::new(Duration::from_millis(50));
@@ -293,18 +753,199 @@ mod tests {
         assert!(matches!(events[0].kind, ChangeKind::Created));
     }
 
+    #[test]
+    fn test_debouncer_merge_modify_attr_changed() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(50));
+
+        debouncer.add_event(PathBuf::from("/test/file.txt"), ChangeKind::Modified);
+        debouncer.add_event(PathBuf::from("/test/file.txt"), ChangeKind::AttrChanged);
+
+        thread::sleep(Duration::from_millis(60));
+
+        let events = debouncer.flush();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].kind, ChangeKind::Modified));
+    }
+
+    #[test]
+    fn test_debouncer_merge_count() {
+        let mut debouncer = Debouncer::new(Duration::from_millis(50));
+        let hot = PathBuf::from("/test/hot.txt");
+        let cold = PathBuf::from("/test/cold.txt");
+
+        for _ in 0..5 {
+            debouncer.add_event(hot.clone(), ChangeKind::Modified);
+        }
+        debouncer.add_event(hot.clone(), ChangeKind::AttrChanged);
+        debouncer.add_event(cold.clone(), ChangeKind::Modified);
+
+        thread::sleep(Duration::from_millis(60));
+
+        let events = debouncer.flush();
+        let count = |path: &Path| events.iter().find(|e| e.path == path).unwrap().merge_count;
+        assert_eq!(count(&hot), 6);
+        assert_eq!(count(&cold), 1);
+
+        // Counts restart once a change has been flushed
+        debouncer.add_event(hot.clone(), ChangeKind::Modified);
+        let events = debouncer.flush_all();
+        assert_eq!(events[0].merge_count, 1);
+    }
+
+    #[test]
+    fn test_debouncer_pending_paths() {
+        let mut debouncer = Debouncer::new(Duration::from_secs(60));
+        debouncer.add_event(PathBuf::from("/test/b.txt"), ChangeKind::Modified);
+        debouncer.add_event(
+            PathBuf::from("/test/old.txt"),
+            ChangeKind::Renamed {
+                from: PathBuf::from("/test/old.txt"),
+                to: PathBuf::from("/test/new.txt"),
+            },
+        );
+
+        assert_eq!(
+            debouncer.pending_paths(),
+            vec![
+                PathBuf::from("/test/b.txt"),
+                PathBuf::from("/test/new.txt"),
+                PathBuf::from("/test/old.txt"),
+            ]
+        );
+
+        // Peeking does not consume the events
+        assert_eq!(debouncer.flush_all().len(), 2);
+        assert!(debouncer.pending_paths().is_empty());
+    }
+
     #[test]
     fn test_watcher_creation() {
         let dir = tempdir().unwrap();
-        let watcher = FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(100));
+        let watcher = FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(100), true);
         assert!(watcher.is_ok());
     }
 
+    #[test]
+    fn test_watcher_raw_mode() {
+        let dir = tempdir().unwrap();
+        let debounced =
+            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(100), true).unwrap();
+        assert!(!debounced.is_raw());
+
+        let raw = FileWatcher::new(dir.path().to_path_buf(), Duration::ZERO, true).unwrap();
+        assert!(raw.is_raw());
+        assert!(raw.process_events().is_empty());
+        assert!(raw.pending_paths().is_empty());
+        drop(raw);
+
+        // Events are forwarded as they arrive, with nothing held back
+        let raw = FileWatcher::with_mode(
+            dir.path().to_path_buf(),
+            Duration::ZERO,
+            true,
+            WatchMode::Poll,
+            Duration::from_millis(20),
+        )
+        .unwrap();
+        assert!(raw.is_raw());
+
+        let created = dir.path().join("raw.txt");
+        std::fs::write(&created, "content").unwrap();
+
+        let mut events = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while events.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+            assert!(raw.pending_paths().is_empty());
+            events.extend(raw.process_events());
+        }
+
+        let event = events
+            .iter()
+            .find(|e| e.path == created)
+            .expect("raw mode forwards the change");
+        assert_eq!(event.kind, ChangeKind::Created);
+        assert_eq!(event.merge_count, 1);
+    }
+
+    #[test]
+    fn test_watcher_poll_mode() {
+        let dir = tempdir().unwrap();
+        let existing = dir.path().join("existing.txt");
+        std::fs::write(&existing, "initial").unwrap();
+
+        let watcher = FileWatcher::with_mode(
+            dir.path().to_path_buf(),
+            Duration::from_millis(10),
+            true,
+            WatchMode::Poll,
+            Duration::from_millis(20),
+        )
+        .unwrap();
+        assert_eq!(watcher.mode(), WatchMode::Poll);
+
+        let created = dir.path().join("new_file.txt");
+        std::fs::write(&created, "content").unwrap();
+        std::fs::remove_file(&existing).unwrap();
+
+        let mut events = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while events.len() < 2 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+            events.extend(watcher.process_events());
+        }
+
+        assert!(events
+            .iter()
+            .any(|e| e.path == created && e.kind == ChangeKind::Created));
+        assert!(events
+            .iter()
+            .any(|e| e.path == existing && e.kind == ChangeKind::Deleted));
+    }
+
+    #[test]
+    fn test_watch_handle() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("hot")).unwrap();
+
+        let watcher = FileWatcher::with_mode(
+            dir.path().join("hot"),
+            Duration::from_millis(10),
+            true,
+            WatchMode::Poll,
+            Duration::from_millis(20),
+        )
+        .unwrap();
+        let handle = WatchHandle::new(watcher, dir.path().to_path_buf());
+        let slot = handle.slot();
+        assert!(handle.is_active());
+
+        std::fs::write(dir.path().join("hot/a.txt"), "a").unwrap();
+
+        let mut events = Vec::new();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while events.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+            events.extend(handle.events());
+        }
+        assert!(events
+            .iter()
+            .any(|e| e.path == "hot/a.txt" && e.kind == "created"));
+
+        // The owner can stop the watch through its weak slot
+        slot.upgrade().unwrap().lock().take();
+        assert!(!handle.is_active());
+        assert!(handle.events().is_empty());
+
+        drop(handle);
+        assert!(slot.upgrade().is_none());
+    }
+
     #[test]
     fn test_watcher_detects_file_creation() {
         let dir = tempdir().unwrap();
         let watcher =
-            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50)).unwrap();
+            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50), true).unwrap();
 
         // Create a file
         let file_path = dir.path().join("new_file.txt");
@@ -333,7 +974,7 @@ mod tests {
         std::fs::write(&file_path, "initial").unwrap();
 
         let watcher =
-            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50)).unwrap();
+            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50), true).unwrap();
 
         // Modify the file
         std::fs::write(&file_path, "modified").unwrap();
@@ -356,7 +997,7 @@ mod tests {
         std::fs::write(&file_path, "content").unwrap();
 
         let watcher =
-            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50)).unwrap();
+            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50), true).unwrap();
 
         // Delete the file
         std::fs::remove_file(&file_path).unwrap();
@@ -373,4 +1014,89 @@ mod tests {
 
         assert!(!deleted_events.is_empty(), "Should detect file deletion");
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watcher_detects_permission_change() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempdir().unwrap();
+
+        // Create file before watching
+        let file_path = dir.path().join("script.sh");
+        std::fs::write(&file_path, "echo hi").unwrap();
+
+        let watcher =
+            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50), true).unwrap();
+
+        std::fs::set_permissions(&file_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Poll until the debounced event surfaces
+        let mut events = Vec::new();
+        for _ in 0..20 {
+            thread::sleep(Duration::from_millis(50));
+            events.extend(watcher.process_events());
+            if !events.is_empty() {
+                break;
+            }
+        }
+
+        let attr_events: Vec<_> = events
+            .iter()
+            .filter(|e| matches!(e.kind, ChangeKind::AttrChanged))
+            .collect();
+
+        assert!(!attr_events.is_empty(), "Should detect permission change");
+    }
+
+    #[test]
+    fn test_watcher_non_recursive_ignores_subdirs() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+        let watcher =
+            FileWatcher::new(dir.path().to_path_buf(), Duration::from_millis(50), false).unwrap();
+
+        std::fs::write(dir.path().join("sub/nested.txt"), "nested").unwrap();
+        std::fs::write(dir.path().join("top.txt"), "top").unwrap();
+
+        // Poll until the top-level change surfaces, then once more
+        let mut events = Vec::new();
+        for _ in 0..20 {
+            thread::sleep(Duration::from_millis(50));
+            events.extend(watcher.process_events());
+            if events.iter().any(|e| e.path.ends_with("top.txt")) {
+                break;
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+        events.extend(watcher.process_events());
+
+        assert!(
+            events.iter().any(|e| e.path.ends_with("top.txt")),
+            "Should detect top-level change"
+        );
+        assert!(
+            !events.iter().any(|e| e.path.ends_with("nested.txt")),
+            "Should ignore changes in subdirectories"
+        );
+    }
+
+    #[test]
+    fn test_watcher_unhealthy_when_root_removed() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("watched");
+        std::fs::create_dir(&root).unwrap();
+
+        let watcher = FileWatcher::new(root.clone(), Duration::from_millis(10), true).unwrap();
+        assert!(watcher.is_healthy());
+
+        std::fs::remove_dir_all(&root).unwrap();
+        watcher.process_events();
+        assert!(!watcher.is_healthy());
+
+        // Recreating the directory does not revive the watcher
+        std::fs::create_dir(&root).unwrap();
+        assert!(!watcher.is_healthy());
+    }
 }
@@ -22,9 +22,43 @@ fn _rust_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Register search result types
     m.add_class::<fs::searcher::SearchResult>()?;
     m.add_class::<fs::searcher::SearchOptions>()?;
+    m.add_class::<fs::searcher::SearchSummary>()?;
+    m.add_class::<fs::searcher::SearchIter>()?;
+    m.add_class::<fs::searcher::Location>()?;
+    m.add_class::<fs::searcher::SearchOrder>()?;
+    m.add_class::<fs::searcher::BinaryMode>()?;
+
+    // Register pattern validation helpers
+    m.add_function(wrap_pyfunction!(fs::searcher::validate_glob, m)?)?;
+    m.add_function(wrap_pyfunction!(fs::searcher::validate_regex, m)?)?;
+
+    // Register in-memory search
+    m.add_function(wrap_pyfunction!(fs::searcher::grep_text_content, m)?)?;
+
+    // Register the open file limit controls
+    m.add_function(wrap_pyfunction!(utils::limit::set_max_open_files, m)?)?;
+    m.add_function(wrap_pyfunction!(utils::limit::max_open_files, m)?)?;
 
     // Register metadata types
     m.add_class::<fs::index::FileMetadata>()?;
+    m.add_class::<fs::io::LineEnding>()?;
+    m.add_class::<fs::diff::DiffHunk>()?;
+    m.add_class::<fs::follow::FollowHandle>()?;
+    m.add_class::<fs::watcher::WatchMode>()?;
+    m.add_class::<fs::watcher::WatchHandle>()?;
+    m.add_class::<fs::watcher::WatchEvent>()?;
+
+    // Register exceptions
+    m.add(
+        "WriteVerificationError",
+        m.py().get_type::<utils::error::WriteVerificationError>(),
+    )?;
+
+    // Default directory ignores, for inspection and editing
+    m.add(
+        "DEFAULT_IGNORED_DIRS",
+        utils::path::DEFAULT_IGNORED_DIRS.to_vec(),
+    )?;
 
     // Module version
     m.add("__version__", env!("CARGO_PKG_VERSION"))?;
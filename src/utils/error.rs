@@ -1,9 +1,17 @@
 //! Error types for Agent-Gear
 
-use pyo3::exceptions::{PyIOError, PyRuntimeError, PyValueError};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyIOError, PyPermissionError, PyRuntimeError, PyValueError};
 use pyo3::PyErr;
 use thiserror::Error;
 
+create_exception!(
+    _rust_core,
+    WriteVerificationError,
+    PyIOError,
+    "Raised when a verified write reads back different bytes than were written."
+);
+
 /// Main error type for Agent-Gear operations
 #[derive(Error, Debug)]
 pub enum AgentGearError {
@@ -39,6 +47,30 @@ pub enum AgentGearError {
     #[error("Regex error: {0}")]
     Regex(String),
 
+    /// Rename would cross filesystems and therefore not be atomic
+    #[error("Cannot atomically move {0} to {1}: paths are on different filesystems")]
+    CrossDevice(String, String),
+
+    /// File read back after a verified write differs from what was written
+    #[error("Verification failed for {0}: content on disk differs from what was written")]
+    VerifyFailed(String),
+
+    /// Unknown encoding, or text that cannot be converted
+    #[error("Encoding error: {0}")]
+    Encoding(String),
+
+    /// File is larger than the read limit and was not loaded
+    #[error("File too large: {path} is {size} bytes, over the read limit of {limit} bytes")]
+    FileTooLarge { path: String, size: u64, limit: u64 },
+
+    /// Write refused by the FileSystem's writable_extensions policy
+    #[error("Write not allowed: {0}")]
+    WriteNotAllowed(String),
+
+    /// Unified diff is malformed or does not match the file
+    #[error("Patch failed: {0}")]
+    Patch(String),
+
     /// Generic internal error
     #[error("Internal error: {0}")]
     Internal(String),
@@ -61,6 +93,25 @@ impl From<AgentGearError> for PyErr {
             }
             AgentGearError::Glob(e) => PyValueError::new_err(format!("Glob error: {}", e)),
             AgentGearError::Regex(e) => PyValueError::new_err(format!("Regex error: {}", e)),
+            AgentGearError::CrossDevice(from, to) => PyIOError::new_err(format!(
+                "Cannot atomically move {} to {}: paths are on different filesystems; \
+                 read the file and use write_file() to copy it instead",
+                from, to
+            )),
+            AgentGearError::VerifyFailed(p) => WriteVerificationError::new_err(format!(
+                "Verification failed for {}: content on disk differs from what was written",
+                p
+            )),
+            AgentGearError::Encoding(e) => PyValueError::new_err(e),
+            AgentGearError::FileTooLarge { path, size, limit } => PyIOError::new_err(format!(
+                "File too large: {} is {} bytes, over the read limit of {} bytes; \
+                 use read_lines() or read_file_range() to read part of it",
+                path, size, limit
+            )),
+            AgentGearError::WriteNotAllowed(p) => {
+                PyPermissionError::new_err(format!("Write not allowed: {}", p))
+            }
+            AgentGearError::Patch(e) => PyValueError::new_err(format!("Patch failed: {}", e)),
             AgentGearError::Internal(e) => {
                 PyRuntimeError::new_err(format!("Internal error: {}", e))
             }
@@ -0,0 +1,128 @@
+//! Process-wide cap on concurrently open files
+//!
+//! Parallel batch reads and searches open one file per Rayon worker, and a
+//! large pool on a host with a low `ulimit -n` can run out of descriptors
+//! (EMFILE), which surfaces as unrelated-looking I/O errors. Every such open
+//! goes through `acquire`, which blocks once the cap is reached. The limit is
+//! per process because the descriptor table is.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::{Condvar, Mutex};
+
+/// Default number of files that may be open at once
+pub const DEFAULT_MAX_OPEN_FILES: usize = 256;
+
+static LIMITER: OpenFileLimiter = OpenFileLimiter::new(DEFAULT_MAX_OPEN_FILES);
+
+struct State {
+    open: usize,
+    limit: usize,
+}
+
+/// Counting semaphore over open file descriptors
+struct OpenFileLimiter {
+    state: Mutex<State>,
+    released: Condvar,
+}
+
+impl OpenFileLimiter {
+    const fn new(limit: usize) -> Self {
+        Self {
+            state: Mutex::new(State { open: 0, limit }),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> OpenFilePermit<'_> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while state.open >= state.limit {
+            state = self.released.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        state.open += 1;
+        OpenFilePermit { limiter: self }
+    }
+
+    fn set_limit(&self, limit: usize) {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).limit = limit;
+        self.released.notify_all();
+    }
+
+    fn limit(&self) -> usize {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).limit
+    }
+}
+
+/// Slot for one open file, returned to the pool on drop
+pub struct OpenFilePermit<'a> {
+    limiter: &'a OpenFileLimiter,
+}
+
+impl Drop for OpenFilePermit<'_> {
+    fn drop(&mut self) {
+        self.limiter
+            .state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .open -= 1;
+        self.limiter.released.notify_one();
+    }
+}
+
+/// Wait for a free slot; hold the permit while the file is open
+#[inline]
+pub fn acquire() -> OpenFilePermit<'static> {
+    LIMITER.acquire()
+}
+
+/// Set how many files batch reads and searches may hold open at once
+///
+/// Args:
+///     limit: Maximum concurrently open files (must be at least 1)
+#[pyfunction]
+pub fn set_max_open_files(limit: usize) -> PyResult<()> {
+    if limit == 0 {
+        return Err(PyValueError::new_err("max open files must be at least 1"));
+    }
+    LIMITER.set_limit(limit);
+    Ok(())
+}
+
+/// Get the current cap on concurrently open files
+#[pyfunction]
+pub fn max_open_files() -> usize {
+    LIMITER.limit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_limiter_bounds_concurrency() {
+        let limiter = Arc::new(OpenFileLimiter::new(2));
+        let open = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let (limiter, open, peak) = (limiter.clone(), open.clone(), peak.clone());
+                std::thread::spawn(move || {
+                    let _permit = limiter.acquire();
+                    let now = open.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(10));
+                    open.fetch_sub(1, Ordering::SeqCst);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+        assert_eq!(limiter.state.lock().unwrap().open, 0);
+    }
+}
@@ -1,3 +1,5 @@
 //! Utility modules
 
 pub mod error;
+pub mod limit;
+pub mod path;
@@ -0,0 +1,413 @@
+//! Path string helpers
+//!
+//! Paths handed back to Python always use `/` as the separator, so they
+//! round-trip through globs and look the same on every platform. Native
+//! separators are kept internally for filesystem operations.
+
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Convert a path to a string with forward-slash separators
+#[inline]
+pub fn to_posix_string(path: &Path) -> String {
+    let s = path.to_string_lossy();
+    if cfg!(windows) {
+        s.replace('\\', "/")
+    } else {
+        s.into_owned()
+    }
+}
+
+/// Case-fold a path for case-insensitive comparison
+#[inline]
+pub fn fold_case(path: &Path) -> PathBuf {
+    PathBuf::from(path.to_string_lossy().to_lowercase())
+}
+
+/// Detect whether the filesystem holding `dir` ignores case
+///
+/// Probes `dir` itself under a case-swapped name, so nothing is written to
+/// disk. Falls back to the platform default (case-insensitive on Windows and
+/// macOS) when the name has no cased letters or cannot be probed.
+pub fn is_case_insensitive_fs(dir: &Path) -> bool {
+    let platform_default = cfg!(any(windows, target_os = "macos"));
+
+    let Some(name) = dir.file_name().and_then(|n| n.to_str()) else {
+        return platform_default;
+    };
+    let swapped: String = name
+        .chars()
+        .map(|c| {
+            if c.is_lowercase() {
+                c.to_ascii_uppercase()
+            } else {
+                c.to_ascii_lowercase()
+            }
+        })
+        .collect();
+    if swapped == name {
+        return platform_default;
+    }
+
+    match (
+        std::fs::metadata(dir),
+        std::fs::metadata(dir.with_file_name(swapped)),
+    ) {
+        #[cfg(unix)]
+        (Ok(a), Ok(b)) => {
+            use std::os::unix::fs::MetadataExt;
+            a.dev() == b.dev() && a.ino() == b.ino()
+        }
+        #[cfg(not(unix))]
+        (Ok(_), Ok(_)) => true,
+        (Ok(_), Err(_)) => false,
+        _ => platform_default,
+    }
+}
+
+/// Longest shared leading path, compared component-wise
+///
+/// `src/foo` and `src/foobar` share `src`, not `src/foo`. Returns None for
+/// an empty input.
+pub fn common_prefix(paths: &[PathBuf]) -> Option<PathBuf> {
+    let (first, rest) = paths.split_first()?;
+    let mut prefix: Vec<_> = first.components().collect();
+
+    for path in rest {
+        let shared = prefix
+            .iter()
+            .zip(path.components())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        prefix.truncate(shared);
+    }
+
+    Some(prefix.iter().collect())
+}
+
+/// Resolve root-relative paths to their canonical form, in place
+///
+/// Symlinks are followed with one `canonicalize` syscall per path, spread
+/// across the rayon pool. A result inside the canonical root stays relative
+/// to it; one a symlink leads out of root becomes absolute. Paths that fail
+/// to resolve, such as dangling links, are left unchanged.
+pub fn canonicalize_paths<'a, I>(root: &Path, paths: I)
+where
+    I: IntoParallelIterator<Item = &'a mut String>,
+{
+    let canonical_root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    paths.into_par_iter().for_each(|path| {
+        let Ok(resolved) = std::fs::canonicalize(root.join(&*path)) else {
+            return;
+        };
+        *path = match resolved.strip_prefix(&canonical_root) {
+            Ok(relative) => to_posix_string(relative),
+            Err(_) => to_posix_string(&resolved),
+        };
+    });
+}
+
+/// Allowlist of file extensions, compared case-insensitively
+///
+/// Extensions are given without the leading dot (`"rs"`, `"py"`); a leading
+/// dot is tolerated. Directories are never filtered by callers, so the walk
+/// still reaches allowed files nested under any directory.
+#[derive(Clone, Debug)]
+pub struct ExtensionFilter {
+    extensions: HashSet<String>,
+}
+
+impl ExtensionFilter {
+    /// Create a filter allowing the given extensions
+    pub fn new<I, S>(extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            extensions: extensions
+                .into_iter()
+                .map(|e| e.as_ref().trim_start_matches('.').to_lowercase())
+                .collect(),
+        }
+    }
+
+    /// Whether the file's extension is in the allowlist
+    #[inline]
+    pub fn allows(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| self.extensions.contains(&e.to_lowercase()))
+    }
+}
+
+/// Per-extension overrides of the null-byte binary heuristic
+///
+/// An extension listed as both binary and text is treated as text.
+#[derive(Clone, Debug)]
+pub struct BinaryOverrides {
+    binary: ExtensionFilter,
+    text: ExtensionFilter,
+}
+
+impl Default for BinaryOverrides {
+    fn default() -> Self {
+        Self::new(Vec::<String>::new(), Vec::<String>::new())
+    }
+}
+
+impl BinaryOverrides {
+    /// Create overrides forcing the given extensions binary or text
+    pub fn new<I, J, S, T>(always_binary: I, always_text: J) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        J: IntoIterator<Item = T>,
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        Self {
+            binary: ExtensionFilter::new(always_binary),
+            text: ExtensionFilter::new(always_text),
+        }
+    }
+
+    /// The forced classification of a file, or None to sniff its bytes
+    #[inline]
+    pub fn lookup(&self, path: &Path) -> Option<bool> {
+        if self.text.allows(path) {
+            Some(false)
+        } else if self.binary.allows(path) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// Vendor, build and cache directories skipped by `use_default_ignores`
+///
+/// These are rarely wanted by an agent yet often missing from `.gitignore`.
+pub const DEFAULT_IGNORED_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "__pycache__",
+    "dist",
+    "build",
+    ".venv",
+    "venv",
+    ".tox",
+    ".mypy_cache",
+    ".pytest_cache",
+    ".gradle",
+    ".next",
+];
+
+/// Directory names left out wherever they appear in the tree
+///
+/// Names are matched exactly against single path components, so `build`
+/// skips `build/` and `web/build/` but not `build.rs` or `rebuild/`. Only
+/// directories are matched; a file that happens to be named `target` is
+/// kept.
+#[derive(Clone, Debug)]
+pub struct DirIgnore {
+    names: HashSet<String>,
+}
+
+impl DirIgnore {
+    /// Create a filter skipping directories with the given names
+    pub fn new<I, S>(names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            names: names
+                .into_iter()
+                .map(|n| n.as_ref().trim_matches('/').to_string())
+                .collect(),
+        }
+    }
+
+    /// Whether a directory with this file name is skipped
+    #[inline]
+    pub fn skips_name(&self, name: &std::ffi::OsStr) -> bool {
+        name.to_str().is_some_and(|n| self.names.contains(n))
+    }
+
+    /// Whether a walk should descend into or yield `entry`, for
+    /// `WalkBuilder::filter_entry`
+    ///
+    /// The walk root itself is always kept, even if its name is skipped.
+    pub fn keeps_entry(&self, entry: &ignore::DirEntry) -> bool {
+        entry.depth() == 0
+            || !entry.file_type().is_some_and(|t| t.is_dir())
+            || !self.skips_name(entry.file_name())
+    }
+
+    /// Whether a path relative to the walk root is skipped or lies inside a
+    /// skipped directory
+    pub fn skips(&self, relative: &Path, is_dir: bool) -> bool {
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            let is_last = components.peek().is_none();
+            if (!is_last || is_dir) && self.skips_name(component.as_os_str()) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Extensions a FileSystem may write to, checked before any write I/O
+///
+/// Targets without an extension (`Makefile`, `.bashrc`, or a directory
+/// name) are refused unless `allow_extensionless` is set. The check is on
+/// the path alone, so it also applies to files that do not exist yet.
+#[derive(Clone, Debug)]
+pub struct WritePolicy {
+    extensions: ExtensionFilter,
+    allow_extensionless: bool,
+}
+
+impl WritePolicy {
+    /// Create a policy allowing the given extensions
+    pub fn new(extensions: ExtensionFilter, allow_extensionless: bool) -> Self {
+        Self {
+            extensions,
+            allow_extensionless,
+        }
+    }
+
+    /// Whether a write to `path` is allowed
+    pub fn allows(&self, path: &Path) -> bool {
+        match path.extension() {
+            Some(_) => self.extensions.allows(path),
+            None => self.allow_extensionless,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joined_path_uses_forward_slashes() {
+        let path = Path::new("src").join("fs").join("mod.rs");
+        assert_eq!(to_posix_string(&path), "src/fs/mod.rs");
+    }
+
+    #[test]
+    fn test_common_prefix_is_component_wise() {
+        let paths = vec![PathBuf::from("/r/src/foo"), PathBuf::from("/r/src/foobar")];
+        assert_eq!(common_prefix(&paths), Some(PathBuf::from("/r/src")));
+
+        let paths = vec![PathBuf::from("/r/src/a.rs"), PathBuf::from("/r/tests/b.rs")];
+        assert_eq!(common_prefix(&paths), Some(PathBuf::from("/r")));
+
+        assert_eq!(common_prefix(&[]), None);
+    }
+
+    #[test]
+    fn test_fold_case() {
+        assert_eq!(
+            fold_case(Path::new("/R/Src/Foo.TXT")),
+            PathBuf::from("/r/src/foo.txt")
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_detects_case_sensitive_fs() {
+        let dir = tempfile::tempdir().unwrap();
+        let probe = dir.path().join("Probe");
+        std::fs::create_dir(&probe).unwrap();
+        assert!(!is_case_insensitive_fs(&probe));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_separators_normalized() {
+        assert_eq!(
+            to_posix_string(Path::new(r"src\fs\mod.rs")),
+            "src/fs/mod.rs"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_canonicalize_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/real.rs"), "").unwrap();
+        std::fs::write(outside.path().join("shared.rs"), "").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("src"), dir.path().join("alias")).unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("vendor")).unwrap();
+
+        let mut paths = vec![
+            "alias/real.rs".to_string(),
+            "src/real.rs".to_string(),
+            "vendor/shared.rs".to_string(),
+            "missing.rs".to_string(),
+        ];
+        canonicalize_paths(dir.path(), paths.par_iter_mut());
+
+        let outside_file = std::fs::canonicalize(outside.path().join("shared.rs")).unwrap();
+        assert_eq!(paths[0], "src/real.rs");
+        assert_eq!(paths[1], "src/real.rs");
+        assert_eq!(paths[2], to_posix_string(&outside_file));
+        assert_eq!(paths[3], "missing.rs");
+    }
+
+    #[test]
+    fn test_extension_filter() {
+        let filter = ExtensionFilter::new(["rs", ".PY"]);
+        assert!(filter.allows(Path::new("src/main.rs")));
+        assert!(filter.allows(Path::new("tool.py")));
+        assert!(filter.allows(Path::new("Tool.Py")));
+        assert!(!filter.allows(Path::new("logo.png")));
+        assert!(!filter.allows(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn test_binary_overrides() {
+        let overrides = BinaryOverrides::new(["dat", "log"], ["log", ".fixture"]);
+        assert_eq!(overrides.lookup(Path::new("model.DAT")), Some(true));
+        assert_eq!(overrides.lookup(Path::new("run.log")), Some(false));
+        assert_eq!(overrides.lookup(Path::new("a/b.fixture")), Some(false));
+        assert_eq!(overrides.lookup(Path::new("main.rs")), None);
+        assert_eq!(BinaryOverrides::default().lookup(Path::new("x.dat")), None);
+    }
+
+    #[test]
+    fn test_dir_ignore() {
+        let ignore = DirIgnore::new(["node_modules", "build/"]);
+        assert!(ignore.skips(Path::new("node_modules"), true));
+        assert!(ignore.skips(Path::new("web/node_modules/react/index.js"), false));
+        assert!(ignore.skips(Path::new("build/out.o"), false));
+        assert!(!ignore.skips(Path::new("build.rs"), false));
+        assert!(!ignore.skips(Path::new("rebuild/out.o"), false));
+        // A file named like an ignored directory is kept
+        assert!(!ignore.skips(Path::new("scripts/build"), false));
+        assert!(ignore.skips(Path::new("scripts/build"), true));
+    }
+
+    #[test]
+    fn test_write_policy() {
+        let policy = WritePolicy::new(ExtensionFilter::new(["rs", "md"]), false);
+        assert!(policy.allows(Path::new("src/lib.rs")));
+        assert!(policy.allows(Path::new("README.MD")));
+        assert!(!policy.allows(Path::new("scripts/deploy.sh")));
+        assert!(!policy.allows(Path::new("Makefile")));
+        assert!(!policy.allows(Path::new(".bashrc")));
+        assert!(!policy.allows(Path::new("src")));
+
+        let policy = WritePolicy::new(ExtensionFilter::new(["rs"]), true);
+        assert!(policy.allows(Path::new("Makefile")));
+        assert!(policy.allows(Path::new(".bashrc")));
+        assert!(!policy.allows(Path::new("deploy.sh")));
+    }
+}
@@ -128,6 +128,20 @@ class TestExternalPathsEnabled:
         assert lines[0] == "Log line 1"
         assert lines[1] == "Log line 2"
 
+    def test_read_indexed_external(self, temp_project, external_dir):
+        """Test external read_indexed offsets slice the content by character."""
+        from agent_gear import FileSystem
+
+        fs = FileSystem(str(temp_project), allow_external=True)
+
+        external_file = external_dir / "unicode.txt"
+        external_file.write_text("é☃\n日本\nx\n", encoding="utf-8")
+        content, offsets = fs.read_indexed(str(external_file))
+
+        assert offsets == [0, 3, 6]
+        assert content[offsets[1] : offsets[2]] == "日本\n"
+        assert fs.line_offsets(str(external_file)) == [0, 6, 13]
+
     def test_write_external_file(self, temp_project, external_dir):
         """Test writing to external file."""
         from agent_gear import FileSystem
@@ -151,7 +165,7 @@ class TestExternalPathsEnabled:
         external_file = str(external_dir / "test.log")
         result = fs.edit_replace(external_file, "Log line 1", "Modified line 1")
 
-        assert result is True
+        assert result == 1
         content = Path(external_file).read_text()
         assert "Modified line 1" in content
         assert "Log line 1" not in content
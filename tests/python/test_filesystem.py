@@ -98,6 +98,43 @@ class TestFileSystem:
         assert "def main():" in content
         assert "Hello, World!" in content
 
+    def test_read_file_encoding_with_cache(self, temp_project):
+        """Test cached reads still decode the requested encoding."""
+        from agent_gear import FileSystem
+
+        (temp_project / "latin.txt").write_bytes("café\n".encode("latin-1"))
+        fs = FileSystem(str(temp_project), cache_reads=True)
+
+        assert fs.read_file("latin.txt", encoding="latin-1") == "café\n"
+        assert fs.read_file("README.md") == fs.read_file("README.md", encoding="latin-1")
+
+    def test_read_file_limit_override(self, temp_project):
+        """Test a per-call max_read_bytes of 0 lifts the instance limit."""
+        from agent_gear import FileSystem
+
+        fs = FileSystem(str(temp_project), max_read_bytes=4)
+
+        with pytest.raises(IOError, match="too large"):
+            fs.read_file("README.md")
+        assert fs.read_file("README.md", max_read_bytes=0).startswith("# Test Project")
+        assert fs.open("README.md", max_read_bytes=0)
+
+    def test_read_indexed(self, temp_project):
+        """Test read_indexed offsets slice the content by character."""
+        from agent_gear import FileSystem
+
+        (temp_project / "unicode.txt").write_text("é☃\n日本\nx", encoding="utf-8")
+        fs = FileSystem(str(temp_project))
+
+        content, offsets = fs.read_indexed("unicode.txt")
+        assert offsets == [0, 3, 6]
+        assert [content[a:b] for a, b in zip(offsets, offsets[1:] + [None])] == [
+            "é☃\n",
+            "日本\n",
+            "x",
+        ]
+        assert fs.read_indexed("README.md")[1] == fs.line_offsets("README.md")
+
     def test_read_batch(self, temp_project):
         """Test batch file reading."""
         from agent_gear import FileSystem
@@ -115,6 +152,18 @@ class TestFileSystem:
         assert "def main():" in main_content
         assert "def helper():" in utils_content
 
+    def test_read_batch_keys_match_input(self, temp_project):
+        """Test batch results are keyed by the paths as passed in."""
+        from agent_gear import FileSystem
+
+        fs = FileSystem(str(temp_project))
+        fs.wait_ready()
+
+        absolute = str(temp_project / "src" / "utils.py")
+        contents = fs.read_batch(["src/main.py", absolute])
+
+        assert set(contents) == {"src/main.py", absolute}
+
     def test_write_file(self, temp_project):
         """Test writing a file."""
         from agent_gear import FileSystem
@@ -136,7 +185,7 @@ class TestFileSystem:
 
         # Replace text
         result = fs.edit_replace("README.md", "Test Project", "My Project")
-        assert result is True
+        assert result == 1
 
         # Verify
         content = fs.read_file("README.md")
@@ -211,6 +260,18 @@ class TestFileSystem:
             files = fs.list("**/*")
             assert len(files) > 0
 
+    def test_ping(self, temp_project):
+        """Test ping reports the package, version and root."""
+        from agent_gear import AsyncFileSystem, FileSystem, __version__
+
+        fs = FileSystem(str(temp_project))
+        name, version, root = fs.ping().split(" ", 2)
+        assert (name, version) == ("agent-gear", __version__)
+        assert Path(root).resolve() == temp_project.resolve()
+
+        afs = AsyncFileSystem(str(temp_project))
+        assert afs.ping().startswith(f"agent-gear {__version__} ")
+
     def test_get_metadata(self, temp_project):
         """Test getting file metadata."""
         from agent_gear import FileSystem